@@ -1,6 +1,6 @@
 use mapscow_mule::{
     core::MapData,
-    export::{ExportOptions, ExportFormat},
+    export::{ExportOptions, ExportFormat, ExportView},
     export::svg_export::SvgExporter,
     rendering::engine::RenderingEngine,
     styles::StyleManager,
@@ -17,7 +17,7 @@ fn main() -> Result<()> {
     let map_data = create_sample_map_data();
     
     // Create improved SVG exporter with custom settings
-    let svg_exporter = SvgExporter::new()
+    let svg_exporter = SvgExporter::new()?
         .with_precision(2)           // Higher precision for smoother curves
         .with_anti_aliasing(true)    // Better visual quality
         .with_layer_separation(true); // Organized layer structure
@@ -29,9 +29,7 @@ fn main() -> Result<()> {
         "output/sample_map_direct.svg",
         1200,
         800,
-        48.8566,  // Paris latitude
-        2.3522,   // Paris longitude
-        5000.0,   // Scale factor
+        ExportView::new(48.8566, 2.3522, 5000.0), // Paris, scale factor
     )?;
     
     // Example of using the advanced rendering pipeline
@@ -47,16 +45,10 @@ fn main() -> Result<()> {
         .with_simplification(true, 1.0);       // Enable line simplification
     
     // Create export options
-    let export_options = ExportOptions {
-        format: ExportFormat::Svg,
-        output_path: "output/sample_map_advanced.svg".to_string(),
-        width: 1200,
-        height: 800,
-        dpi: 300.0,
-        background_color: None,
-        quality: None,
-        compression: None,
-    };
+    let export_options = ExportOptions::new(ExportFormat::Svg, "output/sample_map_advanced.svg".to_string())
+        .with_size(1200, 800)
+        .with_dpi(300.0)
+        .with_transparent_background();
     
     // Render with advanced features
     let rendered_map = rendering_engine.render_advanced(&styled_map, &export_options)?;
@@ -87,10 +79,9 @@ fn main() -> Result<()> {
 
 fn create_sample_map_data() -> MapData {
     use std::collections::HashMap;
-    use mapscow_mule::core::{MapBounds, Node, Way};
+    use mapscow_mule::core::{Node, Way};
     
-    let mut nodes = HashMap::new();
-    let mut ways = HashMap::new();
+    let mut map_data = MapData::new();
     
     // Create some sample nodes (simplified Paris area)
     let sample_nodes = vec![
@@ -107,7 +98,7 @@ fn create_sample_map_data() -> MapData {
             tags.insert("name".to_string(), "Sample Restaurant".to_string());
         }
         
-        nodes.insert(id, Node {
+        map_data.add_node(Node {
             id,
             lat,
             lon,
@@ -120,7 +111,7 @@ fn create_sample_map_data() -> MapData {
     road_tags.insert("highway".to_string(), "primary".to_string());
     road_tags.insert("name".to_string(), "Sample Street".to_string());
     
-    ways.insert(1, Way {
+    map_data.add_way(Way {
         id: 1,
         nodes: vec![1, 2, 3, 4],
         tags: road_tags,
@@ -131,23 +122,13 @@ fn create_sample_map_data() -> MapData {
     let mut building_tags = HashMap::new();
     building_tags.insert("building".to_string(), "yes".to_string());
     
-    ways.insert(2, Way {
+    map_data.add_way(Way {
         id: 2,
         nodes: vec![1, 2, 3, 1], // Closed way
         tags: building_tags,
         is_closed: true,
     });
     
-    MapData {
-        bounds: MapBounds {
-            min_lat: 48.85,
-            max_lat: 48.88,
-            min_lon: 2.29,
-            max_lon: 2.36,
-        },
-        nodes,
-        ways,
-        relations: HashMap::new(),
-        gpx_tracks: Vec::new(),
-    }
+    // The bounds grow with every node added
+    map_data
 }
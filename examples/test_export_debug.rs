@@ -2,7 +2,7 @@
 // This will help us verify if the road name export is working correctly
 
 use std::path::Path;
-use mapscow_mule::export::{ExportFormat, ExportOptions};
+use mapscow_mule::export::{ExportFormat, ExportOptions, ExportView};
 use mapscow_mule::parsers::osm::OsmParser;
 use mapscow_mule::parsers::Parser;
 use mapscow_mule::export::svg_export::SvgExporter;
@@ -45,7 +45,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Test export with show_all_road_names=true
     let output_path = "test_export_with_debug.svg";
-    let options = ExportOptions::new(ExportFormat::Svg, output_path.to_string());
+    let options = ExportOptions::new(ExportFormat::Svg, output_path.to_string()).with_all_road_names(true);
     let renderer = MapRenderer::new();
     
     let exporter = Exporter::new();
@@ -64,10 +64,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         &map_data,
         &renderer,
         &options,
-        center_lat,
-        center_lon,
-        scale,
-        true, // show_all_road_names=true
+        ExportView::new(center_lat, center_lon, scale),
     ) {
         Ok(_) => {
             println!("✅ Export completed successfully!");
@@ -1,4 +1,5 @@
 use mapscow_mule::export::svg_export::SvgExporter;
+use mapscow_mule::export::ExportView;
 use mapscow_mule::parsers::{osm::OsmParser, Parser};
 use std::path::Path;
 
@@ -68,9 +69,7 @@ fn main() -> anyhow::Result<()> {
         output_path,
         1200, // width
         800,  // height
-        center_lat,
-        center_lon,
-        scale,
+        ExportView::new(center_lat, center_lon, scale),
     )?;
     
     println!("SVG exported to: {}", output_path);
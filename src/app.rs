@@ -1,21 +1,48 @@
-use crate::core::MapData;
-use crate::export::{ExportFormat, ExportOptions, Exporter};
-use crate::gui::{GuiState, GeocodingPanel, GeocodingAction, LayersPanel, MapView, StyleEditor, Toolbar, ToolbarAction, Tool};
+use crate::core::{ElementType, GpxTrack, MapData};
+use crate::core::geometry::FitPadding;
+use crate::export::{ExportFormat, ExportOptions, ExportView, Exporter};
+use crate::export::tiles::TileExporter;
+use crate::gui::widgets::ScaleBar;
+use crate::gui::{GuiState, ExportDialog, GeocodingPanel, GeocodingAction, GpxPanel, LayersPanel, MapView, StyleEditor, TagPanel, Toolbar, ToolbarAction, Tool};
 use crate::parsers::{osm::OsmParser, gpx::GpxParser, Parser};
+use crate::parsers::stylesheet::ColorTransform;
 use crate::rendering::MapRenderer;
-use crate::styles::loader::StyleManager;
+use crate::styles::loader::{StyleManager, DEFAULT_STYLE};
+use crate::utils::background::BackgroundJob;
+use crate::utils::config::{AppConfig, SessionConfig, ViewportConfig};
 use crate::utils::file_dialog::{FileDialog, FileFilters};
+use crate::utils::geocoding::{GeocodeCache, NameIndex};
+use crate::utils::units::{CoordinateFormat, UnitSystem};
 use anyhow::Result;
 use egui::{Context, CentralPanel, TopBottomPanel};
-use log::info;
-use std::path::PathBuf;
+use log::{debug, info, warn};
+use std::path::{Path, PathBuf};
+
+/// Bundled style with the color-blind safe palette, toggled from the View menu
+const COLORBLIND_STYLE: &str = "colorblind";
+
+/// Index the names of freshly parsed data, while still off the UI thread
+fn with_name_index(data: MapData) -> (MapData, NameIndex) {
+    let index = NameIndex::build(&data);
+    (data, index)
+}
+
+/// Outline to clip an export to: the first selected relation or closed way that has one
+fn selection_outline(data: &MapData, selection: &[(ElementType, i64)]) -> Option<Vec<geo_types::Coord<f64>>> {
+    let relations = selection.iter().filter(|(element_type, _)| *element_type == ElementType::Relation);
+    let ways = selection.iter().filter(|(element_type, _)| *element_type == ElementType::Way);
+    relations.chain(ways).find_map(|&(element_type, id)| data.outline(element_type, id))
+}
 
 pub struct MapscowMule {
     // Core data
     map_data: Option<MapData>,
+    /// Names in `map_data`, for searching the loaded map
+    name_index: Option<NameIndex>,
+    gpx_tracks: Vec<GpxTrack>,
     style_manager: StyleManager,
     renderer: MapRenderer,
-    exporter: Exporter,
+    config: AppConfig,
     
     // GUI state
     gui_state: GuiState,
@@ -24,6 +51,9 @@ pub struct MapscowMule {
     toolbar: Toolbar,
     layers_panel: LayersPanel,
     geocoding_panel: GeocodingPanel,
+    gpx_panel: GpxPanel,
+    tag_panel: TagPanel,
+    export_dialog: ExportDialog,
     
     // File dialogs and I/O
     osm_file_path: Option<PathBuf>,
@@ -33,8 +63,10 @@ pub struct MapscowMule {
     // Status and progress
     status_message: String,
     map_status: String,
-    is_loading: bool,
-    progress: f32,
+    /// OSM file being parsed in the background
+    osm_load: Option<(PathBuf, BackgroundJob<(MapData, NameIndex)>)>,
+    /// Export being written in the background, with its output path
+    export_job: Option<(String, BackgroundJob<()>)>,
 }
 
 impl MapscowMule {
@@ -47,18 +79,31 @@ impl MapscowMule {
             })
         });
         
+        let config = AppConfig::load();
+        // A saved session brings back the style last used, otherwise start with the preferred one
+        let gui_state = config.session.gui.clone().unwrap_or_else(|| GuiState {
+            selected_style: config.map.default_style.clone(),
+            ..GuiState::default()
+        });
+        let geocode_cache = GeocodeCache::from_config(&config.geocoding);
+        
         let mut app = Self {
             map_data: None,
+            name_index: None,
+            gpx_tracks: Vec::new(),
             style_manager,
             renderer: MapRenderer::new(),
-            exporter: Exporter::new(),
+            config,
             
-            gui_state: GuiState::new(),
+            gui_state,
             map_view: MapView::new(),
             style_editor: StyleEditor::new(),
             toolbar: Toolbar::new(),
             layers_panel: LayersPanel::new(),
-            geocoding_panel: GeocodingPanel::new(),
+            geocoding_panel: GeocodingPanel::new().with_cache(geocode_cache),
+            gpx_panel: GpxPanel::new(),
+            tag_panel: TagPanel::new(),
+            export_dialog: ExportDialog::new(),
             
             osm_file_path: None,
             gpx_file_path: None,
@@ -66,10 +111,12 @@ impl MapscowMule {
             
             status_message: "Ready".to_string(),
             map_status: "".to_string(),
-            is_loading: false,
-            progress: 0.0,
+            osm_load: None,
+            export_job: None,
         };
         
+        app.restore_style();
+        
         // Load OSM file if provided via command line
         if let Some(osm_path) = osm_file {
             if osm_path.exists() {
@@ -80,50 +127,198 @@ impl MapscowMule {
             } else {
                 info!("OSM file not found: {:?}", osm_path);
             }
+        } else {
+            app.restore_last_file();
         }
         
         app
     }
     
+    /// Apply the style and color adjustment the last session ended with, or the configured
+    /// default style
+    fn restore_style(&mut self) {
+        self.apply_color_transform();
+        let style = self.gui_state.selected_style.clone();
+        if style == DEFAULT_STYLE {
+            return;
+        }
+        match self.style_manager.load_style_or_default(&style) {
+            Ok(loaded) => self.gui_state.selected_style = loaded,
+            Err(e) => {
+                warn!("Could not load the default style either: {}", e);
+                self.gui_state.selected_style = DEFAULT_STYLE.to_string();
+            }
+        }
+    }
+    
+    /// Recolor the style with the brightness, saturation and hue set in the Style menu
+    fn apply_color_transform(&mut self) {
+        if let Err(e) = self.style_manager.set_color_transform(self.gui_state.color_transform) {
+            self.status_message = format!("Error adjusting style colors: {}", e);
+        }
+    }
+    
+    /// Switch to the bundled color-blind safe style, or back to the style used before it
+    fn set_colorblind_palette(&mut self, enabled: bool) {
+        let target = if enabled {
+            self.gui_state.style_before_colorblind = Some(self.gui_state.selected_style.clone());
+            COLORBLIND_STYLE.to_string()
+        } else {
+            self.gui_state.style_before_colorblind.take().unwrap_or_else(|| self.config.map.default_style.clone())
+        };
+        
+        match self.style_manager.load_style(&target) {
+            Ok(()) => {
+                self.gui_state.selected_style = target;
+                self.status_message = if enabled {
+                    "Using the color-blind safe palette".to_string()
+                } else {
+                    "Color-blind safe palette off".to_string()
+                };
+            }
+            Err(e) => self.status_message = format!("Error loading style {}: {}", target, e),
+        }
+    }
+    
+    /// Reopen the last file and put the view back where it was
+    fn restore_last_file(&mut self) {
+        let SessionConfig { last_file, viewport, .. } = self.config.session.clone();
+        let Some(path) = last_file.filter(|path| path.exists()) else {
+            return;
+        };
+        
+        info!("Restoring last session: {:?}", path);
+        if let Err(e) = self.load_osm_file(&path) {
+            warn!("Failed to reopen {:?}: {}", path, e);
+            return;
+        }
+        if let Some(viewport) = viewport.filter(ViewportConfig::is_valid) {
+            self.map_view.center_on_coordinates(viewport.center_lat, viewport.center_lon);
+            self.map_view.set_scale(viewport.scale);
+            self.map_view.set_rotation(viewport.rotation);
+        }
+    }
+    
+    /// Write the open file, viewport and GUI state to the config file
+    fn save_session(&mut self) {
+        let (center_lon, center_lat, scale) = self.map_view.get_viewport_info();
+        self.config.session = SessionConfig {
+            last_file: self.osm_file_path.clone(),
+            viewport: Some(ViewportConfig {
+                center_lat,
+                center_lon,
+                scale,
+                rotation: self.map_view.rotation(),
+            }),
+            gui: Some(self.gui_state.clone()),
+        };
+        
+        if let Err(e) = self.config.save() {
+            warn!("Failed to save session: {}", e);
+        }
+    }
+    
+    /// Load an OSM file right away, blocking until it is parsed. Used at startup, before
+    /// the window is shown; the menus use [`Self::start_osm_load`] instead.
     pub fn load_osm_file(&mut self, path: &PathBuf) -> Result<()> {
-        self.is_loading = true;
         self.status_message = "Loading OSM data...".to_string();
         
         println!("Loading OSM file: {:?}", path);
         
-        let parser = OsmParser::new();
-        match parser.parse_file(path) {
-            Ok(data) => {
+        let result = OsmParser::new()
+            .with_geometry_repair(self.config.map.repair_geometry)
+            .parse_file(path)
+            .map(with_name_index);
+        self.finish_osm_load(path, result)
+    }
+    
+    /// Parse an OSM file on a background thread; [`Self::poll_background_jobs`] picks up the
+    /// result. The current map stays on screen until the new one is ready.
+    pub fn start_osm_load(&mut self, path: &PathBuf) {
+        info!("Loading OSM file in the background: {:?}", path);
+        self.status_message = format!("Loading OSM file: {}", path.display());
+        
+        let parse_path = path.clone();
+        let repair_geometry = self.config.map.repair_geometry;
+        let job = BackgroundJob::spawn(move |progress| {
+            OsmParser::new()
+                .with_geometry_repair(repair_geometry)
+                .with_progress(move |parsed| {
+                    debug!("Parsed {} elements ({} bytes)", parsed.elements, parsed.bytes_read);
+                    if let Some(fraction) = parsed.fraction() {
+                        progress.set(fraction);
+                    }
+                })
+                .parse_file(&parse_path)
+                .map(with_name_index)
+        });
+        self.osm_load = Some((path.clone(), job));
+    }
+    
+    fn finish_osm_load(&mut self, path: &Path, result: Result<(MapData, NameIndex)>) -> Result<()> {
+        match result {
+            Ok((data, index)) => {
                 self.map_data = Some(data);
+                self.name_index = Some(index);
+                self.tag_panel.invalidate();
+                self.osm_file_path = Some(path.to_path_buf());
+                self.config.add_recent_file(path.to_path_buf());
                 // Automatically center and zoom to fit the loaded data
                 self.map_view.zoom_to_fit(&self.map_data);
-                self.status_message = "OSM data loaded successfully".to_string();
-                self.is_loading = false;
+                self.status_message = format!("Successfully loaded: {}", path.display());
                 Ok(())
             }
             Err(e) => {
                 self.status_message = format!("Failed to load OSM data: {}", e);
-                self.is_loading = false;
                 Err(e)
             }
         }
     }
     
+    /// Apply the results of finished background loads and exports, and keep repainting
+    /// while any are running so their progress shows
+    fn poll_background_jobs(&mut self, ctx: &Context) {
+        if let Some((path, job)) = &mut self.osm_load {
+            if let Some(result) = job.try_finish() {
+                let path = path.clone();
+                self.osm_load = None;
+                if let Err(e) = self.finish_osm_load(&path, result) {
+                    warn!("Failed to load {:?}: {}", path, e);
+                }
+            }
+        }
+        if let Some((path, job)) = &mut self.export_job {
+            if let Some(result) = job.try_finish() {
+                self.status_message = match result {
+                    Ok(()) => format!("Exported: {}", path),
+                    Err(e) => format!("Export failed: {}", e),
+                };
+                self.export_job = None;
+            }
+        }
+        
+        if self.osm_load.is_some() || self.export_job.is_some() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+    }
+    
     pub fn load_gpx_file(&mut self, path: &PathBuf) -> Result<()> {
         self.status_message = "Loading GPX data...".to_string();
         
-        let parser = GpxParser::new();
+        let parser = GpxParser::new()
+            .with_smoothing(self.config.gpx.smoothing_window)
+            .with_simplification(self.config.gpx.simplify_tolerance);
         match parser.parse_file(path) {
             Ok(gpx_data) => {
+                self.gpx_file_path = Some(path.clone());
+                self.config.add_recent_file(path.clone());
+                
                 // TODO: Integrate GPX data with map data
-                self.status_message = "GPX data loaded successfully".to_string();
+                self.status_message = format!("Loaded {} GPX track(s)", gpx_data.len());
                 
-                // Center the map on the GPX data if it contains track points
-                if !gpx_data.is_empty() {
-                    // For now, we'll need to convert GPX data to map data format
-                    // This is a placeholder for proper GPX integration
-                    self.status_message = "GPX data loaded and centered".to_string();
-                }
+                // Show the statistics of the new tracks
+                self.gpx_tracks = gpx_data;
+                self.gui_state.show_gpx_stats = !self.gpx_tracks.is_empty();
                 
                 Ok(())
             }
@@ -134,29 +329,72 @@ impl MapscowMule {
         }
     }
     
-    pub fn export_map(&mut self, format: ExportFormat, options: ExportOptions) -> Result<()> {
+    /// Open an entry from the Recent Files menu, picking the loader from the extension
+    fn open_recent_file(&mut self, path: &PathBuf) {
+        let is_gpx = path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case("gpx"));
+        if !is_gpx {
+            self.start_osm_load(path);
+            return;
+        }
+        
+        match self.load_gpx_file(path) {
+            Ok(_) => {
+                self.status_message = format!("Successfully loaded: {}", path.display());
+            }
+            Err(e) => {
+                self.status_message = format!("Error loading {}: {}", path.display(), e);
+            }
+        }
+    }
+    
+    /// Apply an edit to the loaded map, then refresh the name index, tag statistics and
+    /// selection, which are all derived from it
+    fn edit_map_data(&mut self, edit: impl FnOnce(&mut MapData)) {
+        let Some(data) = self.map_data.as_mut() else {
+            return;
+        };
+        edit(data);
+        self.name_index = Some(NameIndex::build(data));
+        self.tag_panel.invalidate();
+        self.map_view.retain_selection(data);
+    }
+    
+    /// Delete the selected nodes and ways; selected relations are kept
+    fn delete_selection(&mut self) {
+        let selection = self.map_view.selection();
+        self.edit_map_data(|data| {
+            let mut deleted = 0;
+            for (element_type, id) in selection {
+                deleted += match element_type {
+                    ElementType::Node => data.delete_node(id).is_some() as usize,
+                    ElementType::Way => data.delete_way(id).is_some() as usize,
+                    ElementType::Relation => 0,
+                };
+            }
+            info!("Deleted {} selected features", deleted);
+        });
+    }
+    
+    /// Export the loaded map as `z/x/y` tiles at the zoom closest to the current view
+    pub fn export_tiles(&mut self, output_dir: &Path) -> Result<()> {
         if let Some(ref map_data) = self.map_data {
-            self.status_message = "Exporting map...".to_string();
+            self.status_message = "Exporting tiles...".to_string();
             
-            // Get viewport information from MapView
-            let (center_lon, center_lat, scale) = self.map_view.get_viewport_info();
+            // The view scale is in pixels per degree; a 256px tile spans 360/2^z degrees
+            let (_, _, scale) = self.map_view.get_viewport_info();
+            let zoom = (scale * 360.0 / 256.0).log2().round().clamp(0.0, 22.0) as u8;
             
-            match self.exporter.export_map_with_viewport(
-                map_data, 
-                &self.renderer, 
-                &options,
-                center_lat,
-                center_lon,
-                scale,
-                self.gui_state.show_all_road_names,
-            ) {
-                Ok(_) => {
-                    self.status_message = "Map exported successfully".to_string();
+            let exporter = TileExporter::new(output_dir)
+                .with_all_road_names(self.gui_state.show_all_road_names);
+            
+            match exporter.export(map_data, zoom) {
+                Ok(summary) => {
+                    self.status_message = format!("Exported {} tiles at zoom {}", summary.tiles_written, summary.zoom);
                     Ok(())
                 }
                 Err(e) => {
-                    self.status_message = format!("Export failed: {}", e);
-                    Err(e)
+                    self.status_message = format!("Tile export failed: {}", e);
+                    Err(e.into())
                 }
             }
         } else {
@@ -164,25 +402,87 @@ impl MapscowMule {
             Err(anyhow::anyhow!("No map data loaded"))
         }
     }
+    
+    /// Export the current view on a background thread; the status bar reports when it is done.
+    /// Given `only`, just those elements (and the nodes they use) are drawn.
+    pub fn export_map(&mut self, format: ExportFormat, options: ExportOptions, only: Option<&[(ElementType, i64)]>) -> Result<()> {
+        if self.export_job.is_some() {
+            return Err(anyhow::anyhow!("Another export is still running"));
+        }
+        if let Some(ref map_data) = self.map_data {
+            
+            // Get viewport information from MapView
+            let (center_lon, center_lat, scale) = self.map_view.get_viewport_info();
+            
+            // Render the same view at the export resolution: canvas, map scale and line widths
+            // all grow together so a high-DPI export matches what is on screen
+            let dpi_factor = (self.gui_state.export_dpi / crate::export::BASELINE_DPI) as f64;
+            let width = (options.width as f64 * dpi_factor).round() as u32;
+            let height = (options.height as f64 * dpi_factor).round() as u32;
+            let scale = scale * dpi_factor;
+            
+            // Exports follow the bearing, style and palette the map is shown with
+            let mut options = options
+                .with_size(width, height)
+                .with_dpi(self.gui_state.export_dpi)
+                .with_svg_text(self.gui_state.svg_text)
+                .with_full_geometry(self.gui_state.full_geometry)
+                .with_densify(self.gui_state.densify_segments)
+                .with_coordinate_grid(self.gui_state.coordinate_grid)
+                .with_rotation(self.map_view.rotation())
+                .with_all_road_names(self.gui_state.show_all_road_names)
+                .with_style(self.gui_state.selected_style.clone())
+                .with_color_transform(self.gui_state.color_transform);
+            
+            // The style background, palette included, as in the map view, unless transparency is on
+            if self.gui_state.transparent_background {
+                options = options.with_transparent_background();
+            }
+            if let Some(image) = &self.gui_state.background_image {
+                options = options.with_background_image(image.to_string_lossy().to_string());
+            }
+            
+            // The worker gets its own copy of the data so the map stays usable meanwhile
+            let map_data = match only {
+                Some(elements) => map_data.subset(elements),
+                None => map_data.clone(),
+            };
+            let output_path = options.output_path.clone();
+            self.status_message = format!("Exporting map to {}...", output_path);
+            let job = BackgroundJob::spawn(move |_| {
+                Exporter::new().export_map_with_viewport(
+                    &map_data,
+                    &MapRenderer::new(),
+                    &options,
+                    ExportView::new(center_lat, center_lon, scale),
+                )?;
+                Ok(())
+            });
+            self.export_job = Some((output_path, job));
+            Ok(())
+        } else {
+            self.status_message = "No map data to export".to_string();
+            Err(anyhow::anyhow!("No map data loaded"))
+        }
+    }
 }
 
 impl eframe::App for MapscowMule {
     fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+        self.poll_background_jobs(ctx);
+        
+        // Pick up a style reloaded from disk by the style watcher
+        if self.style_manager.apply_pending_reload() {
+            self.status_message = "Style reloaded from disk".to_string();
+        }
+        
         // Menu bar
         TopBottomPanel::top("menubar").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
                     if ui.button("Open OSM file...").clicked() {
                         if let Some(path) = FileDialog::open_file("Open OSM File", &[FileFilters::OSM]) {
-                            self.status_message = format!("Loading OSM file: {}", path.display());
-                            match self.load_osm_file(&path) {
-                                Ok(_) => {
-                                    self.status_message = format!("Successfully loaded: {}", path.display());
-                                }
-                                Err(e) => {
-                                    self.status_message = format!("Error loading OSM file: {}", e);
-                                }
-                            }
+                            self.start_osm_load(&path);
                         }
                         ui.close_menu();
                     }
@@ -200,36 +500,86 @@ impl eframe::App for MapscowMule {
                         }
                         ui.close_menu();
                     }
+                    ui.menu_button("Recent Files", |ui| {
+                        let recent_files = self.config.recent_files.clone();
+                        if recent_files.is_empty() {
+                            ui.add_enabled(false, egui::Label::new("No recent files"));
+                        }
+                        
+                        // Files that were moved or deleted stay listed but cannot be opened
+                        for path in &recent_files {
+                            let name = path.file_name()
+                                .map(|name| name.to_string_lossy().to_string())
+                                .unwrap_or_else(|| path.display().to_string());
+                            let response = ui.add_enabled(path.exists(), egui::Button::new(name))
+                                .on_hover_text(path.display().to_string())
+                                .on_disabled_hover_text(format!("{} no longer exists", path.display()));
+                            if response.clicked() {
+                                self.open_recent_file(path);
+                                ui.close_menu();
+                            }
+                        }
+                        
+                        if !recent_files.is_empty() {
+                            ui.separator();
+                            if ui.button("Remove Missing Files").clicked() {
+                                self.config.recent_files.retain(|path| path.exists());
+                            }
+                            if ui.button("Clear Recent Files").clicked() {
+                                self.config.recent_files.clear();
+                                ui.close_menu();
+                            }
+                        }
+                    });
                     ui.separator();
-                    if ui.button("Export as SVG...").clicked() {
-                        if let Some(path) = FileDialog::save_file("Export as SVG", "map.svg", &[FileFilters::SVG]) {
-                            let options = ExportOptions::new(ExportFormat::Svg, path.to_string_lossy().to_string());
-                            match self.export_map(ExportFormat::Svg, options) {
-                                Ok(_) => {
-                                    self.status_message = format!("Successfully exported: {}", path.display());
+                    if ui.button("Save style overrides...").clicked() {
+                        if let Some(path) = FileDialog::save_file("Save Style Overrides", "overrides.json", &[FileFilters::STYLE_OVERRIDES]) {
+                            match self.map_data.as_ref().map(|data| data.save_style_overrides(&path)) {
+                                Some(Ok(_)) => {
+                                    self.status_message = format!("Saved style overrides to: {}", path.display());
                                 }
-                                Err(e) => {
-                                    self.status_message = format!("Error exporting SVG: {}", e);
+                                Some(Err(e)) => {
+                                    self.status_message = format!("Error saving style overrides: {}", e);
+                                }
+                                None => {
+                                    self.status_message = "No map data loaded".to_string();
                                 }
                             }
                         }
                         ui.close_menu();
                     }
-                    if ui.button("Export as PNG...").clicked() {
-                        if let Some(path) = FileDialog::save_file("Export as PNG", "map.png", &[FileFilters::PNG]) {
-                            let options = ExportOptions::new(ExportFormat::Png, path.to_string_lossy().to_string());
-                            match self.export_map(ExportFormat::Png, options) {
-                                Ok(_) => {
-                                    self.status_message = format!("Successfully exported: {}", path.display());
+                    if ui.button("Load style overrides...").clicked() {
+                        if let Some(path) = FileDialog::open_file("Load Style Overrides", &[FileFilters::STYLE_OVERRIDES]) {
+                            match self.map_data.as_mut().map(|data| data.load_style_overrides(&path)) {
+                                Some(Ok(count)) => {
+                                    self.status_message = format!("Loaded {} style overrides", count);
                                 }
-                                Err(e) => {
-                                    self.status_message = format!("Error exporting PNG: {}", e);
+                                Some(Err(e)) => {
+                                    self.status_message = format!("Error loading style overrides: {}", e);
+                                }
+                                None => {
+                                    self.status_message = "Load map data before applying style overrides".to_string();
                                 }
                             }
                         }
                         ui.close_menu();
                     }
                     ui.separator();
+                    for (label, format) in [("Export as SVG...", ExportFormat::Svg), ("Export as PNG...", ExportFormat::Png), ("Export as JPEG...", ExportFormat::Jpeg)] {
+                        if ui.button(label).clicked() {
+                            self.export_dialog.open(format, self.map_view.viewport_size());
+                            ui.close_menu();
+                        }
+                    }
+                    if ui.button("Export tiles...").clicked() {
+                        if let Some(dir) = FileDialog::select_folder("Export Tiles To") {
+                            if let Err(e) = self.export_tiles(&dir) {
+                                self.status_message = format!("Error exporting tiles: {}", e);
+                            }
+                        }
+                        ui.close_menu();
+                    }
+                    ui.separator();
                     if ui.button("Quit").clicked() {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
@@ -238,12 +588,80 @@ impl eframe::App for MapscowMule {
                 ui.menu_button("View", |ui| {
                     ui.checkbox(&mut self.gui_state.show_tool_panel, "Tool Panel");
                     ui.checkbox(&mut self.gui_state.show_layers_panel, "Layers Panel");
+                    ui.checkbox(&mut self.gui_state.show_gpx_stats, "GPX Statistics");
+                    ui.checkbox(&mut self.gui_state.show_tag_stats, "Tag Statistics");
                     ui.checkbox(&mut self.gui_state.show_geocoding_panel, "Search Places");
+                    ui.checkbox(&mut self.gui_state.show_debug_ids, "Feature IDs")
+                        .on_hover_text("Label each way and node with its OSM id, to track down styling problems");
+                    let mut colorblind = self.gui_state.selected_style == COLORBLIND_STYLE;
+                    if ui.checkbox(&mut colorblind, "Color-blind Safe Palette")
+                        .on_hover_text("Okabe-Ito colors that stay distinct with red-green or blue-yellow color blindness")
+                        .changed()
+                    {
+                        self.set_colorblind_palette(colorblind);
+                    }
                     ui.separator();
                     if ui.button("Zoom to Fit").clicked() {
                         self.map_view.zoom_to_fit(&self.map_data);
                         ui.close_menu();
                     }
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        let mut rotation = self.map_view.rotation();
+                        ui.label("Rotation:");
+                        if ui.add(egui::DragValue::new(&mut rotation).speed(1.0).range(-360.0..=360.0).suffix("°")).changed() {
+                            self.map_view.set_rotation(rotation);
+                        }
+                        if ui.button("North Up").clicked() {
+                            self.map_view.set_rotation(0.0);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Max zoom:");
+                        ui.add(egui::DragValue::new(&mut self.gui_state.max_zoom).speed(0.1).range(1.0..=24.0).prefix("z"))
+                            .on_hover_text("Zooming in stops at this web map zoom level");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Units:");
+                        egui::ComboBox::from_id_salt("units")
+                            .selected_text(self.gui_state.units.label())
+                            .show_ui(ui, |ui| {
+                                for units in [UnitSystem::Metric, UnitSystem::Imperial] {
+                                    ui.selectable_value(&mut self.gui_state.units, units, units.label());
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Coordinates:");
+                        egui::ComboBox::from_id_salt("coordinate_format")
+                            .selected_text(self.gui_state.coordinate_format.label())
+                            .show_ui(ui, |ui| {
+                                for format in [CoordinateFormat::Decimal, CoordinateFormat::DegreesMinutesSeconds] {
+                                    ui.selectable_value(&mut self.gui_state.coordinate_format, format, format.label());
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Fit padding:");
+                        let padding = &mut self.gui_state.fit_padding;
+                        let value = match padding {
+                            FitPadding::Pixels(pixels) => ui.add(egui::DragValue::new(pixels).speed(1.0).range(0.0..=200.0).suffix(" px")),
+                            FitPadding::Percent(percent) => ui.add(egui::DragValue::new(percent).speed(0.5).range(0.0..=40.0).suffix(" %")),
+                        };
+                        value.on_hover_text("Room Zoom to Fit leaves around the data, so edge labels stay whole");
+                        let percent = matches!(padding, FitPadding::Percent(_));
+                        if ui.selectable_label(!percent, "px").clicked() && percent {
+                            *padding = FitPadding::Pixels(24.0);
+                        }
+                        if ui.selectable_label(percent, "%").clicked() && !percent {
+                            *padding = FitPadding::default();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Selection tolerance:");
+                        ui.add(egui::DragValue::new(&mut self.gui_state.selection_tolerance).speed(0.5).range(1.0..=50.0).suffix(" px"))
+                            .on_hover_text("Clicks in Select mode pick features this close on screen");
+                    });
                 });
                 
                 ui.menu_button("Style", |ui| {
@@ -251,6 +669,26 @@ impl eframe::App for MapscowMule {
                         self.gui_state.show_style_editor_modal = true;
                         ui.close_menu();
                     }
+                    
+                    let mut watch_style = self.style_manager.watched_style_path().is_some();
+                    if ui.checkbox(&mut watch_style, "Reload style on file change").changed() {
+                        if watch_style {
+                            let repaint_ctx = ctx.clone();
+                            self.style_manager.set_reload_notifier(move || repaint_ctx.request_repaint());
+                            
+                            let result = match self.style_manager.current_style_path().map(|p| p.to_path_buf()) {
+                                Some(path) => self.style_manager.watch_style(&path).map(|_| path).map_err(anyhow::Error::from),
+                                None => Err(anyhow::anyhow!("Current style has no file")),
+                            };
+                            self.status_message = match result {
+                                Ok(path) => format!("Watching {} for changes", path.display()),
+                                Err(e) => format!("Cannot watch style: {}", e),
+                            };
+                        } else {
+                            self.style_manager.unwatch_style();
+                            self.status_message = "Stopped watching style file".to_string();
+                        }
+                    }
                     ui.separator();
                     
                     // Style selector dropdown
@@ -261,6 +699,7 @@ impl eframe::App for MapscowMule {
                             match s.as_str() {
                                 "google-maps" => "Google Maps".to_string(),
                                 "osm-default" => "OSM Default".to_string(),
+                                COLORBLIND_STYLE => "Color-blind Safe".to_string(),
                                 name => name.replace('-', " ").replace('_', " ")
                                     .split_whitespace()
                                     .map(|word| {
@@ -279,6 +718,7 @@ impl eframe::App for MapscowMule {
                         let current_display_name = match self.gui_state.selected_style.as_str() {
                             "google-maps" => "Google Maps".to_string(),
                             "osm-default" => "OSM Default".to_string(),
+                            COLORBLIND_STYLE => "Color-blind Safe".to_string(),
                             name => name.replace('-', " ").replace('_', " ")
                                 .split_whitespace()
                                 .map(|word| {
@@ -310,6 +750,31 @@ impl eframe::App for MapscowMule {
                                 }
                             });
                     });
+                    ui.separator();
+                    
+                    // Color adjustment, redrawn live while dragging
+                    let transform = &mut self.gui_state.color_transform;
+                    let mut recolor = false;
+                    ui.label("Color adjustment:");
+                    ui.horizontal(|ui| {
+                        ui.label("Brightness:");
+                        recolor |= ui.add(egui::Slider::new(&mut transform.brightness, 0.1..=1.5)).changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Saturation:");
+                        recolor |= ui.add(egui::Slider::new(&mut transform.saturation, 0.0..=2.0)).changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Hue shift:");
+                        recolor |= ui.add(egui::Slider::new(&mut transform.hue_shift, -180.0..=180.0).suffix("°")).changed();
+                    });
+                    if ui.add_enabled(!transform.is_identity(), egui::Button::new("Reset Colors")).clicked() {
+                        *transform = ColorTransform::IDENTITY;
+                        recolor = true;
+                    }
+                    if recolor {
+                        self.apply_color_transform();
+                    }
                 });
                 
                 ui.menu_button("Help", |ui| {
@@ -336,33 +801,20 @@ impl eframe::App for MapscowMule {
                 ToolbarAction::FitToWindow => {
                     self.map_view.zoom_to_fit(&self.map_data);
                 }
+                ToolbarAction::SetZoom(zoom) => {
+                    self.map_view.set_zoom_level(zoom);
+                }
                 ToolbarAction::ExportSvg => {
-                    if let Some(path) = crate::utils::file_dialog::FileDialog::save_file("Export as SVG", "map.svg", &[crate::utils::file_dialog::FileFilters::SVG]) {
-                        let options = crate::export::ExportOptions::new(crate::export::ExportFormat::Svg, path.to_string_lossy().to_string());
-                        if let Err(e) = self.export_map(crate::export::ExportFormat::Svg, options) {
-                            self.status_message = format!("Export failed: {}", e);
-                        } else {
-                            self.status_message = format!("Exported SVG to: {}", path.display());
-                        }
-                    }
+                    self.export_dialog.open(ExportFormat::Svg, self.map_view.viewport_size());
                 }
                 ToolbarAction::ExportPng => {
-                    if let Some(path) = crate::utils::file_dialog::FileDialog::save_file("Export as PNG", "map.png", &[crate::utils::file_dialog::FileFilters::PNG]) {
-                        let options = crate::export::ExportOptions::new(crate::export::ExportFormat::Png, path.to_string_lossy().to_string());
-                        if let Err(e) = self.export_map(crate::export::ExportFormat::Png, options) {
-                            self.status_message = format!("Export failed: {}", e);
-                        } else {
-                            self.status_message = format!("Exported PNG to: {}", path.display());
-                        }
-                    }
+                    self.export_dialog.open(ExportFormat::Png, self.map_view.viewport_size());
                 }
                 ToolbarAction::ExportPdf => {
                     if let Some(path) = crate::utils::file_dialog::FileDialog::save_file("Export as PDF", "map.pdf", &[crate::utils::file_dialog::FileFilters::PDF]) {
                         let options = crate::export::ExportOptions::new(crate::export::ExportFormat::Pdf, path.to_string_lossy().to_string());
-                        if let Err(e) = self.export_map(crate::export::ExportFormat::Pdf, options) {
+                        if let Err(e) = self.export_map(crate::export::ExportFormat::Pdf, options, None) {
                             self.status_message = format!("Export failed: {}", e);
-                        } else {
-                            self.status_message = format!("Exported PDF to: {}", path.display());
                         }
                     }
                 }
@@ -387,6 +839,10 @@ impl eframe::App for MapscowMule {
                             // Show selected element info in status bar
                             ui.separator();
                             ui.colored_label(egui::Color32::YELLOW, "Selected:");
+                            let selection = self.map_view.selection();
+                            if selection.len() > 1 {
+                                ui.label(format!("{} features, last", selection.len()));
+                            }
                             ui.label(format!("{} #{}", 
                                 match selected_element.element_type {
                                     crate::gui::map_view::ElementType::Way => "Way",
@@ -412,6 +868,50 @@ impl eframe::App for MapscowMule {
                             if ui.small_button("📋 Copy").clicked() {
                                 ui.output_mut(|o| o.copied_text = selected_element.style_info.toml_section.clone());
                             }
+                            
+                            // One-off colour for the selected elements only, over their shared style;
+                            // the picker starts from the colour the last one picked is drawn in
+                            if let Some(ref mut map_data) = self.map_data {
+                                let element_type: crate::core::ElementType = (&selected_element.element_type).into();
+                                let current = self.map_view.drawn_color(map_data, self.style_manager.get_current_style(), element_type, selected_element.element_id)
+                                    .unwrap_or(egui::Color32::BLACK);
+                                let mut rgb = [current.r(), current.g(), current.b()];
+                                
+                                ui.separator();
+                                ui.label("Override:");
+                                if ui.color_edit_button_srgb(&mut rgb).changed() {
+                                    let color = crate::parsers::stylesheet::Color::new(rgb[0], rgb[1], rgb[2], 255);
+                                    for &(element_type, id) in &selection {
+                                        let mut style = map_data.get_style_override(element_type, id).cloned().unwrap_or_default();
+                                        style.line_color = Some(color);
+                                        style.fill_color = Some(color);
+                                        map_data.set_style_override(element_type, id, style);
+                                    }
+                                }
+                                let overridden = selection.iter().any(|&(element_type, id)| map_data.get_style_override(element_type, id).is_some());
+                                if overridden && ui.small_button("↺ Reset").clicked() {
+                                    for &(element_type, id) in &selection {
+                                        map_data.clear_style_override(element_type, id);
+                                    }
+                                }
+                            }
+                            
+                            // Basic data fixes on the selection
+                            ui.separator();
+                            let ways: Vec<i64> = selection.iter()
+                                .filter(|(element_type, _)| *element_type == ElementType::Way)
+                                .map(|&(_, id)| id)
+                                .collect();
+                            if !ways.is_empty() && ui.small_button("⇄ Reverse").on_hover_text("Reverse the direction of the selected ways").clicked() {
+                                self.edit_map_data(|data| {
+                                    for id in ways {
+                                        data.reverse_way(id);
+                                    }
+                                });
+                            }
+                            if ui.small_button("🗑 Delete").on_hover_text("Delete the selected nodes and ways (Del)").clicked() {
+                                self.delete_selection();
+                            }
                         } else {
                             ui.label("Click on map elements to select and edit their style");
                         }
@@ -429,10 +929,21 @@ impl eframe::App for MapscowMule {
                     ui.separator();
                     ui.label(&self.map_status);
                 }
-                if self.is_loading {
+                if self.map_data.is_some() {
+                    ui.separator();
+                    ScaleBar::new(self.map_view.pixels_per_meter(), 100.0)
+                        .with_units(self.gui_state.units)
+                        .show(ui);
+                }
+                if let Some((_, job)) = &self.osm_load {
                     ui.separator();
                     ui.spinner();
-                    ui.add(egui::ProgressBar::new(self.progress).show_percentage());
+                    ui.add(egui::ProgressBar::new(job.progress()).show_percentage());
+                }
+                if self.export_job.is_some() {
+                    ui.separator();
+                    ui.spinner();
+                    ui.label("Exporting...");
                 }
             });
         });
@@ -456,10 +967,19 @@ impl eframe::App for MapscowMule {
                 if ui.input(|i| i.key_pressed(egui::Key::C)) {
                     self.map_view.clear_selection();
                 }
+                if !ctx.wants_keyboard_input() && ui.input(|i| i.key_pressed(egui::Key::Delete)) {
+                    self.delete_selection();
+                }
+            }
+            
+            if let Some((id, lat, lon)) = self.map_view.take_node_move() {
+                self.edit_map_data(|data| {
+                    data.move_node(id, lat, lon);
+                });
             }
             
             // Update map status information
-            self.map_status = self.map_view.get_status_info(hover_pos, response.rect, &self.map_data);
+            self.map_status = self.map_view.get_status_info(hover_pos, response.rect, &self.map_data, self.gui_state.units, self.gui_state.coordinate_format);
         });
         
         // Modal dialogs
@@ -473,15 +993,33 @@ impl eframe::App for MapscowMule {
         
         // Layers Panel (floating window)
         self.layers_panel.show(ctx, &mut self.gui_state);
+        self.gpx_panel.show(ctx, &mut self.gui_state, &self.gpx_tracks);
+        self.tag_panel.show(ctx, &mut self.gui_state, self.map_data.as_ref());
+        
+        // Export options, asked before anything is written
+        let selection = self.map_view.selection();
+        if let Some(options) = self.export_dialog.show(ctx, &mut self.gui_state, self.style_manager.get_current_style(), selection.len()) {
+            let only = self.export_dialog.selected_only().then_some(selection.as_slice());
+            let outline = self.export_dialog.clip_to_selection()
+                .then(|| self.map_data.as_ref().and_then(|data| selection_outline(data, &selection)));
+            let result = match outline {
+                Some(None) => Err(anyhow::anyhow!("the selection has no closed outline to clip to")),
+                Some(Some(ring)) => self.export_map(options.format, options.with_clip_polygon(Some(ring)), only),
+                None => self.export_map(options.format, options, only),
+            };
+            if let Err(e) = result {
+                self.status_message = format!("Export failed: {}", e);
+            }
+        }
         
         // Geocoding Panel (floating window)
-        let geocoding_action = self.geocoding_panel.show(ctx, &mut self.gui_state);
+        let geocoding_action = self.geocoding_panel.show(ctx, &mut self.gui_state, self.name_index.as_ref());
         
         // Handle geocoding actions
         match geocoding_action {
             GeocodingAction::CenterOnLocation(lat, lon) => {
-                // Center the map on the selected location with a reasonable zoom
-                self.map_view.center_on_coordinates_with_zoom(lat, lon, 50000.0);
+                // Center the map on the selected location at street level
+                self.map_view.center_on_coordinates_with_zoom(lat, lon, 16.0);
                 self.status_message = format!("Centered on location: {:.6}, {:.6}", lat, lon);
             }
             GeocodingAction::None => {}
@@ -508,4 +1046,8 @@ impl eframe::App for MapscowMule {
                 });
         }
     }
+    
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_session();
+    }
 }
@@ -1,5 +1,77 @@
 use geo_types::{Coord, Point};
 use nalgebra::Matrix3;
+use serde::{Deserialize, Serialize};
+
+/// Narrowest map extent in degrees that is fitted to a view, about 100 m. Fitting a single
+/// point or a perfectly vertical or horizontal way would otherwise divide by zero.
+pub const MIN_MAP_EXTENT: f64 = 0.001;
+
+/// Longest mitre of an offset line's corner, as a multiple of the offset, so sharp turns
+/// don't shoot out spikes
+const MAX_MITER: f64 = 4.0;
+
+/// Space left around data fitted to a view, so features and labels at its edges stay whole
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FitPadding {
+    /// Pixels on every side
+    Pixels(f64),
+    /// Percent of the width or height on every side
+    Percent(f64),
+}
+
+impl FitPadding {
+    /// Length left for the data out of `length` pixels
+    pub fn inner(&self, length: f64) -> f64 {
+        let inner = match *self {
+            FitPadding::Pixels(pixels) => length - 2.0 * pixels,
+            FitPadding::Percent(percent) => length * (1.0 - percent / 50.0),
+        };
+        // Padding larger than the view still leaves something to draw into
+        inner.max(length * 0.1)
+    }
+    
+    /// Length that leaves `inner` pixels for the data once padded
+    pub fn outer(&self, inner: f64) -> f64 {
+        match *self {
+            FitPadding::Pixels(pixels) => inner + 2.0 * pixels,
+            FitPadding::Percent(percent) => inner / (1.0 - percent / 50.0).max(0.1),
+        }
+    }
+}
+
+impl Default for FitPadding {
+    fn default() -> Self {
+        FitPadding::Percent(5.0)
+    }
+}
+
+impl std::fmt::Display for FitPadding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FitPadding::Pixels(pixels) => write!(f, "{}px", pixels),
+            FitPadding::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+impl std::str::FromStr for FitPadding {
+    type Err = anyhow::Error;
+    
+    /// Parse `24`, `24px` or `5%`
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        let (number, percent) = match s.strip_suffix('%') {
+            Some(number) => (number, true),
+            None => (s.strip_suffix("px").unwrap_or(s), false),
+        };
+        let value = number.trim().parse::<f64>().map_err(|_| anyhow::anyhow!("'{}' is not a padding like 24px or 5%", s))?;
+        if value.is_nan() || value < 0.0 || (percent && value >= 50.0) {
+            return Err(anyhow::anyhow!("padding '{}' is out of range", s));
+        }
+        Ok(if percent { FitPadding::Percent(value) } else { FitPadding::Pixels(value) })
+    }
+}
 
 /// Geometry utilities for map processing
 pub struct GeometryUtils;
@@ -19,6 +91,46 @@ impl GeometryUtils {
         6371000.0 * c // Earth's radius in meters
     }
     
+    /// Split segments of `coords` (x = lon, y = lat) longer than `max_segment_length` meters
+    /// into pieces along the great circle between their ends, so that long straight segments
+    /// curve on the projected map as they do on the globe. The original vertices are kept.
+    pub fn densify_great_circle(coords: &[Coord<f64>], max_segment_length: f64) -> Vec<Coord<f64>> {
+        let Some(&first) = coords.first() else {
+            return Vec::new();
+        };
+        let mut result = vec![first];
+        for pair in coords.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            let distance = Self::haversine_distance(&start.into(), &end.into());
+            let angle = distance / 6371000.0;
+            if max_segment_length > 0.0 && distance > max_segment_length && angle.sin().abs() > f64::EPSILON {
+                let pieces = (distance / max_segment_length).ceil() as usize;
+                let (lat1, lon1) = (start.y.to_radians(), start.x.to_radians());
+                let (lat2, lon2) = (end.y.to_radians(), end.x.to_radians());
+                for i in 1..pieces {
+                    let f = i as f64 / pieces as f64;
+                    let a = ((1.0 - f) * angle).sin() / angle.sin();
+                    let b = (f * angle).sin() / angle.sin();
+                    let x = a * lat1.cos() * lon1.cos() + b * lat2.cos() * lon2.cos();
+                    let y = a * lat1.cos() * lon1.sin() + b * lat2.cos() * lon2.sin();
+                    let z = a * lat1.sin() + b * lat2.sin();
+                    let lat = z.atan2((x * x + y * y).sqrt()).to_degrees();
+                    // Keep longitudes continuous with the segment start across the antimeridian
+                    let mut lon = y.atan2(x).to_degrees();
+                    while lon - start.x > 180.0 {
+                        lon -= 360.0;
+                    }
+                    while lon - start.x < -180.0 {
+                        lon += 360.0;
+                    }
+                    result.push(Coord { x: lon, y: lat });
+                }
+            }
+            result.push(end);
+        }
+        result
+    }
+    
     /// Simplify a line using the Douglas-Peucker algorithm
     pub fn simplify_line(points: &[Coord<f64>], tolerance: f64) -> Vec<Coord<f64>> {
         if points.len() <= 2 {
@@ -28,8 +140,106 @@ impl GeometryUtils {
         douglas_peucker(points, tolerance)
     }
     
+    /// Indices of the points the Douglas-Peucker algorithm keeps, for callers that carry
+    /// data alongside the coordinates (elevation, timestamps)
+    pub fn simplify_line_indices(points: &[Coord<f64>], tolerance: f64) -> Vec<usize> {
+        if points.len() <= 2 {
+            return (0..points.len()).collect();
+        }
+        
+        let mut indices = douglas_peucker_indices(points, tolerance, 0);
+        indices.push(points.len() - 1);
+        indices
+    }
+    
+    /// Shift a line `distance` sideways: to the right of its direction in a y-down plane such
+    /// as the screen, to the left in a y-up one. Corners are mitred, cut short at
+    /// `MAX_MITER` times the distance. A line closing on its first point stays closed.
+    pub fn offset_line(points: &[Coord<f64>], distance: f64) -> Vec<Coord<f64>> {
+        if distance == 0.0 || points.len() < 2 {
+            return points.to_vec();
+        }
+        let closed = points.len() > 2 && points.first() == points.last();
+        // Right-hand unit normal of each segment in a y-down plane, none for repeated points
+        let normals: Vec<Option<Coord<f64>>> = points.windows(2)
+            .map(|pair| {
+                let (dx, dy) = (pair[1].x - pair[0].x, pair[1].y - pair[0].y);
+                let length = dx.hypot(dy);
+                (length > 0.0).then(|| Coord { x: -dy / length, y: dx / length })
+            })
+            .collect();
+        let wrap_before = closed.then(|| normals.iter().rev().find_map(|normal| *normal)).flatten();
+        let wrap_after = closed.then(|| normals.iter().find_map(|normal| *normal)).flatten();
+        
+        let mut result = Vec::with_capacity(points.len());
+        for (i, point) in points.iter().enumerate() {
+            let before = normals[..i].iter().rev().find_map(|normal| *normal).or(wrap_before);
+            let after = normals[i..].iter().find_map(|normal| *normal).or(wrap_after);
+            let (before, after) = match (before, after) {
+                (Some(before), Some(after)) => (before, after),
+                (Some(normal), None) | (None, Some(normal)) => (normal, normal),
+                (None, None) => return points.to_vec(),
+            };
+            let (x, y) = (before.x + after.x, before.y + after.y);
+            let length = x.hypot(y);
+            // A line doubling back on itself has no mitre; keep to the incoming side
+            let (miter, scale) = if length < 1e-9 {
+                (before, 1.0)
+            } else {
+                let miter = Coord { x: x / length, y: y / length };
+                (miter, (1.0 / (miter.x * before.x + miter.y * before.y)).min(MAX_MITER))
+            };
+            result.push(Coord {
+                x: point.x + miter.x * distance * scale,
+                y: point.y + miter.y * distance * scale,
+            });
+        }
+        result
+    }
+    
+    /// Drop the corners of a ring that lie within `tolerance` of the straight line between
+    /// their neighbours, e.g. the extra vertices along the wall of a traced building.
+    /// A closing point equal to the first is kept; rings never drop below a triangle.
+    pub fn simplify_ring(ring: &[Coord<f64>], tolerance: f64) -> Vec<Coord<f64>> {
+        let (mut points, closed) = open_ring(ring);
+        let mut changed = true;
+        while changed && points.len() > 3 {
+            changed = false;
+            let mut i = 0;
+            while i < points.len() && points.len() > 3 {
+                let n = points.len();
+                let (previous, next) = (points[(i + n - 1) % n], points[(i + 1) % n]);
+                if perpendicular_distance(&points[i], &previous, &next) < tolerance {
+                    points.remove(i);
+                    changed = true;
+                } else {
+                    i += 1;
+                }
+            }
+        }
+        close_ring(points, closed)
+    }
+    
+    /// Square off a ring whose walls all run within `threshold_degrees` of two perpendicular
+    /// directions, the ones of its longest wall. Walls are straightened to those directions
+    /// and the corners moved to where they meet. Rings that are not nearly rectilinear (round
+    /// towers, angled wings) come back unchanged.
+    pub fn rectangularize_ring(ring: &[Coord<f64>], threshold_degrees: f64) -> Vec<Coord<f64>> {
+        let (points, closed) = open_ring(ring);
+        match rectangularized(&points, threshold_degrees.to_radians()) {
+            Some(squared) => close_ring(squared, closed),
+            None => ring.to_vec(),
+        }
+    }
+    
     /// Calculate the area of a polygon in square meters
     pub fn polygon_area(coords: &[Coord<f64>]) -> f64 {
+        Self::signed_area(coords).abs()
+    }
+    
+    /// Area of a ring in its own units, positive when it runs counter-clockwise with y
+    /// pointing up (north for lon/lat) and negative when clockwise
+    pub fn signed_area(coords: &[Coord<f64>]) -> f64 {
         if coords.len() < 3 {
             return 0.0;
         }
@@ -43,7 +253,7 @@ impl GeometryUtils {
             area -= coords[j].x * coords[i].y;
         }
         
-        area.abs() / 2.0
+        area / 2.0
     }
     
     /// Check if a point is inside a polygon
@@ -65,6 +275,15 @@ impl GeometryUtils {
         inside
     }
     
+    /// Widen the range `min..max` around its middle to at least `min_extent`
+    pub fn with_min_extent(min: f64, max: f64, min_extent: f64) -> (f64, f64) {
+        if max - min >= min_extent {
+            return (min, max);
+        }
+        let middle = (min + max) / 2.0;
+        (middle - min_extent / 2.0, middle + min_extent / 2.0)
+    }
+    
     /// Calculate the bounding box of a set of coordinates
     pub fn bounding_box(coords: &[Coord<f64>]) -> Option<(Coord<f64>, Coord<f64>)> {
         if coords.is_empty() {
@@ -124,7 +343,24 @@ fn douglas_peucker(points: &[Coord<f64>], tolerance: f64) -> Vec<Coord<f64>> {
     }
 }
 
-/// Calculate perpendicular distance from a point to a line segment
+/// Douglas-Peucker over `points`, returning the kept indices (offset by `offset`) without the last one
+fn douglas_peucker_indices(points: &[Coord<f64>], tolerance: f64, offset: usize) -> Vec<usize> {
+    let end = points.len() - 1;
+    let farthest = (1..end)
+        .map(|i| (i, perpendicular_distance(&points[i], &points[0], &points[end])))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+    
+    match farthest {
+        Some((index, distance)) if distance > tolerance => {
+            let mut indices = douglas_peucker_indices(&points[..=index], tolerance, offset);
+            indices.extend(douglas_peucker_indices(&points[index..], tolerance, offset + index));
+            indices
+        }
+        _ => vec![offset],
+    }
+}
+
+/// Calculate perpendicular distance from a point to the line through a segment
 fn perpendicular_distance(point: &Coord<f64>, line_start: &Coord<f64>, line_end: &Coord<f64>) -> f64 {
     let dx = line_end.x - line_start.x;
     let dy = line_end.y - line_start.y;
@@ -143,6 +379,278 @@ fn perpendicular_distance(point: &Coord<f64>, line_start: &Coord<f64>, line_end:
     numerator / denominator
 }
 
+/// Axis-aligned rectangle features are clipped to, e.g. an export canvas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipRect {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+const OUT_LEFT: u8 = 1;
+const OUT_RIGHT: u8 = 2;
+const OUT_TOP: u8 = 4;
+const OUT_BOTTOM: u8 = 8;
+
+impl ClipRect {
+    pub fn new(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Self {
+        Self { min_x, min_y, max_x, max_y }
+    }
+    
+    /// The rectangle grown by `margin` on every side
+    pub fn expanded(&self, margin: f64) -> Self {
+        Self::new(self.min_x - margin, self.min_y - margin, self.max_x + margin, self.max_y + margin)
+    }
+    
+    pub fn contains(&self, point: &Coord<f64>) -> bool {
+        self.outcode(point) == 0
+    }
+    
+    /// Cohen-Sutherland region code: which sides of the rectangle the point lies beyond
+    fn outcode(&self, point: &Coord<f64>) -> u8 {
+        let mut code = 0;
+        if point.x < self.min_x {
+            code |= OUT_LEFT;
+        } else if point.x > self.max_x {
+            code |= OUT_RIGHT;
+        }
+        if point.y < self.min_y {
+            code |= OUT_TOP;
+        } else if point.y > self.max_y {
+            code |= OUT_BOTTOM;
+        }
+        code
+    }
+    
+    /// Cohen-Sutherland clipping of one segment; `None` if it lies entirely outside
+    pub fn clip_segment(&self, start: Coord<f64>, end: Coord<f64>) -> Option<(Coord<f64>, Coord<f64>)> {
+        let (mut start, mut end) = (start, end);
+        let (mut start_code, mut end_code) = (self.outcode(&start), self.outcode(&end));
+        
+        loop {
+            if start_code | end_code == 0 {
+                return Some((start, end));
+            }
+            if start_code & end_code != 0 {
+                return None;
+            }
+            
+            // Move the endpoint that is outside onto the edge it crosses
+            let code = if start_code != 0 { start_code } else { end_code };
+            let (dx, dy) = (end.x - start.x, end.y - start.y);
+            let point = if code & OUT_TOP != 0 {
+                Coord { x: start.x + dx * (self.min_y - start.y) / dy, y: self.min_y }
+            } else if code & OUT_BOTTOM != 0 {
+                Coord { x: start.x + dx * (self.max_y - start.y) / dy, y: self.max_y }
+            } else if code & OUT_LEFT != 0 {
+                Coord { x: self.min_x, y: start.y + dy * (self.min_x - start.x) / dx }
+            } else {
+                Coord { x: self.max_x, y: start.y + dy * (self.max_x - start.x) / dx }
+            };
+            
+            if code == start_code {
+                start = point;
+                start_code = self.outcode(&start);
+            } else {
+                end = point;
+                end_code = self.outcode(&end);
+            }
+        }
+    }
+    
+    /// Clip an open line, which may split into several visible pieces
+    pub fn clip_polyline(&self, points: &[Coord<f64>]) -> Vec<Vec<Coord<f64>>> {
+        let mut parts: Vec<Vec<Coord<f64>>> = Vec::new();
+        for pair in points.windows(2) {
+            let Some((start, end)) = self.clip_segment(pair[0], pair[1]) else {
+                continue;
+            };
+            // A segment continues the current piece unless clipping cut the line in between
+            match parts.last_mut() {
+                Some(part) if part.last() == Some(&start) => part.push(end),
+                _ => parts.push(vec![start, end]),
+            }
+        }
+        parts
+    }
+    
+    /// Sutherland-Hodgman clipping of a ring (without its repeated closing point). Parts of
+    /// the ring outside the rectangle are replaced by runs along its edges.
+    pub fn clip_polygon(&self, ring: &[Coord<f64>]) -> Vec<Coord<f64>> {
+        let at_x = |p: &Coord<f64>, q: &Coord<f64>, x: f64| Coord { x, y: p.y + (q.y - p.y) * (x - p.x) / (q.x - p.x) };
+        let at_y = |p: &Coord<f64>, q: &Coord<f64>, y: f64| Coord { x: p.x + (q.x - p.x) * (y - p.y) / (q.y - p.y), y };
+        
+        let ring = clip_ring_against(ring, |p| p.x >= self.min_x, |p, q| at_x(p, q, self.min_x));
+        let ring = clip_ring_against(&ring, |p| p.x <= self.max_x, |p, q| at_x(p, q, self.max_x));
+        let ring = clip_ring_against(&ring, |p| p.y >= self.min_y, |p, q| at_y(p, q, self.min_y));
+        clip_ring_against(&ring, |p| p.y <= self.max_y, |p, q| at_y(p, q, self.max_y))
+    }
+}
+
+/// One Sutherland-Hodgman pass: keep the part of the ring on the inside of a single edge
+fn clip_ring_against<I, X>(ring: &[Coord<f64>], inside: I, intersect: X) -> Vec<Coord<f64>>
+where
+    I: Fn(&Coord<f64>) -> bool,
+    X: Fn(&Coord<f64>, &Coord<f64>) -> Coord<f64>,
+{
+    let mut clipped = Vec::with_capacity(ring.len());
+    let Some(mut previous) = ring.last() else {
+        return clipped;
+    };
+    for current in ring {
+        match (inside(previous), inside(current)) {
+            (true, true) => clipped.push(*current),
+            (true, false) => clipped.push(intersect(previous, current)),
+            (false, true) => {
+                clipped.push(intersect(previous, current));
+                clipped.push(*current);
+            }
+            (false, false) => {}
+        }
+        previous = current;
+    }
+    clipped
+}
+
+/// Polygon features are clipped to, e.g. an administrative boundary. Lines are cut exactly
+/// where they cross it. Areas are clipped by Sutherland-Hodgman, which only holds for convex
+/// polygons; against a concave one they are kept whole, and the output must be masked with
+/// the polygon for a clean edge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipPolygon {
+    /// Without a repeated closing point
+    points: Vec<Coord<f64>>,
+    bounds: ClipRect,
+    /// +1.0 if the points run counter-clockwise with y pointing up, -1.0 if clockwise
+    orientation: f64,
+    convex: bool,
+}
+
+impl ClipPolygon {
+    /// `None` unless the points outline an area
+    pub fn new(points: &[Coord<f64>]) -> Option<Self> {
+        let (mut points, _) = open_ring(points);
+        points.dedup();
+        let area = GeometryUtils::signed_area(&points);
+        if points.len() < 3 || area == 0.0 || !area.is_finite() {
+            return None;
+        }
+        let orientation = area.signum();
+        
+        let n = points.len();
+        let convex = (0..n).all(|i| {
+            let (a, b, c) = (points[i], points[(i + 1) % n], points[(i + 2) % n]);
+            cross(&a, &b, &c) * orientation >= 0.0
+        });
+        let bounds = points.iter().fold(
+            ClipRect::new(f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            |bounds, p| ClipRect::new(bounds.min_x.min(p.x), bounds.min_y.min(p.y), bounds.max_x.max(p.x), bounds.max_y.max(p.y)),
+        );
+        Some(Self { points, bounds, orientation, convex })
+    }
+    
+    pub fn points(&self) -> &[Coord<f64>] {
+        &self.points
+    }
+    
+    pub fn bounds(&self) -> ClipRect {
+        self.bounds
+    }
+    
+    pub fn is_convex(&self) -> bool {
+        self.convex
+    }
+    
+    pub fn contains(&self, point: &Coord<f64>) -> bool {
+        self.bounds.contains(point) && GeometryUtils::point_in_polygon(point, &self.points)
+    }
+    
+    /// Clip an open line, which may split into several pieces inside the polygon
+    pub fn clip_polyline(&self, points: &[Coord<f64>]) -> Vec<Vec<Coord<f64>>> {
+        let n = self.points.len();
+        let mut parts: Vec<Vec<Coord<f64>>> = Vec::new();
+        for pair in points.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            if self.bounds.clip_segment(start, end).is_none() {
+                continue;
+            }
+            // Cut the segment where it crosses the outline, keeping the pieces inside
+            let mut cuts = vec![0.0, 1.0];
+            for i in 0..n {
+                if let Some(t) = segment_crossing(start, end, self.points[i], self.points[(i + 1) % n]) {
+                    cuts.push(t);
+                }
+            }
+            cuts.sort_by(|a, b| a.total_cmp(b));
+            let at = |t: f64| Coord { x: start.x + (end.x - start.x) * t, y: start.y + (end.y - start.y) * t };
+            for window in cuts.windows(2).filter(|window| window[1] > window[0]) {
+                if !self.contains(&at((window[0] + window[1]) / 2.0)) {
+                    continue;
+                }
+                let (from, to) = (at(window[0]), at(window[1]));
+                match parts.last_mut() {
+                    Some(part) if part.last() == Some(&from) => part.push(to),
+                    _ => parts.push(vec![from, to]),
+                }
+            }
+        }
+        parts
+    }
+    
+    /// Clip a ring (without its repeated closing point). Against a convex polygon the parts
+    /// outside are replaced by runs along its edges; against a concave one the ring is kept
+    /// whole if it can reach inside. Empty when nothing of it is inside.
+    pub fn clip_polygon(&self, ring: &[Coord<f64>]) -> Vec<Coord<f64>> {
+        if !self.convex {
+            let outside = ring.iter().all(|p| p.x < self.bounds.min_x) || ring.iter().all(|p| p.x > self.bounds.max_x)
+                || ring.iter().all(|p| p.y < self.bounds.min_y) || ring.iter().all(|p| p.y > self.bounds.max_y);
+            return if outside { Vec::new() } else { ring.to_vec() };
+        }
+        if ring.iter().all(|point| self.contains(point)) {
+            return ring.to_vec();
+        }
+        
+        let n = self.points.len();
+        let mut clipped = ring.to_vec();
+        for i in 0..n {
+            let (a, b) = (self.points[i], self.points[(i + 1) % n]);
+            clipped = clip_ring_against(
+                &clipped,
+                |p| cross(&a, &b, p) * self.orientation >= 0.0,
+                |p, q| {
+                    let (side_p, side_q) = (cross(&a, &b, p), cross(&a, &b, q));
+                    let t = side_p / (side_p - side_q);
+                    Coord { x: p.x + (q.x - p.x) * t, y: p.y + (q.y - p.y) * t }
+                },
+            );
+            if clipped.is_empty() {
+                break;
+            }
+        }
+        clipped
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `c`: positive when `c` is to the left of
+/// `a` to `b` with y pointing up
+fn cross(a: &Coord<f64>, b: &Coord<f64>, c: &Coord<f64>) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+/// Where along `start` to `end`, from 0.0 to 1.0, it crosses the segment `a` to `b`
+fn segment_crossing(start: Coord<f64>, end: Coord<f64>, a: Coord<f64>, b: Coord<f64>) -> Option<f64> {
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let (ex, ey) = (b.x - a.x, b.y - a.y);
+    let denominator = dx * ey - dy * ex;
+    if denominator == 0.0 {
+        return None;
+    }
+    let t = ((a.x - start.x) * ey - (a.y - start.y) * ex) / denominator;
+    let u = ((a.x - start.x) * dy - (a.y - start.y) * dx) / denominator;
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then_some(t)
+}
+
 /// 2D transformation matrix for map projections and scaling
 #[derive(Debug, Clone, Copy)]
 pub struct Transform2D {
@@ -183,6 +691,13 @@ impl Transform2D {
         Self { matrix }
     }
     
+    /// Rotation by `angle_rad` around the point (cx, cy) instead of the origin
+    pub fn rotation_about(angle_rad: f64, cx: f64, cy: f64) -> Self {
+        Self::translation(cx, cy)
+            .compose(&Self::rotation(angle_rad))
+            .compose(&Self::translation(-cx, -cy))
+    }
+    
     pub fn compose(&self, other: &Transform2D) -> Self {
         Self {
             matrix: self.matrix * other.matrix,
@@ -202,4 +717,106 @@ impl Transform2D {
     pub fn transform_points(&self, points: &[Coord<f64>]) -> Vec<Coord<f64>> {
         points.iter().map(|p| self.transform_point(p)).collect()
     }
+    
+    /// The transform undoing this one; `None` when it flattens the plane (a zero scale)
+    pub fn inverse(&self) -> Option<Self> {
+        self.matrix.try_inverse().map(|matrix| Self { matrix })
+    }
+    
+    /// The point this transform maps onto `point`
+    pub fn inverse_transform_point(&self, point: &Coord<f64>) -> Option<Coord<f64>> {
+        self.inverse().map(|inverse| inverse.transform_point(point))
+    }
+}
+
+/// The distinct corners of a ring and whether it repeated its first point at the end
+fn open_ring(ring: &[Coord<f64>]) -> (Vec<Coord<f64>>, bool) {
+    match ring.split_last() {
+        Some((last, rest)) if ring.len() > 1 && *last == ring[0] => (rest.to_vec(), true),
+        _ => (ring.to_vec(), false),
+    }
+}
+
+fn close_ring(mut points: Vec<Coord<f64>>, closed: bool) -> Vec<Coord<f64>> {
+    if closed {
+        if let Some(&first) = points.first() {
+            points.push(first);
+        }
+    }
+    points
+}
+
+/// See [`GeometryUtils::rectangularize_ring`]; works on the open ring
+fn rectangularized(points: &[Coord<f64>], threshold: f64) -> Option<Vec<Coord<f64>>> {
+    use std::f64::consts::FRAC_PI_2;
+    
+    let n = points.len();
+    if n < 4 {
+        return None;
+    }
+    let edge = |i: usize| (points[i], points[(i + 1) % n]);
+    let length = |(a, b): (Coord<f64>, Coord<f64>)| (b.x - a.x).hypot(b.y - a.y);
+    
+    // Work in a frame turned so the longest wall is horizontal
+    let longest = (0..n).max_by(|&a, &b| length(edge(a)).total_cmp(&length(edge(b))))?;
+    let (a, b) = edge(longest);
+    let (sin, cos) = (b.y - a.y).atan2(b.x - a.x).sin_cos();
+    let to_frame = |p: Coord<f64>| Coord { x: p.x * cos + p.y * sin, y: -p.x * sin + p.y * cos };
+    let from_frame = |p: Coord<f64>| Coord { x: p.x * cos - p.y * sin, y: p.x * sin + p.y * cos };
+    let local: Vec<Coord<f64>> = points.iter().copied().map(to_frame).collect();
+    
+    // Each wall becomes a horizontal line (true, its y) or a vertical one (false, its x),
+    // averaged along runs of walls in the same direction weighted by their length
+    let mut walls: Vec<(bool, f64, f64)> = Vec::with_capacity(n);
+    for i in 0..n {
+        let (start, end) = (local[i], local[(i + 1) % n]);
+        let wall_length = (end.x - start.x).hypot(end.y - start.y);
+        if wall_length == 0.0 {
+            continue;
+        }
+        let angle = (end.y - start.y).atan2(end.x - start.x).rem_euclid(std::f64::consts::PI);
+        let horizontal = if angle.min(std::f64::consts::PI - angle) <= threshold {
+            true
+        } else if (angle - FRAC_PI_2).abs() <= threshold {
+            false
+        } else {
+            return None;
+        };
+        let position = if horizontal { (start.y + end.y) / 2.0 } else { (start.x + end.x) / 2.0 };
+        match walls.last_mut() {
+            Some((last_horizontal, sum, weight)) if *last_horizontal == horizontal => {
+                *sum += position * wall_length;
+                *weight += wall_length;
+            }
+            _ => walls.push((horizontal, position * wall_length, wall_length)),
+        }
+    }
+    // The last run may continue into the first
+    if walls.len() > 1 && walls[0].0 == walls[walls.len() - 1].0 {
+        let (_, sum, weight) = walls.pop()?;
+        walls[0].1 += sum;
+        walls[0].2 += weight;
+    }
+    if walls.len() < 4 || !walls.len().is_multiple_of(2) {
+        return None;
+    }
+    
+    // Corner i is where wall i - 1 meets wall i; the walls alternate direction
+    let squared: Vec<Coord<f64>> = (0..walls.len())
+        .map(|i| {
+            let (previous_horizontal, previous_sum, previous_weight) = walls[(i + walls.len() - 1) % walls.len()];
+            let (_, sum, weight) = walls[i];
+            let (previous, current) = (previous_sum / previous_weight, sum / weight);
+            let corner = if previous_horizontal {
+                Coord { x: current, y: previous }
+            } else {
+                Coord { x: previous, y: current }
+            };
+            from_frame(corner)
+        })
+        .collect();
+    
+    // Squaring a sliver can fold it over itself; keep the original rather than a mangled outline
+    let (before, after) = (GeometryUtils::polygon_area(points), GeometryUtils::polygon_area(&squared));
+    (after > before * 0.5 && after < before * 2.0).then_some(squared)
 }
@@ -1,8 +1,66 @@
 pub mod geometry;
 pub mod projection;
+pub mod road_graph;
 
+use crate::core::geometry::GeometryUtils;
+use crate::core::projection::METERS_PER_DEGREE;
+use crate::core::road_graph::RoadGraph;
+use crate::parsers::stylesheet::{Color, DrawMode, RenderStyle};
+use anyhow::Result;
 use geo_types::{Coord, LineString, Polygon};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Per-element style overrides, merged onto the element's stylesheet rule
+pub type StyleOverrides = HashMap<(ElementType, i64), StyleOverride>;
+
+/// The parts of an element's style changed by hand; whatever is left `None` comes from its rule
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StyleOverride {
+    #[serde(default)]
+    pub line_color: Option<Color>,
+    #[serde(default)]
+    pub fill_color: Option<Color>,
+    /// Line width in pixels
+    #[serde(default)]
+    pub line_width: Option<f32>,
+}
+
+impl StyleOverride {
+    /// The colour an element is drawn in: the fill of an area, else its line
+    pub fn color(&self, is_area: bool) -> Option<Color> {
+        if is_area {
+            self.fill_color.or(self.line_color)
+        } else {
+            self.line_color.or(self.fill_color)
+        }
+    }
+
+    /// `style` with the overridden parts replaced. A fill colour only shows on styles that fill.
+    pub fn apply_to(&self, mut style: RenderStyle) -> RenderStyle {
+        if let Some(color) = self.line_color {
+            style.line_color = Some(color);
+        }
+        if let Some(color) = self.fill_color.filter(|_| !matches!(style.draw_mode, DrawMode::Line)) {
+            style.fill_color = Some(color);
+        }
+        if let Some(width) = self.line_width {
+            style.line_width = width;
+        }
+        style
+    }
+}
+
+/// Ways missing more than this fraction of their nodes are treated as incomplete and not drawn
+pub const MAX_MISSING_NODE_FRACTION: f64 = 0.5;
+
+/// Source of `MapData` revisions, shared by all datasets so a replaced dataset never repeats one
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(0);
+
+/// Area ways whose ends are at most this many meters apart are closed by `MapData::validate_and_repair`
+pub const RING_CLOSE_TOLERANCE: f64 = 1.0;
 
 /// Represents a complete map dataset with all geographic features
 #[derive(Debug, Clone)]
@@ -12,6 +70,49 @@ pub struct MapData {
     pub ways: HashMap<i64, Way>,
     pub relations: HashMap<i64, Relation>,
     pub gpx_tracks: Vec<GpxTrack>,
+    pub style_overrides: StyleOverrides,
+    /// Ways whose node references could not be resolved well enough to draw
+    pub incomplete_ways: HashSet<i64>,
+    /// Admin level of the most important administrative boundary relation each member way
+    /// belongs to; boundaries are mostly mapped as relations over untagged ways
+    pub boundary_levels: HashMap<i64, String>,
+    /// Changed by every edit made through the methods below, see [`MapData::revision`]
+    revision: u64,
+}
+
+/// Dangling node references found by `MapData::resolve_missing_nodes`
+#[derive(Debug, Clone, Default)]
+pub struct MissingNodeReport {
+    pub total_references: usize,
+    pub missing_references: usize,
+    pub affected_ways: usize,
+    pub incomplete_ways: usize,
+}
+
+/// What `MapData::validate_and_repair` fixed and what it had to leave alone
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    /// Area ways whose nearly touching ends were joined
+    pub rings_closed: usize,
+    /// Rings turned round so outer rings run counter-clockwise and inner rings clockwise
+    pub rings_reversed: usize,
+    pub unrepairable: Vec<GeometryIssue>,
+}
+
+/// An area `MapData::validate_and_repair` could not fix, and why
+#[derive(Debug, Clone)]
+pub struct GeometryIssue {
+    pub element_type: ElementType,
+    pub id: i64,
+    pub reason: String,
+}
+
+/// Serialized form of a single style override
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StyleOverrideEntry {
+    element_type: ElementType,
+    id: i64,
+    style: StyleOverride,
 }
 
 /// Geographic bounds of the map area
@@ -56,7 +157,7 @@ pub struct RelationMember {
     pub role: String,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ElementType {
     Node,
     Way,
@@ -83,14 +184,186 @@ pub struct GpxPoint {
     pub time: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-/// Projection system for coordinate transformations
+/// Summary figures for a GPX track or segment. Elevation and time figures are `None` when
+/// the recording has no elevations or timestamps to compute them from.
 #[derive(Debug, Clone, Copy)]
+pub struct GpxStats {
+    /// Meters along the ground
+    pub distance: f64,
+    pub elevation_gain: Option<f64>,
+    pub elevation_loss: Option<f64>,
+    pub duration: Option<chrono::Duration>,
+}
+
+impl GpxStats {
+    /// Meters per second over the recorded duration
+    pub fn average_speed(&self) -> Option<f64> {
+        let seconds = self.duration?.num_milliseconds() as f64 / 1000.0;
+        (seconds > 0.0).then(|| self.distance / seconds)
+    }
+}
+
+/// Sum of the values that are present, `None` if none are
+fn sum_present(values: impl Iterator<Item = Option<f64>>) -> Option<f64> {
+    values.flatten().fold(None, |total, value| Some(total.unwrap_or(0.0) + value))
+}
+
+impl GpxTrack {
+    pub fn total_distance(&self) -> f64 {
+        self.segments.iter().map(GpxSegment::total_distance).sum()
+    }
+
+    pub fn elevation_gain(&self) -> Option<f64> {
+        sum_present(self.segments.iter().map(GpxSegment::elevation_gain))
+    }
+
+    pub fn elevation_loss(&self) -> Option<f64> {
+        sum_present(self.segments.iter().map(GpxSegment::elevation_loss))
+    }
+
+    /// Recorded time summed over the segments, so pauses between segments do not count
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        self.segments.iter().filter_map(GpxSegment::duration).reduce(|total, duration| total + duration)
+    }
+
+    pub fn stats(&self) -> GpxStats {
+        GpxStats {
+            distance: self.total_distance(),
+            elevation_gain: self.elevation_gain(),
+            elevation_loss: self.elevation_loss(),
+            duration: self.duration(),
+        }
+    }
+
+    /// The track with every segment smoothed, see [`GpxSegment::smoothed`]
+    pub fn smoothed(&self, window: usize) -> Self {
+        Self {
+            name: self.name.clone(),
+            segments: self.segments.iter().map(|segment| segment.smoothed(window)).collect(),
+        }
+    }
+
+    /// The track with every segment simplified, see [`GpxSegment::simplified`]
+    pub fn simplified(&self, tolerance: f64) -> Self {
+        Self {
+            name: self.name.clone(),
+            segments: self.segments.iter().map(|segment| segment.simplified(tolerance)).collect(),
+        }
+    }
+}
+
+impl GpxSegment {
+    /// Haversine length of the segment in meters
+    pub fn total_distance(&self) -> f64 {
+        self.points.windows(2)
+            .map(|pair| geometry::GeometryUtils::haversine_distance(
+                &geo_types::Point::new(pair[0].lon, pair[0].lat),
+                &geo_types::Point::new(pair[1].lon, pair[1].lat),
+            ))
+            .sum()
+    }
+
+    /// Total climb in meters, over consecutive points that both have an elevation
+    pub fn elevation_gain(&self) -> Option<f64> {
+        sum_present(self.elevation_changes().map(|change| change.map(|change| change.max(0.0))))
+    }
+
+    /// Total descent in meters, as a positive number
+    pub fn elevation_loss(&self) -> Option<f64> {
+        sum_present(self.elevation_changes().map(|change| change.map(|change| (-change).max(0.0))))
+    }
+
+    fn elevation_changes(&self) -> impl Iterator<Item = Option<f64>> + '_ {
+        self.points.windows(2).map(|pair| Some(pair[1].elevation? - pair[0].elevation?))
+    }
+
+    /// Time from the first to the last timestamped point
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        let mut times = self.points.iter().filter_map(|point| point.time);
+        let first = times.next()?;
+        let last = times.next_back()?;
+        Some(last - first)
+    }
+
+    pub fn stats(&self) -> GpxStats {
+        GpxStats {
+            distance: self.total_distance(),
+            elevation_gain: self.elevation_gain(),
+            elevation_loss: self.elevation_loss(),
+            duration: self.duration(),
+        }
+    }
+
+    /// Moving average of the positions over `window` points centred on each one, to take
+    /// the jitter out of phone recordings. Elevations are averaged the same way and each
+    /// point keeps its timestamp; the first and last points stay where they are.
+    pub fn smoothed(&self, window: usize) -> Self {
+        let half = window / 2;
+        if half == 0 || self.points.len() < 3 {
+            return self.clone();
+        }
+
+        let last = self.points.len() - 1;
+        let points = self.points.iter().enumerate().map(|(i, point)| {
+            if i == 0 || i == last {
+                return point.clone();
+            }
+            let neighbours = &self.points[i.saturating_sub(half)..=(i + half).min(last)];
+            let count = neighbours.len() as f64;
+            let elevations: Vec<f64> = neighbours.iter().filter_map(|p| p.elevation).collect();
+            GpxPoint {
+                lat: neighbours.iter().map(|p| p.lat).sum::<f64>() / count,
+                lon: neighbours.iter().map(|p| p.lon).sum::<f64>() / count,
+                elevation: (!elevations.is_empty()).then(|| elevations.iter().sum::<f64>() / elevations.len() as f64),
+                time: point.time,
+            }
+        });
+        Self { points: points.collect() }
+    }
+
+    /// Douglas-Peucker simplification with `tolerance` in meters. The points that are kept
+    /// are original ones, with their elevation and timestamp.
+    pub fn simplified(&self, tolerance: f64) -> Self {
+        if tolerance <= 0.0 || self.points.len() < 3 {
+            return self.clone();
+        }
+
+        // Local equirectangular projection in meters around the segment's first point
+        let lon_scale = projection::METERS_PER_DEGREE * self.points[0].lat.to_radians().cos();
+        let coords: Vec<Coord<f64>> = self.points.iter()
+            .map(|p| Coord { x: p.lon * lon_scale, y: p.lat * projection::METERS_PER_DEGREE })
+            .collect();
+        let points = geometry::GeometryUtils::simplify_line_indices(&coords, tolerance)
+            .into_iter()
+            .map(|i| self.points[i].clone())
+            .collect();
+        Self { points }
+    }
+}
+
+/// Projection system for coordinate transformations, used through
+/// [`projection::project`] and [`projection::unproject`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum ProjectionSystem {
+    /// Spherical Mercator of web maps (EPSG:3857), in meters
     WebMercator,
+    /// Universal Transverse Mercator zone 1-60 of either hemisphere, in meters
     Utm { zone: u8, north: bool },
+    /// Longitude and latitude as x and y, in degrees
+    #[default]
     LatLon,
 }
 
+impl ProjectionSystem {
+    /// The UTM zone a point falls in
+    pub fn utm_for(lat: f64, lon: f64) -> Self {
+        ProjectionSystem::Utm {
+            zone: projection::CoordinateUtils::utm_zone_from_lon(lon).min(60),
+            north: projection::CoordinateUtils::is_northern_hemisphere(lat),
+        }
+    }
+}
+
 impl MapData {
     pub fn new() -> Self {
         Self {
@@ -104,15 +377,41 @@ impl MapData {
             ways: HashMap::new(),
             relations: HashMap::new(),
             gpx_tracks: Vec::new(),
+            style_overrides: HashMap::new(),
+            incomplete_ways: HashSet::new(),
+            boundary_levels: HashMap::new(),
+            revision: NEXT_REVISION.fetch_add(1, Ordering::Relaxed),
         }
     }
     
+    /// Changes whenever nodes or ways are added, moved, reversed, repaired or deleted through
+    /// `MapData`'s methods, so anything derived from them can be cached until then. Editing
+    /// `nodes` or `ways` directly does not change it.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn touch(&mut self) {
+        self.revision = NEXT_REVISION.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn add_node(&mut self, node: Node) {
+        self.touch();
         self.update_bounds(node.lat, node.lon);
         self.nodes.insert(node.id, node);
     }
     
     pub fn add_way(&mut self, way: Way) {
+        self.touch();
+        // Ways can be added after their nodes were inserted directly into `nodes`,
+        // so make sure every resolvable node is inside the bounds
+        for node_id in &way.nodes {
+            if let Some(node) = self.nodes.get(node_id) {
+                let (lat, lon) = (node.lat, node.lon);
+                self.update_bounds(lat, lon);
+            }
+        }
+
         // Prevent overwriting ways with better data
         if let Some(existing) = self.ways.get(&way.id) {
             // Only replace if new way has more tags or same number of tags but more nodes
@@ -127,6 +426,20 @@ impl MapData {
     }
     
     pub fn add_relation(&mut self, relation: Relation) {
+        if relation.tags.get("boundary").map(|s| s.as_str()) == Some("administrative") {
+            if let Some(level) = relation.tags.get("admin_level") {
+                let rank = |level: &str| level.trim().parse::<u8>().unwrap_or(u8::MAX);
+                for member in relation.members.iter().filter(|member| member.element_type == ElementType::Way) {
+                    self.boundary_levels.entry(member.id)
+                        .and_modify(|existing| {
+                            if rank(level) < rank(existing) {
+                                *existing = level.clone();
+                            }
+                        })
+                        .or_insert_with(|| level.clone());
+                }
+            }
+        }
         self.relations.insert(relation.id, relation);
     }
     
@@ -140,31 +453,445 @@ impl MapData {
         self.gpx_tracks.push(track);
     }
     
+    /// Merge another dataset into this one. Elements from `other` replace existing
+    /// ones with the same id (ways keep the better version, as in `add_way`).
+    pub fn merge(&mut self, other: MapData) {
+        self.nodes.extend(other.nodes);
+        for way in other.ways.into_values() {
+            self.add_way(way);
+        }
+        for relation in other.relations.into_values() {
+            self.add_relation(relation);
+        }
+        self.gpx_tracks.extend(other.gpx_tracks);
+        self.style_overrides.extend(other.style_overrides);
+
+        self.recompute_bounds();
+        self.resolve_missing_nodes();
+    }
+
+    /// A copy holding only the given elements, with the nodes their ways use and the members
+    /// of given relations, e.g. to export a selection on its own. Style overrides and boundary
+    /// levels of the kept elements carry over; GPX tracks are left out.
+    pub fn subset(&self, elements: &[(ElementType, i64)]) -> MapData {
+        let mut subset = MapData::new();
+        let mut way_ids: Vec<i64> = Vec::new();
+        let mut node_ids: Vec<i64> = Vec::new();
+        for &(element_type, id) in elements {
+            match element_type {
+                ElementType::Node => node_ids.push(id),
+                ElementType::Way => way_ids.push(id),
+                ElementType::Relation => {
+                    let Some(relation) = self.relations.get(&id) else {
+                        continue;
+                    };
+                    for member in &relation.members {
+                        match member.element_type {
+                            ElementType::Node => node_ids.push(member.id),
+                            ElementType::Way => way_ids.push(member.id),
+                            ElementType::Relation => {}
+                        }
+                    }
+                    subset.relations.insert(id, relation.clone());
+                }
+            }
+        }
+        for way in way_ids.iter().filter_map(|id| self.ways.get(id)) {
+            node_ids.extend(&way.nodes);
+            subset.ways.insert(way.id, way.clone());
+            if let Some(level) = self.boundary_levels.get(&way.id) {
+                subset.boundary_levels.insert(way.id, level.clone());
+            }
+        }
+        for node in node_ids.iter().filter_map(|id| self.nodes.get(id)) {
+            subset.nodes.insert(node.id, node.clone());
+        }
+
+        let kept = |element_type: ElementType, id: i64| match element_type {
+            ElementType::Node => subset.nodes.contains_key(&id),
+            ElementType::Way => subset.ways.contains_key(&id),
+            ElementType::Relation => subset.relations.contains_key(&id),
+        };
+        let overrides: StyleOverrides = self.style_overrides.iter()
+            .filter(|((element_type, id), _)| kept(*element_type, *id))
+            .map(|(key, style)| (*key, style.clone()))
+            .collect();
+        subset.style_overrides = overrides;
+        subset.recompute_bounds();
+        subset.resolve_missing_nodes();
+        subset
+    }
+
+    /// Recalculate the bounds from scratch from all node and GPX point coordinates.
+    /// Use after editing `nodes` directly, since removals can shrink the bounds.
+    pub fn recompute_bounds(&mut self) {
+        self.bounds = MapBounds {
+            min_lat: f64::INFINITY,
+            max_lat: f64::NEG_INFINITY,
+            min_lon: f64::INFINITY,
+            max_lon: f64::NEG_INFINITY,
+        };
+
+        let node_coords = self.nodes.values().map(|node| (node.lat, node.lon));
+        let gpx_coords = self.gpx_tracks
+            .iter()
+            .flat_map(|track| track.segments.iter())
+            .flat_map(|segment| segment.points.iter())
+            .map(|point| (point.lat, point.lon));
+
+        let bounds = &mut self.bounds;
+        for (lat, lon) in node_coords.chain(gpx_coords) {
+            bounds.min_lat = bounds.min_lat.min(lat);
+            bounds.max_lat = bounds.max_lat.max(lat);
+            bounds.min_lon = bounds.min_lon.min(lon);
+            bounds.max_lon = bounds.max_lon.max(lon);
+        }
+    }
+
     fn update_bounds(&mut self, lat: f64, lon: f64) {
         self.bounds.min_lat = self.bounds.min_lat.min(lat);
         self.bounds.max_lat = self.bounds.max_lat.max(lat);
         self.bounds.min_lon = self.bounds.min_lon.min(lon);
         self.bounds.max_lon = self.bounds.max_lon.max(lon);
     }
-    
+
+    /// Whether a point lies on the edge of the bounds, so moving or removing it may shrink them
+    fn on_bounds_edge(&self, lat: f64, lon: f64) -> bool {
+        lat == self.bounds.min_lat || lat == self.bounds.max_lat || lon == self.bounds.min_lon || lon == self.bounds.max_lon
+    }
+
+    /// Reverse the node order of a way. Tags are kept as they are, so a `oneway` road or a
+    /// coastline ends up pointing the other way. `false` if there is no such way.
+    pub fn reverse_way(&mut self, id: i64) -> bool {
+        let Some(way) = self.ways.get_mut(&id) else {
+            return false;
+        };
+        way.nodes.reverse();
+        self.touch();
+        true
+    }
+
+    /// Move a node to a new position, growing or shrinking the bounds to match.
+    /// `false` if there is no such node.
+    pub fn move_node(&mut self, id: i64, lat: f64, lon: f64) -> bool {
+        let Some(node) = self.nodes.get_mut(&id) else {
+            return false;
+        };
+        let (old_lat, old_lon) = (node.lat, node.lon);
+        node.lat = lat;
+        node.lon = lon;
+        self.touch();
+        if self.on_bounds_edge(old_lat, old_lon) {
+            self.recompute_bounds();
+        } else {
+            self.update_bounds(lat, lon);
+        }
+        true
+    }
+
+    /// Remove a way together with its relation memberships, style override and boundary
+    /// level. Its untagged nodes go with it unless another way or relation still uses them.
+    pub fn delete_way(&mut self, id: i64) -> Option<Way> {
+        let way = self.ways.remove(&id)?;
+        self.touch();
+        self.forget_element(ElementType::Way, id);
+        self.incomplete_ways.remove(&id);
+        self.boundary_levels.remove(&id);
+
+        let still_used: HashSet<i64> = self.ways.values()
+            .flat_map(|way| way.node_refs())
+            .chain(self.relations.values()
+                .flat_map(|relation| &relation.members)
+                .filter(|member| member.element_type == ElementType::Node)
+                .map(|member| member.id))
+            .collect();
+        let mut shrink = false;
+        for node_id in &way.nodes {
+            let orphan = self.nodes.get(node_id).is_some_and(|node| node.tags.is_empty() && !still_used.contains(node_id));
+            if let Some(node) = orphan.then(|| self.nodes.remove(node_id)).flatten() {
+                self.style_overrides.remove(&(ElementType::Node, node.id));
+                shrink |= self.on_bounds_edge(node.lat, node.lon);
+            }
+        }
+        if shrink {
+            self.recompute_bounds();
+        }
+        Some(way)
+    }
+
+    /// Remove a node and every reference to it. Ways using it are joined across the gap and
+    /// closed ways stay closed. A ring left with fewer than three distinct nodes is opened
+    /// into a line, or deleted if it was an area; ways left with fewer than two distinct nodes
+    /// are deleted as in [`Self::delete_way`].
+    pub fn delete_node(&mut self, id: i64) -> Option<Node> {
+        let node = self.nodes.remove(&id)?;
+        self.touch();
+        self.forget_element(ElementType::Node, id);
+
+        let mut degenerate = Vec::new();
+        for way in self.ways.values_mut().filter(|way| way.nodes.contains(&id)) {
+            way.nodes.retain(|&node_id| node_id != id);
+            way.nodes.dedup();
+            let distinct = way.nodes.iter().collect::<HashSet<_>>().len();
+            if way.is_closed {
+                // The closing node may be gone, or now repeat the one before it
+                while way.nodes.len() > 1 && way.nodes.first() == way.nodes.last() {
+                    way.nodes.pop();
+                }
+                if distinct >= 3 {
+                    way.nodes.push(way.nodes[0]);
+                } else {
+                    way.is_closed = false;
+                    if way.is_area_if_closed() {
+                        degenerate.push(way.id);
+                        continue;
+                    }
+                }
+            }
+            if distinct < 2 {
+                degenerate.push(way.id);
+            }
+        }
+        degenerate.sort_unstable();
+        for way_id in degenerate {
+            self.delete_way(way_id);
+        }
+
+        if self.on_bounds_edge(node.lat, node.lon) {
+            self.recompute_bounds();
+        }
+        Some(node)
+    }
+
+    /// Drop the style override of a deleted element and its memberships in relations
+    fn forget_element(&mut self, element_type: ElementType, id: i64) {
+        self.style_overrides.remove(&(element_type, id));
+        for relation in self.relations.values_mut() {
+            relation.members.retain(|member| member.element_type != element_type || member.id != id);
+        }
+    }
+
+    /// Override the style of a single element independently of its stylesheet rule
+    pub fn set_style_override(&mut self, element_type: ElementType, id: i64, style: StyleOverride) {
+        self.style_overrides.insert((element_type, id), style);
+    }
+
+    /// Remove a per-element override, returning the element to its rule-based style
+    pub fn clear_style_override(&mut self, element_type: ElementType, id: i64) -> Option<StyleOverride> {
+        self.style_overrides.remove(&(element_type, id))
+    }
+
+    pub fn get_style_override(&self, element_type: ElementType, id: i64) -> Option<&StyleOverride> {
+        self.style_overrides.get(&(element_type, id))
+    }
+
+    /// Save the per-element overrides as JSON so they survive reloading the data
+    pub fn save_style_overrides<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut entries: Vec<StyleOverrideEntry> = self.style_overrides
+            .iter()
+            .map(|(&(element_type, id), style)| StyleOverrideEntry {
+                element_type,
+                id,
+                style: style.clone(),
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.id);
+
+        let content = serde_json::to_string_pretty(&entries)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Load overrides previously written by `save_style_overrides`, replacing existing ones with the same key
+    pub fn load_style_overrides<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        let content = std::fs::read_to_string(path)?;
+        let entries: Vec<StyleOverrideEntry> = serde_json::from_str(&content)?;
+        let count = entries.len();
+
+        for entry in entries {
+            self.style_overrides.insert((entry.element_type, entry.id), entry.style);
+        }
+
+        Ok(count)
+    }
+
+    /// Count node references that point outside the loaded data and mark ways
+    /// that lost too many nodes (or are left with fewer than two) as incomplete
+    pub fn resolve_missing_nodes(&mut self) -> MissingNodeReport {
+        let mut report = MissingNodeReport::default();
+        self.incomplete_ways.clear();
+
+        for way in self.ways.values() {
+            let missing = way.nodes
+                .iter()
+                .filter(|node_id| !self.nodes.contains_key(node_id))
+                .count();
+
+            report.total_references += way.nodes.len();
+            report.missing_references += missing;
+
+            if missing == 0 {
+                continue;
+            }
+            report.affected_ways += 1;
+
+            let resolved = way.nodes.len() - missing;
+            let missing_fraction = missing as f64 / way.nodes.len() as f64;
+
+            if resolved < 2 || missing_fraction > MAX_MISSING_NODE_FRACTION {
+                log::warn!("Way {} is missing {} of {} nodes, marking it incomplete", way.id, missing, way.nodes.len());
+                self.incomplete_ways.insert(way.id);
+            } else {
+                log::debug!("Way {} is missing {} of {} nodes", way.id, missing, way.nodes.len());
+            }
+        }
+
+        report.incomplete_ways = self.incomplete_ways.len();
+        report
+    }
+
+    /// Fix the rings areas are filled from. Ways meant as areas whose ends lie within
+    /// [`RING_CLOSE_TOLERANCE`] meters of each other are closed, and closed rings are turned so
+    /// outer rings run counter-clockwise and the untagged inner rings of multipolygons clockwise.
+    /// Ways with linear tags keep their direction, which means something for coastlines and
+    /// one-way roads. Areas without extent, areas left open and multipolygons whose ways don't
+    /// join into closed rings are reported but not changed.
+    pub fn validate_and_repair(&mut self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let multipolygons: Vec<&Relation> = self.relations.values()
+            .filter(|relation| relation.tags.get("type").map(|s| s.as_str()) == Some("multipolygon"))
+            .collect();
+        let mut outer_members = HashSet::new();
+        let mut inner_members = HashSet::new();
+        for member in multipolygons.iter().flat_map(|relation| &relation.members) {
+            if member.element_type == ElementType::Way {
+                match member.role.as_str() {
+                    "inner" => inner_members.insert(member.id),
+                    _ => outer_members.insert(member.id),
+                };
+            }
+        }
+        let mut multipolygon_ids: Vec<i64> = multipolygons.iter().map(|relation| relation.id).collect();
+        multipolygon_ids.sort_unstable();
+
+        let mut way_ids: Vec<i64> = self.ways.keys().copied().collect();
+        way_ids.sort_unstable();
+        for id in way_ids {
+            let member = outer_members.contains(&id) || inner_members.contains(&id);
+            let way = &self.ways[&id];
+            if self.is_way_incomplete(id) || !(member || (!way.tags.is_empty() && way.is_area_if_closed())) {
+                continue;
+            }
+            let mut ring: Vec<Coord<f64>> = way.node_refs()
+                .filter_map(|node_id| self.nodes.get(&node_id))
+                .map(|node| Coord { x: node.lon, y: node.lat })
+                .collect();
+            ring.dedup();
+
+            if !way.is_closed {
+                let gap = match (ring.first(), ring.last()) {
+                    (Some(first), Some(last)) => {
+                        let x_scale = METERS_PER_DEGREE * first.y.to_radians().cos();
+                        ((last.x - first.x) * x_scale).hypot((last.y - first.y) * METERS_PER_DEGREE)
+                    }
+                    _ => f64::INFINITY,
+                };
+                if ring.len() < 3 || gap > RING_CLOSE_TOLERANCE {
+                    // Open pieces of multipolygon rings are checked with their relation
+                    if !member {
+                        let reason = format!("area is not closed, its ends are {:.1} m apart", gap);
+                        report.unrepairable.push(GeometryIssue { element_type: ElementType::Way, id, reason });
+                    }
+                    continue;
+                }
+                let way = self.ways.get_mut(&id).expect("way id taken from the map");
+                let first_node = way.nodes[0];
+                if gap == 0.0 {
+                    *way.nodes.last_mut().expect("way has nodes") = first_node;
+                    ring.pop();
+                } else {
+                    way.nodes.push(first_node);
+                }
+                way.is_closed = true;
+                report.rings_closed += 1;
+            }
+
+            // The closing point adds nothing to the area
+            if ring.first() == ring.last() {
+                ring.pop();
+            }
+            let area = GeometryUtils::signed_area(&ring);
+            if ring.len() < 3 || area == 0.0 {
+                report.unrepairable.push(GeometryIssue { element_type: ElementType::Way, id, reason: "ring has no area".to_string() });
+                continue;
+            }
+            let way = self.ways.get_mut(&id).expect("way id taken from the map");
+            let clockwise = inner_members.contains(&id) && !outer_members.contains(&id) && way.tags.is_empty();
+            if way.is_area_if_closed() && (area < 0.0) != clockwise {
+                way.nodes.reverse();
+                report.rings_reversed += 1;
+            }
+        }
+
+        // Every end of an open member way must meet the end of another for the rings to close
+        for id in multipolygon_ids {
+            let relation = &self.relations[&id];
+            let member_ways: Vec<i64> = relation.members.iter()
+                .filter(|member| member.element_type == ElementType::Way)
+                .map(|member| member.id)
+                .collect();
+            let missing = member_ways.iter().filter(|way_id| !self.ways.contains_key(way_id)).count();
+            let mut ends: HashMap<i64, usize> = HashMap::new();
+            for way in member_ways.iter().filter_map(|way_id| self.ways.get(way_id)).filter(|way| !way.is_closed) {
+                for end in [way.nodes.first(), way.nodes.last()].into_iter().flatten() {
+                    *ends.entry(*end).or_insert(0) += 1;
+                }
+            }
+            let reason = if missing > 0 {
+                format!("{} of {} member ways are not in the data", missing, member_ways.len())
+            } else if ends.values().any(|count| count % 2 == 1) {
+                "member ways don't join into closed rings".to_string()
+            } else {
+                continue;
+            };
+            report.unrepairable.push(GeometryIssue { element_type: ElementType::Relation, id, reason });
+        }
+
+        if report.rings_closed + report.rings_reversed > 0 {
+            self.touch();
+        }
+        report
+    }
+
+    /// Whether a way has been marked incomplete by `resolve_missing_nodes`
+    pub fn is_way_incomplete(&self, way_id: i64) -> bool {
+        self.incomplete_ways.contains(&way_id)
+    }
+
     /// Get the geometry of a way as a LineString or Polygon
     pub fn get_way_geometry(&self, way: &Way) -> Option<geo_types::Geometry<f64>> {
-        let coords: Vec<Coord<f64>> = way.nodes
-            .iter()
-            .filter_map(|&node_id| {
+        if self.is_way_incomplete(way.id) {
+            return None;
+        }
+
+        let mut coords: Vec<Coord<f64>> = way.node_refs()
+            .filter_map(|node_id| {
                 self.nodes.get(&node_id).map(|node| Coord {
                     x: node.lon,
                     y: node.lat,
                 })
             })
             .collect();
+        // Distinct nodes can share a position too; either way the segment has no length
+        coords.dedup();
             
-        if coords.is_empty() {
+        // A single resolved node can't be drawn as a way
+        if coords.len() < 2 {
             return None;
         }
         
-        if way.is_closed && coords.len() > 2 {
-            // Create polygon
+        if way.is_area() && coords.len() > 2 {
+            // Create polygon (geo_types closes the ring for area=yes ways that aren't closed)
             let exterior = LineString::from(coords);
             Some(geo_types::Geometry::Polygon(Polygon::new(exterior, vec![])))
         } else {
@@ -173,6 +900,75 @@ impl MapData {
         }
     }
     
+    /// Bounding box of a way's resolvable nodes
+    pub fn way_bounds(&self, way: &Way) -> Option<MapBounds> {
+        let mut nodes = way.nodes.iter().filter_map(|node_id| self.nodes.get(node_id));
+        let first = nodes.next()?;
+        let mut bounds = MapBounds {
+            min_lat: first.lat,
+            max_lat: first.lat,
+            min_lon: first.lon,
+            max_lon: first.lon,
+        };
+        for node in nodes {
+            bounds.min_lat = bounds.min_lat.min(node.lat);
+            bounds.max_lat = bounds.max_lat.max(node.lat);
+            bounds.min_lon = bounds.min_lon.min(node.lon);
+            bounds.max_lon = bounds.max_lon.max(node.lon);
+        }
+        Some(bounds)
+    }
+
+    /// Outline of a closed way, or the largest ring joined from the `outer` members of a
+    /// relation, as `(lon, lat)` coordinates with the first point repeated at the end.
+    /// `None` if no closed ring can be formed, e.g. when members are missing from the data.
+    pub fn outline(&self, element_type: ElementType, id: i64) -> Option<Vec<Coord<f64>>> {
+        let way_coords = |way: &Way| -> Vec<Coord<f64>> {
+            way.nodes.iter()
+                .filter_map(|node_id| self.nodes.get(node_id))
+                .map(|node| Coord { x: node.lon, y: node.lat })
+                .collect()
+        };
+        let rings = match element_type {
+            ElementType::Node => return None,
+            ElementType::Way => vec![way_coords(self.ways.get(&id)?)],
+            ElementType::Relation => {
+                let pieces = self.relations.get(&id)?.members.iter()
+                    .filter(|member| member.element_type == ElementType::Way && (member.role == "outer" || member.role.is_empty()))
+                    .filter_map(|member| self.ways.get(&member.id))
+                    .map(way_coords)
+                    .filter(|coords| coords.len() >= 2)
+                    .collect();
+                join_rings(pieces)
+            }
+        };
+        rings.into_iter()
+            .filter(|ring| ring.len() >= 4 && ring.first() == ring.last())
+            .map(|ring| (GeometryUtils::polygon_area(&ring), ring))
+            .filter(|(area, _)| *area > 0.0)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, ring)| ring)
+    }
+
+    /// How many nodes, ways and relations carry each value of each key, e.g.
+    /// `stats["highway"]["residential"]`. Untagged elements are not counted.
+    pub fn tag_statistics(&self) -> HashMap<String, HashMap<String, usize>> {
+        let mut statistics: HashMap<String, HashMap<String, usize>> = HashMap::new();
+        let tags = self.nodes.values().map(|node| &node.tags)
+            .chain(self.ways.values().map(|way| &way.tags))
+            .chain(self.relations.values().map(|relation| &relation.tags));
+        for (key, value) in tags.flatten() {
+            *statistics.entry(key.clone()).or_default().entry(value.clone()).or_insert(0) += 1;
+        }
+        statistics
+    }
+
+    /// The road network as a graph of junctions and the stretches of road between them,
+    /// with length, one-way, class, access and surface on each edge
+    pub fn build_road_graph(&self) -> RoadGraph {
+        RoadGraph::from_map_data(self)
+    }
+
     /// Get all ways that match certain tag criteria
     pub fn get_ways_by_tags(&self, tag_filter: &HashMap<String, Vec<String>>) -> Vec<&Way> {
         self.ways
@@ -188,6 +984,34 @@ impl MapData {
     }
 }
 
+/// Entries of a count table, most frequent first and alphabetical among equals
+pub fn counts_descending(counts: &HashMap<String, usize>) -> Vec<(&str, usize)> {
+    let mut entries: Vec<(&str, usize)> = counts.iter().map(|(name, &count)| (name.as_str(), count)).collect();
+    entries.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    entries
+}
+
+/// Chain open way pieces end to end into rings, turning pieces round where needed. Pieces
+/// that are already closed pass through; chains that never close are returned open.
+fn join_rings(mut pieces: Vec<Vec<Coord<f64>>>) -> Vec<Vec<Coord<f64>>> {
+    let mut rings = Vec::new();
+    while let Some(mut ring) = pieces.pop() {
+        while ring.first() != ring.last() {
+            let end = ring[ring.len() - 1];
+            let Some(index) = pieces.iter().position(|piece| piece[0] == end || piece[piece.len() - 1] == end) else {
+                break;
+            };
+            let mut next = pieces.swap_remove(index);
+            if next[0] != end {
+                next.reverse();
+            }
+            ring.extend(next.into_iter().skip(1));
+        }
+        rings.push(ring);
+    }
+    rings
+}
+
 impl MapBounds {
     pub fn center(&self) -> (f64, f64) {
         (
@@ -205,8 +1029,172 @@ impl MapBounds {
     }
 }
 
+impl std::str::FromStr for MapBounds {
+    type Err = anyhow::Error;
+
+    /// Parse a `minlon,minlat,maxlon,maxlat` bounding box, the order used by the OSM API
+    fn from_str(s: &str) -> Result<Self> {
+        let values = s
+            .split(',')
+            .map(|part| {
+                part.trim()
+                    .parse::<f64>()
+                    .map_err(|_| anyhow::anyhow!("'{}' is not a number", part.trim()))
+            })
+            .collect::<Result<Vec<f64>>>()?;
+
+        let [min_lon, min_lat, max_lon, max_lat] = values[..] else {
+            return Err(anyhow::anyhow!(
+                "expected 4 values minlon,minlat,maxlon,maxlat, got {}",
+                values.len()
+            ));
+        };
+
+        if !(-180.0..=180.0).contains(&min_lon) || !(-180.0..=180.0).contains(&max_lon) {
+            return Err(anyhow::anyhow!("longitudes must be between -180 and 180"));
+        }
+        if !(-90.0..=90.0).contains(&min_lat) || !(-90.0..=90.0).contains(&max_lat) {
+            return Err(anyhow::anyhow!("latitudes must be between -90 and 90"));
+        }
+        if min_lon >= max_lon {
+            return Err(anyhow::anyhow!("minlon ({}) must be less than maxlon ({})", min_lon, max_lon));
+        }
+        if min_lat >= max_lat {
+            return Err(anyhow::anyhow!("minlat ({}) must be less than maxlat ({})", min_lat, max_lat));
+        }
+
+        Ok(Self { min_lat, max_lat, min_lon, max_lon })
+    }
+}
+
+impl Way {
+    /// Whether this way should be treated as an area rather than a line.
+    ///
+    /// Roundabouts are always lines. Otherwise an explicit `area=yes`/`area=no`
+    /// wins; without it only closed ways can be areas, and closed ways carrying a
+    /// linear tag (e.g. `highway`, `barrier`, `railway`) stay lines, following the
+    /// OSM area rules.
+    pub fn is_area(&self) -> bool {
+        if self.is_roundabout() {
+            return false;
+        }
+
+        match self.tags.get("area").map(|v| v.as_str()) {
+            Some("yes") => return self.nodes.len() > 2,
+            Some("no") => return false,
+            _ => {}
+        }
+
+        if !self.is_closed {
+            return false;
+        }
+
+        !self.tags.iter().any(|(key, value)| Self::is_linear_tag(key, value))
+    }
+
+    /// Whether the way is meant as an area, going by its tags alone. Open ways for which
+    /// this holds are broken outlines, or pieces of a multipolygon ring.
+    pub fn is_area_if_closed(&self) -> bool {
+        if self.is_roundabout() {
+            return false;
+        }
+
+        match self.tags.get("area").map(|v| v.as_str()) {
+            Some("yes") => true,
+            Some("no") => false,
+            _ => !self.tags.iter().any(|(key, value)| Self::is_linear_tag(key, value)),
+        }
+    }
+
+    /// `junction=roundabout` or `junction=circular`: a one-way loop drawn in the direction of
+    /// its nodes, never filled even when it is closed or mistagged `area=yes`
+    pub fn is_roundabout(&self) -> bool {
+        matches!(self.tags.get("junction").map(|v| v.as_str()), Some("roundabout" | "circular"))
+    }
+
+    /// Vertical ordering from the `layer` tag: bridges are usually 1 or more, tunnels
+    /// negative. Missing or malformed values count as ground level.
+    pub fn layer(&self) -> i32 {
+        self.tags
+            .get("layer")
+            .and_then(|layer| layer.trim().parse::<i32>().ok())
+            .unwrap_or(0)
+    }
+
+    /// Node refs with consecutive repeats collapsed. Some imported data repeats a ref,
+    /// which would otherwise produce zero-length segments.
+    pub fn node_refs(&self) -> impl Iterator<Item = i64> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|&(i, id)| i == 0 || self.nodes[i - 1] != *id)
+            .map(|(_, &id)| id)
+    }
+
+    /// Tags that describe a linear feature even when the way is closed
+    fn is_linear_tag(key: &str, value: &str) -> bool {
+        match key {
+            "highway" => !matches!(value, "rest_area" | "services" | "platform"),
+            "railway" => !matches!(value, "platform" | "station" | "turntable" | "roundhouse"),
+            "waterway" => !matches!(value, "riverbank" | "dock" | "boatyard" | "dam"),
+            "barrier" | "aerialway" | "route" | "boundary" => true,
+            "power" => matches!(value, "line" | "minor_line" | "cable"),
+            "natural" => matches!(value, "coastline" | "cliff" | "ridge" | "arete" | "tree_row"),
+            "man_made" => matches!(value, "embankment" | "cutline" | "pipeline" | "breakwater" | "groyne"),
+            "aeroway" => matches!(value, "taxiway" | "runway"),
+            _ => false,
+        }
+    }
+}
+
 impl Default for MapData {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map_with_ways(ways: &[(i64, &[i64], (&str, &str))]) -> MapData {
+        let mut data = MapData::new();
+        for id in 1..=4 {
+            data.add_node(Node { id, lat: id as f64 * 0.001, lon: (id % 2) as f64 * 0.001, tags: HashMap::new() });
+        }
+        for &(id, nodes, (key, value)) in ways {
+            data.add_way(Way {
+                id,
+                nodes: nodes.to_vec(),
+                tags: HashMap::from([(key.to_string(), value.to_string())]),
+                is_closed: nodes.len() > 2 && nodes.first() == nodes.last(),
+            });
+        }
+        data
+    }
+
+    #[test]
+    fn deleting_nodes_leaves_no_degenerate_ways() {
+        let mut data = map_with_ways(&[
+            (10, &[1, 2, 3, 1], ("building", "yes")),
+            (11, &[1, 2, 3, 1], ("barrier", "fence")),
+            (12, &[1, 2, 3, 4, 1], ("landuse", "grass")),
+            (13, &[1, 3], ("highway", "path")),
+        ]);
+        data.delete_node(1);
+
+        // A triangle down to two nodes has no area left
+        assert!(!data.ways.contains_key(&10));
+        // A closed line is opened instead of kept as [2, 3, 2]
+        let fence = &data.ways[&11];
+        assert_eq!((fence.nodes.as_slice(), fence.is_closed), ([2, 3].as_slice(), false));
+        // A ring losing its closing node is closed again over the remaining ones
+        let grass = &data.ways[&12];
+        assert_eq!((grass.nodes.as_slice(), grass.is_closed), ([2, 3, 4, 2].as_slice(), true));
+        // A single point is no line
+        assert!(!data.ways.contains_key(&13));
+
+        data.delete_node(3);
+        assert!(data.ways.is_empty());
+    }
+}
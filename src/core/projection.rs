@@ -1,6 +1,46 @@
-use crate::core::ProjectionSystem;
+use crate::core::{MapBounds, ProjectionSystem};
 use geo_types::Coord;
 
+/// Semi-major axis of the WGS84 ellipsoid in meters. Every projection here works on the
+/// WGS84 datum, the one OSM data and GPS tracks are recorded in.
+pub const WGS84_SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+
+/// Flattening of the WGS84 ellipsoid
+pub const WGS84_FLATTENING: f64 = 1.0 / 298.257_223_563;
+
+/// Scale factor on the central meridian of a UTM zone
+const UTM_SCALE_FACTOR: f64 = 0.9996;
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+/// Added to northings south of the equator so they stay positive
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+
+/// Project geographic coordinates to map coordinates in `system`: meters for Web Mercator
+/// (EPSG:3857) and UTM, `(lon, lat)` in degrees for LatLon. Returns `(x, y)`, y pointing north.
+pub fn project(system: ProjectionSystem, lat: f64, lon: f64) -> (f64, f64) {
+    match system {
+        ProjectionSystem::WebMercator => {
+            let x = WGS84_SEMI_MAJOR_AXIS * lon.to_radians();
+            let y = WGS84_SEMI_MAJOR_AXIS * (std::f64::consts::FRAC_PI_4 + lat.to_radians() / 2.0).tan().ln();
+            (x, y)
+        }
+        ProjectionSystem::Utm { zone, north } => utm_forward(lat, lon, zone, north),
+        ProjectionSystem::LatLon => (lon, lat),
+    }
+}
+
+/// Geographic coordinates `(lat, lon)` of map coordinates in `system`; the inverse of [`project`]
+pub fn unproject(system: ProjectionSystem, x: f64, y: f64) -> (f64, f64) {
+    match system {
+        ProjectionSystem::WebMercator => {
+            let lon = (x / WGS84_SEMI_MAJOR_AXIS).to_degrees();
+            let lat = (2.0 * (y / WGS84_SEMI_MAJOR_AXIS).exp().atan() - std::f64::consts::FRAC_PI_2).to_degrees();
+            (lat, lon)
+        }
+        ProjectionSystem::Utm { zone, north } => utm_inverse(x, y, zone, north),
+        ProjectionSystem::LatLon => (y, x),
+    }
+}
+
 /// Coordinate projection utilities
 pub struct Projector {
     system: ProjectionSystem,
@@ -13,107 +53,100 @@ impl Projector {
     
     /// Project geographic coordinates (lat, lon) to map coordinates (x, y)
     pub fn project(&self, lat: f64, lon: f64) -> Coord<f64> {
-        match self.system {
-            ProjectionSystem::WebMercator => self.to_web_mercator(lat, lon),
-            ProjectionSystem::Utm { zone, north } => self.to_utm(lat, lon, zone, north),
-            ProjectionSystem::LatLon => Coord { x: lon, y: lat },
-        }
+        let (x, y) = project(self.system, lat, lon);
+        Coord { x, y }
     }
     
     /// Inverse projection from map coordinates to geographic coordinates
     pub fn unproject(&self, x: f64, y: f64) -> (f64, f64) {
-        match self.system {
-            ProjectionSystem::WebMercator => self.from_web_mercator(x, y),
-            ProjectionSystem::Utm { zone, north } => self.from_utm(x, y, zone, north),
-            ProjectionSystem::LatLon => (y, x),
-        }
+        unproject(self.system, x, y)
     }
+}
+
+/// First eccentricity squared of the WGS84 ellipsoid
+fn eccentricity_squared() -> f64 {
+    WGS84_FLATTENING * (2.0 - WGS84_FLATTENING)
+}
+
+/// Longitude of the central meridian of a UTM zone
+fn utm_central_meridian(zone: u8) -> f64 {
+    zone as f64 * 6.0 - 183.0
+}
+
+/// Distance along the meridian from the equator to latitude `lat` (radians), in meters
+fn meridional_arc(lat: f64) -> f64 {
+    let e2 = eccentricity_squared();
+    let (e4, e6) = (e2 * e2, e2 * e2 * e2);
     
-    /// Web Mercator projection (EPSG:3857)
-    fn to_web_mercator(&self, lat: f64, lon: f64) -> Coord<f64> {
-        const EARTH_RADIUS: f64 = 6378137.0;
-        
-        let x = EARTH_RADIUS * lon.to_radians();
-        let y = EARTH_RADIUS * (std::f64::consts::PI / 4.0 + lat.to_radians() / 2.0).tan().ln();
-        
-        Coord { x, y }
-    }
+    WGS84_SEMI_MAJOR_AXIS * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * lat
+        - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * lat).sin()
+        + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * lat).sin()
+        - (35.0 * e6 / 3072.0) * (6.0 * lat).sin())
+}
+
+/// Transverse Mercator series (Snyder, "Map Projections: A Working Manual", p. 61), accurate
+/// to millimeters within a zone and to well under a meter a few degrees outside it
+fn utm_forward(lat: f64, lon: f64, zone: u8, north: bool) -> (f64, f64) {
+    let e2 = eccentricity_squared();
+    let ep2 = e2 / (1.0 - e2);
+    let lat_rad = lat.to_radians();
+    let (sin_lat, cos_lat, tan_lat) = (lat_rad.sin(), lat_rad.cos(), lat_rad.tan());
     
-    /// Inverse Web Mercator projection
-    fn from_web_mercator(&self, x: f64, y: f64) -> (f64, f64) {
-        const EARTH_RADIUS: f64 = 6378137.0;
-        
-        let lon = (x / EARTH_RADIUS).to_degrees();
-        let lat = (2.0 * (y / EARTH_RADIUS).exp().atan() - std::f64::consts::PI / 2.0).to_degrees();
-        
-        (lat, lon)
-    }
+    let n = WGS84_SEMI_MAJOR_AXIS / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+    let t = tan_lat * tan_lat;
+    let c = ep2 * cos_lat * cos_lat;
+    let a = cos_lat * (lon - utm_central_meridian(zone)).to_radians();
     
-    /// UTM projection (simplified version)
-    fn to_utm(&self, lat: f64, lon: f64, zone: u8, north: bool) -> Coord<f64> {
-        // Simplified UTM projection - in a real implementation, you'd use a proper library like proj
-        let central_meridian = (zone as f64 - 1.0) * 6.0 - 180.0 + 3.0;
-        let lon_diff = lon - central_meridian;
-        
-        let lat_rad = lat.to_radians();
-        let lon_diff_rad = lon_diff.to_radians();
-        
-        // Simplified calculation - this is not accurate for production use
-        let k0 = 0.9996; // Scale factor
-        let a = 6378137.0; // Semi-major axis
-        let e2 = 0.00669438; // First eccentricity squared
-        
-        let n = a / (1.0 - e2 * lat_rad.sin().powi(2)).sqrt();
-        let t = lat_rad.tan();
-        let c = e2 * lat_rad.cos().powi(2) / (1.0 - e2);
-        let a_val = lat_rad.cos() * lon_diff_rad;
-        
-        let x = k0 * n * (a_val + (1.0 - t.powi(2) + c) * a_val.powi(3) / 6.0) + 500000.0;
-        let mut y = k0 * (self.meridional_arc(lat_rad) + n * t * (a_val.powi(2) / 2.0));
-        
-        if !north {
-            y += 10000000.0; // False northing for southern hemisphere
-        }
-        
-        Coord { x, y }
-    }
+    let x = UTM_SCALE_FACTOR * n * (a
+        + (1.0 - t + c) * a.powi(3) / 6.0
+        + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0)
+        + UTM_FALSE_EASTING;
+    let y = UTM_SCALE_FACTOR * (meridional_arc(lat_rad) + n * tan_lat * (a * a / 2.0
+        + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+        + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0));
     
-    /// Inverse UTM projection (simplified)
-    fn from_utm(&self, x: f64, y: f64, zone: u8, north: bool) -> (f64, f64) {
-        // Simplified inverse UTM - this is a placeholder
-        // In practice, you'd use a proper geodetic library
-        let central_meridian = (zone as f64 - 1.0) * 6.0 - 180.0 + 3.0;
-        
-        // Very rough approximation
-        let lon = central_meridian + (x - 500000.0) / 111320.0;
-        let mut y_adj = y;
-        
-        if !north {
-            y_adj -= 10000000.0;
-        }
-        
-        let lat = y_adj / 111320.0;
-        
-        (lat, lon)
-    }
+    (x, if north { y } else { y + UTM_FALSE_NORTHING_SOUTH })
+}
+
+/// Inverse of [`utm_forward`], through the footpoint latitude
+fn utm_inverse(x: f64, y: f64, zone: u8, north: bool) -> (f64, f64) {
+    let e2 = eccentricity_squared();
+    let ep2 = e2 / (1.0 - e2);
+    let northing = if north { y } else { y - UTM_FALSE_NORTHING_SOUTH };
     
-    /// Calculate meridional arc (simplified)
-    fn meridional_arc(&self, lat: f64) -> f64 {
-        const A: f64 = 6378137.0;
-        const E2: f64 = 0.00669438;
-        
-        let e4 = E2 * E2;
-        let e6 = e4 * E2;
-        
-        let m = A * ((1.0 - E2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * lat
-            - (3.0 * E2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * lat).sin()
-            + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * lat).sin()
-            - (35.0 * e6 / 3072.0) * (6.0 * lat).sin());
-        
-        m
-    }
+    let m = northing / UTM_SCALE_FACTOR;
+    let mu = m / (WGS84_SEMI_MAJOR_AXIS * (1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2.powi(3) / 256.0));
+    let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+    let footpoint = mu
+        + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+        + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+        + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+        + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+    
+    let (sin_fp, cos_fp, tan_fp) = (footpoint.sin(), footpoint.cos(), footpoint.tan());
+    let c1 = ep2 * cos_fp * cos_fp;
+    let t1 = tan_fp * tan_fp;
+    let n1 = WGS84_SEMI_MAJOR_AXIS / (1.0 - e2 * sin_fp * sin_fp).sqrt();
+    let r1 = WGS84_SEMI_MAJOR_AXIS * (1.0 - e2) / (1.0 - e2 * sin_fp * sin_fp).powf(1.5);
+    let d = (x - UTM_FALSE_EASTING) / (n1 * UTM_SCALE_FACTOR);
+    
+    let lat = footpoint - (n1 * tan_fp / r1) * (d * d / 2.0
+        - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1 * c1 - 9.0 * ep2) * d.powi(4) / 24.0
+        + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1 * t1 - 252.0 * ep2 - 3.0 * c1 * c1) * d.powi(6) / 720.0);
+    let lon = (d
+        - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+        + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1 * c1 + 8.0 * ep2 + 24.0 * t1 * t1) * d.powi(5) / 120.0)
+        / cos_fp;
+    
+    (lat.to_degrees(), utm_central_meridian(zone) + lon.to_degrees())
 }
 
+/// Equatorial circumference of the WGS84 ellipsoid in meters
+pub const EARTH_CIRCUMFERENCE: f64 = 40_075_016.686;
+
+/// Meters per degree of latitude, the same approximation used for distances elsewhere
+pub const METERS_PER_DEGREE: f64 = EARTH_CIRCUMFERENCE / 360.0;
+
 /// Utility functions for coordinate system conversions
 pub struct CoordinateUtils;
 
@@ -133,6 +166,42 @@ impl CoordinateUtils {
         1.0 / lat.to_radians().cos()
     }
     
+    /// Convert geographic coordinates to slippy map tile indices at the given zoom
+    pub fn lat_lon_to_tile(lat: f64, lon: f64, zoom: u8) -> (u32, u32) {
+        let n = 2f64.powi(zoom as i32);
+        let lat_rad = lat.clamp(-85.0511, 85.0511).to_radians();
+        
+        let x = ((lon + 180.0) / 360.0 * n).floor();
+        let y = ((1.0 - (lat_rad.tan() + 1.0 / lat_rad.cos()).ln() / std::f64::consts::PI) / 2.0 * n).floor();
+        
+        let max_index = n - 1.0;
+        (x.clamp(0.0, max_index) as u32, y.clamp(0.0, max_index) as u32)
+    }
+    
+    /// Ground resolution of a web map at a (fractional) zoom level, in meters per pixel
+    pub fn meters_per_pixel_at_zoom(zoom: f64, lat: f64, tile_size: u32) -> f64 {
+        EARTH_CIRCUMFERENCE * lat.to_radians().cos() / (tile_size as f64 * 2f64.powf(zoom))
+    }
+    
+    /// Fractional web map zoom level showing `meters_per_pixel` at the given latitude
+    pub fn zoom_for_meters_per_pixel(meters_per_pixel: f64, lat: f64, tile_size: u32) -> f64 {
+        (EARTH_CIRCUMFERENCE * lat.to_radians().cos() / (tile_size as f64 * meters_per_pixel)).log2()
+    }
+    
+    /// Geographic bounds covered by a slippy map tile
+    pub fn tile_bounds(x: u32, y: u32, zoom: u8) -> MapBounds {
+        let n = 2f64.powi(zoom as i32);
+        let lon_at = |tx: f64| tx / n * 360.0 - 180.0;
+        let lat_at = |ty: f64| (std::f64::consts::PI * (1.0 - 2.0 * ty / n)).sinh().atan().to_degrees();
+        
+        MapBounds {
+            min_lat: lat_at(y as f64 + 1.0),
+            max_lat: lat_at(y as f64),
+            min_lon: lon_at(x as f64),
+            max_lon: lon_at(x as f64 + 1.0),
+        }
+    }
+    
     /// Convert degrees to decimal degrees (for DMS input)
     pub fn dms_to_decimal(degrees: i32, minutes: i32, seconds: f64) -> f64 {
         degrees.abs() as f64 + minutes as f64 / 60.0 + seconds / 3600.0
@@ -150,3 +219,88 @@ impl CoordinateUtils {
         (sign * degrees, minutes, seconds)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn assert_close(actual: (f64, f64), expected: (f64, f64), tolerance: f64) {
+        assert!(
+            (actual.0 - expected.0).abs() <= tolerance && (actual.1 - expected.1).abs() <= tolerance,
+            "{:?} is not within {} of {:?}",
+            actual, tolerance, expected
+        );
+    }
+    
+    fn assert_round_trip(system: ProjectionSystem, lat: f64, lon: f64) {
+        let (x, y) = project(system, lat, lon);
+        // 1e-8 degrees is about a millimeter on the ground
+        assert_close(unproject(system, x, y), (lat, lon), 1e-8);
+    }
+    
+    /// Snyder's worked example for the ellipsoidal Transverse Mercator (p. 269): 40°30'N,
+    /// 73°30'W in UTM zone 18. Snyder works it on the Clarke 1866 ellipsoid; the expected
+    /// figures are the same point on WGS84, from Krüger's series.
+    #[test]
+    fn utm_matches_snyder_worked_example() {
+        let (lat, lon) = (40.5, -73.5);
+        let system = ProjectionSystem::utm_for(lat, lon);
+        assert_eq!(system, ProjectionSystem::Utm { zone: 18, north: true });
+        assert_close(project(system, lat, lon), (627_103.087, 4_484_335.402), 0.01);
+    }
+    
+    #[test]
+    fn utm_southern_hemisphere_adds_false_northing() {
+        // Sydney, zone 56 south
+        let (lat, lon) = (-33.8688, 151.2093);
+        let system = ProjectionSystem::utm_for(lat, lon);
+        assert_eq!(system, ProjectionSystem::Utm { zone: 56, north: false });
+        assert_close(project(system, lat, lon), (334_368.634, 6_250_948.345), 0.01);
+    }
+    
+    #[test]
+    fn utm_zone_numbers() {
+        assert_eq!(CoordinateUtils::utm_zone_from_lon(-180.0), 1);
+        assert_eq!(CoordinateUtils::utm_zone_from_lon(-174.000_001), 1);
+        assert_eq!(CoordinateUtils::utm_zone_from_lon(-174.0), 2);
+        assert_eq!(CoordinateUtils::utm_zone_from_lon(-0.000_001), 30);
+        assert_eq!(CoordinateUtils::utm_zone_from_lon(0.0), 31);
+        assert_eq!(CoordinateUtils::utm_zone_from_lon(2.35), 31);
+        assert_eq!(CoordinateUtils::utm_zone_from_lon(179.999_999), 60);
+        // The antimeridian belongs to zone 60, not a zone 61
+        assert_eq!(ProjectionSystem::utm_for(0.0, 180.0), ProjectionSystem::Utm { zone: 60, north: true });
+        assert_eq!(ProjectionSystem::utm_for(-0.000_001, 0.0), ProjectionSystem::Utm { zone: 31, north: false });
+    }
+    
+    #[test]
+    fn utm_round_trips() {
+        for (lat, lon) in [(40.5, -73.5), (48.853, 2.3499), (-33.8688, 151.2093), (-54.8, -68.3), (0.0, 3.0), (71.0, 25.8)] {
+            assert_round_trip(ProjectionSystem::utm_for(lat, lon), lat, lon);
+        }
+    }
+    
+    #[test]
+    fn utm_round_trips_at_zone_edges() {
+        // Zone 18 runs from 78°W to 72°W
+        for lat in [-60.0, -12.5, 0.0, 12.5, 60.0] {
+            for lon in [-78.0, -72.000_001] {
+                assert_round_trip(ProjectionSystem::Utm { zone: 18, north: lat >= 0.0 }, lat, lon);
+            }
+        }
+    }
+    
+    #[test]
+    fn web_mercator_matches_epsg_3857() {
+        let half_world = std::f64::consts::PI * WGS84_SEMI_MAJOR_AXIS;
+        assert_close(project(ProjectionSystem::WebMercator, 0.0, 180.0), (half_world, 0.0), 1e-6);
+        assert_close(project(ProjectionSystem::WebMercator, 85.051_128_779_806_6, -180.0), (-half_world, half_world), 1e-3);
+        assert_close(project(ProjectionSystem::WebMercator, -85.051_128_779_806_6, 0.0), (0.0, -half_world), 1e-3);
+    }
+    
+    #[test]
+    fn web_mercator_round_trips() {
+        for (lat, lon) in [(48.853, 2.3499), (-33.8688, 151.2093), (0.0, 0.0), (-85.0, -179.9), (85.0, 179.9), (-0.5, -0.5)] {
+            assert_round_trip(ProjectionSystem::WebMercator, lat, lon);
+        }
+    }
+}
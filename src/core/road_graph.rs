@@ -0,0 +1,177 @@
+use crate::core::geometry::GeometryUtils;
+use crate::core::{MapData, Way};
+use geo_types::Point;
+use std::collections::HashMap;
+
+/// `highway` values that are not part of the network: lifecycle stages and features mapped
+/// on a way that can't be travelled along
+const UNROUTABLE_HIGHWAYS: &[&str] = &[
+    "construction", "proposed", "disused", "abandoned", "razed",
+    "platform", "bus_stop", "rest_area", "services", "elevator",
+];
+
+/// Which way traffic may go along an edge, relative to the node order of its way
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Oneway {
+    No,
+    /// Only in node order
+    Forward,
+    /// Only against node order, `oneway=-1`
+    Backward,
+}
+
+impl Oneway {
+    /// From the `oneway` tag; roundabouts and motorways are one-way unless tagged `oneway=no`
+    pub fn of(way: &Way) -> Self {
+        match way.tags.get("oneway").map(|s| s.as_str()) {
+            Some("yes" | "true" | "1") => Oneway::Forward,
+            Some("-1" | "reverse") => Oneway::Backward,
+            Some(_) => Oneway::No,
+            None if way.is_roundabout() || way.tags.get("highway").map(|s| s.as_str()) == Some("motorway") => Oneway::Forward,
+            None => Oneway::No,
+        }
+    }
+}
+
+/// A junction or dead end of the road network
+#[derive(Debug, Clone, Copy)]
+pub struct GraphNode {
+    /// OSM node id
+    pub id: i64,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// The stretch of a road way between two graph nodes, with no junction in between
+#[derive(Debug, Clone)]
+pub struct RoadEdge {
+    pub way_id: i64,
+    pub from: i64,
+    pub to: i64,
+    /// Every node along the edge in way order, `from` first and `to` last
+    pub nodes: Vec<i64>,
+    /// Meters along the nodes, by haversine distance
+    pub length: f64,
+    /// The `highway` value, e.g. `residential`
+    pub highway: String,
+    pub oneway: Oneway,
+    pub access: Option<String>,
+    pub surface: Option<String>,
+}
+
+impl RoadEdge {
+    /// The end across from `node`, if the edge touches it
+    pub fn other_end(&self, node: i64) -> Option<i64> {
+        if node == self.from {
+            Some(self.to)
+        } else if node == self.to {
+            Some(self.from)
+        } else {
+            None
+        }
+    }
+
+    /// Whether traffic may set off along the edge from `node`, one of its ends
+    pub fn allows_from(&self, node: i64) -> bool {
+        match self.oneway {
+            Oneway::No => node == self.from || node == self.to,
+            Oneway::Forward => node == self.from,
+            Oneway::Backward => node == self.to,
+        }
+    }
+}
+
+/// Read-only graph of the road network, for routing experiments. Nodes are the OSM nodes
+/// where road ways meet or end; edges are the stretches of way between them. Ways marked
+/// incomplete and nodes missing from the data are left out.
+#[derive(Debug, Clone, Default)]
+pub struct RoadGraph {
+    pub nodes: HashMap<i64, GraphNode>,
+    pub edges: Vec<RoadEdge>,
+    /// Indices into `edges` of the edges at each node
+    adjacency: HashMap<i64, Vec<usize>>,
+}
+
+impl RoadGraph {
+    pub fn from_map_data(map_data: &MapData) -> Self {
+        let roads: Vec<(&Way, Vec<i64>)> = map_data.ways.values()
+            .filter(|way| is_routable(way) && !map_data.is_way_incomplete(way.id))
+            .map(|way| (way, way.node_refs().filter(|id| map_data.nodes.contains_key(id)).collect::<Vec<i64>>()))
+            .filter(|(_, nodes)| nodes.len() >= 2)
+            .collect();
+
+        // Nodes on more than one road, or twice on the same one, are junctions
+        let mut uses: HashMap<i64, usize> = HashMap::new();
+        for (_, nodes) in &roads {
+            for &id in nodes {
+                *uses.entry(id).or_insert(0) += 1;
+            }
+        }
+
+        let mut graph = RoadGraph::default();
+        for (way, nodes) in roads {
+            let mut start = 0;
+            for i in 1..nodes.len() {
+                if i < nodes.len() - 1 && uses[&nodes[i]] < 2 {
+                    continue;
+                }
+                graph.add_edge(map_data, way, &nodes[start..=i]);
+                start = i;
+            }
+        }
+        graph
+    }
+
+    /// The edges meeting at `node`, whatever their direction
+    pub fn edges_at(&self, node: i64) -> impl Iterator<Item = &RoadEdge> + '_ {
+        self.adjacency.get(&node)
+            .into_iter()
+            .flatten()
+            .map(|&index| &self.edges[index])
+    }
+
+    /// The edges traffic may leave `node` by, each with the node it leads to
+    pub fn outgoing(&self, node: i64) -> impl Iterator<Item = (&RoadEdge, i64)> + '_ {
+        self.edges_at(node)
+            .filter(move |edge| edge.allows_from(node))
+            .filter_map(move |edge| edge.other_end(node).map(|to| (edge, to)))
+    }
+
+    fn add_edge(&mut self, map_data: &MapData, way: &Way, nodes: &[i64]) {
+        let points: Vec<Point<f64>> = nodes.iter()
+            .map(|id| &map_data.nodes[id])
+            .map(|node| Point::new(node.lon, node.lat))
+            .collect();
+        let length = points.windows(2)
+            .map(|pair| GeometryUtils::haversine_distance(&pair[0], &pair[1]))
+            .sum();
+
+        let (from, to) = (nodes[0], nodes[nodes.len() - 1]);
+        for id in [from, to] {
+            let node = &map_data.nodes[&id];
+            self.nodes.entry(id).or_insert(GraphNode { id, lat: node.lat, lon: node.lon });
+        }
+        let index = self.edges.len();
+        self.adjacency.entry(from).or_default().push(index);
+        if to != from {
+            self.adjacency.entry(to).or_default().push(index);
+        }
+        self.edges.push(RoadEdge {
+            way_id: way.id,
+            from,
+            to,
+            nodes: nodes.to_vec(),
+            length,
+            highway: way.tags.get("highway").cloned().unwrap_or_default(),
+            oneway: Oneway::of(way),
+            access: way.tags.get("access").cloned(),
+            surface: way.tags.get("surface").cloned(),
+        });
+    }
+}
+
+/// Roads, tracks and paths that can be travelled along; areas such as pedestrian squares
+/// are left out
+fn is_routable(way: &Way) -> bool {
+    way.tags.get("highway").is_some_and(|highway| !UNROUTABLE_HIGHWAYS.contains(&highway.as_str())) && !way.is_area()
+}
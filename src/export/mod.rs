@@ -1,16 +1,22 @@
 pub mod svg_export;
+pub mod tiles;
 // pub mod png_export; // Disabled for now due to compatibility issues
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 
-use crate::rendering::RenderedMap;
 use crate::core::MapData;
+use crate::parsers::stylesheet::ColorTransform;
+use crate::core::geometry::{FitPadding, MIN_MAP_EXTENT};
+use geo_types::Coord;
 use crate::rendering::MapRenderer;
+use crate::styles::loader::OverlayPosition;
 
 /// Available export formats
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ExportFormat {
     Svg,
     Png,
@@ -18,6 +24,319 @@ pub enum ExportFormat {
     Pdf,
 }
 
+/// Largest width or height of a PNG or JPEG export in pixels, before DPI scaling; rasterizing
+/// much larger images runs out of memory
+pub const MAX_EXPORT_SIZE: u32 = 20_000;
+
+/// Error types for export operations
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0:?} export is not supported yet - use SVG, PNG or JPEG instead")]
+    UnsupportedFormat(ExportFormat),
+
+    #[error("Invalid dimensions {width}x{height}: width and height must be greater than 0")]
+    InvalidDimensions { width: u32, height: u32 },
+
+    #[error("Export size {width}x{height} is too large: width and height can be at most {max} pixels")]
+    TooLarge { width: u32, height: u32, max: u32 },
+
+    #[error("Invalid DPI {0}: must be greater than 0")]
+    InvalidDpi(f32),
+
+    #[error("Invalid JPEG quality {0}: must be between 1 and 100")]
+    InvalidQuality(u8),
+
+    #[error("Output directory does not exist: {}", .0.display())]
+    OutputDirMissing(PathBuf),
+
+    #[error("Map has no data to export")]
+    NoData,
+
+    #[error("Zoom level {0} is out of range (0-22)")]
+    InvalidZoom(u8),
+
+    #[error("Style error: {0}")]
+    Style(#[from] crate::styles::StyleError),
+
+    /// Failures while building or rasterizing the document
+    #[error(transparent)]
+    Render(#[from] anyhow::Error),
+}
+
+/// SVG `shape-rendering` hint applied to the exported document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ShapeRendering {
+    Auto,
+    OptimizeSpeed,
+    CrispEdges,
+    #[default]
+    GeometricPrecision,
+}
+
+impl ShapeRendering {
+    pub fn as_svg_value(&self) -> &'static str {
+        match self {
+            ShapeRendering::Auto => "auto",
+            ShapeRendering::OptimizeSpeed => "optimizeSpeed",
+            ShapeRendering::CrispEdges => "crispEdges",
+            ShapeRendering::GeometricPrecision => "geometricPrecision",
+        }
+    }
+}
+
+/// SVG `text-rendering` hint for labels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextRendering {
+    #[default]
+    Auto,
+    OptimizeSpeed,
+    OptimizeLegibility,
+    GeometricPrecision,
+}
+
+impl TextRendering {
+    pub fn as_svg_value(&self) -> &'static str {
+        match self {
+            TextRendering::Auto => "auto",
+            TextRendering::OptimizeSpeed => "optimizeSpeed",
+            TextRendering::OptimizeLegibility => "optimizeLegibility",
+            TextRendering::GeometricPrecision => "geometricPrecision",
+        }
+    }
+}
+
+/// How SVG exports carry the font of their labels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SvgTextMode {
+    /// `<text>` naming the style font; viewers without it substitute another
+    Font,
+    /// `<text>` plus the bundled Noto Sans embedded as a base64 `@font-face`
+    EmbeddedFont,
+    /// Labels converted to outlines, so they look the same everywhere and survive print workflows
+    #[default]
+    Paths,
+}
+
+impl SvgTextMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SvgTextMode::Font => "Text (system font)",
+            SvgTextMode::EmbeddedFont => "Text (embedded font)",
+            SvgTextMode::Paths => "Paths",
+        }
+    }
+}
+
+/// Sans-serif faces tried, in order, for labels whose font is not installed
+const FALLBACK_FONTS: &[&str] = &["Noto Sans", "DejaVu Sans", "Liberation Sans", "Arial", "Helvetica"];
+
+/// Fonts for rendering labels: the system fonts plus any TTF/OTF files in `assets/fonts`.
+/// Scanning them is slow, so the database is built once and shared.
+pub(crate) fn font_database() -> std::sync::Arc<resvg::usvg::fontdb::Database> {
+    static FONTS: std::sync::OnceLock<std::sync::Arc<resvg::usvg::fontdb::Database>> = std::sync::OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut fontdb = resvg::usvg::fontdb::Database::new();
+            fontdb.load_system_fonts();
+            fontdb.load_fonts_dir("assets/fonts");
+            // Labels name fonts that may not be installed, and usvg then falls back to the serif
+            // family; point both generic families at a sans-serif face that is actually there
+            let fallback = FALLBACK_FONTS.iter().find(|family| {
+                fontdb.faces().any(|face| face.families.iter().any(|(name, _)| name == *family))
+            });
+            if let Some(family) = fallback {
+                fontdb.set_sans_serif_family(*family);
+                fontdb.set_serif_family(*family);
+            }
+            std::sync::Arc::new(fontdb)
+        })
+        .clone()
+}
+
+/// What an export actually drew, for checking completeness in batch jobs
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ExportStats {
+    /// Way paths added to the document (a way drawn in two layers counts twice)
+    pub ways_rendered: usize,
+    pub nodes_rendered: usize,
+    pub labels_placed: usize,
+    /// Named features whose label was not drawn (filtered out or no room along the way)
+    pub labels_skipped: usize,
+    pub bytes_written: u64,
+}
+
+impl std::fmt::Display for ExportStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ways, {} nodes, {} labels placed, {} labels skipped, {} bytes",
+            self.ways_rendered, self.nodes_rendered, self.labels_placed, self.labels_skipped, self.bytes_written
+        )
+    }
+}
+
+/// Passes writes through while counting bytes, so streamed exports can still report their size
+pub(crate) struct CountingWriter<W> {
+    inner: W,
+    bytes_written: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner, bytes_written: 0 }
+    }
+
+    pub(crate) fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes_written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Render an SVG document to PNG bytes at `width` x `height` pixels
+pub(crate) fn rasterize_svg(svg_data: &str, width: u32, height: u32) -> Result<Vec<u8>> {
+    Ok(render_svg(svg_data, width, height)?.encode_png()?)
+}
+
+/// Render an SVG document to JPEG bytes at `width` x `height` pixels. JPEG has no alpha, so
+/// whatever the document leaves transparent shows `background` instead.
+pub(crate) fn rasterize_svg_jpeg(svg_data: &str, width: u32, height: u32, background: crate::parsers::stylesheet::Color, quality: u8) -> Result<Vec<u8>> {
+    let pixmap = render_svg(svg_data, width, height)?;
+
+    // The pixmap is premultiplied, so compositing over the background is a single blend per channel
+    let blend = |channel: u8, under: u8, alpha: u8| channel + ((under as u16 * (255 - alpha) as u16 + 127) / 255) as u8;
+    let rgb: Vec<u8> = pixmap.pixels().iter()
+        .flat_map(|pixel| {
+            let alpha = pixel.alpha();
+            [
+                blend(pixel.red(), background.r, alpha),
+                blend(pixel.green(), background.g, alpha),
+                blend(pixel.blue(), background.b, alpha),
+            ]
+        })
+        .collect();
+
+    let mut jpeg = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, quality)
+        .encode(&rgb, width, height, image::ExtendedColorType::Rgb8)?;
+    Ok(jpeg)
+}
+
+fn render_svg(svg_data: &str, width: u32, height: u32) -> Result<resvg::tiny_skia::Pixmap> {
+    let options = resvg::usvg::Options { fontdb: font_database(), ..Default::default() };
+
+    let tree = resvg::usvg::Tree::from_str(svg_data, &options)?;
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| anyhow::anyhow!("Failed to allocate {}x{} image", width, height))?;
+
+    let tree_size = tree.size();
+    let transform = resvg::tiny_skia::Transform::from_scale(
+        width as f32 / tree_size.width(),
+        height as f32 / tree_size.height(),
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+    Ok(pixmap)
+}
+
+/// JPEG quality used when the options don't set one
+pub const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+/// Resolution style widths are authored at (CSS pixels, and what the map view draws with)
+pub const BASELINE_DPI: f32 = 96.0;
+
+/// Credit OpenStreetMap data must carry wherever it is shown
+pub const OSM_ATTRIBUTION: &str = "© OpenStreetMap contributors";
+
+/// Text stamped in a corner of an export, outside the map layers: the data attribution, with
+/// an optional title and date above it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attribution {
+    pub text: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Written as given, e.g. `"October 2026"`
+    #[serde(default)]
+    pub date: Option<String>,
+    #[serde(default = "Attribution::default_position")]
+    pub position: OverlayPosition,
+    /// `None` uses the style's label font
+    #[serde(default)]
+    pub font_family: Option<String>,
+    /// In pixels at 96 DPI; the title is drawn a third larger
+    #[serde(default = "Attribution::default_font_size")]
+    pub font_size: f32,
+    /// Distance from the canvas edges in pixels
+    #[serde(default = "Attribution::default_margin")]
+    pub margin: f32,
+}
+
+impl Attribution {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            title: None,
+            date: None,
+            position: Self::default_position(),
+            font_family: None,
+            font_size: Self::default_font_size(),
+            margin: Self::default_margin(),
+        }
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn with_date(mut self, date: impl Into<String>) -> Self {
+        self.date = Some(date.into());
+        self
+    }
+
+    pub fn with_position(mut self, position: OverlayPosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_font(mut self, family: impl Into<String>, size: f32) -> Self {
+        self.font_family = Some(family.into());
+        self.font_size = size;
+        self
+    }
+
+    /// Bottom right, where it stays when a map is cropped from the top left as most tools do
+    fn default_position() -> OverlayPosition {
+        OverlayPosition::BottomRight
+    }
+
+    fn default_font_size() -> f32 {
+        10.0
+    }
+
+    fn default_margin() -> f32 {
+        6.0
+    }
+}
+
+impl Default for Attribution {
+    fn default() -> Self {
+        Self::new(OSM_ATTRIBUTION)
+    }
+}
+
 /// Export configuration options
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportOptions {
@@ -25,10 +344,65 @@ pub struct ExportOptions {
     pub output_path: String,
     pub width: u32,
     pub height: u32,
+    /// Output resolution; line widths are scaled by `dpi / BASELINE_DPI`
     pub dpi: f32,
-    pub background_color: Option<crate::parsers::stylesheet::Color>,
+    /// What fills the canvas behind the map, by default the background colour of `style`
+    #[serde(default)]
+    pub background: svg_export::SvgBackground,
+    /// Raster or SVG image embedded behind the map, drawn over the background colour
+    #[serde(default)]
+    pub background_image: Option<String>,
     pub quality: Option<u8>, // For JPEG
     pub compression: Option<u8>, // For PNG
+    #[serde(default)]
+    pub shape_rendering: ShapeRendering, // For SVG
+    #[serde(default)]
+    pub text_rendering: TextRendering, // For SVG
+    /// Map bearing in degrees, clockwise from north-up
+    #[serde(default)]
+    pub rotation: f64,
+    /// Label every named road instead of only the major ones. Follows the map view's
+    /// toggle, so it is not saved with the other options.
+    #[serde(skip)]
+    pub all_road_names: bool,
+    /// Name of the style to render with (e.g. "osm-default"); `None` uses the default style
+    #[serde(default)]
+    pub style: Option<String>,
+    /// Colour adjustment applied to the style, as in the map view's palette settings
+    #[serde(default)]
+    pub color_transform: ColorTransform,
+    #[serde(default)]
+    pub svg_text: SvgTextMode, // For SVG
+    /// Write every vertex of every way instead of cutting geometry at the canvas edge
+    #[serde(default)]
+    pub full_geometry: bool,
+    /// Split way segments longer than this many meters so they follow the great circle;
+    /// only noticeable on maps covering a wide area
+    #[serde(default)]
+    pub densify: Option<f64>,
+    /// Snap SVG coordinates to multiples of this many pixels to shrink files; positions move
+    /// by at most half a step per axis. `None` keeps three decimals (a 0.001 px grid).
+    #[serde(default)]
+    pub coordinate_grid: Option<f64>,
+    /// Drop way vertices within this many pixels (at 96 DPI) of the line through their
+    /// neighbours; `None` writes every vertex
+    #[serde(default)]
+    pub simplify_tolerance: Option<f64>,
+    /// Tolerance for the centerlines road labels follow, kept apart from `simplify_tolerance`
+    /// so labels don't jitter on coarse geometry; `None` follows every node
+    #[serde(default)]
+    pub label_simplify_tolerance: Option<f64>,
+    /// Layers left out of the export, by id (e.g. `"labels"`)
+    #[serde(default)]
+    pub hidden_layers: Vec<String>,
+    /// Stamped on the export, the OpenStreetMap credit by default; `None` leaves it off
+    #[serde(default = "ExportOptions::default_attribution")]
+    pub attribution: Option<Attribution>,
+    /// Outline in longitude and latitude (x = lon, y = lat) to cut the map to, e.g. a district
+    /// from [`MapData::outline`]; the background outside it is left blank. Taken from the
+    /// current selection, so it is not saved with the other options.
+    #[serde(skip)]
+    pub clip_polygon: Option<Vec<Coord<f64>>>,
 }
 
 impl ExportOptions {
@@ -38,13 +412,33 @@ impl ExportOptions {
             output_path,
             width: 1024,
             height: 768,
-            dpi: 300.0,
-            background_color: None,
-            quality: Some(90),
+            dpi: BASELINE_DPI,
+            background: svg_export::SvgBackground::Style,
+            background_image: None,
+            quality: Some(DEFAULT_JPEG_QUALITY),
             compression: Some(6),
+            shape_rendering: ShapeRendering::default(),
+            text_rendering: TextRendering::default(),
+            rotation: 0.0,
+            all_road_names: false,
+            style: None,
+            color_transform: ColorTransform::IDENTITY,
+            svg_text: SvgTextMode::default(),
+            full_geometry: false,
+            densify: None,
+            coordinate_grid: None,
+            simplify_tolerance: None,
+            label_simplify_tolerance: None,
+            hidden_layers: Vec::new(),
+            attribution: Self::default_attribution(),
+            clip_polygon: None,
         }
     }
     
+    fn default_attribution() -> Option<Attribution> {
+        Some(Attribution::default())
+    }
+
     pub fn with_size(mut self, width: u32, height: u32) -> Self {
         self.width = width;
         self.height = height;
@@ -57,14 +451,114 @@ impl ExportOptions {
     }
     
     pub fn with_background(mut self, color: crate::parsers::stylesheet::Color) -> Self {
-        self.background_color = Some(color);
+        self.background = svg_export::SvgBackground::Color(color);
         self
     }
     
+    pub fn with_transparent_background(mut self) -> Self {
+        self.background = svg_export::SvgBackground::Transparent;
+        self
+    }
+
+    pub fn with_background_image(mut self, path: impl Into<String>) -> Self {
+        self.background_image = Some(path.into());
+        self
+    }
+
     pub fn with_quality(mut self, quality: u8) -> Self {
         self.quality = Some(quality);
         self
     }
+    
+    pub fn with_shape_rendering(mut self, shape_rendering: ShapeRendering) -> Self {
+        self.shape_rendering = shape_rendering;
+        self
+    }
+    
+    pub fn with_text_rendering(mut self, text_rendering: TextRendering) -> Self {
+        self.text_rendering = text_rendering;
+        self
+    }
+
+    pub fn with_rotation(mut self, degrees: f64) -> Self {
+        self.rotation = degrees;
+        self
+    }
+
+    pub fn with_all_road_names(mut self, enabled: bool) -> Self {
+        self.all_road_names = enabled;
+        self
+    }
+
+    pub fn with_style(mut self, name: impl Into<String>) -> Self {
+        self.style = Some(name.into());
+        self
+    }
+
+    pub fn with_color_transform(mut self, transform: ColorTransform) -> Self {
+        self.color_transform = transform;
+        self
+    }
+
+    pub fn with_svg_text(mut self, mode: SvgTextMode) -> Self {
+        self.svg_text = mode;
+        self
+    }
+
+    pub fn with_full_geometry(mut self, enabled: bool) -> Self {
+        self.full_geometry = enabled;
+        self
+    }
+
+    pub fn with_densify(mut self, max_segment_length: Option<f64>) -> Self {
+        self.densify = max_segment_length;
+        self
+    }
+
+    pub fn with_coordinate_grid(mut self, step: Option<f64>) -> Self {
+        self.coordinate_grid = step;
+        self
+    }
+
+    pub fn with_simplify_tolerance(mut self, tolerance: Option<f64>) -> Self {
+        self.simplify_tolerance = tolerance;
+        self
+    }
+
+    pub fn with_label_simplify_tolerance(mut self, tolerance: Option<f64>) -> Self {
+        self.label_simplify_tolerance = tolerance;
+        self
+    }
+
+    pub fn with_hidden_layers(mut self, layers: Vec<String>) -> Self {
+        self.hidden_layers = layers;
+        self
+    }
+
+    pub fn with_attribution(mut self, attribution: Option<Attribution>) -> Self {
+        self.attribution = attribution;
+        self
+    }
+
+    pub fn with_clip_polygon(mut self, outline: Option<Vec<Coord<f64>>>) -> Self {
+        self.clip_polygon = outline;
+        self
+    }
+}
+
+/// The part of the map an export shows: the center and the scale in pixels per degree of
+/// latitude. The output size comes from the options or the caller.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportView {
+    pub center_lat: f64,
+    pub center_lon: f64,
+    pub scale: f64,
+}
+
+impl ExportView {
+    pub fn new(center_lat: f64, center_lon: f64, scale: f64) -> Self {
+        Self { center_lat, center_lon, scale }
+    }
 }
 
 /// Main exporter that handles different output formats
@@ -76,70 +570,156 @@ impl Exporter {
     }
     
     pub fn export_map_with_viewport(
+        &self,
+        map_data: &MapData,
+        renderer: &MapRenderer,
+        options: &ExportOptions,
+        view: ExportView,
+    ) -> Result<(), ExportError> {
+        // Check the options first so failing exports don't leave an empty file behind
+        Self::ensure_supported(options.format)?;
+        self.validate_options(options)?;
+        let file = BufWriter::new(File::create(&options.output_path)?);
+        let stats = self.export_map_with_viewport_to_writer(
+            map_data,
+            renderer,
+            options,
+            file,
+            view,
+        )?;
+        log::info!("Exported {}: {}", options.output_path, stats);
+        Ok(())
+    }
+
+    /// Same as `export_map_with_viewport`, writing into `writer` instead of `options.output_path`
+    pub fn export_map_with_viewport_to_writer<W: Write>(
         &self,
         map_data: &MapData,
         _renderer: &MapRenderer,
         options: &ExportOptions,
-        center_lat: f64,
-        center_lon: f64,
-        scale: f64,
-        show_all_road_names: bool,
-    ) -> Result<()> {
+        writer: W,
+        view: ExportView,
+    ) -> Result<ExportStats, ExportError> {
+        Self::ensure_supported(options.format)?;
+        let mut exporter = svg_export::SvgExporter::new()?
+            .with_all_road_names(options.all_road_names)
+            .with_shape_rendering(options.shape_rendering)
+            .with_text_rendering(options.text_rendering)
+            .with_rotation(options.rotation)
+            .with_dpi(options.dpi)
+            .with_text_mode(options.svg_text)
+            .with_clipping(!options.full_geometry)
+            .with_densification(options.densify)
+            .with_coordinate_grid(options.coordinate_grid)
+            .with_simplification(options.simplify_tolerance)
+            .with_label_simplification(options.label_simplify_tolerance)
+            .with_hidden_layers(options.hidden_layers.iter().cloned())
+            .with_attribution(options.attribution.clone())
+            .with_clip_polygon(options.clip_polygon.clone())
+            .with_background(options.background.clone())
+            .with_background_image(options.background_image.as_ref().map(std::path::PathBuf::from));
+        if let Some(style) = &options.style {
+            exporter.style_manager.load_style(style)?;
+        }
+        if options.color_transform != ColorTransform::IDENTITY {
+            exporter.style_manager.set_color_transform(options.color_transform)?;
+        }
+
         match options.format {
-            ExportFormat::Svg => {
-                let exporter = svg_export::SvgExporter::new()?
-                    .with_all_road_names(show_all_road_names);
-                exporter.export_with_data(
-                    map_data,
-                    &options.output_path, 
-                    options.width, 
-                    options.height,
-                    center_lat,
-                    center_lon,
-                    scale,
-                )
-            }
             ExportFormat::Png => {
-                Err(anyhow::anyhow!("PNG export not available yet - use SVG instead"))
+                let (document, stats) = exporter.build_document_with_stats(map_data, options.width, options.height, view);
+                let png = rasterize_svg(&document.to_string(), options.width, options.height)?;
+                let mut writer = writer;
+                writer.write_all(&png)?;
+                writer.flush()?;
+                Ok(ExportStats { bytes_written: png.len() as u64, ..stats })
             }
             ExportFormat::Jpeg => {
-                Err(anyhow::anyhow!("JPEG export not available yet - use SVG instead"))
-            }
-            ExportFormat::Pdf => {
-                Err(anyhow::anyhow!("PDF export not implemented yet"))
+                let (document, stats) = exporter.build_document_with_stats(map_data, options.width, options.height, view);
+                let background = exporter.background_color().unwrap_or(crate::parsers::stylesheet::Color::new(255, 255, 255, 255));
+                let quality = options.quality.unwrap_or(DEFAULT_JPEG_QUALITY);
+                let jpeg = rasterize_svg_jpeg(&document.to_string(), options.width, options.height, background, quality)?;
+                let mut writer = writer;
+                writer.write_all(&jpeg)?;
+                writer.flush()?;
+                Ok(ExportStats { bytes_written: jpeg.len() as u64, ..stats })
             }
+            _ => Ok(exporter.export_with_data_to_writer(map_data, writer, options.width, options.height, view)?),
         }
     }
 
+    /// Export the same view once per style into `output_dir`, one `<style>.<ext>` file each,
+    /// for comparing styles side by side. The data is parsed once and shared by all exports.
+    /// Unless `options` sets another background, each file gets its own style's.
+    pub fn export_styles(
+        &self,
+        map_data: &MapData,
+        renderer: &MapRenderer,
+        options: &ExportOptions,
+        styles: &[&str],
+        output_dir: &Path,
+        view: ExportView,
+    ) -> Result<Vec<PathBuf>, ExportError> {
+        if !output_dir.is_dir() {
+            return Err(ExportError::OutputDirMissing(output_dir.to_path_buf()));
+        }
+
+        let mut written = Vec::with_capacity(styles.len());
+        for &style in styles {
+            let output_path = output_dir.join(format!("{}.{}", style, Self::get_extension(options.format)));
+            let mut style_options = options.clone().with_style(style);
+            style_options.output_path = output_path.to_string_lossy().to_string();
+
+            self.export_map_with_viewport(map_data, renderer, &style_options, view)?;
+            written.push(output_path);
+        }
+        Ok(written)
+    }
+
+    /// Export the whole map, fitted into `options.width` by `options.height` with the default
+    /// padding around it
     pub fn export_map(
         &self,
-        _map_data: &MapData,
-        _renderer: &MapRenderer,
+        map_data: &MapData,
+        renderer: &MapRenderer,
         options: &ExportOptions,
-    ) -> Result<()> {
-        // Create a minimal rendered map for now
-        let rendered_map = RenderedMap {
-            elements: Vec::new(),
-        };
-        
-        match options.format {
-            ExportFormat::Svg => {
-                svg_export::SvgExporter::new()?.export(
-                    &rendered_map, 
-                    &options.output_path, 
-                    options.width, 
-                    options.height
-                )
-            }
-            ExportFormat::Png => {
-                Err(anyhow::anyhow!("PNG export not available yet - use SVG instead"))
-            }
-            ExportFormat::Jpeg => {
-                Err(anyhow::anyhow!("JPEG export not available yet - use SVG instead"))
-            }
-            ExportFormat::Pdf => {
-                Err(anyhow::anyhow!("PDF export not implemented yet"))
-            }
+    ) -> Result<(), ExportError> {
+        let view = Self::fit_view(map_data, options)?;
+        self.export_map_with_viewport(map_data, renderer, options, view)
+    }
+
+    /// Same as `export_map`, writing into `writer` instead of `options.output_path`
+    pub fn export_map_to_writer<W: Write>(
+        &self,
+        map_data: &MapData,
+        renderer: &MapRenderer,
+        options: &ExportOptions,
+        writer: W,
+    ) -> Result<(), ExportError> {
+        let view = Self::fit_view(map_data, options)?;
+        self.export_map_with_viewport_to_writer(map_data, renderer, options, writer, view)?;
+        Ok(())
+    }
+
+    /// The view that fits the data bounds into the output
+    fn fit_view(map_data: &MapData, options: &ExportOptions) -> Result<ExportView, ExportError> {
+        let bounds = &map_data.bounds;
+        if !bounds.min_lat.is_finite() || !bounds.min_lon.is_finite() {
+            return Err(ExportError::NoData);
+        }
+        let (center_lat, center_lon) = bounds.center();
+        let padding = FitPadding::default();
+        let width = (bounds.width() * center_lat.to_radians().cos()).max(MIN_MAP_EXTENT);
+        let height = bounds.height().max(MIN_MAP_EXTENT);
+        let scale = (padding.inner(options.width as f64) / width).min(padding.inner(options.height as f64) / height);
+        Ok(ExportView::new(center_lat, center_lon, scale))
+    }
+
+    /// Formats the map exporters can write today
+    fn ensure_supported(format: ExportFormat) -> Result<(), ExportError> {
+        match format {
+            ExportFormat::Svg | ExportFormat::Png | ExportFormat::Jpeg => Ok(()),
+            ExportFormat::Pdf => Err(ExportError::UnsupportedFormat(format)),
         }
     }
     
@@ -154,22 +734,143 @@ impl Exporter {
     }
     
     /// Validate export options
-    pub fn validate_options(&self, options: &ExportOptions) -> Result<()> {
+    pub fn validate_options(&self, options: &ExportOptions) -> Result<(), ExportError> {
         if options.width == 0 || options.height == 0 {
-            return Err(anyhow::anyhow!("Width and height must be greater than 0"));
+            return Err(ExportError::InvalidDimensions { width: options.width, height: options.height });
         }
-        
         if options.dpi <= 0.0 {
-            return Err(anyhow::anyhow!("DPI must be greater than 0"));
+            return Err(ExportError::InvalidDpi(options.dpi));
         }
         
-        // Check if output directory exists
-        if let Some(parent) = Path::new(&options.output_path).parent() {
+        // The canvas has already grown with the DPI; the limit is on the size asked for
+        if matches!(options.format, ExportFormat::Png | ExportFormat::Jpeg) {
+            let unscaled = |pixels: u32| (pixels as f32 * BASELINE_DPI / options.dpi).round() as u32;
+            let (width, height) = (unscaled(options.width), unscaled(options.height));
+            if width > MAX_EXPORT_SIZE || height > MAX_EXPORT_SIZE {
+                return Err(ExportError::TooLarge { width, height, max: MAX_EXPORT_SIZE });
+            }
+        }
+
+        if let Some(quality) = options.quality.filter(|quality| !(1..=100).contains(quality)) {
+            return Err(ExportError::InvalidQuality(quality));
+        }
+
+        // Check if output directory exists; a bare file name goes to the current directory
+        if let Some(parent) = Path::new(&options.output_path).parent().filter(|parent| !parent.as_os_str().is_empty()) {
             if !parent.exists() {
-                return Err(anyhow::anyhow!("Output directory does not exist: {}", parent.display()));
+                return Err(ExportError::OutputDirMissing(parent.to_path_buf()));
             }
         }
         
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::stylesheet::Color;
+
+    /// Export a JPEG of an empty map and decode it back
+    fn export_jpeg(options: &ExportOptions) -> image::DynamicImage {
+        let mut jpeg = Vec::new();
+        Exporter::new()
+            .export_map_with_viewport_to_writer(&MapData::new(), &MapRenderer::new(), options, &mut jpeg, ExportView::new(48.853, 2.3499, 50_000.0))
+            .unwrap();
+        image::load_from_memory_with_format(&jpeg, image::ImageFormat::Jpeg).unwrap()
+    }
+
+    /// The corners clear of the north arrow, which sits top right
+    fn corners(image: &image::RgbImage) -> [image::Rgb<u8>; 3] {
+        let (width, height) = image.dimensions();
+        [*image.get_pixel(0, 0), *image.get_pixel(0, height - 1), *image.get_pixel(width - 1, height - 1)]
+    }
+
+    fn assert_near(pixel: image::Rgb<u8>, color: Color) {
+        // JPEG is lossy, but a flat area comes back within a few levels
+        let near = |channel: u8, expected: u8| (channel as i16 - expected as i16).abs() <= 4;
+        assert!(
+            near(pixel[0], color.r) && near(pixel[1], color.g) && near(pixel[2], color.b),
+            "{:?} is not close to {:?}",
+            pixel, color
+        );
+    }
+
+    #[test]
+    fn jpeg_export_has_the_background_and_no_alpha() {
+        let background = Color::new(30, 120, 200, 255);
+        let options = ExportOptions::new(ExportFormat::Jpeg, "map.jpg".to_string())
+            .with_size(320, 200)
+            .with_background(background)
+            .with_attribution(None);
+        let image = export_jpeg(&options);
+
+        assert_eq!((image.width(), image.height()), (320, 200));
+        assert_eq!(image.color(), image::ColorType::Rgb8);
+        for pixel in corners(&image.to_rgb8()) {
+            assert_near(pixel, background);
+        }
+    }
+
+    #[test]
+    fn jpeg_export_composites_a_transparent_background_over_white() {
+        let options = ExportOptions::new(ExportFormat::Jpeg, "map.jpg".to_string())
+            .with_size(300, 300)
+            .with_transparent_background()
+            .with_attribution(None);
+        let image = export_jpeg(&options);
+
+        assert_eq!((image.width(), image.height()), (300, 300));
+        assert!(!image.color().has_alpha());
+        for pixel in corners(&image.to_rgb8()) {
+            assert_near(pixel, Color::new(255, 255, 255, 255));
+        }
+    }
+
+    #[test]
+    fn size_limit_applies_before_dpi_scaling() {
+        let exporter = Exporter::new();
+        // 2000 px at 1200 DPI renders 25000 px wide, but 2000 px were asked for
+        let options = ExportOptions::new(ExportFormat::Png, "map.png".to_string())
+            .with_size(25_000, 12_500)
+            .with_dpi(1200.0);
+        assert!(exporter.validate_options(&options).is_ok());
+
+        let too_large = options.with_dpi(BASELINE_DPI);
+        assert!(matches!(
+            exporter.validate_options(&too_large),
+            Err(ExportError::TooLarge { width: 25_000, height: 12_500, max: MAX_EXPORT_SIZE })
+        ));
+        // SVG is never rasterized
+        let svg = ExportOptions { format: ExportFormat::Svg, ..too_large };
+        assert!(exporter.validate_options(&svg).is_ok());
+    }
+
+    #[test]
+    fn background_defaults_to_the_style_colour() {
+        let options = ExportOptions::new(ExportFormat::Jpeg, "map.jpg".to_string())
+            .with_size(200, 200)
+            .with_attribution(None);
+        assert!(matches!(options.background, svg_export::SvgBackground::Style));
+        for pixel in corners(&export_jpeg(&options).to_rgb8()) {
+            assert_near(pixel, Color::new(0xF2, 0xF1, 0xEC, 255));
+        }
+
+        // The colour is the one of the style drawn with, after its colour transform
+        let osm = options.clone().with_style("osm-default");
+        for pixel in corners(&export_jpeg(&osm).to_rgb8()) {
+            assert_near(pixel, Color::new(0xF2, 0xF0, 0xE9, 255));
+        }
+        let transform = ColorTransform { brightness: 0.5, ..ColorTransform::IDENTITY };
+        let darkened = transform.apply(Color::new(0xF2, 0xF0, 0xE9, 255));
+        for pixel in corners(&export_jpeg(&osm.clone().with_color_transform(transform)).to_rgb8()) {
+            assert_near(pixel, darkened);
+        }
+
+        // A chosen colour stays whichever style is used
+        let chosen = Color::new(10, 20, 30, 255);
+        for pixel in corners(&export_jpeg(&osm.with_background(chosen).with_style("google-maps")).to_rgb8()) {
+            assert_near(pixel, chosen);
+        }
+    }
+}
@@ -1,18 +1,327 @@
-use svg::node::element::{Group, Rectangle, Text, Path, Circle, Element};
+use svg::node::element::{Group, Rectangle, Text, TSpan, Path, Circle, ClipPath, Element, Image, Definitions, Line, Pattern, Style};
 use svg::node::element::path::Data;
-use svg::node::{Node, Text as TextNode};
+use svg::node::{Blob, Node, Text as TextNode};
 use svg::Document;
 use anyhow::Result;
 use crate::rendering::{RenderedMap, RenderElement, ElementStyle};
-use crate::core::MapData;
-use crate::parsers::stylesheet::Color;
-use crate::styles::loader::StyleManager;
+use crate::core::{ElementType, MapData, ProjectionSystem};
+use crate::core::geometry::{ClipPolygon, ClipRect, GeometryUtils, Transform2D};
+use crate::core::projection::{project, METERS_PER_DEGREE};
+use crate::export::{font_database, Attribution, CountingWriter, ExportStats, ExportView, ShapeRendering, SvgTextMode, TextRendering, BASELINE_DPI};
+use crate::parsers::stylesheet::{Color, FillPattern, LineCap, LineJoin, PatternKind};
+use crate::rendering::appearance::{building_parts_in_draw_order, road_class, roads_in_draw_order, way_appearance, FootprintShaping, LineDecoration, WayAppearance, WayPass, WayStroke};
+use crate::rendering::carriageways::carriageway_offsets;
+use crate::rendering::clustering::{cluster_points, PoiCluster};
+use crate::rendering::labels::{direction_chevrons, display_name, housenumber_labels, label_position, label_priority, line_offsets, perpendicular_ticks, place_labels, points_along, shield_text, wrap_label, LabelDensityGrid};
+use crate::rendering::north_arrow::NorthArrowShape;
+use crate::rendering::patterns::pattern_id;
+use crate::rendering::road_nodes::{road_node_marks, RoadNodeMark};
+use crate::styles::loader::{LabelPlacement, LabelStyle, LineSymbol, MapStyle, OverlayPosition, RoadNodeShape, StyleManager};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use base64::Engine;
+
+/// How far past the canvas edge clipped geometry reaches, in style pixels, so wide strokes
+/// and casings never show their cut ends
+const CLIP_MARGIN: f32 = 32.0;
+
+/// Largest shift of the clip region's outline when it is simplified, in output pixels
+const CLIP_REGION_TOLERANCE: f64 = 0.25;
+
+/// Id of the `<clipPath>` holding the clip region
+const CLIP_REGION_ID: &str = "clip-region";
+
+/// Inkscape labels and fill rules of the built-in layers; other layers are labelled with their name
+const BUILTIN_LAYERS: &[(&str, &str, bool)] = &[
+    ("water", "Water", true),
+    ("landuse", "Land Use", true),
+    ("aeroway", "Aeroway", true),
+    ("buildings", "Buildings", true),
+    ("barriers", "Barriers and Cliffs", false),
+    ("railway", "Railway", false),
+    ("roads", "Roads", false),
+    ("boundaries", "Boundaries", false),
+    ("pois", "Points of Interest", false),
+    ("shields", "Road Shields", false),
+    ("labels", "Labels", false),
+];
+
+/// The way passes in drawing order, with the layer each one draws into unless a layer rule
+/// moves the way elsewhere. Roads come after them.
+const WAY_PASSES: [(WayPass, &str); 8] = [
+    (WayPass::Water, "water"),
+    (WayPass::Landuse, "landuse"),
+    (WayPass::Aeroway, "aeroway"),
+    (WayPass::Building, "buildings"),
+    // Building parts over the outlines they detail
+    (WayPass::BuildingPart, "buildings"),
+    (WayPass::Decorated, "barriers"),
+    (WayPass::Railway, "railway"),
+    (WayPass::Boundary, "boundaries"),
+];
+
+/// The Inkscape layer group for `name`, without content
+fn layer_group(name: &str) -> Group {
+    let even_odd = BUILTIN_LAYERS.iter().find(|(id, _, _)| *id == name).is_none_or(|&(_, _, even_odd)| even_odd);
+    let group = Group::new().set("id", name);
+    let group = if even_odd { group.set("fill-rule", "evenodd") } else { group };
+    group.set("inkscape:label", layer_label(name)).set("inkscape:groupmode", "layer")
+}
+
+/// Put `layer` right above `default_layer` in the stacking order, or on top if that is not
+/// stacked either; layers already stacked stay where they are
+fn stack_layer<'a>(order: &mut Vec<&'a str>, layer: &'a str, default_layer: &str) {
+    if !order.contains(&layer) {
+        let position = order.iter().position(|name| *name == default_layer).map_or(order.len(), |i| i + 1);
+        order.insert(position, layer);
+    }
+}
+
+/// Where way geometry is cut, in output pixels
+struct ExportClip {
+    /// The canvas with a margin, unless full geometry was asked for
+    canvas: Option<ClipRect>,
+    region: Option<ClipPolygon>,
+}
+
+/// A road that survived clipping, kept from the road pass until its layer is drawn
+struct RoadDraw<'a> {
+    way: &'a crate::core::Way,
+    /// Export layer the road goes to
+    layer: &'a str,
+    look: WayAppearance,
+    data: Data,
+    /// Shift off the centerline, in output pixels
+    offset: f64,
+}
+
+/// Receives the export in document order: the root and the map group, then each layer's
+/// elements between `start_layer` and `end_layer`, then the defs and overlays
+trait SvgSink {
+    /// `root` and `map_group` come with their attributes, and the map group with the background
+    fn begin(&mut self, root: Document, map_group: Group);
+    fn start_layer(&mut self, group: Group);
+    fn add<T: Into<Box<dyn Node>>>(&mut self, node: T);
+    fn end_layer(&mut self);
+    fn finish(&mut self, defs: Option<Definitions>, overlays: Vec<Group>);
+}
+
+/// Assembles the export into a document, for rasterizing and outlining labels
+#[derive(Default)]
+struct DocumentSink {
+    root: Option<Document>,
+    map_group: Option<Group>,
+    layer: Option<Group>,
+}
+
+impl DocumentSink {
+    fn into_document(self) -> Document {
+        self.root.unwrap_or_else(Document::new)
+    }
+}
+
+impl SvgSink for DocumentSink {
+    fn begin(&mut self, root: Document, map_group: Group) {
+        self.root = Some(root);
+        self.map_group = Some(map_group);
+    }
+
+    fn start_layer(&mut self, group: Group) {
+        self.layer = Some(group);
+    }
+
+    fn add<T: Into<Box<dyn Node>>>(&mut self, node: T) {
+        if let Some(layer) = self.layer.as_mut() {
+            layer.append(node);
+        }
+    }
+
+    fn end_layer(&mut self) {
+        if let (Some(map_group), Some(layer)) = (self.map_group.as_mut(), self.layer.take()) {
+            map_group.append(layer);
+        }
+    }
+
+    fn finish(&mut self, defs: Option<Definitions>, overlays: Vec<Group>) {
+        let Some(root) = self.root.as_mut() else {
+            return;
+        };
+        if let Some(map_group) = self.map_group.take() {
+            root.append(map_group);
+        }
+        if let Some(defs) = defs {
+            root.append(defs);
+        }
+        for overlay in overlays {
+            root.append(overlay);
+        }
+    }
+}
+
+/// Writes the export as it is drawn, so no element outlives the moment it is written. The
+/// text is the same a `DocumentSink` document serializes to. The first write error stops
+/// the writing and is handed back by `into_result`.
+struct StreamSink<W: Write> {
+    writer: CountingWriter<W>,
+    /// Closing tags of the map group and the root, in writing order
+    closing: Vec<String>,
+    /// The current layer's group until its first element is written, so an empty layer
+    /// comes out as a single empty tag
+    pending_layer: Option<Group>,
+    layer_closing: Option<String>,
+    /// Written last inside the root, as `SvgExporter::finish_document` adds the embedded font
+    trailer: Option<Style>,
+    error: Option<std::io::Error>,
+}
+
+impl<W: Write> StreamSink<W> {
+    fn new(writer: W, trailer: Option<Style>) -> Self {
+        Self {
+            writer: CountingWriter::new(writer),
+            closing: Vec::new(),
+            pending_layer: None,
+            layer_closing: None,
+            trailer,
+            error: None,
+        }
+    }
+
+    fn write(&mut self, text: impl std::fmt::Display) {
+        if self.error.is_none() {
+            if let Err(e) = write!(self.writer, "{}", text) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Bytes written, once everything is flushed
+    fn into_result(mut self) -> std::io::Result<u64> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.writer.flush()?;
+        Ok(self.writer.bytes_written())
+    }
+}
+
+impl<W: Write> SvgSink for StreamSink<W> {
+    fn begin(&mut self, root: Document, map_group: Group) {
+        let (root_open, root_close) = split_tags(root);
+        let (map_open, map_close) = split_tags(map_group);
+        self.write(root_open);
+        self.write(format_args!("\n{}", map_open));
+        self.closing = vec![map_close, root_close];
+    }
+
+    fn start_layer(&mut self, group: Group) {
+        self.pending_layer = Some(group);
+    }
+
+    fn add<T: Into<Box<dyn Node>>>(&mut self, node: T) {
+        if let Some(group) = self.pending_layer.take() {
+            let (open, close) = split_tags(group);
+            self.write(format_args!("\n{}", open));
+            self.layer_closing = Some(close);
+        }
+        self.write(format_args!("\n{}", node.into()));
+    }
+
+    fn end_layer(&mut self) {
+        match self.pending_layer.take() {
+            Some(group) => self.write(format_args!("\n{}", group)),
+            None => {
+                if let Some(close) = self.layer_closing.take() {
+                    self.write(close);
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, defs: Option<Definitions>, overlays: Vec<Group>) {
+        let mut closing = std::mem::take(&mut self.closing).into_iter();
+        if let Some(map_close) = closing.next() {
+            self.write(map_close);
+        }
+        if let Some(defs) = defs {
+            self.write(format_args!("\n{}", defs));
+        }
+        for overlay in overlays {
+            self.write(format_args!("\n{}", overlay));
+        }
+        if let Some(style) = self.trailer.take() {
+            self.write(format_args!("\n{}", style));
+        }
+        for close in closing {
+            self.write(close);
+        }
+    }
+}
+
+/// The opening tag of an element together with the content it already has, and its closing
+/// tag, written as they would be around one more child
+fn split_tags<T: Node>(mut element: T) -> (String, String) {
+    const PLACEHOLDER: &str = "<!--content-->";
+    element.append(Blob::new(PLACEHOLDER));
+    let text = element.to_string();
+    let (open, close) = text.split_once(PLACEHOLDER).expect("placeholder was just added");
+    (open.trim_end_matches('\n').to_string(), close.to_string())
+}
+
+/// Name shown for an export layer, e.g. "Points of Interest" for `pois`; layers the style
+/// makes up are shown by their id
+pub fn layer_label(name: &str) -> &str {
+    BUILTIN_LAYERS.iter().find(|(id, _, _)| *id == name).map_or(name, |&(_, label, _)| label)
+}
+
+/// What fills the canvas behind the map
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub enum SvgBackground {
+    /// The background colour of the style the export is drawn with, its colour transform
+    /// included
+    #[default]
+    Style,
+    Color(Color),
+    /// No background at all, so the SVG composites over whatever is behind it
+    Transparent,
+}
 
 pub struct SvgExporter {
+    /// Decimal places written for coordinates and sizes. Positions land on a grid of
+    /// 10^-precision pixels, so they are off by at most half of that on each axis.
     pub precision: usize,
-    pub anti_aliasing: bool,
+    /// Snap projected positions to multiples of this many pixels instead of rounding them
+    /// to `precision` decimals. Points move by at most half a step on each axis (step / √2
+    /// overall) and points closer than a step may merge; sizes such as line widths and font
+    /// sizes are not snapped.
+    pub coordinate_grid: Option<f64>,
+    pub shape_rendering: ShapeRendering,
+    pub text_rendering: TextRendering,
+    pub text_mode: SvgTextMode,
     pub layer_separation: bool,
     pub show_all_road_names: bool,  // New option to display all road names
+    pub rotation: f64,  // Degrees clockwise, applied around the canvas center
+    pub dpi: f32,  // Output resolution; line widths grow with it past BASELINE_DPI
+    pub background: SvgBackground,
+    pub background_image: Option<PathBuf>,
+    /// Cut way geometry at the canvas edge instead of writing every vertex
+    pub clip_to_viewport: bool,
+    /// Outline in longitude and latitude the map is cut to, with everything outside it left
+    /// blank, background included
+    pub clip_polygon: Option<Vec<geo_types::Coord<f64>>>,
+    /// Split way segments longer than this many meters along the great circle before projecting
+    pub densify_segments: Option<f64>,
+    /// Drop way vertices closer than this many pixels (at 96 DPI) to the line through their
+    /// neighbours. Road labels follow their own centerline, see `label_simplify_tolerance`.
+    pub simplify_tolerance: Option<f64>,
+    /// Simplify the centerlines road labels are placed along with this tolerance, in pixels
+    /// at 96 DPI; `None` places them along every node of the way
+    pub label_simplify_tolerance: Option<f64>,
+    /// Layers left out of the export, by id
+    pub hidden_layers: HashSet<String>,
+    /// Credit stamped in a corner, in its own layer above the map
+    pub attribution: Option<Attribution>,
     pub style_manager: StyleManager,
 }
 
@@ -20,9 +329,23 @@ impl SvgExporter {
     pub fn new() -> Result<Self> {
         Ok(Self {
             precision: 3,
-            anti_aliasing: true,
+            coordinate_grid: None,
+            shape_rendering: ShapeRendering::GeometricPrecision,
+            text_rendering: TextRendering::Auto,
+            text_mode: SvgTextMode::default(),
             layer_separation: true,
             show_all_road_names: false,  // Default to showing only major roads
+            rotation: 0.0,
+            dpi: BASELINE_DPI,
+            background: SvgBackground::Style,
+            background_image: None,
+            clip_to_viewport: true,
+            clip_polygon: None,
+            densify_segments: None,
+            simplify_tolerance: None,
+            label_simplify_tolerance: None,
+            hidden_layers: HashSet::new(),
+            attribution: None,
             style_manager: StyleManager::new()?,
         })
     }
@@ -32,8 +355,33 @@ impl SvgExporter {
         self
     }
 
+    pub fn with_coordinate_grid(mut self, step: Option<f64>) -> Self {
+        self.coordinate_grid = step.filter(|step| *step > 0.0);
+        self
+    }
+
+    /// Shorthand for `geometricPrecision` (enabled) or `auto` (disabled) shape rendering
     pub fn with_anti_aliasing(mut self, enabled: bool) -> Self {
-        self.anti_aliasing = enabled;
+        self.shape_rendering = if enabled {
+            ShapeRendering::GeometricPrecision
+        } else {
+            ShapeRendering::Auto
+        };
+        self
+    }
+
+    pub fn with_shape_rendering(mut self, shape_rendering: ShapeRendering) -> Self {
+        self.shape_rendering = shape_rendering;
+        self
+    }
+
+    pub fn with_text_rendering(mut self, text_rendering: TextRendering) -> Self {
+        self.text_rendering = text_rendering;
+        self
+    }
+
+    pub fn with_text_mode(mut self, text_mode: SvgTextMode) -> Self {
+        self.text_mode = text_mode;
         self
     }
 
@@ -47,16 +395,210 @@ impl SvgExporter {
         self
     }
 
+    pub fn with_rotation(mut self, degrees: f64) -> Self {
+        self.rotation = degrees;
+        self
+    }
+
+    pub fn with_dpi(mut self, dpi: f32) -> Self {
+        self.dpi = dpi;
+        self
+    }
+
+    pub fn with_clipping(mut self, enabled: bool) -> Self {
+        self.clip_to_viewport = enabled;
+        self
+    }
+
+    pub fn with_clip_polygon(mut self, outline: Option<Vec<geo_types::Coord<f64>>>) -> Self {
+        self.clip_polygon = outline;
+        self
+    }
+
+    pub fn with_densification(mut self, max_segment_length: Option<f64>) -> Self {
+        self.densify_segments = max_segment_length;
+        self
+    }
+
+    pub fn with_simplification(mut self, tolerance: Option<f64>) -> Self {
+        self.simplify_tolerance = tolerance.filter(|tolerance| *tolerance > 0.0);
+        self
+    }
+
+    pub fn with_label_simplification(mut self, tolerance: Option<f64>) -> Self {
+        self.label_simplify_tolerance = tolerance.filter(|tolerance| *tolerance > 0.0);
+        self
+    }
+
+    pub fn with_hidden_layers<I: IntoIterator<Item = String>>(mut self, layers: I) -> Self {
+        self.hidden_layers = layers.into_iter().collect();
+        self
+    }
+
+    pub fn with_attribution(mut self, attribution: Option<Attribution>) -> Self {
+        self.attribution = attribution;
+        self
+    }
+
+    /// Convert a style width, authored for a 96 DPI screen, to output pixels
+    fn line_width(&self, width: f32) -> f32 {
+        width * self.dpi / BASELINE_DPI
+    }
+
+    pub fn with_background(mut self, background: SvgBackground) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn with_background_image(mut self, path: Option<PathBuf>) -> Self {
+        self.background_image = path;
+        self
+    }
+
     pub fn export_with_data<P: AsRef<std::path::Path>>(
         &self,
         map_data: &MapData,
         output_path: P,
         width: u32,
         height: u32,
-        center_lat: f64,
-        center_lon: f64,
-        scale: f64,
-    ) -> Result<()> {
+        view: ExportView,
+    ) -> Result<ExportStats> {
+        let file = BufWriter::new(File::create(output_path)?);
+        self.export_with_data_to_writer(map_data, file, width, height, view)
+    }
+
+    /// Same as `export_with_data`, streaming the SVG into any writer (e.g. an HTTP response body).
+    /// Each layer is written element by element as it is drawn, so the export is never held
+    /// whole, except with labels outlined as paths, which need the whole document.
+    pub fn export_with_data_to_writer<W: Write>(
+        &self,
+        map_data: &MapData,
+        writer: W,
+        width: u32,
+        height: u32,
+        view: ExportView,
+    ) -> Result<ExportStats> {
+        let trailer = match self.text_mode {
+            SvgTextMode::Font => None,
+            SvgTextMode::EmbeddedFont => self.embedded_font_style(),
+            SvgTextMode::Paths => {
+                let (document, mut stats) = self.build_document_with_stats(map_data, width, height, view);
+                let mut writer = CountingWriter::new(writer);
+                writer.write_all(self.finish_document(document)?.as_bytes())?;
+                writer.flush()?;
+                stats.bytes_written = writer.bytes_written();
+                return Ok(stats);
+            }
+        };
+
+        let mut sink = StreamSink::new(writer, trailer);
+        let mut stats = self.draw_map(&mut sink, map_data, width, height, view);
+        stats.bytes_written = sink.into_result()?;
+        Ok(stats)
+    }
+
+    /// Serialize a built document, embedding the label font or outlining the labels
+    /// according to `text_mode`
+    pub fn finish_document(&self, document: Document) -> Result<String> {
+        match self.text_mode {
+            SvgTextMode::Font => Ok(document.to_string()),
+            SvgTextMode::EmbeddedFont => Ok(match self.embedded_font_style() {
+                Some(style) => document.add(style).to_string(),
+                None => document.to_string(),
+            }),
+            SvgTextMode::Paths => self.text_to_paths(&document.to_string()),
+        }
+    }
+
+    /// `@font-face` rules carrying the bundled Noto Sans, registered under the style's label
+    /// font name too so every label picks it up. `None` if the font files are missing.
+    fn embedded_font_style(&self) -> Option<Style> {
+        const FACES: [(&str, &str); 2] = [
+            ("assets/fonts/noto-sans/NotoSans-Regular.woff2", "normal"),
+            ("assets/fonts/noto-sans/NotoSans-Bold.woff2", "bold"),
+        ];
+
+        let label_family = self.style_manager.get_current_style().labels.font_family
+            .split(',')
+            .next()
+            .map(|family| family.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+            .filter(|family| !family.is_empty());
+        let mut families = vec!["Noto Sans".to_string()];
+        families.extend(label_family.filter(|family| family != "Noto Sans"));
+
+        let mut css = String::new();
+        for (path, weight) in FACES {
+            let data = match std::fs::read(path) {
+                Ok(data) => data,
+                Err(e) => {
+                    log::warn!("Cannot embed font {}: {}", path, e);
+                    continue;
+                }
+            };
+            let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+            for family in &families {
+                css.push_str(&format!(
+                    "@font-face{{font-family:'{}';font-weight:{};src:url(data:font/woff2;base64,{}) format('woff2');}}",
+                    family, weight, encoded
+                ));
+            }
+        }
+
+        (!css.is_empty()).then(|| Style::new(css))
+    }
+
+    /// Replace every `<text>` with its glyph outlines. Without any font to shape with, the
+    /// labels would vanish, so they are kept as text instead.
+    fn text_to_paths(&self, svg_data: &str) -> Result<String> {
+        let fontdb = font_database();
+        if fontdb.is_empty() {
+            log::warn!("No fonts available to outline labels; keeping them as text");
+            return Ok(svg_data.to_string());
+        }
+
+        let options = resvg::usvg::Options { fontdb, ..Default::default() };
+        let tree = resvg::usvg::Tree::from_str(svg_data, &options)?;
+        Ok(tree.to_string(&resvg::usvg::WriteOptions::default()))
+    }
+
+    /// Build the styled SVG document showing `view` on a `width` by `height` canvas
+    pub fn build_document(
+        &self,
+        map_data: &MapData,
+        width: u32,
+        height: u32,
+        view: ExportView,
+    ) -> Document {
+        self.build_document_with_stats(map_data, width, height, view).0
+    }
+
+    /// Same as `build_document`, also counting what was drawn (`bytes_written` is left at 0)
+    pub fn build_document_with_stats(
+        &self,
+        map_data: &MapData,
+        width: u32,
+        height: u32,
+        view: ExportView,
+    ) -> (Document, ExportStats) {
+        let mut sink = DocumentSink::default();
+        let stats = self.draw_map(&mut sink, map_data, width, height, view);
+        (sink.into_document(), stats)
+    }
+
+    /// Draw the map into `sink`, one layer after the other in stacking order. Whatever has
+    /// to be decided across layers (which roads show, where labels and shields fit, which
+    /// POIs are clustered) is worked out first; the elements themselves are only made as
+    /// their layer is drawn.
+    fn draw_map<S: SvgSink>(
+        &self,
+        sink: &mut S,
+        map_data: &MapData,
+        width: u32,
+        height: u32,
+        view: ExportView,
+    ) -> ExportStats {
+        let ExportView { center_lat, center_lon, scale } = view;
+        let mut stats = ExportStats::default();
         let mut document = Document::new()
             .set("viewBox", (0, 0, width, height))
             .set("width", width)
@@ -65,14 +607,16 @@ impl SvgExporter {
             .set("xmlns:inkscape", "http://www.inkscape.org/namespaces/inkscape")
             .set("xmlns:sodipodi", "http://sodipodi.sourceforge.net/DTD/sodipodi-0.dtd");
 
-        // Add Inkscape-specific metadata for better compatibility
-        if self.anti_aliasing {
-            document = document.set("shape-rendering", "geometricPrecision");
-        }
-        
-        // Add Google Maps color scheme metadata
+        // Rendering hints are inherited by every shape and label in the document
+        document = document
+            .set("shape-rendering", self.shape_rendering.as_svg_value())
+            .set("text-rendering", self.text_rendering.as_svg_value());
+
+        // Add Google Maps color scheme metadata; a clip region leaves the canvas around it blank
         let style = self.style_manager.get_current_style();
-        document = document.set("style", format!("background-color:{}", style.background.color));
+        if let Some((color, _)) = self.background_fill(&style.background.color).filter(|_| self.clip_polygon.is_none()) {
+            document = document.set("style", format!("background-color:{}", color));
+        }
 
         // Create main group for all elements with Inkscape layer support
         let mut main_group = Group::new()
@@ -80,302 +624,425 @@ impl SvgExporter {
             .set("inkscape:label", "Map")
             .set("inkscape:groupmode", "layer");
 
-        // Background color from style
-        let background = Rectangle::new()
-            .set("x", 0)
-            .set("y", 0)
-            .set("width", width)
-            .set("height", height)
-            .set("fill", style.background.color.as_str())
-            .set("stroke", "none");
+        // Background color from style, unless overridden or transparent
+        main_group = main_group.add(self.create_background(&style.background.color, width, height));
 
-        main_group = main_group.add(background);
+        // Map bearing turns the projected map around the canvas center; labels are placed
+        // in the rotated space afterwards, so their angle normalisation keeps them upright
+        let bearing = Transform2D::rotation_about(self.rotation.to_radians(), width as f64 / 2.0, height as f64 / 2.0);
 
-        // Improved coordinate transformation with Web Mercator-like projection
+        // Same view as the map window: longitude and latitude, with longitude shrunk by the
+        // cosine of the center latitude so shapes keep their proportions there
+        let (center_x, center_y) = project(ProjectionSystem::LatLon, center_lat, center_lon);
+        let x_scale = scale * center_lat.to_radians().cos();
         let to_svg_coords = |lat: f64, lon: f64| -> (f64, f64) {
-            // Simple equirectangular projection with better scaling
-            let _lat_rad = lat.to_radians();
-            let center_lat_rad = center_lat.to_radians();
-            
-            // Apply Web Mercator-like scaling for better visual representation
-            let y_scale = center_lat_rad.cos();
-            
-            let x = (width as f64 / 2.0) + (lon - center_lon) * scale * y_scale;
-            let y = (height as f64 / 2.0) - (lat - center_lat) * scale;
-            
-            (self.round_value(x), self.round_value(y))
+            let (map_x, map_y) = project(ProjectionSystem::LatLon, lat, lon);
+            let x = (width as f64 / 2.0) + (map_x - center_x) * x_scale;
+            let y = (height as f64 / 2.0) - (map_y - center_y) * scale;
+            let rotated = bearing.transform_point(&geo_types::Coord { x, y });
+
+            self.round_coords((rotated.x, rotated.y))
         };
 
-        // Create separate layer groups for better organization (Google Maps style)
-        let mut water_group = Group::new()
-            .set("id", "water")
-            .set("inkscape:label", "Water")
-            .set("inkscape:groupmode", "layer");
-        let mut landuse_group = Group::new()
-            .set("id", "landuse")
-            .set("inkscape:label", "Land Use")
-            .set("inkscape:groupmode", "layer");
-        let mut aeroway_group = Group::new()
-            .set("id", "aeroway")
-            .set("inkscape:label", "Aeroway")
-            .set("inkscape:groupmode", "layer");
-        let mut buildings_group = Group::new()
-            .set("id", "buildings")
-            .set("inkscape:label", "Buildings")
-            .set("inkscape:groupmode", "layer");
-        let mut roads_group = Group::new()
-            .set("id", "roads")
-            .set("inkscape:label", "Roads")
-            .set("inkscape:groupmode", "layer");
-        let mut railway_group = Group::new()
-            .set("id", "railway")
-            .set("inkscape:label", "Railway")
-            .set("inkscape:groupmode", "layer");
-        let mut boundaries_group = Group::new()
-            .set("id", "boundaries")
-            .set("inkscape:label", "Boundaries")
-            .set("inkscape:groupmode", "layer");
-        let mut pois_group = Group::new()
-            .set("id", "pois")
-            .set("inkscape:label", "Points of Interest")
-            .set("inkscape:groupmode", "layer");
-        let mut labels_group = Group::new()
-            .set("id", "labels")
-            .set("inkscape:label", "Labels")
-            .set("inkscape:groupmode", "layer");
+        // Only the visible part of each way is written, unless full geometry was asked for,
+        // and only the part inside the clip region if there is one
+        let clip = ExportClip {
+            canvas: self.clip_to_viewport.then(|| {
+                ClipRect::new(0.0, 0.0, width as f64, height as f64).expanded(self.line_width(CLIP_MARGIN) as f64)
+            }),
+            region: self.clip_polygon.as_ref().and_then(|outline| {
+                let points: Vec<geo_types::Coord<f64>> = outline.iter()
+                    .map(|coord| to_svg_coords(coord.y, coord.x))
+                    .map(|(x, y)| geo_types::Coord { x, y })
+                    .collect();
+                ClipPolygon::new(&GeometryUtils::simplify_ring(&points, CLIP_REGION_TOLERANCE))
+            }),
+        };
+        let in_region = |x: f64, y: f64| clip.region.as_ref().is_none_or(|region| region.contains(&geo_types::Coord { x, y }));
+        let on_canvas = |x: f64, y: f64| x >= 0.0 && x <= width as f64 && y >= 0.0 && y <= height as f64 && in_region(x, y);
 
-        // Draw water bodies using style
-        for way in map_data.ways.values() {
-            if self.is_water_feature(way) {
-                if let Some(path_data) = self.way_to_svg_path(way, map_data, &to_svg_coords) {
-                    let water_path = Path::new()
-                        .set("d", path_data)
-                        .set("fill", style.water.color.as_str())
-                        .set("stroke", "none")
-                        .set("opacity", style.water.opacity);
-                    water_group = water_group.add(water_path);
-                }
-            }
+        // The clip region masks everything on the map, background included, so features kept
+        // whole by the clipping below still end on its outline
+        if clip.region.is_some() {
+            main_group = main_group.set("clip-path", format!("url(#{})", CLIP_REGION_ID));
         }
 
-        // Draw land use areas using style
-        for way in map_data.ways.values() {
-            if let Some(landuse) = way.tags.get("landuse") {
-                if let Some(fill_color) = style.get_landuse_color(landuse) {
-                    if let Some(path_data) = self.way_to_svg_path(way, map_data, &to_svg_coords) {
-                        let area_path = Path::new()
-                            .set("d", path_data)
-                            .set("fill", fill_color)
-                            .set("stroke", "none")
-                            .set("opacity", 1.0);
-                        landuse_group = landuse_group.add(area_path);
+        let ways: Vec<&crate::core::Way> = map_data.ways.values().collect();
+        let building_parts = building_parts_in_draw_order(ways.iter().copied());
+        let pass_ways = |pass: WayPass| if pass == WayPass::BuildingPart { &building_parts } else { &ways };
+
+        // Style roads first: labels, shields and the stacking order depend on which roads show
+        let mut road_label_candidates = Vec::new();
+        let mut shield_candidates = Vec::new();
+        // Style units per meter on the ground, for roads drawn to scale; `line_width` brings them back to output pixels
+        let pixels_per_meter = scale / METERS_PER_DEGREE / self.line_width(1.0) as f64;
+        let mut roads: Vec<RoadDraw> = Vec::new();
+        let carriageways = carriageway_offsets(&style.dual_carriageways, map_data);
+        for way in roads_in_draw_order(style, map_data.ways.values(), pixels_per_meter) {
+            let Some((highway, _)) = road_class(way) else {
+                continue;
+            };
+            if let Some(look) = way_appearance(style, map_data, way, WayPass::Road, scale) {
+                let look = look.with_tagged_width(style, way, pixels_per_meter);
+                let look = match carriageways.get(&way.id) {
+                    Some(&offset) => look.with_carriageway_offset(style, offset, pixels_per_meter),
+                    None => look,
+                };
+                let offset = self.line_width(look.offset) as f64;
+                if let Some(data) = self.way_to_svg_path(way, map_data, &to_svg_coords, &clip, None, offset) {
+                    let look = match map_data.get_style_override(ElementType::Way, way.id) {
+                        Some(style_override) => look.with_override(style_override),
+                        None => look,
+                    };
+                    let layer = style.layers.layer_for(&way.tags, "roads");
+                    roads.push(RoadDraw { way, layer, look, data, offset });
+                    stats.ways_rendered += 1;
+
+                    // Queue ref shields; a name that only repeats the ref is left to the shield
+                    let shield = way.tags.get("ref")
+                        .and_then(|road_ref| shield_text(road_ref))
+                        .filter(|_| style.get_shield_style(highway).is_some() && scale >= style.shields.min_scale);
+                    let name_is_ref = shield.as_ref().is_some_and(|text| display_name(&way.tags, &style.labels.languages) == Some(text));
+                    if let Some(text) = shield {
+                        shield_candidates.push((label_priority(&way.tags), way, text, highway));
                     }
-                }
-            }
-            
-            // Handle leisure areas (parks, etc.)
-            if let Some(leisure) = way.tags.get("leisure") {
-                if let Some(fill_color) = style.get_leisure_color(leisure) {
-                    if let Some(path_data) = self.way_to_svg_path(way, map_data, &to_svg_coords) {
-                        let area_path = Path::new()
-                            .set("d", path_data)
-                            .set("fill", fill_color)
-                            .set("stroke", "none")
-                            .set("opacity", 1.0);
-                        landuse_group = landuse_group.add(area_path);
+
+                    // Queue road name labels; they are placed by importance after all roads are drawn
+                    if let Some(name) = display_name(&way.tags, &style.labels.languages).filter(|_| !name_is_ref) {
+                        println!("DEBUG: Found road with name '{}' and highway '{}'", name, highway);
+                        if self.should_label_road(highway) && !name.trim().is_empty() && scale >= style.labels.label_min_scale {
+                            println!("DEBUG: Should label road: {}", name);
+                            road_label_candidates.push((label_priority(&way.tags), way, name, highway));
+                        } else {
+                            println!("DEBUG: Road '{}' should not be labeled (highway: {})", name, highway);
+                            stats.labels_skipped += 1;
+                        }
+                    } else {
+                        println!("DEBUG: Road has no name, highway: {}", highway);
                     }
                 }
             }
-            
-            // Handle natural areas
-            if let Some(natural) = way.tags.get("natural") {
-                if let Some(fill_color) = style.get_natural_color(natural) {
-                    if let Some(path_data) = self.way_to_svg_path(way, map_data, &to_svg_coords) {
-                        let area_path = Path::new()
-                            .set("d", path_data)
-                            .set("fill", fill_color)
-                            .set("stroke", "none")
-                            .set("opacity", 1.0);
-                        landuse_group = landuse_group.add(area_path);
-                    }
-                }
+        }
+
+        // Mini roundabouts, turning circles and the like, each on the layer of its road
+        let road_marks: Vec<(&str, RoadNodeMark)> = road_node_marks(style, map_data, scale, pixels_per_meter)
+            .into_iter()
+            .map(|mark| {
+                let layer = map_data.ways.get(&mark.road_id).map_or("roads", |way| style.layers.layer_for(&way.tags, "roads"));
+                (layer, mark)
+            })
+            .collect();
+
+        // Road names by importance, capping density per cell
+        road_label_candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.id.cmp(&b.1.id)));
+        let mut label_grid = LabelDensityGrid::new(style.labels.label_cell_size, style.labels.max_labels_per_cell);
+        let mut road_labels = Vec::new();
+        for (_, way, name, highway) in road_label_candidates {
+            let anchor = way.nodes.get(way.nodes.len() / 2)
+                .and_then(|node_id| map_data.nodes.get(node_id))
+                .map(|node| to_svg_coords(node.lat, node.lon));
+            if anchor.is_some_and(|(x, y)| in_region(x, y) && label_grid.try_place(x, y)) {
+                road_labels.push((way, name, highway));
+            } else {
+                stats.labels_skipped += 1;
             }
         }
-        
-        // Draw aeroway areas using style
-        for way in map_data.ways.values() {
-            if way.tags.contains_key("aeroway") {
-                if let Some(path_data) = self.way_to_svg_path(way, map_data, &to_svg_coords) {
-                    let aeroway_path = Path::new()
-                        .set("d", path_data)
-                        .set("fill", style.aeroway.default.as_str())
-                        .set("stroke", "none")
-                        .set("opacity", 1.0);
-                    aeroway_group = aeroway_group.add(aeroway_path);
+
+        // Ref shields at intervals along each road, sharing the density cap with names
+        shield_candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.id.cmp(&b.1.id)));
+        let mut shields = Vec::new();
+        for (_, way, text, highway) in shield_candidates {
+            let points: Vec<(f64, f64)> = way.node_refs()
+                .filter_map(|node_id| map_data.nodes.get(&node_id))
+                .map(|node| to_svg_coords(node.lat, node.lon))
+                .collect();
+            for (x, y) in points_along(&points, style.shields.spacing) {
+                if on_canvas(x, y) && label_grid.try_place(x, y) {
+                    shields.push((highway, text.clone(), x, y));
+                } else {
+                    stats.labels_skipped += 1;
                 }
             }
         }
 
-        // Draw buildings using style
-        for way in map_data.ways.values() {
-            if way.tags.contains_key("building") {
-                if let Some(path_data) = self.way_to_svg_path(way, map_data, &to_svg_coords) {
-                    let building_path = Path::new()
-                        .set("d", path_data)
-                        .set("fill", style.buildings.fill.as_str())
-                        .set("stroke", style.buildings.stroke.as_str())
-                        .set("stroke-width", style.buildings.stroke_width)
-                        .set("opacity", 1.0);
-                    buildings_group = buildings_group.add(building_path);
+        // Merge dense groups of same-type POIs into counted markers; restyled POIs stay on their own
+        let mut clusters = Vec::new();
+        let mut clustered: HashSet<i64> = HashSet::new();
+        if style.poi_clusters.active_at(scale) {
+            // Crossings, barriers and the like belong to their way and are never merged
+            let points = map_data.nodes.values()
+                .filter(|node| MapStyle::way_node_key(&node.tags).is_none())
+                .filter(|node| self.override_color(map_data, ElementType::Node, node.id).is_none())
+                .filter_map(|node| {
+                    let poi_key = MapStyle::poi_key(&node.tags)?;
+                    let (x, y) = to_svg_coords(node.lat, node.lon);
+                    on_canvas(x, y).then_some((node.id, poi_key, x, y))
+                });
+
+            for cluster in cluster_points(points, style.poi_clusters.radius) {
+                if cluster.len() >= style.poi_clusters.min_count.max(2) {
+                    clustered.extend(cluster.members.iter().copied());
+                    clusters.push(cluster);
                 }
             }
         }
-        
-        // Draw railways using style
-        for way in map_data.ways.values() {
-            if let Some(railway) = way.tags.get("railway") {
-                if railway == "rail" {
-                    if let Some(path_data) = self.way_to_svg_path(way, map_data, &to_svg_coords) {
-                        // Railway main line
-                        let railway_path = Path::new()
-                            .set("d", path_data.clone())
-                            .set("fill", "none")
-                            .set("stroke", style.railway.rail_color.as_str())
-                            .set("stroke-width", style.railway.rail_width)
-                            .set("stroke-linecap", "round");
-                        railway_group = railway_group.add(railway_path);
-                        
-                        // Railway dashes
-                        let railway_dashes = Path::new()
-                            .set("d", path_data)
-                            .set("fill", "none")
-                            .set("stroke", style.railway.rail_dash_color.as_str())
-                            .set("stroke-width", style.railway.rail_dash_width)
-                            .set("stroke-dasharray", style.railway.rail_dash_pattern.as_str())
-                            .set("stroke-linecap", "round");
-                        railway_group = railway_group.add(railway_dashes);
-                    }
+
+        // Stacking order: the style's, then built-in layers it leaves out, with layers only
+        // rules name right above the layer their first way would have gone to
+        let mut order: Vec<&str> = Vec::new();
+        for name in &style.layers.order {
+            if !order.contains(&name.as_str()) {
+                order.push(name);
+            }
+        }
+        for (name, _, _) in BUILTIN_LAYERS {
+            if !order.contains(name) {
+                order.push(name);
+            }
+        }
+        let style_layers = &style.layers;
+        for (pass, default_layer) in WAY_PASSES {
+            for &way in pass_ways(pass) {
+                let layer = style_layers.layer_for(&way.tags, default_layer);
+                if !order.contains(&layer) && way_appearance(style, map_data, way, pass, scale).is_some() {
+                    stack_layer(&mut order, layer, default_layer);
                 }
             }
         }
+        for layer in roads.iter().map(|road| road.layer).chain(road_marks.iter().map(|(layer, _)| *layer)) {
+            stack_layer(&mut order, layer, "roads");
+        }
 
-        // Draw roads with styling from config
-        for way in map_data.ways.values() {
-            if let Some(highway) = way.tags.get("highway") {
-                let (stroke_color, stroke_width, border_color, border_width) = style.get_road_style(highway);
-                
-                if let Some(path_data) = self.way_to_svg_path(way, map_data, &to_svg_coords) {
-                    // Draw road border first (if exists)
-                    if !border_color.is_empty() && border_width > 0.0 {
-                        let border_path = Path::new()
-                            .set("d", path_data.clone())
-                            .set("fill", "none")
-                            .set("stroke", border_color)
-                            .set("stroke-width", stroke_width + border_width * 2.0)
-                            .set("stroke-linecap", "round")
-                            .set("stroke-linejoin", "round");
-                        roads_group = roads_group.add(border_path);
+        // Each layer gets what every pass puts in it, in the order the passes run
+        let mut patterns: HashMap<String, FillPattern> = HashMap::new();
+        let mut icon_cache: HashMap<String, Option<String>> = HashMap::new();
+        sink.begin(document, main_group);
+        for &name in order.iter().filter(|name| !self.hidden_layers.contains(**name)) {
+            sink.start_layer(layer_group(name));
+
+            // Way styling is resolved by the same rules as the interactive view, pass by pass
+            let routed_here = style_layers.rules.iter().any(|rule| rule.layer == name);
+            for (pass, default_layer) in WAY_PASSES.into_iter().filter(|&(_, default_layer)| routed_here || default_layer == name) {
+                for &way in pass_ways(pass) {
+                    if style_layers.layer_for(&way.tags, default_layer) != name {
+                        continue;
                     }
-                    
-                    // Draw main road
-                    let road_path = Path::new()
-                        .set("d", path_data)
-                        .set("fill", "none")
-                        .set("stroke", stroke_color)
-                        .set("stroke-width", stroke_width)
-                        .set("stroke-linecap", "round")
-                        .set("stroke-linejoin", "round");
-                    roads_group = roads_group.add(road_path);
-
-                    // Add road name labels following road direction
-                    if let Some(name) = way.tags.get("name") {
-                        println!("DEBUG: Found road with name '{}' and highway '{}'", name, highway);
-                        if self.should_label_road(highway) && !name.trim().is_empty() {
-                            println!("DEBUG: Should label road: {}", name);
-                            let font_size = style.get_road_label_font_size(highway) as f64;
-                            println!("DEBUG: Font size: {}", font_size);
-                            let text_labels = self.create_curved_text_labels(way, map_data, &to_svg_coords, name, font_size);
-                            println!("DEBUG: Created {} text labels", text_labels.len());
-                            
-                            for label in text_labels {
-                                labels_group = labels_group.add(label);
-                            }
-                        } else {
-                            println!("DEBUG: Road '{}' should not be labeled (highway: {})", name, highway);
+                    let Some(look) = way_appearance(style, map_data, way, pass, scale) else {
+                        continue;
+                    };
+                    let footprint = look.footprint.map(|shaping| shaping.scaled(self.line_width(1.0) as f64));
+                    let offset = self.line_width(look.offset) as f64;
+                    let Some(path_data) = self.way_to_svg_path(way, map_data, &to_svg_coords, &clip, footprint.as_ref(), offset) else {
+                        continue;
+                    };
+                    let look = match map_data.get_style_override(ElementType::Way, way.id) {
+                        Some(style_override) => look.with_override(style_override),
+                        None => look,
+                    };
+                    for path in self.appearance_paths(path_data, &look, &mut patterns) {
+                        sink.add(path);
+                    }
+                    if let Some(decoration) = &look.decoration {
+                        let points: Vec<(f64, f64)> = way.node_refs()
+                            .filter_map(|node_id| map_data.nodes.get(&node_id))
+                            .map(|node| to_svg_coords(node.lat, node.lon))
+                            .collect();
+                        if let Some(marks) = self.decoration_path(&points, decoration, width, height) {
+                            let marks = if look.opacity < 1.0 { marks.set("opacity", look.opacity) } else { marks };
+                            sink.add(marks);
                         }
-                    } else {
-                        println!("DEBUG: Road has no name, highway: {}", highway);
+                    }
+                    stats.ways_rendered += 1;
+                }
+            }
+
+            // Merged junctions draw every casing of a `layer` tag value before its fills,
+            // otherwise each road is drawn casing then fill
+            let merge_junctions = style.road_casings.merge_junctions;
+            for group in roads.chunk_by(|a, b| merge_junctions && a.way.layer() == b.way.layer()) {
+                let group: Vec<&RoadDraw> = group.iter().filter(|road| road.layer == name).collect();
+                for road in &group {
+                    // A casing shifted off the road follows its own line
+                    let Some(casing) = &road.look.casing else {
+                        continue;
+                    };
+                    let shifted = (casing.offset != 0.0).then(|| {
+                        let offset = road.offset + self.line_width(casing.offset) as f64;
+                        self.way_to_svg_path(road.way, map_data, &to_svg_coords, &clip, None, offset)
+                    });
+                    let path = match shifted.flatten() {
+                        Some(data) => self.stroke_path(&data, casing),
+                        None => self.stroke_path(&road.data, casing),
+                    };
+                    sink.add(if road.look.opacity < 1.0 { path.set("opacity", road.look.opacity) } else { path });
+                }
+                for road in &group {
+                    let look = WayAppearance { casing: None, ..road.look.clone() };
+                    for path in self.appearance_paths(road.data.clone(), &look, &mut patterns) {
+                        sink.add(path);
                     }
                 }
             }
-        }
 
-        // Draw boundaries using style
-        for way in map_data.ways.values() {
-            if let Some(boundary) = way.tags.get("boundary") {
-                if boundary == "administrative" {
-                    if let Some(path_data) = self.way_to_svg_path(way, map_data, &to_svg_coords) {
-                        let boundary_path = Path::new()
-                            .set("d", path_data)
-                            .set("fill", "none")
-                            .set("stroke", style.boundaries.administrative_color.as_str())
-                            .set("stroke-width", style.boundaries.administrative_width)
-                            .set("stroke-dasharray", style.boundaries.administrative_dash.as_str())
-                            .set("opacity", style.boundaries.administrative_opacity);
-                        boundaries_group = boundaries_group.add(boundary_path);
+            // Direction arrows go over every road so crossing casings can't hide them
+            for road in roads.iter().filter(|road| road.layer == name && road.way.is_roundabout() && style.roundabouts.arrows_at(scale)) {
+                let points: Vec<(f64, f64)> = road.way.node_refs()
+                    .filter_map(|node_id| map_data.nodes.get(&node_id))
+                    .map(|node| to_svg_coords(node.lat, node.lon))
+                    .collect();
+                let mut data = Data::new();
+                for chevron in direction_chevrons(&points, style.roundabouts.arrow_spacing, style.roundabouts.arrow_size as f64) {
+                    if chevron.iter().any(|&(x, y)| x < 0.0 || x > width as f64 || y < 0.0 || y > height as f64) {
+                        continue;
                     }
+                    data = data.move_to(self.round_coords(chevron[0]))
+                        .line_to(self.round_coords(chevron[1]))
+                        .line_to(self.round_coords(chevron[2]));
+                }
+                if data.is_empty() {
+                    continue;
                 }
+                let arrows = Path::new()
+                    .set("d", data)
+                    .set("fill", "none")
+                    .set("stroke", style.roundabouts.arrow_color.as_str())
+                    .set("stroke-width", self.line_width(style.roundabouts.arrow_width))
+                    .set("stroke-linecap", style.strokes.line_cap.as_svg_value())
+                    .set("stroke-linejoin", style.strokes.line_join.as_svg_value());
+                sink.add(arrows);
             }
-        }
 
-        // Draw POIs with styling from config
-        for node in map_data.nodes.values() {
-            if let Some(amenity) = node.tags.get("amenity") {
-                let (x, y) = to_svg_coords(node.lat, node.lon);
-                if x >= 0.0 && x <= width as f64 && y >= 0.0 && y <= height as f64 {
-                    let (color, radius) = style.get_poi_style(amenity);
-                    
-                    let poi_circle = Circle::new()
-                        .set("cx", x)
-                        .set("cy", y)
-                        .set("r", radius)
-                        .set("fill", color)
-                        .set("stroke", "#ffffff")
-                        .set("stroke-width", 1.5)
-                        .set("opacity", 1.0);
-                    pois_group = pois_group.add(poi_circle);
-                    
-                    // Add labels for important POIs
-                    if self.is_important_poi(amenity) {
-                        if let Some(name) = node.tags.get("name") {
-                            let label = Text::new(name)
-                                .set("x", x + radius as f64 + 8.0)
-                                .set("y", y)
-                                .set("text-anchor", "start")
-                                .set("dominant-baseline", "central")
-                                .set("font-family", style.labels.font_family.as_str())
-                                .set("font-size", 10)
-                                .set("font-weight", "normal")
-                                .set("fill", "#333333")
-                                .set("stroke", style.labels.poi_label_stroke.as_str())
-                                .set("stroke-width", style.labels.poi_label_stroke_width)
-                                .set("paint-order", "stroke fill");
-                            labels_group = labels_group.add(label);
+            // Mini roundabouts, turning circles and the like sit on top of their road
+            for (_, mark) in road_marks.iter().filter(|(layer, _)| *layer == name) {
+                let (x, y) = to_svg_coords(mark.lat, mark.lon);
+                if !on_canvas(x, y) {
+                    continue;
+                }
+                sink.add(self.road_node_symbol(mark, self.round_coords((x, y))));
+                stats.nodes_rendered += 1;
+            }
+
+            match name {
+                "pois" => {
+                    for cluster in &clusters {
+                        sink.add(self.create_poi_cluster(style, cluster));
+                        stats.nodes_rendered += 1;
+                    }
+
+                    // Draw POIs with styling from config, using the style's symbol when one is mapped
+                    for node in map_data.nodes.values() {
+                        if clustered.contains(&node.id) || !style.poi_visible_at(&node.tags, scale) {
+                            continue;
+                        }
+                        let Some(poi_key) = MapStyle::poi_key(&node.tags) else {
+                            continue;
+                        };
+                        let (x, y) = to_svg_coords(node.lat, node.lon);
+                        if !on_canvas(x, y) {
+                            continue;
                         }
+                        let override_fill = self.override_color(map_data, ElementType::Node, node.id);
+                        let icon_uri = match override_fill {
+                            None => icon_cache
+                                .entry(poi_key.clone())
+                                .or_insert_with(|| self.load_icon_data_uri(style, &poi_key))
+                                .clone(),
+                            Some(_) => None,
+                        };
+                        if let Some(uri) = icon_uri {
+                            let size = style.icons.size as f64;
+                            let icon = Image::new()
+                                .set("x", x - size / 2.0)
+                                .set("y", y - size / 2.0)
+                                .set("width", size)
+                                .set("height", size)
+                                .set("href", uri);
+                            sink.add(icon);
+                        } else {
+                            let (color, radius) = style.get_poi_style(&poi_key);
+                            let poi_circle = Circle::new()
+                                .set("cx", x)
+                                .set("cy", y)
+                                .set("r", radius)
+                                .set("fill", override_fill.unwrap_or_else(|| color.to_string()))
+                                .set("stroke", "#ffffff")
+                                .set("stroke-width", 1.5)
+                                .set("opacity", 1.0);
+                            sink.add(poi_circle);
+                        }
+                        stats.nodes_rendered += 1;
                     }
                 }
-            }
-            
-            // Handle place labels (cities, towns, etc.)
-            if let Some(place) = node.tags.get("place") {
-                let (x, y) = to_svg_coords(node.lat, node.lon);
-                if x >= 0.0 && x <= width as f64 && y >= 0.0 && y <= height as f64 {
-                    if let Some(name) = node.tags.get("name") {
-                        let font_size = style.get_place_label_font_size(place);
-                        let place_label = Text::new(name)
-                            .set("x", x)
-                            .set("y", y)
-                            .set("text-anchor", "middle")
+                "shields" => {
+                    for (highway, text, x, y) in &shields {
+                        sink.add(self.create_shield(style, highway, text, *x, *y));
+                        stats.labels_placed += 1;
+                    }
+                }
+                "labels" => {
+                    // Road name labels following road direction
+                    for &(way, name, highway) in &road_labels {
+                        let font_size = style.get_road_label_font_size(highway) as f64;
+                        println!("DEBUG: Font size: {}", font_size);
+                        let text_labels = self.create_curved_text_labels(way, map_data, &to_svg_coords, name, font_size, &style.labels.road);
+                        println!("DEBUG: Created {} text labels", text_labels.len());
+
+                        if text_labels.is_empty() {
+                            stats.labels_skipped += 1;
+                        }
+                        for label in text_labels {
+                            sink.add(label);
+                            stats.labels_placed += 1;
+                        }
+                    }
+
+                    // Labels for important POIs, clear of their icon or dot
+                    for node in map_data.nodes.values() {
+                        if clustered.contains(&node.id) || !style.poi_visible_at(&node.tags, scale) {
+                            continue;
+                        }
+                        let Some(poi_key) = MapStyle::poi_key(&node.tags).filter(|poi_key| self.is_important_poi(poi_key)) else {
+                            continue;
+                        };
+                        let Some(name) = display_name(&node.tags, &style.labels.languages) else {
+                            continue;
+                        };
+                        let (x, y) = to_svg_coords(node.lat, node.lon);
+                        if !on_canvas(x, y) {
+                            continue;
+                        }
+                        let has_icon = self.override_color(map_data, ElementType::Node, node.id).is_none() && icon_cache
+                            .entry(poi_key.clone())
+                            .or_insert_with(|| self.load_icon_data_uri(style, &poi_key))
+                            .is_some();
+                        let half_extent = if has_icon { style.icons.size as f64 / 2.0 } else { style.get_poi_style(&poi_key).1 as f64 };
+                        let placement = style.labels.poi;
+                        let (label_x, label_y) = label_position(placement.text_anchor, (x, y), half_extent, placement.text_offset);
+                        let label = self.wrapped_text(name, (label_x, label_y), 10.0, &style.labels)
+                            .set("text-anchor", placement.text_anchor.as_svg_value())
+                            .set("dominant-baseline", "central")
+                            .set("font-family", style.labels.font_family.as_str())
+                            .set("font-size", 10)
+                            .set("font-weight", "normal")
+                            .set("fill", "#333333")
+                            .set("stroke", style.labels.poi_label_stroke.as_str())
+                            .set("stroke-width", style.labels.poi_label_stroke_width)
+                            .set("paint-order", "stroke fill");
+                        sink.add(label);
+                        stats.labels_placed += 1;
+                    }
+
+                    // Place labels (cities, towns, etc.), including places mapped as areas
+                    for place in place_labels(map_data, &style.labels.languages) {
+                        let (x, y) = to_svg_coords(place.lat, place.lon);
+                        if !on_canvas(x, y) {
+                            continue;
+                        }
+                        let font_size = style.get_place_label_font_size(place.place);
+                        let placement = style.labels.place;
+                        let (label_x, label_y) = label_position(placement.text_anchor, (x, y), 0.0, placement.text_offset);
+                        let place_label = self.wrapped_text(place.name, (label_x, label_y), font_size as f64, &style.labels)
+                            .set("text-anchor", placement.text_anchor.as_svg_value())
                             .set("dominant-baseline", "central")
                             .set("font-family", style.labels.font_family.as_str())
                             .set("font-size", font_size)
@@ -384,36 +1051,518 @@ impl SvgExporter {
                             .set("stroke", style.labels.place_label_stroke.as_str())
                             .set("stroke-width", style.labels.place_label_stroke_width)
                             .set("paint-order", "stroke fill");
-                        labels_group = labels_group.add(place_label);
+                        sink.add(place_label);
+                        stats.labels_placed += 1;
+                    }
+
+                    // House numbers only when zoomed far in, at most one per small cell so they never pile up
+                    if style.housenumbers.visible_at(scale) {
+                        let housenumbers = &style.housenumbers;
+                        let mut grid = LabelDensityGrid::new(housenumbers.cell_size, 1);
+                        for housenumber in housenumber_labels(map_data) {
+                            let (x, y) = to_svg_coords(housenumber.lat, housenumber.lon);
+                            if !on_canvas(x, y) || !grid.try_place(x, y) {
+                                stats.labels_skipped += 1;
+                                continue;
+                            }
+                            let label = Text::new(housenumber.text)
+                                .set("x", x)
+                                .set("y", y)
+                                .set("text-anchor", "middle")
+                                .set("dominant-baseline", "central")
+                                .set("font-family", style.labels.font_family.as_str())
+                                .set("font-size", housenumbers.font_size)
+                                .set("fill", housenumbers.color.as_str())
+                                .set("stroke", housenumbers.halo_color.as_str())
+                                .set("stroke-width", housenumbers.halo_width)
+                                .set("paint-order", "stroke fill");
+                            sink.add(label);
+                            stats.labels_placed += 1;
+                        }
                     }
                 }
+                _ => {}
             }
+
+            sink.end_layer();
         }
 
-        // Add all layer groups in proper Google Maps order (back to front)
-        main_group = main_group.add(water_group);
-        main_group = main_group.add(landuse_group);
-        main_group = main_group.add(aeroway_group);
-        main_group = main_group.add(buildings_group);
-        main_group = main_group.add(railway_group);
-        main_group = main_group.add(roads_group);
-        main_group = main_group.add(boundaries_group);
-        main_group = main_group.add(pois_group);
-        main_group = main_group.add(labels_group);
+        let mut defs = (!patterns.is_empty()).then(|| self.create_pattern_defs(patterns));
+        if let Some(region) = &clip.region {
+            let mut data = Data::new();
+            for (i, &point) in region.points().iter().enumerate() {
+                let point = self.round_coords((point.x, point.y));
+                data = if i == 0 { data.move_to(point) } else { data.line_to(point) };
+            }
+            let clip_path = ClipPath::new()
+                .set("id", CLIP_REGION_ID)
+                .add(Path::new().set("d", data.close()));
+            defs = Some(defs.unwrap_or_default().add(clip_path));
+        }
 
-        document = document.add(main_group);
+        let mut overlays = Vec::new();
+        if style.north_arrow.enabled {
+            overlays.push(self.create_north_arrow(style, width, height));
+        }
+        if let Some(attribution) = &self.attribution {
+            overlays.push(self.create_attribution(style, attribution, width, height));
+        }
 
-        // Write to file using svg crate's save function
-        svg::save(output_path, &document)?;
-        Ok(())
+        sink.finish(defs, overlays);
+        stats
     }
 
-    fn is_water_feature(&self, way: &crate::core::Way) -> bool {
-        if let Some(natural) = way.tags.get("natural") {
-            matches!(natural.as_str(), "water" | "coastline")
-        } else {
-            way.tags.contains_key("waterway")
+    /// The paths drawing one way, bottom to top: casing, fill or stroke, hatching, overlay.
+    /// Hatching patterns used are collected into `patterns` for the `<defs>`.
+    fn appearance_paths(&self, path_data: Data, look: &WayAppearance, patterns: &mut HashMap<String, FillPattern>) -> Vec<Path> {
+        let stroke_path = |stroke: &WayStroke| self.stroke_path(&path_data, stroke);
+
+        let mut paths = Vec::new();
+        if let Some(casing) = &look.casing {
+            paths.push(stroke_path(casing));
+        }
+        if let Some(gap) = &look.gap {
+            paths.push(stroke_path(gap));
+        }
+        match (&look.fill, &look.stroke) {
+            (Some(fill), stroke) => {
+                let mut path = Path::new().set("d", path_data.clone()).set("fill", fill.as_str());
+                path = match stroke {
+                    Some(stroke) => path
+                        .set("stroke", stroke.color.as_str())
+                        .set("stroke-width", self.line_width(stroke.width)),
+                    None => path.set("stroke", "none"),
+                };
+                paths.push(path);
+            }
+            (None, Some(stroke)) => paths.push(stroke_path(stroke)),
+            (None, None) => {}
+        }
+        if let Some(pattern) = look.pattern {
+            let pattern = self.scale_pattern(pattern);
+            let id = pattern_id(&pattern);
+            paths.push(
+                Path::new()
+                    .set("d", path_data.clone())
+                    .set("fill", format!("url(#{})", id))
+                    .set("stroke", "none"),
+            );
+            patterns.insert(id, pattern);
+        }
+        if let Some(overlay) = &look.overlay {
+            paths.push(stroke_path(overlay));
+        }
+
+        if look.opacity < 1.0 {
+            paths = paths.into_iter().map(|path| path.set("opacity", look.opacity)).collect();
+        }
+        paths
+    }
+
+    /// One stroke of a way along `path_data`, unfilled
+    fn stroke_path(&self, path_data: &Data, stroke: &WayStroke) -> Path {
+        let mut path = Path::new()
+            .set("d", path_data.clone())
+            .set("fill", "none")
+            .set("stroke", stroke.color.as_str())
+            .set("stroke-width", self.line_width(stroke.width));
+        if let Some(lengths) = stroke.dash_array(self.line_width(stroke.width)) {
+            let lengths: Vec<String> = lengths.iter().map(|&length| self.round_value(length as f64).to_string()).collect();
+            path = path.set("stroke-dasharray", lengths.join(","));
+        }
+        // Butt caps and miter joins are the SVG defaults
+        if stroke.cap != LineCap::Butt {
+            path = path.set("stroke-linecap", stroke.cap.as_svg_value());
+        }
+        if stroke.join != LineJoin::Miter {
+            path = path.set("stroke-linejoin", stroke.join.as_svg_value());
+        }
+        path
+    }
+
+    /// Ticks or dots along a projected line as one path; marks reaching off the canvas are left out
+    fn decoration_path(&self, points: &[(f64, f64)], decoration: &LineDecoration, width: u32, height: u32) -> Option<Path> {
+        let on_canvas = |&(x, y): &(f64, f64)| x >= 0.0 && x <= width as f64 && y >= 0.0 && y <= height as f64;
+        let spacing = self.line_width(decoration.spacing as f32) as f64;
+        let size = self.line_width(decoration.size) as f64;
+
+        let mut data = Data::new();
+        let path = match decoration.symbol {
+            LineSymbol::Ticks => {
+                for tick in perpendicular_ticks(points, spacing, size, decoration.side) {
+                    if !tick.iter().all(on_canvas) {
+                        continue;
+                    }
+                    data = data.move_to(self.round_coords(tick[0])).line_to(self.round_coords(tick[1]));
+                }
+                Path::new()
+                    .set("fill", "none")
+                    .set("stroke", decoration.color.as_str())
+                    .set("stroke-width", self.line_width(decoration.width))
+            }
+            LineSymbol::Dots => {
+                let radius = self.round_value(size / 2.0);
+                for center in points_along(points, spacing).into_iter().filter(on_canvas) {
+                    let (x, y) = self.round_coords(center);
+                    data = data
+                        .move_to((x - radius, y))
+                        .elliptical_arc_by((radius, radius, 0, 1, 0, radius * 2.0, 0))
+                        .elliptical_arc_by((radius, radius, 0, 1, 0, -radius * 2.0, 0))
+                        .close();
+                }
+                Path::new().set("fill", decoration.color.as_str()).set("stroke", "none")
+            }
+        };
+        (!data.is_empty()).then(|| path.set("d", data))
+    }
+
+    /// Pattern sizes are style pixels, so they grow with the output DPI like line widths
+    fn scale_pattern(&self, pattern: FillPattern) -> FillPattern {
+        FillPattern {
+            spacing: self.line_width(pattern.spacing),
+            line_width: self.line_width(pattern.line_width),
+            ..pattern
+        }
+    }
+
+    /// `<defs>` with one tile per distinct pattern, referenced by id from area fills
+    fn create_pattern_defs(&self, patterns: HashMap<String, FillPattern>) -> Definitions {
+        let mut patterns: Vec<(String, FillPattern)> = patterns.into_iter().collect();
+        patterns.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut defs = Definitions::new();
+        for (id, pattern) in patterns {
+            let spacing = pattern.spacing;
+            let color = self.color_to_string(&pattern.color);
+            let half = spacing / 2.0;
+            let mut tile = Pattern::new()
+                .set("id", id)
+                .set("patternUnits", "userSpaceOnUse")
+                .set("width", spacing)
+                .set("height", spacing);
+
+            // Marks sit in the middle of the tile so strokes are not cut in half at its edges
+            let line = |(x1, y1): (f32, f32), (x2, y2): (f32, f32)| {
+                Line::new()
+                    .set("x1", x1)
+                    .set("y1", y1)
+                    .set("x2", x2)
+                    .set("y2", y2)
+                    .set("stroke", color.as_str())
+                    .set("stroke-width", pattern.line_width)
+            };
+            tile = match pattern.kind {
+                PatternKind::Hatch => tile
+                    .set("patternTransform", "rotate(45)")
+                    .add(line((half, 0.0), (half, spacing))),
+                PatternKind::CrossHatch => tile
+                    .set("patternTransform", "rotate(45)")
+                    .add(line((half, 0.0), (half, spacing)))
+                    .add(line((0.0, half), (spacing, half))),
+                PatternKind::Dots => tile.add(
+                    Circle::new()
+                        .set("cx", half)
+                        .set("cy", half)
+                        .set("r", pattern.line_width)
+                        .set("fill", color.as_str()),
+                ),
+            };
+            defs = defs.add(tile);
+        }
+        defs
+    }
+
+    /// Circle in the POI type's colour with the number of POIs it stands for
+    fn create_poi_cluster(&self, style: &MapStyle, cluster: &PoiCluster) -> Group {
+        let (color, _) = style.get_poi_style(&cluster.key);
+        let radius = style.poi_clusters.marker_radius(cluster.len());
+
+        let marker = Circle::new()
+            .set("cx", cluster.x)
+            .set("cy", cluster.y)
+            .set("r", radius)
+            .set("fill", color)
+            .set("stroke", "#ffffff")
+            .set("stroke-width", 1.5);
+        let count = Text::new(cluster.len().to_string())
+            .set("x", cluster.x)
+            .set("y", cluster.y)
+            .set("text-anchor", "middle")
+            .set("dominant-baseline", "central")
+            .set("font-family", style.labels.font_family.as_str())
+            .set("font-size", style.poi_clusters.font_size)
+            .set("font-weight", "bold")
+            .set("fill", style.poi_clusters.text_color.as_str());
+
+        Group::new()
+            .add(marker)
+            .add(count)
+    }
+
+    /// Rounded badge with a road ref centered on (x, y)
+    fn create_shield(&self, style: &MapStyle, highway: &str, text: &str, x: f64, y: f64) -> Group {
+        let shields = &style.shields;
+        let (fill, text_color) = style.get_shield_style(highway).unwrap_or(("#757575", "#ffffff"));
+        let font_size = shields.font_size as f64;
+        let padding = shields.padding as f64;
+
+        // Approximate glyph advance; good enough to size a badge around short refs
+        let badge_width = text.chars().count() as f64 * font_size * 0.62 + padding * 2.0;
+        let badge_height = font_size + padding * 2.0;
+
+        let badge = Rectangle::new()
+            .set("x", self.round_value(x - badge_width / 2.0))
+            .set("y", self.round_value(y - badge_height / 2.0))
+            .set("width", self.round_value(badge_width))
+            .set("height", self.round_value(badge_height))
+            .set("rx", shields.corner_radius)
+            .set("fill", fill)
+            .set("stroke", shields.border_color.as_str())
+            .set("stroke-width", 1);
+
+        let label = Text::new(text)
+            .set("x", self.round_value(x))
+            .set("y", self.round_value(y))
+            .set("text-anchor", "middle")
+            .set("dominant-baseline", "central")
+            .set("font-family", style.labels.font_family.as_str())
+            .set("font-size", font_size)
+            .set("font-weight", "bold")
+            .set("fill", text_color);
+
+        Group::new()
+            .set("class", "shield")
+            .add(badge)
+            .add(label)
+    }
+
+    /// The colour behind the map, the current style's unless another was chosen; `None` when
+    /// the export is transparent
+    pub fn background_color(&self) -> Option<Color> {
+        match &self.background {
+            SvgBackground::Style => Color::from_hex(&self.style_manager.get_current_style().background.color),
+            SvgBackground::Color(color) => Some(*color),
+            SvgBackground::Transparent => None,
+        }
+    }
+
+    /// Background fill colour, or `None` when the export is transparent
+    fn background_fill(&self, style_color: &str) -> Option<(String, f32)> {
+        match &self.background {
+            SvgBackground::Style => Some((style_color.to_string(), 1.0)),
+            SvgBackground::Color(color) => Some((
+                format!("#{:02X}{:02X}{:02X}", color.r, color.g, color.b),
+                color.a as f32 / 255.0,
+            )),
+            SvgBackground::Transparent => None,
+        }
+    }
+
+    /// Background rectangle plus the optional embedded image, both stretched over the canvas
+    fn create_background(&self, style_color: &str, width: u32, height: u32) -> Group {
+        let mut group = Group::new().set("id", "background");
+
+        if let Some((color, opacity)) = self.background_fill(style_color) {
+            let mut rect = Rectangle::new()
+                .set("x", 0)
+                .set("y", 0)
+                .set("width", width)
+                .set("height", height)
+                .set("fill", color)
+                .set("stroke", "none");
+            if opacity < 1.0 {
+                rect = rect.set("fill-opacity", opacity);
+            }
+            group = group.add(rect);
         }
+
+        if let Some(path) = &self.background_image {
+            match Self::load_image_data_uri(path) {
+                Ok(uri) => {
+                    group = group.add(
+                        Image::new()
+                            .set("x", 0)
+                            .set("y", 0)
+                            .set("width", width)
+                            .set("height", height)
+                            .set("preserveAspectRatio", "xMidYMid slice")
+                            .set("href", uri),
+                    );
+                }
+                Err(e) => log::warn!("Skipping background image {}: {}", path.display(), e),
+            }
+        }
+
+        group
+    }
+
+    /// Base64-encode an image file as a `data:` URI so the exported SVG stays self-contained
+    fn load_image_data_uri(path: &std::path::Path) -> Result<String> {
+        let bytes = std::fs::read(path)?;
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+        let mime = match extension.as_str() {
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "webp" => "image/webp",
+            "svg" => "image/svg+xml",
+            other => return Err(anyhow::anyhow!("Unsupported image type '{}'", other)),
+        };
+        Ok(format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(bytes)))
+    }
+
+    /// North arrow overlay as its own Inkscape layer, turned with the map bearing
+    fn create_north_arrow(&self, style: &MapStyle, width: u32, height: u32) -> Group {
+        let arrow_style = &style.north_arrow;
+        let shape = NorthArrowShape::new(arrow_style, width as f64, height as f64, self.rotation);
+
+        let triangle = |points: &[(f64, f64); 3]| {
+            Data::new()
+                .move_to((self.round_value(points[0].0), self.round_value(points[0].1)))
+                .line_to((self.round_value(points[1].0), self.round_value(points[1].1)))
+                .line_to((self.round_value(points[2].0), self.round_value(points[2].1)))
+                .close()
+        };
+
+        let mut group = Group::new()
+            .set("id", "north-arrow")
+            .set("inkscape:label", "North Arrow")
+            .set("inkscape:groupmode", "layer");
+
+        group = group.add(
+            Path::new()
+                .set("d", triangle(&shape.light_half))
+                .set("fill", arrow_style.stroke.as_str())
+                .set("stroke", arrow_style.fill.as_str())
+                .set("stroke-width", arrow_style.stroke_width)
+                .set("stroke-linejoin", "round"),
+        );
+        group = group.add(
+            Path::new()
+                .set("d", triangle(&shape.dark_half))
+                .set("fill", arrow_style.fill.as_str())
+                .set("stroke", arrow_style.fill.as_str())
+                .set("stroke-width", arrow_style.stroke_width)
+                .set("stroke-linejoin", "round"),
+        );
+
+        if arrow_style.show_label {
+            let label = Text::new("N")
+                .set("x", self.round_value(shape.label_pos.0))
+                .set("y", self.round_value(shape.label_pos.1))
+                .set("text-anchor", "middle")
+                .set("dominant-baseline", "central")
+                .set("font-family", style.labels.font_family.as_str())
+                .set("font-size", self.round_value(shape.label_size))
+                .set("font-weight", "bold")
+                .set("fill", arrow_style.fill.as_str())
+                .set("stroke", arrow_style.stroke.as_str())
+                .set("stroke-width", arrow_style.stroke_width)
+                .set("paint-order", "stroke");
+            group = group.add(label);
+        }
+
+        group
+    }
+
+    /// Title, date and credit stacked in their corner, aligned to its side and haloed like
+    /// labels so they read over any map
+    fn create_attribution(&self, style: &MapStyle, attribution: &Attribution, width: u32, height: u32) -> Group {
+        let font_size = self.line_width(attribution.font_size) as f64;
+        let margin = self.line_width(attribution.margin) as f64;
+        let font_family = attribution.font_family.as_deref().unwrap_or(style.labels.font_family.as_str());
+
+        // Title a third larger, then the date and the credit
+        let lines: Vec<(&str, f64, &str)> = [
+            attribution.title.as_deref().map(|title| (title, font_size * 4.0 / 3.0, "bold")),
+            attribution.date.as_deref().map(|date| (date, font_size, "normal")),
+            Some((attribution.text.as_str(), font_size, "normal")),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|(text, _, _)| !text.trim().is_empty())
+        .collect();
+
+        let (x, anchor) = match attribution.position {
+            OverlayPosition::TopLeft | OverlayPosition::BottomLeft => (margin, "start"),
+            OverlayPosition::TopRight | OverlayPosition::BottomRight => (width as f64 - margin, "end"),
+        };
+        // Baselines sit a fifth of the font size above the bottom of each line, for descenders
+        let block_height: f64 = lines.iter().map(|(_, size, _)| size * 1.2).sum();
+        let mut top = match attribution.position {
+            OverlayPosition::TopLeft | OverlayPosition::TopRight => margin,
+            OverlayPosition::BottomLeft | OverlayPosition::BottomRight => height as f64 - margin - block_height,
+        };
+
+        let mut group = Group::new()
+            .set("id", "attribution")
+            .set("inkscape:label", "Attribution")
+            .set("inkscape:groupmode", "layer");
+        for (text, size, weight) in lines {
+            top += size * 1.2;
+            group = group.add(
+                Text::new(text)
+                    .set("x", self.round_value(x))
+                    .set("y", self.round_value(top - size * 0.2))
+                    .set("text-anchor", anchor)
+                    .set("font-family", font_family)
+                    .set("font-size", self.round_value(size))
+                    .set("font-weight", weight)
+                    .set("fill", "#333333")
+                    .set("stroke", "#ffffff")
+                    .set("stroke-width", self.round_value(size / 5.0))
+                    .set("stroke-linejoin", "round")
+                    .set("paint-order", "stroke fill"),
+            );
+        }
+        group
+    }
+
+    /// Read a POI icon and encode it as a `data:` URI so the exported SVG stays self-contained
+    /// A road node symbol centred on `(x, y)`
+    fn road_node_symbol(&self, mark: &RoadNodeMark, (x, y): (f64, f64)) -> Box<dyn Node> {
+        let size = self.line_width(mark.size) as f64;
+        let fill = mark.fill.as_deref().unwrap_or("none");
+        let (stroke, stroke_width) = mark.outline.as_ref()
+            .map_or(("none", 0.0), |(color, width)| (color.as_str(), self.line_width(*width)));
+        match mark.shape {
+            RoadNodeShape::Disc | RoadNodeShape::Ring => Box::new(Circle::new()
+                .set("cx", x)
+                .set("cy", y)
+                .set("r", size / 2.0)
+                .set("fill", fill)
+                .set("stroke", stroke)
+                .set("stroke-width", stroke_width)),
+            RoadNodeShape::Square => Box::new(Rectangle::new()
+                .set("x", x - size / 2.0)
+                .set("y", y - size / 2.0)
+                .set("width", size)
+                .set("height", size)
+                .set("fill", fill)
+                .set("stroke", stroke)
+                .set("stroke-width", stroke_width)),
+        }
+    }
+
+    /// The style's icon for `poi_key` as a base64 data URI, so the export stands alone
+    fn load_icon_data_uri(&self, style: &MapStyle, poi_key: &str) -> Option<String> {
+        let path = style.get_poi_icon(poi_key)?;
+        match std::fs::read(path) {
+            Ok(content) => Some(format!(
+                "data:image/svg+xml;base64,{}",
+                base64::engine::general_purpose::STANDARD.encode(content)
+            )),
+            Err(e) => {
+                log::warn!("Failed to read POI icon {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// SVG color of a point from its per-element style override, if one is set
+    fn override_color(&self, map_data: &MapData, element_type: ElementType, id: i64) -> Option<String> {
+        let color = map_data.get_style_override(element_type, id)?.color(false)?;
+        Some(self.color_to_string(&color))
     }
 
     fn is_important_poi(&self, amenity: &str) -> bool {
@@ -442,48 +1591,64 @@ impl SvgExporter {
         result
     }
 
+    /// The line road labels are placed along, in output pixels: the way's own nodes, or a
+    /// simplified copy of them. It never shares the simplification of the drawn geometry, so
+    /// labels stay steady however coarse the drawn roads get.
+    fn label_centerline<F>(&self, way: &crate::core::Way, map_data: &MapData, to_svg_coords: &F) -> Vec<(f64, f64)>
+    where
+        F: Fn(f64, f64) -> (f64, f64),
+    {
+        let points: Vec<(f64, f64)> = way.node_refs()
+            .filter_map(|node_id| map_data.nodes.get(&node_id))
+            .map(|node| to_svg_coords(node.lat, node.lon))
+            .collect();
+        match self.label_simplify_tolerance {
+            Some(tolerance) => {
+                let coords: Vec<geo_types::Coord<f64>> = points.iter().map(|&(x, y)| geo_types::Coord { x, y }).collect();
+                GeometryUtils::simplify_line(&coords, self.line_width(tolerance as f32) as f64)
+                    .into_iter()
+                    .map(|coord| (coord.x, coord.y))
+                    .collect()
+            }
+            None => points,
+        }
+    }
+
     fn calculate_road_label_position<F>(&self, way: &crate::core::Way, map_data: &MapData, to_svg_coords: &F) -> Option<(f64, f64, f64)>
     where
         F: Fn(f64, f64) -> (f64, f64),
     {
-        if way.nodes.len() < 2 {
+        if way.nodes.len() < 2 || map_data.is_way_incomplete(way.id) {
             return None;
         }
 
-        // Find the middle segment of the road for better label placement
-        let mid_index = if way.nodes.len() > 2 {
-            way.nodes.len() / 2
-        } else {
-            0
-        };
+        // Label the middle segment that has a length; zero-length segments have no direction
+        let points = self.label_centerline(way, map_data, to_svg_coords);
+        let segments: Vec<((f64, f64), (f64, f64))> = points.windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .filter(|(a, b)| (b.0 - a.0).hypot(b.1 - a.1) > f64::EPSILON)
+            .collect();
         
-        // Calculate center point of the middle segment
-        if let (Some(node1_id), Some(node2_id)) = (way.nodes.get(mid_index), way.nodes.get(mid_index + 1)) {
-            if let (Some(node1), Some(node2)) = (map_data.nodes.get(node1_id), map_data.nodes.get(node2_id)) {
-                // Convert both nodes to SVG coordinates
-                let coords1 = to_svg_coords(node1.lat, node1.lon);
-                let coords2 = to_svg_coords(node2.lat, node2.lon);
-                
-                // Calculate midpoint of the segment
-                let center_x = (coords1.0 + coords2.0) / 2.0;
-                let center_y = (coords1.1 + coords2.1) / 2.0;
-                
-                // Calculate rotation angle for text alignment along road
-                let dx = coords2.0 - coords1.0;
-                let dy = coords2.1 - coords1.1;
-                let angle = dy.atan2(dx).to_degrees();
-                
-                // Normalize angle to keep text readable (avoid upside-down text)
-                let normalized_angle = if angle > 90.0 {
-                    angle - 180.0
-                } else if angle < -90.0 {
-                    angle + 180.0
-                } else {
-                    angle
-                };
-                
-                return Some((self.round_value(center_x), self.round_value(center_y), normalized_angle));
-            }
+        if let Some(&(coords1, coords2)) = segments.get(segments.len() / 2) {
+            // Calculate midpoint of the segment
+            let center_x = (coords1.0 + coords2.0) / 2.0;
+            let center_y = (coords1.1 + coords2.1) / 2.0;
+
+            // Calculate rotation angle for text alignment along road
+            let dx = coords2.0 - coords1.0;
+            let dy = coords2.1 - coords1.1;
+            let angle = dy.atan2(dx).to_degrees();
+
+            // Normalize angle to keep text readable (avoid upside-down text)
+            let normalized_angle = if angle > 90.0 {
+                angle - 180.0
+            } else if angle < -90.0 {
+                angle + 180.0
+            } else {
+                angle
+            };
+
+            return Some((self.round_value(center_x), self.round_value(center_y), normalized_angle));
         }
         
         // Fallback: use middle node if segment calculation fails
@@ -498,40 +1663,113 @@ impl SvgExporter {
         None
     }
 
-    fn way_to_svg_path<F>(&self, way: &crate::core::Way, map_data: &MapData, to_svg_coords: &F) -> Option<Data>
+    /// Path data for a way, cut to `clip`: lines may split into several pieces, rings are
+    /// closed along the clip edges. Lines are shifted `offset` output pixels to the right of
+    /// their direction first. `None` if nothing of the way is left to draw.
+    fn way_to_svg_path<F>(&self, way: &crate::core::Way, map_data: &MapData, to_svg_coords: &F, clip: &ExportClip, footprint: Option<&FootprintShaping>, offset: f64) -> Option<Data>
     where
         F: Fn(f64, f64) -> (f64, f64),
     {
-        if way.nodes.is_empty() {
+        if way.nodes.is_empty() || map_data.is_way_incomplete(way.id) {
             return None;
         }
 
-        let mut data = Data::new();
-        let mut first = true;
-        let mut valid_points = 0;
+        let mut geo_coords: Vec<geo_types::Coord<f64>> = way.node_refs()
+            .filter_map(|node_id| map_data.nodes.get(&node_id))
+            .map(|node| geo_types::Coord { x: node.lon, y: node.lat })
+            .collect();
+        if let Some(max_segment_length) = self.densify_segments {
+            geo_coords = GeometryUtils::densify_great_circle(&geo_coords, max_segment_length);
+        }
 
-        for node_id in &way.nodes {
-            if let Some(node) = map_data.nodes.get(node_id) {
-                let coords = to_svg_coords(node.lat, node.lon);
-                let rounded_coords = self.round_coords(coords);
-                
-                if first {
-                    data = data.move_to(rounded_coords);
-                    first = false;
-                } else {
-                    data = data.line_to(rounded_coords);
-                }
-                valid_points += 1;
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        for coord in geo_coords {
+            let rounded_coords = self.round_coords(to_svg_coords(coord.y, coord.x));
+            // Repeated positions only add zero-length segments
+            if points.last() != Some(&rounded_coords) {
+                points.push(rounded_coords);
             }
         }
 
         // Only return valid paths
-        if valid_points < 2 {
+        if points.len() < 2 {
             return None;
         }
 
-        // Close path if it's a closed way (area) and has enough points
-        if way.is_closed && valid_points > 2 {
+        // Rings with fewer than 3 distinct points have nothing to fill
+        let is_ring = way.is_closed || way.is_area();
+        if is_ring {
+            let mut distinct: Vec<i64> = way.nodes.iter().copied().filter(|id| map_data.nodes.contains_key(id)).collect();
+            distinct.sort_unstable();
+            distinct.dedup();
+            if distinct.len() < 3 {
+                return None;
+            }
+        }
+
+        let mut coords: Vec<geo_types::Coord<f64>> = points.iter().map(|&(x, y)| geo_types::Coord { x, y }).collect();
+        if let Some(shaping) = footprint.filter(|_| is_ring) {
+            coords = shaping.apply(&coords);
+        }
+        if let Some(tolerance) = self.simplify_tolerance {
+            let tolerance = self.line_width(tolerance as f32) as f64;
+            coords = if is_ring {
+                GeometryUtils::simplify_ring(&coords, tolerance)
+            } else {
+                GeometryUtils::simplify_line(&coords, tolerance)
+            };
+        }
+        if offset != 0.0 && !way.is_area() {
+            coords = GeometryUtils::offset_line(&coords, offset);
+        }
+        let mut close = is_ring;
+        let mut parts = match clip.canvas.as_ref().filter(|canvas| !coords.iter().all(|point| canvas.contains(point))) {
+            None => vec![coords],
+            // Only areas are clipped as polygons; closed lines such as roundabouts would
+            // otherwise gain edges along the canvas border
+            Some(canvas) if way.is_area() => {
+                let ring = match coords.split_last() {
+                    Some((last, rest)) if *last == coords[0] => rest,
+                    _ => &coords[..],
+                };
+                let clipped = canvas.clip_polygon(ring);
+                if clipped.len() < 3 {
+                    return None;
+                }
+                vec![clipped]
+            }
+            Some(canvas) => {
+                close = false;
+                canvas.clip_polyline(&coords)
+            }
+        };
+        if let Some(region) = &clip.region {
+            parts = if way.is_area() {
+                parts.iter().map(|ring| region.clip_polygon(ring)).filter(|ring| ring.len() >= 3).collect()
+            } else {
+                let cut: Vec<Vec<geo_types::Coord<f64>>> = parts.iter().flat_map(|line| region.clip_polyline(line)).collect();
+                // A closed line stays closed only if the region left it whole
+                close &= cut == parts;
+                cut
+            };
+        }
+        if parts.is_empty() {
+            return None;
+        }
+
+        let mut data = Data::new();
+        for part in parts {
+            let mut part = part.into_iter().map(|point| self.round_coords((point.x, point.y)));
+            if let Some(start) = part.next() {
+                data = data.move_to(start);
+            }
+            for point in part {
+                data = data.line_to(point);
+            }
+        }
+
+        // Close path for rings and for ways tagged as areas, if there are enough points
+        if close {
             data = data.close();
         }
 
@@ -548,8 +1786,8 @@ impl SvgExporter {
 
         let mut path_commands = Vec::new();
         
-        for (i, node_id) in way.nodes.iter().enumerate() {
-            if let Some(node) = map_data.nodes.get(node_id) {
+        for (i, node_id) in way.node_refs().enumerate() {
+            if let Some(node) = map_data.nodes.get(&node_id) {
                 let (x, y) = to_svg_coords(node.lat, node.lon);
                 let x = self.round_value(x);
                 let y = self.round_value(y);
@@ -602,6 +1840,21 @@ impl SvgExporter {
         }
     }
 
+    /// A label at `(x, y)`, split over centered `<tspan>` lines when wider than the style's wrap width
+    fn wrapped_text(&self, name: &str, (x, y): (f64, f64), font_size: f64, labels: &LabelStyle) -> Text {
+        let lines = wrap_label(name, font_size, labels.wrap_width as f64);
+        if lines.len() == 1 {
+            return Text::new(name).set("x", x).set("y", y);
+        }
+
+        let line_height = font_size * labels.line_spacing as f64;
+        let mut text = Text::new("").set("x", x).set("y", y);
+        for (line, offset) in lines.iter().zip(line_offsets(lines.len(), line_height)) {
+            text = text.add(TSpan::new(line.as_str()).set("x", x).set("y", self.round_value(y + offset)));
+        }
+        text
+    }
+
     fn estimate_text_length(&self, text: &str, font_size: f64) -> f64 {
         // Rough estimation: average character width is about 0.6 * font_size
         text.len() as f64 * font_size * 0.6
@@ -613,6 +1866,18 @@ impl SvgExporter {
         output_path: P,
         width: u32,
         height: u32,
+    ) -> Result<()> {
+        let file = BufWriter::new(File::create(output_path)?);
+        self.export_to_writer(rendered_map, file, width, height)
+    }
+
+    /// Write an already rendered map as SVG into any writer
+    pub fn export_to_writer<W: Write>(
+        &self,
+        rendered_map: &RenderedMap,
+        mut writer: W,
+        width: u32,
+        height: u32,
     ) -> Result<()> {
         let mut document = Document::new()
             .set("viewBox", (0, 0, width, height))
@@ -622,10 +1887,10 @@ impl SvgExporter {
             .set("xmlns:inkscape", "http://www.inkscape.org/namespaces/inkscape")
             .set("xmlns:sodipodi", "http://sodipodi.sourceforge.net/DTD/sodipodi-0.dtd");
 
-        // Add Inkscape-specific metadata for better compatibility
-        if self.anti_aliasing {
-            document = document.set("shape-rendering", "geometricPrecision");
-        }
+        // Rendering hints are inherited by every shape and label in the document
+        document = document
+            .set("shape-rendering", self.shape_rendering.as_svg_value())
+            .set("text-rendering", self.text_rendering.as_svg_value());
 
         // Create main group for all elements with Inkscape layer support
         let mut main_group = Group::new()
@@ -634,14 +1899,21 @@ impl SvgExporter {
             .set("inkscape:groupmode", "layer");
 
         // Add Google Maps background
-        let background = Rectangle::new()
-            .set("x", 0)
-            .set("y", 0)
-            .set("width", width)
-            .set("height", height)
-            .set("fill", "#F2EFE9");
-
-        main_group = main_group.add(background);
+        main_group = main_group.add(self.create_background("#F2EFE9", width, height));
+
+        let patterns: HashMap<String, FillPattern> = rendered_map.elements.iter()
+            .filter_map(|element| match element {
+                RenderElement::Polygon { style, .. } => style.fill_pattern,
+                _ => None,
+            })
+            .map(|pattern| {
+                let pattern = self.scale_pattern(pattern);
+                (pattern_id(&pattern), pattern)
+            })
+            .collect();
+        if !patterns.is_empty() {
+            document = document.add(self.create_pattern_defs(patterns));
+        }
 
         if self.layer_separation {
             // Render elements grouped by type for better organization
@@ -653,8 +1925,8 @@ impl SvgExporter {
 
         document = document.add(main_group);
 
-        // Write to file using svg crate's save function
-        svg::save(output_path, &document)?;
+        writer.write_all(self.finish_document(document)?.as_bytes())?;
+        writer.flush()?;
         Ok(())
     }
 
@@ -752,8 +2024,16 @@ impl SvgExporter {
             }
         }
 
-        let mut path = Path::new().set("d", path_data);
+        let mut path = Path::new().set("d", path_data.clone());
         path = self.apply_style_to_path(path, style);
+
+        if let Some(pattern) = style.fill_pattern {
+            let overlay = Path::new()
+                .set("d", path_data)
+                .set("fill", format!("url(#{})", pattern_id(&self.scale_pattern(pattern))))
+                .set("stroke", "none");
+            return Ok(Group::new().add(path).add(overlay).into());
+        }
         
         Ok(path.into())
     }
@@ -815,7 +2095,7 @@ impl SvgExporter {
 
         if let Some(ref stroke_color) = style.stroke_color {
             path = path.set("stroke", self.color_to_string(stroke_color));
-            path = path.set("stroke-width", style.stroke_width);
+            path = path.set("stroke-width", self.line_width(style.stroke_width));
             
             if style.stroke_opacity < 1.0 {
                 path = path.set("stroke-opacity", style.stroke_opacity);
@@ -828,9 +2108,8 @@ impl SvgExporter {
                 path = path.set("stroke-dasharray", dash_array.join(","));
             }
             
-            // Add line styling
-            path = path.set("stroke-linecap", "round");
-            path = path.set("stroke-linejoin", "round");
+            path = path.set("stroke-linecap", style.line_cap.as_svg_value());
+            path = path.set("stroke-linejoin", style.line_join.as_svg_value());
         }
 
         path
@@ -846,7 +2125,7 @@ impl SvgExporter {
 
         if let Some(ref stroke_color) = style.stroke_color {
             circle = circle.set("stroke", self.color_to_string(stroke_color));
-            circle = circle.set("stroke-width", style.stroke_width);
+            circle = circle.set("stroke-width", self.line_width(style.stroke_width));
             
             if style.stroke_opacity < 1.0 {
                 circle = circle.set("stroke-opacity", style.stroke_opacity);
@@ -875,8 +2154,7 @@ impl SvgExporter {
             text = text.set("font-weight", style.font_weight);
         }
 
-        // Center text alignment
-        text = text.set("text-anchor", "middle");
+        text = text.set("text-anchor", style.text_anchor.as_svg_value());
         text = text.set("dominant-baseline", "central");
 
         text
@@ -893,11 +2171,21 @@ impl SvgExporter {
         }
     }
 
+    /// A position on the output grid: `coordinate_grid` when set, else `precision` decimals
     fn round_coords(&self, coords: (f64, f64)) -> (f64, f64) {
-        (
-            self.round_value(coords.0),
-            self.round_value(coords.1),
-        )
+        match self.coordinate_grid {
+            Some(step) => {
+                // Multiples of e.g. 0.1 aren't exact in binary; trim them to the step's own decimals
+                let decimals = (0..12).find(|&d| {
+                    let scaled = step * 10_f64.powi(d);
+                    (scaled - scaled.round()).abs() < 1e-9
+                }).unwrap_or(12);
+                let multiplier = 10_f64.powi(decimals);
+                let snap = |value: f64| ((value / step).round() * step * multiplier).round() / multiplier;
+                (snap(coords.0), snap(coords.1))
+            }
+            None => (self.round_value(coords.0), self.round_value(coords.1)),
+        }
     }
 
     fn round_value(&self, value: f64) -> f64 {
@@ -905,7 +2193,9 @@ impl SvgExporter {
         (value * multiplier).round() / multiplier
     }
 
-    fn create_curved_text_labels<F>(&self, way: &crate::core::Way, map_data: &MapData, to_svg_coords: &F, text: &str, font_size: f64) -> Vec<Element>
+    /// Road name along its most central long segment, turned to follow it; `placement` moves the
+    /// label along and across the road
+    fn create_curved_text_labels<F>(&self, way: &crate::core::Way, map_data: &MapData, to_svg_coords: &F, text: &str, font_size: f64, placement: &LabelPlacement) -> Vec<Element>
     where
         F: Fn(f64, f64) -> (f64, f64),
     {
@@ -919,31 +2209,30 @@ impl SvgExporter {
         let mut segments = Vec::new();
         let mut total_length = 0.0;
         
-        for i in 0..way.nodes.len()-1 {
-            if let (Some(node1), Some(node2)) = (
-                map_data.nodes.get(&way.nodes[i]),
-                map_data.nodes.get(&way.nodes[i+1])
-            ) {
-                let point1 = to_svg_coords(node1.lat, node1.lon);
-                let point2 = to_svg_coords(node2.lat, node2.lon);
-                
-                let dx = point2.0 - point1.0;
-                let dy = point2.1 - point1.1;
-                let length = (dx * dx + dy * dy).sqrt();
-                let angle = dy.atan2(dx).to_degrees();
-                
-                // Normalize angle to keep text readable
-                let normalized_angle = if angle > 90.0 {
-                    angle - 180.0
-                } else if angle < -90.0 {
-                    angle + 180.0
-                } else {
-                    angle
-                };
-                
-                segments.push((point1, point2, length, normalized_angle));
-                total_length += length;
+        let points = self.label_centerline(way, map_data, to_svg_coords);
+
+        for pair in points.windows(2) {
+            let (point1, point2) = (pair[0], pair[1]);
+            let dx = point2.0 - point1.0;
+            let dy = point2.1 - point1.1;
+            let length = (dx * dx + dy * dy).sqrt();
+            // A zero-length segment has no direction to follow
+            if length <= f64::EPSILON {
+                continue;
             }
+            let angle = dy.atan2(dx).to_degrees();
+
+            // Normalize angle to keep text readable
+            let normalized_angle = if angle > 90.0 {
+                angle - 180.0
+            } else if angle < -90.0 {
+                angle + 180.0
+            } else {
+                angle
+            };
+
+            segments.push((point1, point2, length, normalized_angle));
+            total_length += length;
         }
         
         if segments.is_empty() {
@@ -957,10 +2246,12 @@ impl SvgExporter {
         // If text is too long for the road, use single centered label
         if text_length > total_length * 0.8 {
             if let Some((label_x, label_y, rotation)) = self.calculate_road_label_position(way, map_data, to_svg_coords) {
+                // The offset is applied before turning, so it follows the road
+                let (x, y) = label_position(placement.text_anchor, (label_x, label_y), 0.0, placement.text_offset);
                 let text_element = Text::new(text)
-                    .set("x", label_x)
-                    .set("y", label_y)
-                    .set("text-anchor", "middle")
+                    .set("x", self.round_value(x))
+                    .set("y", self.round_value(y))
+                    .set("text-anchor", placement.text_anchor.as_svg_value())
                     .set("dominant-baseline", "central")
                     .set("font-family", "Noto Sans")
                     .set("font-size", font_size)
@@ -1003,10 +2294,11 @@ impl SvgExporter {
             let center_x = (start.0 + end.0) / 2.0;
             let center_y = (start.1 + end.1) / 2.0;
             
+            let (x, y) = label_position(placement.text_anchor, (center_x, center_y), 0.0, placement.text_offset);
             let text_element = Text::new(text)
-                .set("x", self.round_value(center_x))
-                .set("y", self.round_value(center_y))
-                .set("text-anchor", "middle")
+                .set("x", self.round_value(x))
+                .set("y", self.round_value(y))
+                .set("text-anchor", placement.text_anchor.as_svg_value())
                 .set("dominant-baseline", "central")
                 .set("font-family", "Noto Sans")
                 .set("font-size", font_size)
@@ -1026,3 +2318,94 @@ impl SvgExporter {
         text_elements
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::{osm::OsmParser, Parser};
+
+    /// A named road and a building whose ways repeat node refs back to back
+    const REPEATED_REFS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" lat="48.8530" lon="2.3480"/>
+  <node id="2" lat="48.8532" lon="2.3500"/>
+  <node id="3" lat="48.8531" lon="2.3520"/>
+  <node id="10" lat="48.8520" lon="2.3490"/>
+  <node id="11" lat="48.8520" lon="2.3500"/>
+  <node id="12" lat="48.8525" lon="2.3500"/>
+  <node id="13" lat="48.8525" lon="2.3490"/>
+  <way id="100">
+    <nd ref="1"/><nd ref="1"/><nd ref="2"/><nd ref="2"/><nd ref="2"/><nd ref="3"/><nd ref="3"/>
+    <tag k="highway" v="residential"/>
+    <tag k="name" v="Rue du Test"/>
+  </way>
+  <way id="200">
+    <nd ref="10"/><nd ref="10"/><nd ref="11"/><nd ref="12"/><nd ref="12"/><nd ref="13"/><nd ref="10"/>
+    <tag k="building" v="yes"/>
+  </way>
+</osm>"#;
+
+    /// The points of each subpath of every `d` attribute in `svg`
+    fn subpaths(svg: &str) -> Vec<Vec<(f64, f64)>> {
+        let mut subpaths = Vec::new();
+        for data in svg.split(" d=\"").skip(1).filter_map(|rest| rest.split('"').next()) {
+            for subpath in data.split('M').filter(|subpath| !subpath.trim().is_empty()) {
+                let points = subpath
+                    .split(|c: char| c == 'L' || c == 'z' || c == 'Z' || c.is_whitespace())
+                    .filter(|pair| !pair.is_empty())
+                    .map(|pair| {
+                        let (x, y) = pair.split_once(',').unwrap_or_else(|| panic!("unexpected path data {:?}", data));
+                        (x.parse().unwrap(), y.parse().unwrap())
+                    })
+                    .collect();
+                subpaths.push(points);
+            }
+        }
+        subpaths
+    }
+
+    #[test]
+    fn repeated_node_refs_draw_without_degenerate_segments() {
+        let map_data = OsmParser::new().parse_string(REPEATED_REFS).unwrap();
+        let road = &map_data.ways[&100];
+        assert_eq!(road.node_refs().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        let exporter = SvgExporter::new().unwrap().with_attribution(None);
+        let svg = exporter.build_document(&map_data, 800, 600, ExportView::new(48.8526, 2.35, 200_000.0)).to_string();
+        assert!(!svg.contains("NaN"), "{}", svg);
+        assert!(svg.contains("Rue du Test"), "the road label is placed");
+
+        let subpaths = subpaths(&svg);
+        assert!(!subpaths.is_empty());
+        for points in subpaths {
+            assert!(points.iter().all(|(x, y)| x.is_finite() && y.is_finite()));
+            assert!(points.windows(2).all(|pair| pair[0] != pair[1]), "zero-length segment in {:?}", points);
+        }
+    }
+
+    #[test]
+    fn poi_icons_are_embedded_as_base64() {
+        let map_data = OsmParser::new().parse_string(r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" lat="48.8530" lon="2.3500"><tag k="amenity" v="cafe"/></node>
+</osm>"#).unwrap();
+        let svg = SvgExporter::new().unwrap().build_document(&map_data, 400, 300, ExportView::new(48.853, 2.35, 200_000.0)).to_string();
+
+        let encoded = svg.split("href=\"data:image/svg+xml;base64,").nth(1).and_then(|rest| rest.split('"').next()).unwrap();
+        let icon = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+        assert_eq!(icon, std::fs::read("assets/icons/poi/cafe.svg").unwrap());
+    }
+
+    #[test]
+    fn leisure_nodes_get_their_icon() {
+        let map_data = OsmParser::new().parse_string(r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" lat="48.8530" lon="2.3500"><tag k="leisure" v="park"/></node>
+</osm>"#).unwrap();
+        let svg = SvgExporter::new().unwrap().build_document(&map_data, 400, 300, ExportView::new(48.853, 2.35, 200_000.0)).to_string();
+
+        let encoded = svg.split("href=\"data:image/svg+xml;base64,").nth(1).and_then(|rest| rest.split('"').next()).unwrap();
+        let icon = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+        assert_eq!(icon, std::fs::read("assets/icons/poi/park.svg").unwrap());
+    }
+}
@@ -0,0 +1,182 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::core::projection::CoordinateUtils;
+use crate::core::{MapBounds, MapData};
+use crate::export::{rasterize_svg, ExportError, ExportFormat, ExportView};
+use crate::export::svg_export::SvgExporter;
+
+/// Writes the map as a grid of `z/x/y` tiles covering the data bounds
+pub struct TileExporter {
+    pub output_dir: PathBuf,
+    pub tile_size: u32,
+    pub format: ExportFormat,
+    pub write_index: bool,
+    pub show_all_road_names: bool,
+}
+
+/// Summary of a tiled export run
+#[derive(Debug, Clone)]
+pub struct TileExportSummary {
+    pub zoom: u8,
+    pub min_x: u32,
+    pub max_x: u32,
+    pub min_y: u32,
+    pub max_y: u32,
+    pub tiles_written: usize,
+}
+
+impl TileExporter {
+    pub fn new<P: Into<PathBuf>>(output_dir: P) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+            tile_size: 256,
+            format: ExportFormat::Svg,
+            write_index: true,
+            show_all_road_names: false,
+        }
+    }
+
+    pub fn with_tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    pub fn with_format(mut self, format: ExportFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_index(mut self, enabled: bool) -> Self {
+        self.write_index = enabled;
+        self
+    }
+
+    pub fn with_all_road_names(mut self, show_all: bool) -> Self {
+        self.show_all_road_names = show_all;
+        self
+    }
+
+    /// Export every tile at `zoom` that intersects the map bounds
+    pub fn export(&self, map_data: &MapData, zoom: u8) -> Result<TileExportSummary, ExportError> {
+        self.validate(zoom)?;
+
+        let bounds = map_data.bounds;
+        if !bounds.min_lat.is_finite() || !bounds.min_lon.is_finite() {
+            return Err(ExportError::NoData);
+        }
+
+        // Tile rows grow southwards, so the north edge gives the smallest y
+        let (min_x, min_y) = CoordinateUtils::lat_lon_to_tile(bounds.max_lat, bounds.min_lon, zoom);
+        let (max_x, max_y) = CoordinateUtils::lat_lon_to_tile(bounds.min_lat, bounds.max_lon, zoom);
+
+        let exporter = SvgExporter::new()?.with_all_road_names(self.show_all_road_names);
+        let extension = crate::export::Exporter::get_extension(self.format);
+
+        let mut tiles_written = 0;
+        for x in min_x..=max_x {
+            let column_dir = self.output_dir.join(zoom.to_string()).join(x.to_string());
+            std::fs::create_dir_all(&column_dir)?;
+
+            for y in min_y..=max_y {
+                let tile_bounds = CoordinateUtils::tile_bounds(x, y, zoom);
+                let tile_path = column_dir.join(format!("{}.{}", y, extension));
+                self.export_tile(&exporter, map_data, &tile_bounds, &tile_path)?;
+                tiles_written += 1;
+            }
+        }
+
+        let summary = TileExportSummary {
+            zoom,
+            min_x,
+            max_x,
+            min_y,
+            max_y,
+            tiles_written,
+        };
+
+        if self.write_index {
+            self.write_index_html(&summary, extension)?;
+        }
+
+        log::info!("Exported {} tiles at zoom {} to {}", tiles_written, zoom, self.output_dir.display());
+        Ok(summary)
+    }
+
+    /// Render the single tile `zoom/x/y` into any writer, e.g. to serve tiles on demand
+    pub fn export_tile_to_writer<W: Write>(&self, map_data: &MapData, zoom: u8, x: u32, y: u32, writer: W) -> Result<(), ExportError> {
+        self.validate(zoom)?;
+        let exporter = SvgExporter::new()?.with_all_road_names(self.show_all_road_names);
+        self.write_tile(&exporter, map_data, &CoordinateUtils::tile_bounds(x, y, zoom), writer)
+    }
+
+    fn validate(&self, zoom: u8) -> Result<(), ExportError> {
+        if self.tile_size == 0 {
+            return Err(ExportError::InvalidDimensions { width: self.tile_size, height: self.tile_size });
+        }
+        if zoom > 22 {
+            return Err(ExportError::InvalidZoom(zoom));
+        }
+        if !matches!(self.format, ExportFormat::Svg | ExportFormat::Png) {
+            return Err(ExportError::UnsupportedFormat(self.format));
+        }
+        Ok(())
+    }
+
+    fn export_tile(&self, exporter: &SvgExporter, map_data: &MapData, tile_bounds: &MapBounds, path: &Path) -> Result<(), ExportError> {
+        let file = BufWriter::new(File::create(path)?);
+        self.write_tile(exporter, map_data, tile_bounds, file)
+    }
+
+    fn write_tile<W: Write>(&self, exporter: &SvgExporter, map_data: &MapData, tile_bounds: &MapBounds, mut writer: W) -> Result<(), ExportError> {
+        let (center_lat, center_lon) = tile_bounds.center();
+
+        // The SVG projection scales longitude by cos(latitude), so fit the tile width exactly;
+        // the viewBox then clips anything that spills over the tile edges
+        let lon_span = tile_bounds.width() * center_lat.to_radians().cos();
+        let scale = self.tile_size as f64 / lon_span;
+
+        let document = exporter.build_document(
+            map_data,
+            self.tile_size,
+            self.tile_size,
+            ExportView::new(center_lat, center_lon, scale),
+        );
+
+        match self.format {
+            ExportFormat::Png => writer.write_all(&rasterize_svg(&document.to_string(), self.tile_size, self.tile_size)?)?,
+            _ => writer.write_all(exporter.finish_document(document)?.as_bytes())?,
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write a simple HTML page that stitches the tiles back together for preview
+    fn write_index_html(&self, summary: &TileExportSummary, extension: &str) -> Result<(), ExportError> {
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>Mapscow Mule tiles - zoom {}</title>\n", summary.zoom));
+        html.push_str("<style>body { margin: 0; } .row { display: flex; } img { display: block; }</style>\n");
+        html.push_str("</head>\n<body>\n");
+
+        for y in summary.min_y..=summary.max_y {
+            html.push_str("<div class=\"row\">");
+            for x in summary.min_x..=summary.max_x {
+                html.push_str(&format!(
+                    "<img src=\"{z}/{x}/{y}.{ext}\" width=\"{size}\" height=\"{size}\" alt=\"{z}/{x}/{y}\">",
+                    z = summary.zoom,
+                    x = x,
+                    y = y,
+                    ext = extension,
+                    size = self.tile_size,
+                ));
+            }
+            html.push_str("</div>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        std::fs::write(self.output_dir.join("index.html"), html)?;
+        Ok(())
+    }
+}
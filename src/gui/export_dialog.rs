@@ -0,0 +1,326 @@
+use crate::export::{Attribution, ExportFormat, ExportOptions, SvgTextMode, MAX_EXPORT_SIZE};
+use crate::export::svg_export::layer_label;
+use crate::gui::GuiState;
+use crate::styles::loader::{MapStyle, OverlayPosition};
+use crate::utils::file_dialog::{FileDialog, FileFilters};
+use egui::{Context, Grid, Window};
+
+/// Segment length offered when turning on great-circle densification of exports
+const DEFAULT_DENSIFY_KM: f64 = 50.0;
+
+/// Grid step offered when turning on coordinate snapping of SVG exports, in pixels
+const DEFAULT_COORDINATE_GRID: f64 = 0.1;
+
+/// Tolerance offered when turning on simplification of exports, in pixels
+const DEFAULT_SIMPLIFY_TOLERANCE: f64 = 0.5;
+
+/// Settings asked for before an export is written. The size starts from the map view each
+/// time the dialog opens; the other choices are kept in `GuiState` for the next export.
+pub struct ExportDialog {
+    open: bool,
+    format: ExportFormat,
+    width: u32,
+    height: u32,
+    /// Size of the map view when the dialog was opened, for "Match view"
+    view_size: (u32, u32),
+    /// Export only the selected features instead of everything in view
+    selected_only: bool,
+    /// Clip the export to the outline of the selected boundary or area
+    clip_to_selection: bool,
+}
+
+impl ExportDialog {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            format: ExportFormat::Svg,
+            width: 1024,
+            height: 768,
+            view_size: (1024, 768),
+            selected_only: false,
+            clip_to_selection: false,
+        }
+    }
+    
+    /// Open the dialog on `format`, sized like the map view
+    pub fn open(&mut self, format: ExportFormat, view_size: (u32, u32)) {
+        self.open = true;
+        self.format = format;
+        self.view_size = (view_size.0.max(1), view_size.1.max(1));
+        (self.width, self.height) = self.view_size;
+    }
+    
+    /// Whether the confirmed export is limited to the selected features
+    pub fn selected_only(&self) -> bool {
+        self.selected_only
+    }
+    
+    /// Whether the confirmed export is clipped to the outline of the selection
+    pub fn clip_to_selection(&self) -> bool {
+        self.clip_to_selection
+    }
+    
+    /// The options to export with, once the user has confirmed and picked where to save.
+    /// `selected` is how many features are selected on the map.
+    pub fn show(&mut self, ctx: &Context, gui_state: &mut GuiState, style: &MapStyle, selected: usize) -> Option<ExportOptions> {
+        if !self.open {
+            return None;
+        }
+        
+        let mut open = true;
+        let mut confirmed = false;
+        let mut cancelled = false;
+        
+        Window::new("💾 Export Map")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .default_width(340.0)
+            .show(ctx, |ui| {
+                Grid::new("export_options").num_columns(2).spacing([12.0, 6.0]).show(ui, |ui| {
+                    ui.label("Format:");
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.format, ExportFormat::Svg, "SVG");
+                        ui.selectable_value(&mut self.format, ExportFormat::Png, "PNG");
+                        ui.selectable_value(&mut self.format, ExportFormat::Jpeg, "JPEG");
+                    });
+                    ui.end_row();
+                    
+                    ui.label("Size:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut self.width).range(1..=MAX_EXPORT_SIZE).suffix(" px"));
+                        ui.label("×");
+                        ui.add(egui::DragValue::new(&mut self.height).range(1..=MAX_EXPORT_SIZE).suffix(" px"));
+                        if ui.small_button("Match view").clicked() {
+                            (self.width, self.height) = self.view_size;
+                        }
+                    });
+                    ui.end_row();
+                    
+                    ui.label("DPI:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut gui_state.export_dpi).range(24.0..=1200.0).speed(1.0));
+                        let factor = gui_state.export_dpi / crate::export::BASELINE_DPI;
+                        ui.weak(format!("{} × {} px written",
+                            (self.width as f32 * factor).round(),
+                            (self.height as f32 * factor).round()));
+                    });
+                    ui.end_row();
+                    
+                    ui.label("Background:");
+                    ui.vertical(|ui| {
+                        // JPEG has no alpha channel, transparent parts come out white
+                        ui.checkbox(&mut gui_state.transparent_background, "Transparent");
+                        ui.horizontal(|ui| {
+                            let image_name = gui_state.background_image.as_ref()
+                                .and_then(|path| path.file_name())
+                                .map_or("No image".to_string(), |name| name.to_string_lossy().to_string());
+                            ui.label(image_name);
+                            if ui.small_button("Image...").clicked() {
+                                if let Some(path) = FileDialog::open_file("Choose background image", &[FileFilters::PNG, FileFilters::JPEG, FileFilters::SVG]) {
+                                    gui_state.background_image = Some(path);
+                                }
+                            }
+                            if gui_state.background_image.is_some() && ui.small_button("Clear").clicked() {
+                                gui_state.background_image = None;
+                            }
+                        });
+                    });
+                    ui.end_row();
+                    
+                    if self.format == ExportFormat::Jpeg {
+                        ui.label("JPEG quality:");
+                        ui.add(egui::DragValue::new(&mut gui_state.jpeg_quality).range(1..=100));
+                        ui.end_row();
+                    }
+                    
+                    ui.label("Simplify:");
+                    ui.horizontal(|ui| {
+                        let mut simplify = gui_state.simplify_tolerance.is_some();
+                        if ui.checkbox(&mut simplify, "")
+                            .on_hover_text("Drop vertices closer than this to the line through their neighbours; makes smaller files")
+                            .changed() {
+                            gui_state.simplify_tolerance = simplify.then_some(DEFAULT_SIMPLIFY_TOLERANCE);
+                        }
+                        if let Some(tolerance) = &mut gui_state.simplify_tolerance {
+                            ui.add(egui::DragValue::new(tolerance).range(0.05..=20.0).speed(0.05).suffix(" px"));
+                        }
+                    });
+                    ui.end_row();
+                    
+                    ui.label("Simplify labels:");
+                    ui.horizontal(|ui| {
+                        let mut simplify = gui_state.label_simplify_tolerance.is_some();
+                        if ui.checkbox(&mut simplify, "")
+                            .on_hover_text("Road labels follow every node of the road unless this is set, however much the drawn roads are simplified")
+                            .changed() {
+                            gui_state.label_simplify_tolerance = simplify.then_some(DEFAULT_SIMPLIFY_TOLERANCE);
+                        }
+                        if let Some(tolerance) = &mut gui_state.label_simplify_tolerance {
+                            ui.add(egui::DragValue::new(tolerance).range(0.05..=20.0).speed(0.05).suffix(" px"));
+                        }
+                    });
+                    ui.end_row();
+                    
+                    ui.label("Attribution:");
+                    Self::attribution_options(ui, gui_state);
+                    ui.end_row();
+                    
+                    ui.label("Features:");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.selected_only, false, "All in view");
+                        ui.add_enabled_ui(selected > 0, |ui| {
+                            ui.radio_value(&mut self.selected_only, true, format!("Selected ({})", selected))
+                                .on_disabled_hover_text("Select features on the map with the Select tool first");
+                        });
+                    });
+                    ui.end_row();
+                    
+                    ui.label("Clip:");
+                    ui.add_enabled_ui(selected > 0, |ui| {
+                        ui.checkbox(&mut self.clip_to_selection, "To selected outline")
+                            .on_hover_text("Cut the map to the shape of the selected boundary relation or closed way")
+                            .on_disabled_hover_text("Select a boundary or area on the map with the Select tool first");
+                    });
+                    ui.end_row();
+                });
+                
+                if self.format == ExportFormat::Svg {
+                    ui.separator();
+                    Self::svg_options(ui, gui_state);
+                }
+                
+                ui.separator();
+                ui.collapsing("Layers", |ui| {
+                    for name in style.layers.layer_names() {
+                        let mut visible = !gui_state.export_hidden_layers.iter().any(|hidden| hidden == name);
+                        if ui.checkbox(&mut visible, layer_label(name)).changed() {
+                            if visible {
+                                gui_state.export_hidden_layers.retain(|hidden| hidden != name);
+                            } else {
+                                gui_state.export_hidden_layers.push(name.to_string());
+                            }
+                        }
+                    }
+                });
+                
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Export...").clicked() {
+                        confirmed = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+        
+        if !open || cancelled {
+            self.open = false;
+            return None;
+        }
+        if !confirmed {
+            return None;
+        }
+        self.selected_only &= selected > 0;
+        self.clip_to_selection &= selected > 0;
+        
+        let (title, file_name, filter) = match self.format {
+            ExportFormat::Svg => ("Export as SVG", "map.svg", FileFilters::SVG),
+            ExportFormat::Png => ("Export as PNG", "map.png", FileFilters::PNG),
+            ExportFormat::Jpeg => ("Export as JPEG", "map.jpg", FileFilters::JPEG),
+            ExportFormat::Pdf => ("Export as PDF", "map.pdf", FileFilters::PDF),
+        };
+        // Cancelling the file dialog goes back to the options
+        let path = FileDialog::save_file(title, file_name, &[filter])?;
+        self.open = false;
+        
+        let mut options = ExportOptions::new(self.format, path.to_string_lossy().to_string())
+            .with_size(self.width, self.height)
+            .with_simplify_tolerance(gui_state.simplify_tolerance)
+            .with_label_simplify_tolerance(gui_state.label_simplify_tolerance)
+            .with_hidden_layers(gui_state.export_hidden_layers.clone())
+            .with_attribution(gui_state.attribution.clone());
+        if self.format == ExportFormat::Jpeg {
+            options = options.with_quality(gui_state.jpeg_quality);
+        }
+        Some(options)
+    }
+    
+    /// Credit, title and date stamped on the export, and their corner
+    fn attribution_options(ui: &mut egui::Ui, gui_state: &mut GuiState) {
+        ui.vertical(|ui| {
+            let mut stamp = gui_state.attribution.is_some();
+            if ui.checkbox(&mut stamp, "Stamp credit")
+                .on_hover_text("OpenStreetMap data must be credited wherever the map is shown")
+                .changed() {
+                gui_state.attribution = stamp.then(Attribution::default);
+            }
+            let Some(attribution) = &mut gui_state.attribution else {
+                return;
+            };
+            ui.text_edit_singleline(&mut attribution.text);
+            // Empty title and date fields leave those lines out
+            for (hint, line) in [("Title", &mut attribution.title), ("Date", &mut attribution.date)] {
+                let mut text = line.clone().unwrap_or_default();
+                if ui.add(egui::TextEdit::singleline(&mut text).hint_text(hint)).changed() {
+                    *line = Some(text).filter(|text| !text.is_empty());
+                }
+            }
+            egui::ComboBox::from_id_salt("attribution_position")
+                .selected_text(attribution.position.label())
+                .show_ui(ui, |ui| {
+                    for position in [OverlayPosition::BottomRight, OverlayPosition::BottomLeft, OverlayPosition::TopRight, OverlayPosition::TopLeft] {
+                        ui.selectable_value(&mut attribution.position, position, position.label());
+                    }
+                });
+        });
+    }
+    
+    /// Options only SVG output has
+    fn svg_options(ui: &mut egui::Ui, gui_state: &mut GuiState) {
+        ui.horizontal(|ui| {
+            ui.label("SVG labels:");
+            egui::ComboBox::from_id_salt("svg_text_mode")
+                .selected_text(gui_state.svg_text.label())
+                .show_ui(ui, |ui| {
+                    for mode in [SvgTextMode::Paths, SvgTextMode::EmbeddedFont, SvgTextMode::Font] {
+                        ui.selectable_value(&mut gui_state.svg_text, mode, mode.label());
+                    }
+                });
+        });
+        ui.checkbox(&mut gui_state.full_geometry, "Export full geometry")
+            .on_hover_text("Keep ways whole instead of cutting them at the edge of the view; makes larger files");
+        ui.horizontal(|ui| {
+            let mut densify = gui_state.densify_segments.is_some();
+            if ui.checkbox(&mut densify, "Curve long segments")
+                .on_hover_text("Follow the great circle on segments longer than the given length; for maps of a wide area")
+                .changed() {
+                gui_state.densify_segments = densify.then_some(DEFAULT_DENSIFY_KM * 1000.0);
+            }
+            if let Some(meters) = &mut gui_state.densify_segments {
+                let mut km = *meters / 1000.0;
+                if ui.add(egui::DragValue::new(&mut km).range(1.0..=5000.0).suffix(" km")).changed() {
+                    *meters = km * 1000.0;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut snap = gui_state.coordinate_grid.is_some();
+            if ui.checkbox(&mut snap, "Snap coordinates")
+                .on_hover_text("Round positions to a coarser pixel grid for smaller files; points move by at most half a step")
+                .changed() {
+                gui_state.coordinate_grid = snap.then_some(DEFAULT_COORDINATE_GRID);
+            }
+            if let Some(step) = &mut gui_state.coordinate_grid {
+                ui.add(egui::DragValue::new(step).range(0.01..=10.0).speed(0.01).suffix(" px"));
+            }
+        });
+    }
+}
+
+impl Default for ExportDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,5 +1,5 @@
 use crate::gui::GuiState;
-use crate::utils::geocoding::GeocodingService;
+use crate::utils::geocoding::{GeocodeCache, GeocodingService, NameIndex};
 use egui::{Context, Window, ScrollArea, RichText, Color32};
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -25,7 +25,15 @@ impl GeocodingPanel {
         }
     }
     
-    pub fn show(&mut self, ctx: &Context, gui_state: &mut GuiState) -> GeocodingAction {
+    /// Answer repeated online searches from `cache`
+    pub fn with_cache(mut self, cache: GeocodeCache) -> Self {
+        self.geocoding_service = Arc::new(Mutex::new(GeocodingService::new().with_cache(cache)));
+        self
+    }
+    
+    /// `name_index` covers the loaded map, if any, and is searched instead of the online
+    /// geocoder when the panel is switched to the loaded data
+    pub fn show(&mut self, ctx: &Context, gui_state: &mut GuiState, name_index: Option<&NameIndex>) -> GeocodingAction {
         if !gui_state.show_geocoding_panel {
             return GeocodingAction::None;
         }
@@ -42,6 +50,32 @@ impl GeocodingPanel {
                 ui.vertical(|ui| {
                     ui.spacing_mut().item_spacing.y = 8.0;
                     
+                    // Where to search; only online until a map is loaded
+                    if name_index.is_none() {
+                        gui_state.search_loaded_data = false;
+                    }
+                    ui.horizontal(|ui| {
+                        let mut changed = ui.radio_value(&mut gui_state.search_loaded_data, false, "🌐 Online").changed();
+                        changed |= ui.add_enabled_ui(name_index.is_some(), |ui| {
+                            ui.radio_value(&mut gui_state.search_loaded_data, true, "🗺 Loaded data")
+                        }).inner.changed();
+                        if changed {
+                            gui_state.geocoding_results.clear();
+                        }
+                        if !gui_state.search_loaded_data {
+                            let mut service = self.geocoding_service.blocking_lock();
+                            let cached = service.cache_mut().len();
+                            if ui.add_enabled(cached > 0, egui::Button::new("🗑 Clear cache"))
+                                .on_hover_text(format!("{} recent searches are answered without going online", cached))
+                                .clicked()
+                            {
+                                if let Err(e) = service.cache_mut().clear() {
+                                    log::warn!("Could not clear the geocoding cache: {}", e);
+                                }
+                            }
+                        }
+                    });
+                    
                     // Search input
                     ui.horizontal(|ui| {
                         ui.label("Search:");
@@ -54,7 +88,10 @@ impl GeocodingPanel {
                         if (search_input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))) 
                             || search_button.clicked() {
                             if !gui_state.search_query.trim().is_empty() {
-                                self.perform_search(gui_state);
+                                match name_index.filter(|_| gui_state.search_loaded_data) {
+                                    Some(index) => gui_state.geocoding_results = index.search(&gui_state.search_query),
+                                    None => self.perform_search(gui_state),
+                                }
                             }
                         }
                     });
@@ -139,7 +176,7 @@ impl GeocodingPanel {
         let geocoding_service = Arc::clone(&self.geocoding_service);
         
         match self.runtime.block_on(async {
-            let service = geocoding_service.lock().await;
+            let mut service = geocoding_service.lock().await;
             service.search(&query).await
         }) {
             Ok(results) => {
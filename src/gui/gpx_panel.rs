@@ -0,0 +1,97 @@
+use crate::core::{GpxStats, GpxTrack};
+use crate::gui::GuiState;
+use crate::utils::units::UnitSystem;
+use egui::{Context, Grid, Window};
+
+/// Floating panel with distance, climb, duration and speed of the loaded GPX tracks
+pub struct GpxPanel;
+
+impl GpxPanel {
+    pub fn new() -> Self {
+        Self
+    }
+    
+    pub fn show(&mut self, ctx: &Context, gui_state: &mut GuiState, tracks: &[GpxTrack]) {
+        if !gui_state.show_gpx_stats {
+            return;
+        }
+        
+        let mut open = true;
+        
+        Window::new("🚶 GPX Statistics")
+            .open(&mut open)
+            .resizable(false)
+            .default_width(280.0)
+            .show(ctx, |ui| {
+                if tracks.is_empty() {
+                    ui.label("No GPX track loaded");
+                    return;
+                }
+                
+                for (index, track) in tracks.iter().enumerate() {
+                    let name = track.name.clone().unwrap_or_else(|| format!("Track {}", index + 1));
+                    ui.strong(name);
+                    Self::stats_grid(ui, index, &track.stats(), gui_state.units);
+                    ui.separator();
+                }
+                
+                if tracks.len() > 1 {
+                    let stats: Vec<GpxStats> = tracks.iter().map(GpxTrack::stats).collect();
+                    let total = GpxStats {
+                        distance: stats.iter().map(|s| s.distance).sum(),
+                        elevation_gain: stats.iter().filter_map(|s| s.elevation_gain).reduce(|a, b| a + b),
+                        elevation_loss: stats.iter().filter_map(|s| s.elevation_loss).reduce(|a, b| a + b),
+                        duration: stats.iter().filter_map(|s| s.duration).reduce(|a, b| a + b),
+                    };
+                    ui.strong("All tracks");
+                    Self::stats_grid(ui, tracks.len(), &total, gui_state.units);
+                }
+            });
+        
+        if !open {
+            gui_state.show_gpx_stats = false;
+        }
+    }
+    
+    /// Figures the recording has no data for are shown as a dash
+    fn stats_grid(ui: &mut egui::Ui, id: usize, stats: &GpxStats, units: UnitSystem) {
+        const MISSING: &str = "–";
+        
+        Grid::new(("gpx_stats", id)).num_columns(2).striped(true).show(ui, |ui| {
+            let (long_length, long_unit) = units.long_unit();
+            
+            ui.label("Distance:");
+            ui.label(units.format_distance(stats.distance));
+            ui.end_row();
+            
+            ui.label("Elevation gain:");
+            ui.label(stats.elevation_gain.map_or(MISSING.to_string(), |gain| format!("+{}", units.format_elevation(gain))));
+            ui.end_row();
+            
+            ui.label("Elevation loss:");
+            ui.label(stats.elevation_loss.map_or(MISSING.to_string(), |loss| format!("-{}", units.format_elevation(loss))));
+            ui.end_row();
+            
+            ui.label("Duration:");
+            ui.label(stats.duration.map_or(MISSING.to_string(), |duration| {
+                let seconds = duration.num_seconds().max(0);
+                format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+            }));
+            ui.end_row();
+            
+            ui.label("Average speed:");
+            ui.label(stats.average_speed().filter(|&speed| speed > 0.0).map_or(MISSING.to_string(), |speed| {
+                // Pace as well, the usual figure for running
+                let pace = long_length / speed;
+                format!("{} ({}:{:02} /{})", units.format_speed(speed), (pace / 60.0) as u64, (pace % 60.0) as u64, long_unit)
+            }));
+            ui.end_row();
+        });
+    }
+}
+
+impl Default for GpxPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -67,6 +67,12 @@ impl LayersPanel {
                         ui.label("🌳 Land Use");
                     });
                     
+                    // Boundaries Layer
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut gui_state.show_boundaries, "");
+                        ui.label("🗾 Boundaries");
+                    });
+                    
                     // POIs Layer
                     ui.horizontal(|ui| {
                         ui.checkbox(&mut gui_state.show_pois, "");
@@ -88,6 +94,7 @@ impl LayersPanel {
                             gui_state.show_roads = true;
                             gui_state.show_water = true;
                             gui_state.show_landuse = true;
+                            gui_state.show_boundaries = true;
                             gui_state.show_pois = true;
                             gui_state.show_gpx = true;
                             gui_state.show_all_road_names = true;
@@ -98,6 +105,7 @@ impl LayersPanel {
                             gui_state.show_roads = false;
                             gui_state.show_water = false;
                             gui_state.show_landuse = false;
+                            gui_state.show_boundaries = false;
                             gui_state.show_pois = false;
                             gui_state.show_gpx = false;
                             gui_state.show_all_road_names = false;
@@ -1,10 +1,50 @@
 use crate::core::MapData;
+use crate::core::geometry::{ClipRect, FitPadding, GeometryUtils, Transform2D, MIN_MAP_EXTENT};
+use crate::core::projection::{CoordinateUtils, METERS_PER_DEGREE};
 use crate::gui::{Tool, GuiState};
 use crate::rendering::MapRenderer;
-use crate::styles::loader::StyleManager;
+use crate::rendering::appearance::{building_parts_in_draw_order, road_class, roads_in_draw_order, way_appearance, LineDecoration, WayAppearance, WayPass, WayStroke};
+use crate::rendering::carriageways::carriageway_offsets;
+use crate::rendering::clustering::cluster_points;
+use crate::rendering::labels::{direction_chevrons, display_name, housenumber_labels, label_position, label_priority, line_offsets, perpendicular_ticks, place_labels, points_along, shield_text, wrap_label, LabelDensityGrid};
+use crate::parsers::stylesheet::{FillPattern, LineCap, TextAnchor};
+use crate::rendering::north_arrow::NorthArrowShape;
+use crate::rendering::patterns::{dot_centers, hatch_segments};
+use crate::rendering::road_nodes::road_node_marks;
+use crate::rendering::stats::FeatureCounter;
+use crate::rendering::tessellation::tessellate_polygon;
+use crate::styles::loader::{LabelStyle, LineSymbol, MapStyle, RoadNodeShape, StyleManager};
+use crate::utils::units::{CoordinateFormat, UnitSystem};
 use egui::{Ui, Response, Sense, Vec2, Pos2, Rect, Color32};
 use log::{debug, info, warn};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Tile size the zoom level readout is expressed in, as on common web maps
+const TILE_SIZE: u32 = 256;
+
+/// Most zoomed-out viewport scale, in pixels per degree
+const MIN_SCALE: f64 = 0.001;
+
+/// Default zoom-in limit, in web map zoom levels
+pub const DEFAULT_MAX_ZOOM: f32 = 20.0;
+
+/// Default distance, in screen pixels, within which a click selects a feature
+pub const DEFAULT_SELECTION_TOLERANCE: f32 = 10.0;
+
+/// Feature id labels are capped at this many per square of this many screen pixels
+const DEBUG_ID_CELL_SIZE: f64 = 48.0;
+const DEBUG_IDS_PER_CELL: usize = 3;
+
+/// Distance in screen pixels the cursor moves before the hovered feature is looked up again
+const HOVER_MOVE_THRESHOLD: f32 = 4.0;
+
+/// Tags describing what a feature is, checked in this order for the hover tooltip
+const HOVER_KEYS: &[&str] = &[
+    "highway", "railway", "waterway", "amenity", "shop", "tourism", "leisure", "natural",
+    "landuse", "building", "aeroway", "barrier", "boundary", "place",
+];
 
 /// Main map view widget
 pub struct MapView {
@@ -16,8 +56,59 @@ pub struct MapView {
     selection_rect: Option<SelectionRect>,
     /// Whether rectangle selection mode is active
     selection_mode: bool,
-    /// Selected map element (for style editing)
-    selected_element: Option<SelectedElement>,
+    /// Selected map elements (for style editing), the one picked last at the end
+    selected_elements: Vec<SelectedElement>,
+    /// Rasterized POI icons keyed by icon path (`None` when the icon failed to load)
+    poi_icons: RefCell<HashMap<PathBuf, Option<egui::TextureHandle>>>,
+    /// Way ids in drawing order, sorted again only after the data or the style changes
+    draw_order: RefCell<Option<DrawOrder>>,
+    /// Zoom-in limit in web map zoom levels
+    max_zoom: f64,
+    /// Click distance in screen pixels within which a feature is selected
+    selection_tolerance: f32,
+    /// Margin kept around the data by zoom to fit
+    fit_padding: FitPadding,
+    /// Last hovered-feature lookup, reused until the cursor or the view moves
+    hover: Option<HoverLookup>,
+    /// Latitude and longitude the context menu was opened at
+    context_menu_at: Option<(f64, f64)>,
+    /// Node the context menu asked to move, with its new latitude and longitude
+    node_move: Option<(i64, f64, f64)>,
+}
+
+/// Way ids in the order the passes draw them, for the data revision they were sorted at
+#[derive(Debug, Clone)]
+struct DrawOrder {
+    data_revision: u64,
+    /// Every way by `layer`, then id
+    ways: Vec<i64>,
+    building_parts: Vec<i64>,
+    /// Roads, with the style revision and the scale their widths were measured at
+    roads: Option<RoadOrder>,
+}
+
+#[derive(Debug, Clone)]
+struct RoadOrder {
+    style_revision: u64,
+    /// Pixels per meter, when the style draws tagged road widths to scale
+    pixels_per_meter: Option<f64>,
+    ids: Vec<i64>,
+}
+
+/// Which of the cached orders `MapView::ways_in_draw_order` gives
+#[derive(Debug, Clone, Copy)]
+enum DrawOrderPass {
+    Layer,
+    BuildingParts,
+    Roads,
+}
+
+#[derive(Debug, Clone)]
+struct HoverLookup {
+    pos: Pos2,
+    /// Viewport center, scale and rotation at the time of the lookup
+    view: (f64, f64, f64, f64),
+    description: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -28,13 +119,23 @@ pub struct SelectedElement {
     pub style_info: StyleInfo,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ElementType {
     Way,
     Node,
     Relation,
 }
 
+impl From<&ElementType> for crate::core::ElementType {
+    fn from(element_type: &ElementType) -> Self {
+        match element_type {
+            ElementType::Way => crate::core::ElementType::Way,
+            ElementType::Node => crate::core::ElementType::Node,
+            ElementType::Relation => crate::core::ElementType::Relation,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct StyleInfo {
     pub category: String,  // e.g., "highway", "building", "natural", etc.
@@ -54,6 +155,7 @@ struct Viewport {
     center_x: f64,
     center_y: f64,
     scale: f64,
+    rotation: f64, // Degrees clockwise, north-up is 0
     width: f32,
     height: f32,
 }
@@ -66,23 +168,58 @@ impl MapView {
                 center_x: 0.0,
                 center_y: 0.0,
                 scale: 1.0,
+                rotation: 0.0,
                 width: 800.0,
                 height: 600.0,
             },
             selection_rect: None,
             selection_mode: false,
-            selected_element: None,
+            selected_elements: Vec::new(),
+            poi_icons: RefCell::new(HashMap::new()),
+            draw_order: RefCell::new(None),
+            max_zoom: DEFAULT_MAX_ZOOM as f64,
+            selection_tolerance: DEFAULT_SELECTION_TOLERANCE,
+            fit_padding: FitPadding::default(),
+            hover: None,
+            context_menu_at: None,
+            node_move: None,
         }
     }
     
-    /// Get the currently selected element
+    /// The element selected last
     pub fn get_selected_element(&self) -> Option<&SelectedElement> {
-        self.selected_element.as_ref()
+        self.selected_elements.last()
+    }
+    
+    /// Every selected element, in the order they were picked
+    pub fn selected_elements(&self) -> &[SelectedElement] {
+        &self.selected_elements
+    }
+    
+    /// Type and id of every selected element
+    pub fn selection(&self) -> Vec<(crate::core::ElementType, i64)> {
+        self.selected_elements.iter()
+            .map(|selected| ((&selected.element_type).into(), selected.element_id))
+            .collect()
     }
     
     /// Clear the current selection
     pub fn clear_selection(&mut self) {
-        self.selected_element = None;
+        self.selected_elements.clear();
+    }
+    
+    /// Drop deleted elements from the selection
+    pub fn retain_selection(&mut self, map_data: &MapData) {
+        self.selected_elements.retain(|selected| match selected.element_type {
+            ElementType::Node => map_data.nodes.contains_key(&selected.element_id),
+            ElementType::Way => map_data.ways.contains_key(&selected.element_id),
+            ElementType::Relation => map_data.relations.contains_key(&selected.element_id),
+        });
+    }
+    
+    /// Node move picked from the context menu since the last call, as `(id, lat, lon)`
+    pub fn take_node_move(&mut self) -> Option<(i64, f64, f64)> {
+        self.node_move.take()
     }
     
     pub fn show(&mut self, ui: &mut Ui, map_data: &Option<MapData>, renderer: &MapRenderer, style_manager: &StyleManager, gui_state: &GuiState, modal_is_open: bool) -> (Response, Option<Pos2>) {
@@ -102,6 +239,12 @@ impl MapView {
             }
         }
         
+        if self.max_zoom != gui_state.max_zoom as f64 {
+            self.set_max_zoom(gui_state.max_zoom as f64);
+        }
+        self.set_selection_tolerance(gui_state.selection_tolerance);
+        self.set_fit_padding(gui_state.fit_padding);
+        
         // Update viewport size
         self.viewport.width = rect.width();
         self.viewport.height = rect.height();
@@ -115,10 +258,58 @@ impl MapView {
         // Get hover position before moving response
         let hover_pos = response.hover_pos();
         
+        if !modal_is_open && !self.selection_mode {
+            self.show_context_menu(&response, rect, gui_state.coordinate_format);
+        }
+        
+        // Name or kind of the feature under the cursor, in any tool
+        if !modal_is_open && !response.dragged() && !ui.memory(|memory| memory.any_popup_open()) {
+            if let (Some(pos), Some(data)) = (hover_pos, map_data) {
+                if let Some(description) = self.hover_description(pos, rect, data) {
+                    response = response.on_hover_ui_at_pointer(|ui| {
+                        ui.label(description);
+                    });
+                }
+            }
+        }
+        
         // Return response and hover position
         (response, hover_pos)
     }
     
+    /// Right-click menu acting on the clicked point
+    fn show_context_menu(&mut self, response: &Response, rect: Rect, coordinates: CoordinateFormat) {
+        if response.secondary_clicked() {
+            self.context_menu_at = response.interact_pointer_pos().map(|pos| {
+                let (lon, lat) = self.screen_to_map(pos, rect);
+                (lat, lon)
+            });
+        }
+        let Some((lat, lon)) = self.context_menu_at else {
+            return;
+        };
+        
+        response.context_menu(|ui| {
+            if ui.button("📋 Copy coordinates").on_hover_text(coordinates.format(lat, lon)).clicked() {
+                ui.output_mut(|o| o.copied_text = coordinates.format(lat, lon));
+                ui.close_menu();
+            }
+            if ui.button("📍 Center here").clicked() {
+                self.center_on_coordinates(lat, lon);
+                ui.close_menu();
+            }
+            let selected_node = self.get_selected_element()
+                .filter(|selected| selected.element_type == ElementType::Node)
+                .map(|selected| selected.element_id);
+            if let Some(id) = selected_node {
+                if ui.button("✋ Move selected node here").clicked() {
+                    self.node_move = Some((id, lat, lon));
+                    ui.close_menu();
+                }
+            }
+        });
+    }
+    
     /// Toggle rectangle selection mode on/off
     pub fn toggle_selection_mode(&mut self) {
         self.selection_mode = !self.selection_mode;
@@ -131,15 +322,62 @@ impl MapView {
         self.selection_mode
     }
     
-    /// Get current zoom level
+    /// Current zoom as a fractional web map zoom level (0 shows the world in one tile)
     pub fn get_zoom_level(&self) -> f64 {
-        self.viewport.scale
+        Self::scale_to_zoom(self.viewport.scale, self.viewport.center_y)
+    }
+    
+    /// Jump to a web map zoom level, keeping the center
+    pub fn set_zoom_level(&mut self, zoom: f64) {
+        self.viewport.scale = Self::zoom_to_scale(zoom, self.viewport.center_y);
+        self.clamp_scale();
+    }
+    
+    /// Set the raw viewport scale in pixels per degree, e.g. from a saved session
+    pub fn set_scale(&mut self, scale: f64) {
+        self.viewport.scale = scale;
+        self.clamp_scale();
+    }
+    
+    /// Limit zooming in to this web map zoom level
+    pub fn set_max_zoom(&mut self, zoom: f64) {
+        self.max_zoom = zoom;
+        self.clamp_scale();
+    }
+    
+    /// Select features within `pixels` of a click
+    pub fn set_selection_tolerance(&mut self, pixels: f32) {
+        self.selection_tolerance = pixels.max(0.0);
+    }
+    
+    /// Leave this much room around the data when zooming to fit it
+    pub fn set_fit_padding(&mut self, padding: FitPadding) {
+        self.fit_padding = padding;
+    }
+    
+    /// Web map zoom level showing the same ground resolution as `scale` pixels per degree.
+    /// Latitudes are clamped to the Web Mercator range so the poles stay finite.
+    pub fn scale_to_zoom(scale: f64, lat: f64) -> f64 {
+        let lat = lat.clamp(-85.0511, 85.0511);
+        CoordinateUtils::zoom_for_meters_per_pixel(METERS_PER_DEGREE / scale, lat, TILE_SIZE)
+    }
+    
+    /// Viewport scale in pixels per degree matching a web map zoom level at `lat`
+    pub fn zoom_to_scale(zoom: f64, lat: f64) -> f64 {
+        let lat = lat.clamp(-85.0511, 85.0511);
+        METERS_PER_DEGREE / CoordinateUtils::meters_per_pixel_at_zoom(zoom, lat, TILE_SIZE)
+    }
+    
+    /// Keep the scale between the zoomed-out floor and the configured maximum zoom
+    fn clamp_scale(&mut self) {
+        let max_scale = Self::zoom_to_scale(self.max_zoom, self.viewport.center_y).max(MIN_SCALE);
+        self.viewport.scale = self.viewport.scale.clamp(MIN_SCALE, max_scale);
     }
 
     /// Zoom by a specific factor (e.g., 1.2 for zoom in, 0.83 for zoom out)
     pub fn zoom_by_factor(&mut self, factor: f64) {
         self.viewport.scale *= factor;
-        self.viewport.scale = self.viewport.scale.clamp(0.001, 500000.0);
+        self.clamp_scale();
     }
     
     /// Center the map on specific coordinates
@@ -148,11 +386,20 @@ impl MapView {
         self.viewport.center_y = lat;
     }
     
-    /// Center the map on specific coordinates with a specific zoom level
-    pub fn center_on_coordinates_with_zoom(&mut self, lat: f64, lon: f64, zoom_scale: f64) {
-        self.viewport.center_x = lon;
-        self.viewport.center_y = lat;
-        self.viewport.scale = zoom_scale.clamp(0.001, 500000.0);
+    /// Center the map on specific coordinates at a web map zoom level
+    pub fn center_on_coordinates_with_zoom(&mut self, lat: f64, lon: f64, zoom: f64) {
+        self.center_on_coordinates(lat, lon);
+        self.set_zoom_level(zoom);
+    }
+    
+    /// Map bearing in degrees, clockwise from north-up
+    pub fn rotation(&self) -> f64 {
+        self.viewport.rotation
+    }
+    
+    /// Rotate the map around the viewport center, normalised to [0, 360)
+    pub fn set_rotation(&mut self, degrees: f64) {
+        self.viewport.rotation = degrees.rem_euclid(360.0);
     }
     
     /// Get viewport information for export (center coordinates and scale)
@@ -160,22 +407,36 @@ impl MapView {
         (self.viewport.center_x, self.viewport.center_y, self.viewport.scale)
     }
     
+    /// Size of the map area in screen pixels, as last drawn
+    pub fn viewport_size(&self) -> (u32, u32) {
+        (self.viewport.width.round() as u32, self.viewport.height.round() as u32)
+    }
+    
+    /// Current scale in screen pixels per meter on the ground
+    pub fn pixels_per_meter(&self) -> f64 {
+        self.viewport.scale / METERS_PER_DEGREE
+    }
+    
     /// Get detailed status information for the status bar
-    pub fn get_status_info(&self, hover_pos: Option<Pos2>, rect: Rect, map_data: &Option<crate::core::MapData>) -> String {
+    pub fn get_status_info(&self, hover_pos: Option<Pos2>, rect: Rect, map_data: &Option<crate::core::MapData>, units: UnitSystem, coordinates: CoordinateFormat) -> String {
         let mut status_parts = Vec::new();
         
         // Add zoom level and scale info
-        let scale_meters_per_pixel = 1.0 / self.viewport.scale * 111320.0; // Approximate meters per pixel
-        status_parts.push(format!("Zoom: {:.1}x", self.viewport.scale / 1000.0));
-        status_parts.push(format!("Scale: {:.0}m/px", scale_meters_per_pixel));
+        let scale_meters_per_pixel = METERS_PER_DEGREE / self.viewport.scale;
+        let (unit_length, unit) = units.short_unit();
+        status_parts.push(format!("Zoom: z{:.1}", self.get_zoom_level()));
+        status_parts.push(format!("Scale: {:.1}{}/px", scale_meters_per_pixel / unit_length, unit));
+        if self.viewport.rotation != 0.0 {
+            status_parts.push(format!("Bearing: {:.0}°", self.viewport.rotation));
+        }
         
         // Add center coordinates
-        status_parts.push(format!("Center: {:.6}, {:.6}", self.viewport.center_x, self.viewport.center_y));
+        status_parts.push(format!("Center: {}", coordinates.format(self.viewport.center_y, self.viewport.center_x)));
         
         // Add mouse coordinates if available
         if let Some(mouse_pos) = hover_pos {
             let (mouse_lon, mouse_lat) = self.screen_to_map(mouse_pos, rect);
-            status_parts.push(format!("Mouse: {:.6}, {:.6}", mouse_lon, mouse_lat));
+            status_parts.push(format!("Mouse: {}", coordinates.format(mouse_lat, mouse_lon)));
         }
         
         // Add map data statistics
@@ -197,21 +458,18 @@ impl MapView {
                 
                 // Zoom towards mouse position if available
                 if let Some(mouse_pos) = response.hover_pos() {
-                    let rel_x = (mouse_pos.x - rect.center().x) as f64;
-                    let rel_y = -(mouse_pos.y - rect.center().y) as f64; // Flip Y
-                    
-                    // Convert to map coordinates
-                    let map_x = self.viewport.center_x + rel_x / self.viewport.scale;
-                    let map_y = self.viewport.center_y + rel_y / self.viewport.scale;
+                    // Map coordinates under the mouse before zooming
+                    let (map_x, map_y) = self.screen_to_map(mouse_pos, rect);
                     
                     // Apply zoom
                     let old_scale = self.viewport.scale;
                     self.viewport.scale *= zoom_factor;
                     debug!("Zoom applied: {} -> {}", old_scale, self.viewport.scale);
                     
-                    // Adjust center to zoom towards mouse position
-                    self.viewport.center_x = map_x - rel_x / self.viewport.scale;
-                    self.viewport.center_y = map_y - rel_y / self.viewport.scale;
+                    // Adjust center so the same map point stays under the mouse
+                    let (new_x, new_y) = self.screen_to_map(mouse_pos, rect);
+                    self.viewport.center_x += map_x - new_x;
+                    self.viewport.center_y += map_y - new_y;
                 } else {
                     // Simple zoom at center
                     let old_scale = self.viewport.scale;
@@ -219,8 +477,7 @@ impl MapView {
                     debug!("Simple zoom applied: {} -> {}", old_scale, self.viewport.scale);
                 }
                 
-                // Clamp zoom level to allow for very detailed viewing
-                self.viewport.scale = self.viewport.scale.clamp(0.001, 500000.0);
+                self.clamp_scale();
             }
         }
 
@@ -230,26 +487,31 @@ impl MapView {
             return; // Skip normal panning/zooming in selection mode
         }
         
-        // Handle element selection in Select mode
-        if gui_state.current_tool == Tool::Select && response.clicked() {
-            if let Some(click_pos) = response.interact_pointer_pos() {
-                self.handle_element_selection(click_pos, rect, map_data);
+        // In Select mode a click picks the element under it and a drag selects everything
+        // the dragged box touches; shift adds to the selection. Other buttons still pan.
+        if gui_state.current_tool == Tool::Select {
+            let add = ui.input(|i| i.modifiers.shift);
+            if response.clicked() {
+                if let Some(click_pos) = response.interact_pointer_pos() {
+                    self.handle_element_selection(click_pos, rect, map_data, add);
+                }
+                return;
+            }
+            if response.drag_started_by(egui::PointerButton::Primary) || self.selection_rect.is_some() {
+                self.handle_box_selection(response, rect, map_data, add);
+                return;
             }
-            return; // Skip panning in select mode
         }
         
         // Handle mouse drag for panning
         if response.dragged() {
             if let Some(last_pos) = self.last_mouse_pos {
                 if let Some(current_pos) = response.interact_pointer_pos() {
-                    let delta = current_pos - last_pos;
-                    
-                    // Convert screen delta to map coordinates
-                    let map_delta_x = (delta.x as f64) / self.viewport.scale;
-                    let map_delta_y = -(delta.y as f64) / self.viewport.scale; // Flip Y axis
-                    
-                    self.viewport.center_x -= map_delta_x;
-                    self.viewport.center_y -= map_delta_y;
+                    // Move the map so the point under the pointer follows it
+                    let (last_lon, last_lat) = self.screen_to_map(last_pos, rect);
+                    let (lon, lat) = self.screen_to_map(current_pos, rect);
+                    self.viewport.center_x -= lon - last_lon;
+                    self.viewport.center_y -= lat - last_lat;
                 }
             }
             self.last_mouse_pos = response.interact_pointer_pos();
@@ -261,56 +523,95 @@ impl MapView {
     fn draw_map(&self, ui: &mut Ui, rect: Rect, map_data: &Option<MapData>, renderer: &MapRenderer, style_manager: &StyleManager, gui_state: &GuiState) {
         let painter = ui.painter_at(rect);
         
-        // Draw background using the style from TOML config
+        // Draw background using the style from TOML config, or a checkerboard when exports are transparent
         let style = style_manager.get_current_style();
-        let bg_color = Self::hex_to_rgb(&style.background.color);
-        painter.rect_filled(rect, 0.0, Color32::from_rgb(bg_color.0, bg_color.1, bg_color.2));
+        if gui_state.transparent_background {
+            Self::draw_checkerboard(&painter, rect);
+        } else {
+            let bg_color = Self::hex_to_rgb(&style.background.color);
+            painter.rect_filled(rect, 0.0, Color32::from_rgb(bg_color.0, bg_color.1, bg_color.2));
+        }
         
         if let Some(data) = map_data {
             // Calculate visible bounds
             let visible_bounds = self.calculate_visible_bounds(rect);
             
+            // Within each pass, ways on a higher `layer` (bridges) draw over lower ones (tunnels)
+            let ways = self.ways_in_draw_order(data, DrawOrderPass::Layer, style_manager);
+            
             // Draw map features in proper order (like Google Maps) based on layer visibility
             // 1. Water bodies and areas (lowest layer)
             if gui_state.show_water {
-                self.draw_water_areas(ui, rect, data, &visible_bounds, style_manager);
+                self.draw_way_pass(ui, rect, data, &ways, style_manager, WayPass::Water);
             }
             
             // 2. Land use areas (parks, forests, etc.)
             if gui_state.show_landuse {
-                self.draw_landuse_areas(ui, rect, data, &visible_bounds, style_manager);
+                self.draw_way_pass(ui, rect, data, &ways, style_manager, WayPass::Landuse);
+                self.draw_way_pass(ui, rect, data, &ways, style_manager, WayPass::Aeroway);
             }
             
-            // 3. Buildings (with shadows for 3D effect)
+            // 3. Buildings
             if gui_state.show_buildings {
-                self.draw_buildings(ui, rect, data, &visible_bounds, style_manager);
+                self.draw_way_pass(ui, rect, data, &ways, style_manager, WayPass::Building);
+                let parts = self.ways_in_draw_order(data, DrawOrderPass::BuildingParts, style_manager);
+                self.draw_way_pass(ui, rect, data, &parts, style_manager, WayPass::BuildingPart);
             }
             
-            // 4. Road casings (dark outlines first)
-            if gui_state.show_roads {
-                self.draw_road_casings(ui, rect, data, &visible_bounds, style_manager);
+            // Cliffs, walls and hedges, with their marks
+            if gui_state.show_landuse {
+                self.draw_way_pass(ui, rect, data, &ways, style_manager, WayPass::Decorated);
             }
             
-            // 5. Road fills (lighter colors on top)
+            // 4-5. Road casings (dark outlines first), then fills (lighter colors on top).
+            // Merged junctions draw them a whole layer at a time, otherwise road by road
             if gui_state.show_roads {
-                self.draw_road_fills(ui, rect, data, &visible_bounds, style_manager);
+                let style = style_manager.get_current_style();
+                let roads = self.ways_in_draw_order(data, DrawOrderPass::Roads, style_manager);
+                let groups: Vec<&[&crate::core::Way]> = if style.road_casings.merge_junctions {
+                    roads.chunk_by(|a, b| a.layer() == b.layer()).collect()
+                } else {
+                    roads.chunks(1).collect()
+                };
+                for group in groups {
+                    self.draw_road_casings(ui, rect, data, group, &visible_bounds, style_manager);
+                    self.draw_road_fills(ui, rect, data, group, &visible_bounds, style_manager);
+                }
+                self.draw_road_nodes(ui, rect, data, &visible_bounds, style);
             }
             
             // 6. Railways and other transport
             if gui_state.show_roads {
-                self.draw_railways(ui, rect, data, &visible_bounds, style_manager);
+                self.draw_way_pass(ui, rect, data, &ways, style_manager, WayPass::Railway);
             }
             
-            // 7. Points of Interest (POIs)
+            // 7. Administrative boundaries
+            if gui_state.show_boundaries {
+                self.draw_way_pass(ui, rect, data, &ways, style_manager, WayPass::Boundary);
+            }
+            
+            // 8. Points of Interest (POIs)
             if gui_state.show_pois {
                 self.draw_pois(ui, rect, data, &visible_bounds, style_manager);
             }
             
-            // 8. Text labels (highest layer)
-            self.draw_text_labels(ui, rect, data, &visible_bounds, style_manager);
+            // 9. Text labels and road ref shields (highest layer). As in the export they share
+            // one density cap, names taking their cells before shields.
+            let labels = &style.labels;
+            let mut label_grid = LabelDensityGrid::new(labels.label_cell_size, labels.max_labels_per_cell);
+            self.draw_text_labels(ui, rect, data, &visible_bounds, style_manager, &mut label_grid);
+            if gui_state.show_roads {
+                self.draw_road_shields(ui, rect, data, &visible_bounds, style_manager, &mut label_grid);
+            }
+            if gui_state.show_debug_ids {
+                self.draw_debug_ids(ui, rect, data, &ways, &visible_bounds);
+            }
             
-            // 9. Selection highlight (topmost layer)
+            // 10. Selection highlight
             self.draw_selection_highlight(ui, rect, data);
+            
+            // 11. North arrow overlay (topmost layer)
+            self.draw_north_arrow(ui, rect, style_manager);
         } else {
             // Draw placeholder text
             let text = "No map data loaded";
@@ -328,29 +629,36 @@ impl MapView {
         self.draw_selection_rectangle(ui, rect);
     }
     
+    /// Map coordinates (lon, lat) to screen pixels for the current view drawn in `rect`:
+    /// the view center lands on the middle of `rect`, turned by the map bearing
+    fn view_transform(&self, rect: Rect) -> Transform2D {
+        let center = rect.center();
+        Transform2D::translation(center.x as f64, center.y as f64)
+            .compose(&Transform2D::rotation(self.viewport.rotation.to_radians()))
+            .compose(&Transform2D::scale(self.viewport.scale, -self.viewport.scale)) // Flip Y axis
+            .compose(&Transform2D::translation(-self.viewport.center_x, -self.viewport.center_y))
+    }
+    
     fn map_to_screen(&self, lon: f64, lat: f64, rect: Rect) -> Pos2 {
-        let x = (lon - self.viewport.center_x) * self.viewport.scale + (rect.width() / 2.0) as f64;
-        let y = -(lat - self.viewport.center_y) * self.viewport.scale + (rect.height() / 2.0) as f64;
-        
-        Pos2::new(
-            rect.min.x + x as f32,
-            rect.min.y + y as f32,
-        )
+        let point = self.view_transform(rect).transform_point(&geo_types::Coord { x: lon, y: lat });
+        Pos2::new(point.x as f32, point.y as f32)
     }
     
     fn screen_to_map(&self, screen_pos: Pos2, rect: Rect) -> (f64, f64) {
-        let rel_x = (screen_pos.x - rect.min.x) as f64 - (rect.width() / 2.0) as f64;
-        let rel_y = (screen_pos.y - rect.min.y) as f64 - (rect.height() / 2.0) as f64;
-        
-        let lon = self.viewport.center_x + rel_x / self.viewport.scale;
-        let lat = self.viewport.center_y - rel_y / self.viewport.scale; // Flip Y
-        
-        (lon, lat)
+        let screen = geo_types::Coord { x: screen_pos.x as f64, y: screen_pos.y as f64 };
+        // The view transform only fails to invert at a zero scale, where everything is the center
+        self.view_transform(rect)
+            .inverse_transform_point(&screen)
+            .map_or((self.viewport.center_x, self.viewport.center_y), |point| (point.x, point.y))
     }
     
     fn calculate_visible_bounds(&self, rect: Rect) -> VisibleBounds {
-        let half_width = (rect.width() / 2.0) as f64 / self.viewport.scale;
-        let half_height = (rect.height() / 2.0) as f64 / self.viewport.scale;
+        // A rotated viewport covers the axis-aligned box around its turned corners
+        let (sin_a, cos_a) = self.viewport.rotation.to_radians().sin_cos();
+        let (sin_a, cos_a) = (sin_a.abs(), cos_a.abs());
+        let (w, h) = ((rect.width() / 2.0) as f64, (rect.height() / 2.0) as f64);
+        let half_width = (w * cos_a + h * sin_a) / self.viewport.scale;
+        let half_height = (w * sin_a + h * cos_a) / self.viewport.scale;
         
         let bounds = VisibleBounds {
             min_lon: self.viewport.center_x - half_width,
@@ -369,6 +677,11 @@ impl MapView {
     }
     
     fn way_intersects_bounds(&self, way: &crate::core::Way, map_data: &MapData, bounds: &VisibleBounds) -> bool {
+        // Ways that lost most of their nodes to the extract boundary are never drawn
+        if map_data.is_way_incomplete(way.id) {
+            return false;
+        }
+        
         // Simple bounds check - in production, you'd want more sophisticated culling
         // Make this more permissive for debugging
         way.nodes.iter().any(|&node_id| {
@@ -388,78 +701,39 @@ impl MapView {
         lat >= bounds.min_lat && lat <= bounds.max_lat
     }
     
-    fn get_way_style(&self, way: &crate::core::Way, style_manager: &StyleManager) -> ((u8, u8, u8), f32) {
-        let style = style_manager.get_current_style();
-        
-        // Check for roads first
-        if let Some(highway) = way.tags.get("highway") {
-            let (color_str, width, _, _) = style.get_road_style(highway);
-            return (Self::hex_to_rgb(color_str), width);
-        }
-        
-        // Check for buildings
-        if way.tags.contains_key("building") {
-            return (Self::hex_to_rgb(&style.buildings.fill), style.buildings.stroke_width);
-        }
-        
-        // Check for waterways
-        if way.tags.contains_key("waterway") {
-            return (Self::hex_to_rgb(&style.water.color), 2.0);
+    /// egui alignment matching an SVG `text-anchor`, vertically centered like the export's labels
+    fn text_align(anchor: TextAnchor) -> egui::Align2 {
+        match anchor {
+            TextAnchor::Start => egui::Align2::LEFT_CENTER,
+            TextAnchor::Middle => egui::Align2::CENTER_CENTER,
+            TextAnchor::End => egui::Align2::RIGHT_CENTER,
         }
-        
-        // Check for natural water features
-        if way.tags.get("natural") == Some(&"water".to_string()) {
-            return (Self::hex_to_rgb(&style.water.color), 1.0);
-        }
-        
-        // Check for landuse
-        if let Some(landuse) = way.tags.get("landuse") {
-            if let Some(color) = style.get_landuse_color(landuse) {
-                return (Self::hex_to_rgb(color), 1.0);
-            }
-        }
-        
-        // Check for leisure
-        if let Some(leisure) = way.tags.get("leisure") {
-            if let Some(color) = style.get_leisure_color(leisure) {
-                return (Self::hex_to_rgb(color), 1.0);
-            }
-        }
-        
-        // Check for natural features
-        if let Some(natural) = way.tags.get("natural") {
-            if let Some(color) = style.get_natural_color(natural) {
-                return (Self::hex_to_rgb(color), 1.0);
-            }
-        }
-        
-        // Default fallback
-        ((100, 100, 100), 1.0)
     }
     
-    fn get_node_style(&self, node: &crate::core::Node, style_manager: &StyleManager) -> ((u8, u8, u8), f32) {
-        let style = style_manager.get_current_style();
-        
-        // Check for amenities
-        if let Some(amenity) = node.tags.get("amenity") {
-            let (color_str, radius) = style.get_poi_style(amenity);
-            return (Self::hex_to_rgb(color_str), radius);
-        }
-        
-        // Check for shops
-        if let Some(shop) = node.tags.get("shop") {
-            let (color_str, radius) = style.get_poi_style(shop);
-            return (Self::hex_to_rgb(color_str), radius);
+    /// Draw a place or POI name at `pos`, stacking its lines centered on it when wrapped
+    fn draw_wrapped_label(painter: &egui::Painter, pos: Pos2, anchor: TextAnchor, name: &str, font_size: f32, labels: &LabelStyle, color: Color32) {
+        let lines = wrap_label(name, font_size as f64, labels.wrap_width as f64);
+        let line_height = (font_size * labels.line_spacing) as f64;
+        for (line, offset) in lines.iter().zip(line_offsets(lines.len(), line_height)) {
+            painter.text(
+                Pos2::new(pos.x, pos.y + offset as f32),
+                Self::text_align(anchor),
+                line,
+                egui::FontId::proportional(font_size),
+                color,
+            );
         }
-        
-        // Check for tourism
-        if let Some(tourism) = node.tags.get("tourism") {
-            let (color_str, radius) = style.get_poi_style(tourism);
-            return (Self::hex_to_rgb(color_str), radius);
+    }
+    
+    /// Style colour as egui colour, faded by `opacity`
+    fn style_color(hex: &str, opacity: f32) -> Color32 {
+        let (r, g, b) = Self::hex_to_rgb(hex);
+        let color = Color32::from_rgb(r, g, b);
+        if opacity < 1.0 {
+            color.gamma_multiply(opacity)
+        } else {
+            color
         }
-        
-        // Default small gray node
-        ((128, 128, 128), 1.0)
     }
     
     /// Convert hex color string to RGB tuple
@@ -478,36 +752,57 @@ impl MapView {
         (128, 128, 128)
     }
     
+    /// Colour an element is drawn in now, its override included, to start a colour picker from:
+    /// the fill of an area or the line of a way from the topmost pass drawing it, or a POI's colour
+    pub fn drawn_color(&self, map_data: &MapData, style: &MapStyle, element_type: crate::core::ElementType, id: i64) -> Option<Color32> {
+        match element_type {
+            crate::core::ElementType::Node => {
+                let node = map_data.nodes.get(&id)?;
+                self.override_color(map_data, element_type, id).or_else(|| {
+                    let (r, g, b) = Self::hex_to_rgb(style.get_poi_style(&MapStyle::poi_key(&node.tags)?).0);
+                    Some(Color32::from_rgb(r, g, b))
+                })
+            }
+            crate::core::ElementType::Way => {
+                let way = map_data.ways.get(&id)?;
+                let passes = [WayPass::Road, WayPass::Boundary, WayPass::Railway, WayPass::Decorated, WayPass::BuildingPart,
+                    WayPass::Building, WayPass::Aeroway, WayPass::Landuse, WayPass::Water];
+                let look = passes.into_iter().find_map(|pass| way_appearance(style, map_data, way, pass, self.viewport.scale))?;
+                let look = match map_data.get_style_override(element_type, id) {
+                    Some(style_override) => look.with_override(style_override),
+                    None => look,
+                };
+                let color = look.fill.as_ref().or(look.stroke.as_ref().map(|stroke| &stroke.color))?;
+                Some(Self::style_color(color, 1.0))
+            }
+            crate::core::ElementType::Relation => None,
+        }
+    }
+    
+    /// Color of a point from its per-element style override, if one is set
+    fn override_color(&self, map_data: &MapData, element_type: crate::core::ElementType, id: i64) -> Option<Color32> {
+        let color = map_data.get_style_override(element_type, id)?.color(false)?;
+        Some(Color32::from_rgba_unmultiplied(color.r, color.g, color.b, color.a))
+    }
+    
     pub fn zoom_to_fit(&mut self, map_data: &Option<MapData>) {
         if let Some(data) = map_data {
             if let Some(data_bounds) = self.calculate_data_bounds(data) {
-                // Debug multiple coordinates to check rendering
-                let coords = vec![
-                    (48.9443224247288, 2.177457844649215, "Boulevard de Bezons coord 1"),
-                    (48.94396813214317, 2.1806281043179876, "Rue Georges Bernanos coord"),
-                    (48.9448515, 2.1814650, "Georges Bernanos node 331847783"),
-                    (48.9445555, 2.1812572, "Georges Bernanos node 2558905075"),
-                ];
+                let bounds = data_bounds.bounds;
+                self.viewport.center_x = (bounds.min_lon + bounds.max_lon) / 2.0;
+                self.viewport.center_y = (bounds.min_lat + bounds.max_lat) / 2.0;
                 
-                // Use the first coordinate as center
-                let (target_lat, target_lon, desc) = coords[0];
-                
-                debug!("zoom_to_fit - centering on: {} at lat={:.6}, lon={:.6}", desc, target_lat, target_lon);
-                
-                self.viewport.center_x = target_lon;
-                self.viewport.center_y = target_lat;
-                
-                // Use moderate zoom to see wider area
-                self.viewport.scale = 50000.0;
+                // Fit the (rotated) bounds into the padded viewport; the scale is pixels per degree
+                let (lon_span, lat_span) = (bounds.max_lon - bounds.min_lon, bounds.max_lat - bounds.min_lat);
+                let (sin_a, cos_a) = self.viewport.rotation.to_radians().sin_cos();
+                let (sin_a, cos_a) = (sin_a.abs(), cos_a.abs());
+                let scale_x = self.fit_padding.inner(self.viewport.width as f64) / (lon_span * cos_a + lat_span * sin_a);
+                let scale_y = self.fit_padding.inner(self.viewport.height as f64) / (lon_span * sin_a + lat_span * cos_a);
+                self.viewport.scale = scale_x.min(scale_y);
+                self.clamp_scale();
                 
                 debug!("zoom_to_fit - set viewport center to ({:.6}, {:.6}) with scale {:.1}", 
                          self.viewport.center_x, self.viewport.center_y, self.viewport.scale);
-                
-                // Check which roads exist near these coordinates
-                for (lat, lon, desc) in coords {
-                    debug!("Checking roads near {}: lat={:.6}, lon={:.6}", desc, lat, lon);
-                    self.debug_roads_near_point(data, lat, lon, 0.002); // ~200m radius
-                }
             }
         } else {
             // No data, reset to default
@@ -618,15 +913,9 @@ impl MapView {
         debug!("calculate_data_bounds: raw bounds - lat: {:.6} to {:.6}, lon: {:.6} to {:.6}", 
                  min_lat, max_lat, min_lon, max_lon);
         
-        // Add small padding if bounds are too small
-        if (max_lon - min_lon) < 0.001 {
-            min_lon -= 0.0005;
-            max_lon += 0.0005;
-        }
-        if (max_lat - min_lat) < 0.001 {
-            min_lat -= 0.0005;
-            max_lat += 0.0005;
-        }
+        // Widen bounds that are too small, down to a single point
+        let (min_lon, max_lon) = GeometryUtils::with_min_extent(min_lon, max_lon, MIN_MAP_EXTENT);
+        let (min_lat, max_lat) = GeometryUtils::with_min_extent(min_lat, max_lat, MIN_MAP_EXTENT);
         
         debug!("calculate_data_bounds: final bounds - lat: {:.6} to {:.6}, lon: {:.6} to {:.6}", 
                  min_lat, max_lat, min_lon, max_lon);
@@ -643,48 +932,6 @@ impl MapView {
         })
     }
     
-    fn debug_roads_near_point(&self, map_data: &MapData, target_lat: f64, target_lon: f64, radius: f64) {
-        debug!("Searching for roads near lat={:.6}, lon={:.6}, radius={:.6}", target_lat, target_lon, radius);
-        
-        let mut found_roads = Vec::new();
-        
-        for way in map_data.ways.values() {
-            if let Some(highway) = way.tags.get("highway") {
-                // Check if any node in this way is within the radius
-                let mut has_nearby_node = false;
-                let mut min_distance = f64::INFINITY;
-                
-                for &node_id in &way.nodes {
-                    if let Some(node) = map_data.nodes.get(&node_id) {
-                        let lat_diff = node.lat - target_lat;
-                        let lon_diff = node.lon - target_lon;
-                        let distance = (lat_diff * lat_diff + lon_diff * lon_diff).sqrt();
-                        
-                        if distance < min_distance {
-                            min_distance = distance;
-                        }
-                        
-                        if distance <= radius {
-                            has_nearby_node = true;
-                        }
-                    }
-                }
-                
-                if has_nearby_node {
-                    let name = way.tags.get("name").cloned().unwrap_or_else(|| "<unnamed>".to_string());
-                    found_roads.push((way.id, name, highway.clone(), min_distance));
-                }
-            }
-        }
-        
-        found_roads.sort_by(|a, b| a.3.partial_cmp(&b.3).unwrap());
-        
-        debug!("Found {} roads near target point:", found_roads.len());
-        for (id, name, highway, distance) in found_roads.iter().take(10) {
-            debug!("  Way {}: '{}' ({}), distance: {:.6}", id, name, highway, distance);
-        }
-    }
-    
     fn matches_way_selectors(&self, way: &crate::core::Way, selectors: &[crate::parsers::stylesheet::FeatureSelector]) -> bool {
         for selector in selectors {
             match selector {
@@ -700,12 +947,21 @@ impl MapView {
                         }
                     }
                 }
+                crate::parsers::stylesheet::FeatureSelector::TagListContains { key, value } => {
+                    if let Some(tag_value) = way.tags.get(key) {
+                        if crate::parsers::stylesheet::tag_list_contains(tag_value, value) {
+                            return true;
+                        }
+                    }
+                }
                 crate::parsers::stylesheet::FeatureSelector::ElementType(element_type) => {
                     match element_type {
                         crate::parsers::stylesheet::ElementType::Way => return true,
                         _ => {}
                     }
                 }
+                crate::parsers::stylesheet::FeatureSelector::Conditions(conditions)
+                    if crate::parsers::stylesheet::conditions_match(conditions, &way.tags) => return true,
                 _ => {}
             }
         }
@@ -727,12 +983,21 @@ impl MapView {
                         }
                     }
                 }
+                crate::parsers::stylesheet::FeatureSelector::TagListContains { key, value } => {
+                    if let Some(tag_value) = node.tags.get(key) {
+                        if crate::parsers::stylesheet::tag_list_contains(tag_value, value) {
+                            return true;
+                        }
+                    }
+                }
                 crate::parsers::stylesheet::FeatureSelector::ElementType(element_type) => {
                     match element_type {
                         crate::parsers::stylesheet::ElementType::Node => return true,
                         _ => {}
                     }
                 }
+                crate::parsers::stylesheet::FeatureSelector::Conditions(conditions)
+                    if crate::parsers::stylesheet::conditions_match(conditions, &node.tags) => return true,
                 _ => {}
             }
         }
@@ -816,31 +1081,21 @@ impl MapView {
             return;
         }
         
-        // Convert screen coordinates to map coordinates
-        let center_x = rect.center().x;
-        let center_y = rect.center().y;
-        
-        // Calculate map coordinates for selection bounds
-        let min_map_x = self.viewport.center_x + ((min_x - center_x) as f64) / self.viewport.scale;
-        let max_map_x = self.viewport.center_x + ((max_x - center_x) as f64) / self.viewport.scale;
-        let min_map_y = self.viewport.center_y - ((max_y - center_y) as f64) / self.viewport.scale; // Flip Y
-        let max_map_y = self.viewport.center_y - ((min_y - center_y) as f64) / self.viewport.scale; // Flip Y
-        
-        // Calculate new center
-        self.viewport.center_x = (min_map_x + max_map_x) / 2.0;
-        self.viewport.center_y = (min_map_y + max_map_y) / 2.0;
+        // The selection is drawn in screen space, so its center maps through the current rotation
+        let selection_center = Pos2::new((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        let (center_lon, center_lat) = self.screen_to_map(selection_center, rect);
+        self.viewport.center_x = center_lon;
+        self.viewport.center_y = center_lat;
         
         // Calculate new scale to fit the selection
-        let map_width = max_map_x - min_map_x;
-        let map_height = max_map_y - min_map_y;
+        let map_width = selection_width as f64 / self.viewport.scale;
+        let map_height = selection_height as f64 / self.viewport.scale;
         
         if map_width > 0.0 && map_height > 0.0 {
             let scale_x = (rect.width() as f64 * 0.9) / map_width; // 90% to leave some padding
             let scale_y = (rect.height() as f64 * 0.9) / map_height;
             self.viewport.scale = scale_x.min(scale_y);
-            
-            // Clamp to very detailed zoom levels
-            self.viewport.scale = self.viewport.scale.clamp(0.001, 500000.0);
+            self.clamp_scale();
         }
     }
     
@@ -921,13 +1176,12 @@ impl MapView {
         }
     }
     
-    /// Draw a highlight around the selected element
+    /// Draw a highlight around each selected element
     fn draw_selection_highlight(&self, ui: &mut Ui, rect: Rect, map_data: &MapData) {
-        if let Some(selected) = &self.selected_element {
-            let painter = ui.painter_at(rect);
-            let highlight_color = Color32::from_rgb(255, 100, 0); // Orange highlight
-            let highlight_width = 3.0;
-            
+        let painter = ui.painter_at(rect);
+        let highlight_color = Color32::from_rgb(255, 100, 0); // Orange highlight
+        let highlight_width = 3.0;
+        for selected in &self.selected_elements {
             match selected.element_type {
                 ElementType::Way => {
                     if let Some(way) = map_data.ways.get(&selected.element_id) {
@@ -946,21 +1200,21 @@ impl MapView {
         }
     }
     
+    /// Screen positions of a way's resolvable nodes, with repeated node refs collapsed
+    fn way_screen_points(&self, way: &crate::core::Way, map_data: &MapData, rect: Rect) -> Vec<Pos2> {
+        way.node_refs()
+            .filter_map(|node_id| map_data.nodes.get(&node_id))
+            .map(|node| self.map_to_screen(node.lon, node.lat, rect))
+            .collect()
+    }
+    
     /// Draw highlight for a selected way
     fn draw_way_highlight(&self, painter: &egui::Painter, rect: Rect, way: &crate::core::Way, map_data: &MapData, color: Color32, width: f32) {
         if way.nodes.len() < 2 {
             return;
         }
         
-        let mut screen_points = Vec::new();
-        
-        // Convert way nodes to screen coordinates
-        for &node_id in &way.nodes {
-            if let Some(node) = map_data.nodes.get(&node_id) {
-                let screen_pos = self.map_to_screen(node.lon, node.lat, rect);
-                screen_points.push(screen_pos);
-            }
-        }
+        let screen_points = self.way_screen_points(way, map_data, rect);
         
         if screen_points.len() < 2 {
             return;
@@ -991,410 +1245,578 @@ impl MapView {
 
     // Google Maps-style specialized drawing methods
     
-    fn draw_water_areas(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, visible_bounds: &VisibleBounds, style_manager: &StyleManager) {
+    /// Draw every way on screen that takes part in `pass`, styled by the same rules as the SVG export
+    fn draw_way_pass(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, ways: &[&crate::core::Way], style_manager: &StyleManager, pass: WayPass) {
         let painter = ui.painter_at(rect);
+        let style = style_manager.get_current_style();
+        let visible_bounds = self.calculate_visible_bounds(rect);
         
-        for way in map_data.ways.values() {
-            if !self.way_intersects_bounds(way, map_data, visible_bounds) {
+        for &way in ways {
+            if !self.way_intersects_bounds(way, map_data, &visible_bounds) {
                 continue;
             }
+            let Some(look) = way_appearance(style, map_data, way, pass, self.viewport.scale) else {
+                continue;
+            };
             
-            // Check if it's a water feature
-            let is_water = way.tags.get("natural") == Some(&"water".to_string()) ||
-                          way.tags.get("waterway").is_some() ||
-                          way.tags.get("water").is_some();
+            let mut points = self.way_screen_points(way, map_data, rect);
+            if let Some(shaping) = look.footprint.filter(|_| look.is_area()) {
+                let ring: Vec<geo_types::Coord<f64>> = points.iter().map(|p| geo_types::Coord { x: p.x as f64, y: p.y as f64 }).collect();
+                points = shaping.apply(&ring).into_iter().map(|c| Pos2::new(c.x as f32, c.y as f32)).collect();
+            }
+            let look = match map_data.get_style_override(crate::core::ElementType::Way, way.id) {
+                Some(style_override) => look.with_override(style_override),
+                None => look,
+            };
             
-            if is_water && way.is_closed {
-                let points: Vec<Pos2> = way.nodes
-                    .iter()
-                    .filter_map(|&node_id| map_data.nodes.get(&node_id))
-                    .map(|node| self.map_to_screen(node.lon, node.lat, rect))
-                    .collect();
-                
-                if points.len() > 2 {
-                    // Use StyleManager for water colors
-                    let style = style_manager.get_current_style();
-                    let water_color = Self::hex_to_rgb(&style.water.color);
-                    let fill_color = Color32::from_rgba_unmultiplied(
-                        water_color.0, 
-                        water_color.1, 
-                        water_color.2, 
-                        (255.0 * style.water.opacity) as u8
-                    );
-                    
-                    painter.add(egui::Shape::convex_polygon(
-                        points,
-                        fill_color,
-                        egui::Stroke::new(1.0, Color32::from_rgba_unmultiplied(
-                            water_color.0.saturating_sub(30), 
-                            water_color.1.saturating_sub(30), 
-                            water_color.2.saturating_sub(30), 
-                            255
-                        )),
-                    ));
+            if let Some(fill) = &look.fill {
+                if points.len() < 3 {
+                    continue;
+                }
+                let fill_color = Self::style_color(fill, look.opacity);
+                let outline = look.stroke.as_ref()
+                    .map_or(egui::Stroke::NONE, |stroke| egui::Stroke::new(stroke.width, Self::style_color(&stroke.color, look.opacity)));
+                match &look.pattern {
+                    Some(pattern) => {
+                        Self::fill_polygon(&painter, points.clone(), fill_color, egui::Stroke::NONE);
+                        Self::draw_fill_pattern(&painter, rect, &points, pattern);
+                        if outline.width > 0.0 {
+                            painter.add(egui::Shape::closed_line(points, outline));
+                        }
+                    }
+                    None => Self::fill_polygon(&painter, points, fill_color, outline),
+                }
+            } else if points.len() >= 2 {
+                if let Some(stroke) = &look.stroke {
+                    let color = Self::style_color(&stroke.color, look.opacity);
+                    Self::draw_way_stroke(&painter, &points, stroke, color);
+                }
+                if let Some(overlay) = &look.overlay {
+                    Self::draw_way_stroke(&painter, &points, overlay, Self::style_color(&overlay.color, look.opacity));
+                }
+                if let Some(decoration) = &look.decoration {
+                    let color = Self::style_color(&decoration.color, look.opacity);
+                    Self::draw_line_decoration(&painter, &points, decoration, color);
                 }
             }
         }
     }
     
-    fn draw_landuse_areas(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, visible_bounds: &VisibleBounds, style_manager: &StyleManager) {
-        let painter = ui.painter_at(rect);
-        let style = style_manager.get_current_style();
-        
-        for way in map_data.ways.values() {
-            if !self.way_intersects_bounds(way, map_data, visible_bounds) || !way.is_closed {
-                continue;
+    /// Ticks or dots along a way's line
+    fn draw_line_decoration(painter: &egui::Painter, points: &[Pos2], decoration: &LineDecoration, color: Color32) {
+        let points: Vec<(f64, f64)> = points.iter().map(|p| (p.x as f64, p.y as f64)).collect();
+        let to_screen = |(x, y): (f64, f64)| Pos2::new(x as f32, y as f32);
+        match decoration.symbol {
+            LineSymbol::Ticks => {
+                let stroke = egui::Stroke::new(decoration.width, color);
+                for [from, to] in perpendicular_ticks(&points, decoration.spacing, decoration.size as f64, decoration.side) {
+                    painter.line_segment([to_screen(from), to_screen(to)], stroke);
+                }
             }
-            
-            // Check if this way has landuse, leisure, or natural tags we care about
-            let mut should_draw = false;
-            let mut fill_color = Color32::TRANSPARENT;
-            let stroke_color = Color32::from_rgb(200, 200, 200); // Default light stroke
-            
-            // Use StyleManager for landuse colors
-            if let Some(landuse) = way.tags.get("landuse") {
-                if let Some(color_str) = style.get_landuse_color(landuse) {
-                    let (r, g, b) = Self::hex_to_rgb(color_str);
-                    fill_color = Color32::from_rgb(r, g, b);
-                    should_draw = true;
+            LineSymbol::Dots => {
+                for center in points_along(&points, decoration.spacing) {
+                    painter.circle_filled(to_screen(center), decoration.size / 2.0, color);
                 }
-            } else if let Some(leisure) = way.tags.get("leisure") {
-                if let Some(color_str) = style.get_leisure_color(leisure) {
-                    let (r, g, b) = Self::hex_to_rgb(color_str);
-                    fill_color = Color32::from_rgb(r, g, b);
-                    should_draw = true;
+            }
+        }
+    }
+    
+    /// Stroke a way's line, dashed when the style gives a dash pattern. egui lines end flat,
+    /// so round and square caps are added here.
+    fn draw_way_stroke(painter: &egui::Painter, points: &[Pos2], stroke: &WayStroke, color: Color32) {
+        let egui_stroke = egui::Stroke::new(stroke.width, color);
+        match stroke.dash_lengths() {
+            Some((dash_length, gap_length)) => {
+                painter.add(egui::Shape::dashed_line(points, egui_stroke, dash_length, gap_length));
+            }
+            None => {
+                let mut points = points.to_vec();
+                let half_width = stroke.width / 2.0;
+                if stroke.cap == LineCap::Square && points.len() >= 2 {
+                    let last = points.len() - 1;
+                    points[0] = Self::extend_end(points[1], points[0], half_width);
+                    points[last] = Self::extend_end(points[last - 1], points[last], half_width);
                 }
-            } else if let Some(natural) = way.tags.get("natural") {
-                if let Some(color_str) = style.get_natural_color(natural) {
-                    let (r, g, b) = Self::hex_to_rgb(color_str);
-                    fill_color = Color32::from_rgb(r, g, b);
-                    should_draw = true;
+                // A cap over a translucent line would show where the two overlap
+                if stroke.cap == LineCap::Round && color.is_opaque() {
+                    for end in [points.first(), points.last()].into_iter().flatten() {
+                        painter.circle_filled(*end, half_width, color);
+                    }
                 }
+                painter.add(egui::Shape::line(points, egui_stroke));
             }
-            
-            if !should_draw {
-                continue;
-            }
-
-            
-            let points: Vec<Pos2> = way.nodes
-                .iter()
-                .filter_map(|&node_id| map_data.nodes.get(&node_id))
-                .map(|node| self.map_to_screen(node.lon, node.lat, rect))
-                .collect();
-            
-            if points.len() > 2 {
-                painter.add(egui::Shape::convex_polygon(
-                    points,
-                    fill_color,
-                    egui::Stroke::new(0.5, stroke_color),
-                ));
+        }
+    }
+    
+    /// `end` moved `by` pixels further along the segment from `from`
+    fn extend_end(from: Pos2, end: Pos2, by: f32) -> Pos2 {
+        let direction = end - from;
+        if direction.length() > 0.0 {
+            end + direction.normalized() * by
+        } else {
+            end
+        }
+    }
+    
+    fn draw_checkerboard(painter: &egui::Painter, rect: Rect) {
+        const CELL: f32 = 12.0;
+        painter.rect_filled(rect, 0.0, Color32::from_gray(255));
+        
+        let columns = (rect.width() / CELL).ceil() as usize;
+        let rows = (rect.height() / CELL).ceil() as usize;
+        for row in 0..rows {
+            for column in (row % 2..columns).step_by(2) {
+                let min = rect.min + Vec2::new(column as f32 * CELL, row as f32 * CELL);
+                let cell = Rect::from_min_size(min, Vec2::splat(CELL)).intersect(rect);
+                painter.rect_filled(cell, 0.0, Color32::from_gray(204));
             }
         }
     }
     
-    fn draw_buildings(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, visible_bounds: &VisibleBounds, style_manager: &StyleManager) {
-        let painter = ui.painter_at(rect);
+    /// Fill an area ring of any shape (even-odd rule, like the SVG export) and outline it.
+    /// Rings with fewer than 3 distinct points are skipped.
+    fn fill_polygon(painter: &egui::Painter, points: Vec<Pos2>, fill: Color32, stroke: egui::Stroke) {
+        let ring: Vec<(f32, f32)> = points.iter().map(|p| (p.x, p.y)).collect();
+        let Some(triangles) = tessellate_polygon(&ring) else {
+            return;
+        };
         
-        for way in map_data.ways.values() {
-            if !self.way_intersects_bounds(way, map_data, visible_bounds) {
-                continue;
+        let mut mesh = egui::Mesh::default();
+        for &[x, y] in &triangles.vertices {
+            mesh.colored_vertex(Pos2::new(x, y), fill);
+        }
+        mesh.indices = triangles.indices;
+        painter.add(egui::Shape::mesh(mesh));
+        
+        if stroke.width > 0.0 {
+            painter.add(egui::Shape::closed_line(points, stroke));
+        }
+    }
+    
+    /// The ways a pass draws, in order. All ways go by their `layer` tag with the id as a
+    /// tie-break, so the order is stable between frames instead of following `HashMap`
+    /// iteration. The orders are sorted once and kept until the data is edited or replaced, and
+    /// the road order until the style changes too, or the zoom when roads are drawn to scale.
+    fn ways_in_draw_order<'a>(&self, map_data: &'a MapData, pass: DrawOrderPass, style_manager: &StyleManager) -> Vec<&'a crate::core::Way> {
+        let mut cache = self.draw_order.borrow_mut();
+        let order = match cache.as_mut() {
+            Some(order) if order.data_revision == map_data.revision() => order,
+            _ => {
+                let mut ways: Vec<&crate::core::Way> = map_data.ways.values().collect();
+                ways.sort_by_key(|way| (way.layer(), way.id));
+                let building_parts = building_parts_in_draw_order(ways.iter().copied()).iter().map(|way| way.id).collect();
+                cache.insert(DrawOrder {
+                    data_revision: map_data.revision(),
+                    ways: ways.iter().map(|way| way.id).collect(),
+                    building_parts,
+                    roads: None,
+                })
             }
-            
-            if way.tags.contains_key("building") && way.is_closed {
-                let points: Vec<Pos2> = way.nodes
-                    .iter()
-                    .filter_map(|&node_id| map_data.nodes.get(&node_id))
-                    .map(|node| self.map_to_screen(node.lon, node.lat, rect))
-                    .collect();
-                
-                if points.len() > 2 {
-                    // Use style from StyleManager
-                    let ((r, g, b), stroke_width) = self.get_way_style(way, style_manager);
-                    let building_color = Color32::from_rgb(r, g, b);
-                    let building_stroke = Color32::from_rgb(r.saturating_sub(28), g.saturating_sub(28), b.saturating_sub(28));
-                    
-                    // Draw shadow first (slightly offset)
-                    if self.viewport.scale > 5000.0 { // Only at high zoom levels
-                        let shadow_points: Vec<Pos2> = points.iter()
-                            .map(|p| Pos2::new(p.x + 1.0, p.y + 1.0))
-                            .collect();
-                        painter.add(egui::Shape::convex_polygon(
-                            shadow_points,
-                            Color32::from_rgba_unmultiplied(0, 0, 0, 20), // Subtle shadow
-                            egui::Stroke::NONE,
-                        ));
-                    }
-                    
-                    // Draw building
-                    painter.add(egui::Shape::convex_polygon(
-                        points,
-                        building_color,
-                        egui::Stroke::new(stroke_width, building_stroke),
-                    ));
+        };
+        
+        let ids = match pass {
+            DrawOrderPass::Layer => &order.ways,
+            DrawOrderPass::BuildingParts => &order.building_parts,
+            DrawOrderPass::Roads => {
+                let style = style_manager.get_current_style();
+                let pixels_per_meter = style.road_widths.to_scale.then(|| self.pixels_per_meter());
+                let stale = order.roads.as_ref().is_none_or(|roads| {
+                    roads.style_revision != style_manager.revision() || roads.pixels_per_meter != pixels_per_meter
+                });
+                if stale {
+                    let ways = order.ways.iter().filter_map(|id| map_data.ways.get(id));
+                    order.roads = Some(RoadOrder {
+                        style_revision: style_manager.revision(),
+                        pixels_per_meter,
+                        ids: roads_in_draw_order(style, ways, self.pixels_per_meter()).iter().map(|way| way.id).collect(),
+                    });
                 }
+                &order.roads.as_ref().expect("road order was just set").ids
+            }
+        };
+        ids.iter().filter_map(|id| map_data.ways.get(id)).collect()
+    }
+    
+    /// Each drawn feature's OSM id, `w123` for ways and `n456` for nodes, at its center: the
+    /// middle of an area's box or of a line's vertices, or a POI's position
+    fn draw_debug_ids(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, ways: &[&crate::core::Way], visible_bounds: &VisibleBounds) {
+        let painter = ui.painter_at(rect);
+        let mut grid = LabelDensityGrid::new(DEBUG_ID_CELL_SIZE, DEBUG_IDS_PER_CELL);
+        let mut draw_id = |text: String, center: Pos2| {
+            if !rect.contains(center) || !grid.try_place((center.x - rect.min.x) as f64, (center.y - rect.min.y) as f64) {
+                return;
+            }
+            let galley = painter.layout_no_wrap(text, egui::FontId::monospace(9.0), Color32::from_rgb(200, 0, 120));
+            let background = Rect::from_center_size(center, galley.size() + Vec2::splat(2.0));
+            painter.rect_filled(background, 2.0, Color32::from_rgba_unmultiplied(255, 255, 255, 200));
+            painter.galley(center - galley.size() / 2.0, galley, Color32::PLACEHOLDER);
+        };
+        
+        // Untagged ways are only drawn as members of a boundary relation
+        for &way in ways {
+            if (way.tags.is_empty() && !map_data.boundary_levels.contains_key(&way.id))
+                || !self.way_intersects_bounds(way, map_data, visible_bounds) {
+                continue;
             }
+            let points = self.way_screen_points(way, map_data, rect);
+            let center = if way.is_closed && points.len() >= 3 {
+                Rect::from_points(&points).center()
+            } else if let Some(&middle) = points.get(points.len() / 2) {
+                middle
+            } else {
+                continue;
+            };
+            draw_id(format!("w{}", way.id), center);
+        }
+        
+        let mut nodes: Vec<&crate::core::Node> = map_data.nodes.values()
+            .filter(|node| MapStyle::poi_key(&node.tags).is_some())
+            .collect();
+        nodes.sort_by_key(|node| node.id);
+        for node in nodes {
+            draw_id(format!("n{}", node.id), self.map_to_screen(node.lon, node.lat, rect));
+        }
+    }
+    
+    /// Approximate an SVG pattern fill by clipping hatch lines or dots to the polygon.
+    /// Marks are laid out from the map's top-left corner, like the pattern tiles in the export.
+    fn draw_fill_pattern(painter: &egui::Painter, rect: Rect, points: &[Pos2], pattern: &FillPattern) {
+        let ring: Vec<(f32, f32)> = points.iter().map(|p| (p.x - rect.min.x, p.y - rect.min.y)).collect();
+        let to_screen = |(x, y): (f32, f32)| Pos2::new(x + rect.min.x, y + rect.min.y);
+        let color = Color32::from_rgba_unmultiplied(pattern.color.r, pattern.color.g, pattern.color.b, pattern.color.a);
+        
+        for [a, b] in hatch_segments(pattern, &ring) {
+            painter.line_segment([to_screen(a), to_screen(b)], egui::Stroke::new(pattern.line_width, color));
+        }
+        for center in dot_centers(pattern, &ring) {
+            painter.circle_filled(to_screen(center), pattern.line_width, color);
         }
     }
     
-    fn draw_road_casings(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, visible_bounds: &VisibleBounds, style_manager: &StyleManager) {
+    /// `look` drawn as half of a merged dual carriageway, when `way_id` has been paired
+    fn as_carriageway(&self, look: WayAppearance, style: &MapStyle, carriageways: &HashMap<i64, f64>, way_id: i64) -> WayAppearance {
+        match carriageways.get(&way_id) {
+            Some(&offset) => look.with_carriageway_offset(style, offset, self.pixels_per_meter()),
+            None => look,
+        }
+    }
+    
+    /// Screen points shifted `offset` pixels to the right of their direction
+    fn offset_points(points: Vec<Pos2>, offset: f32) -> Vec<Pos2> {
+        if offset == 0.0 || points.len() < 2 {
+            return points;
+        }
+        let coords: Vec<geo_types::Coord<f64>> = points.iter().map(|p| geo_types::Coord { x: p.x as f64, y: p.y as f64 }).collect();
+        GeometryUtils::offset_line(&coords, offset as f64).into_iter()
+            .map(|c| Pos2::new(c.x as f32, c.y as f32))
+            .collect()
+    }
+    
+    fn draw_road_casings(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, ways: &[&crate::core::Way], visible_bounds: &VisibleBounds, style_manager: &StyleManager) {
         let painter = ui.painter_at(rect);
         
-        let mut total_roads = 0;
-        let mut filtered_roads = 0;
-        let mut rendered_roads = 0;
+        let mut roads = FeatureCounter::new();
+        let carriageways = carriageway_offsets(&style_manager.get_current_style().dual_carriageways, map_data);
         
-        for way in map_data.ways.values() {
-            if let Some(highway) = way.tags.get("highway") {
-                total_roads += 1;
+        for &way in ways {
+            if let Some((highway, _)) = road_class(way) {
+                roads.count();
                 
-                if !self.way_intersects_bounds(way, map_data, visible_bounds) {
-                    filtered_roads += 1;
-                    
-                    // Debug: Check for specific roads
-                    if let Some(name) = way.tags.get("name") {
-                        if name.to_lowercase().contains("bezons") || name.to_lowercase().contains("bernanos") {
-                            debug!("'{}' ({}) is OUTSIDE visible bounds", name, highway);
-                            
-                            // Show coordinates and bounds
-                            let mut coords = Vec::new();
-                            for &node_id in &way.nodes {
-                                if let Some(node) = map_data.nodes.get(&node_id) {
-                                    coords.push((node.lat, node.lon));
-                                }
-                            }
-                            
-                            if !coords.is_empty() {
-                                debug!("  First node: lat={:.6}, lon={:.6}", coords[0].0, coords[0].1);
-                                debug!("  Last node: lat={:.6}, lon={:.6}", coords[coords.len()-1].0, coords[coords.len()-1].1);
-                            }
-                            
-                            debug!("  Visible bounds: lat {:.6} to {:.6}, lon {:.6} to {:.6}", 
-                                     visible_bounds.min_lat, visible_bounds.max_lat, 
-                                     visible_bounds.min_lon, visible_bounds.max_lon);
-                        }
-                    }
+                if !style_manager.get_current_style().highway_visible_at(highway, self.viewport.scale) {
+                    roads.filter();
                     continue;
                 }
                 
-                // Debug: Check if this is a road we want to track
-                if let Some(name) = way.tags.get("name") {
-                    if name.to_lowercase().contains("bezons") || name.to_lowercase().contains("bernanos") {
-                        debug!("'{}' ({}) is INSIDE visible bounds and will be rendered", name, highway);
-                    }
+                if !self.way_intersects_bounds(way, map_data, visible_bounds) {
+                    roads.filter();
+                    continue;
                 }
                 
-                let (casing_width, casing_color) = {
-                    let style = style_manager.get_current_style();
-                    let (_, _, border_color, border_width) = style.get_road_style(highway);
-                    
-                    if border_width > 0.0 && !border_color.is_empty() {
-                        let (r, g, b) = Self::hex_to_rgb(border_color);
-                        (border_width, Color32::from_rgb(r, g, b))
-                    } else {
-                        (0.0, Color32::TRANSPARENT)
-                    }
-                };
+                let style = style_manager.get_current_style();
+                let look = way_appearance(style, map_data, way, WayPass::Road, self.viewport.scale)
+                    .map(|look| look.with_tagged_width(style, way, self.pixels_per_meter()))
+                    .map(|look| self.as_carriageway(look, style, &carriageways, way.id));
+                let opacity = look.as_ref().map_or(1.0, |look| look.opacity);
+                let road_offset = look.as_ref().map_or(0.0, |look| look.offset);
                 
-                if casing_width > 0.0 {
-                    let points: Vec<Pos2> = way.nodes
-                        .iter()
-                        .filter_map(|&node_id| map_data.nodes.get(&node_id))
-                        .map(|node| self.map_to_screen(node.lon, node.lat, rect))
-                        .collect();
+                if let Some(casing) = look.and_then(|look| look.casing).filter(|casing| casing.width > 0.0) {
+                    let points = Self::offset_points(self.way_screen_points(way, map_data, rect), road_offset + casing.offset);
                     
                     if points.len() >= 2 {
-                        rendered_roads += 1;
-                        
-                        // Debug: Check if this is a specific road
-                        if let Some(name) = way.tags.get("name") {
-                            if name.to_lowercase().contains("bezons") || name.to_lowercase().contains("bernanos") {
-                                debug!("Rendering casing for '{}' - highway: {}, width: {:.1}, color: {:?}", 
-                                         name, highway, casing_width, casing_color);
-                                debug!("  {} screen points: {:?}", points.len(), 
-                                         points.iter().take(3).collect::<Vec<_>>());
-                            }
-                        }
+                        roads.render();
                         
-                        painter.add(egui::Shape::line(
-                            points,
-                            egui::Stroke::new(casing_width, casing_color),
-                        ));
+                        Self::draw_way_stroke(&painter, &points, &casing, Self::style_color(&casing.color, opacity));
                     }
                 }
             }
         }
         
-        debug!("Road casings - Total: {}, Filtered: {}, Rendered: {}", 
-                 total_roads, filtered_roads, rendered_roads);
+        debug!("Road casings - {}", roads);
     }
     
-    fn draw_road_fills(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, visible_bounds: &VisibleBounds, style_manager: &StyleManager) {
+    fn draw_road_fills(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, ways: &[&crate::core::Way], visible_bounds: &VisibleBounds, style_manager: &StyleManager) {
         let painter = ui.painter_at(rect);
         
-        let mut total_roads = 0;
-        let mut filtered_roads = 0;
-        let mut rendered_roads = 0;
+        let mut roads = FeatureCounter::new();
+        let mut roundabouts = Vec::new();
+        let carriageways = carriageway_offsets(&style_manager.get_current_style().dual_carriageways, map_data);
         
-        for way in map_data.ways.values() {
-            if let Some(highway) = way.tags.get("highway") {
-                total_roads += 1;
+        for &way in ways {
+            if let Some((highway, _)) = road_class(way) {
+                roads.count();
                 
-                if !self.way_intersects_bounds(way, map_data, visible_bounds) {
-                    filtered_roads += 1;
+                if !style_manager.get_current_style().highway_visible_at(highway, self.viewport.scale)
+                    || !self.way_intersects_bounds(way, map_data, visible_bounds) {
+                    roads.filter();
                     continue;
                 }
                 
-                // Use style from StyleManager instead of hardcoded colors
-                let ((r, g, b), width) = self.get_way_style(way, style_manager);
-                let color = Color32::from_rgb(r, g, b);
+                let style = style_manager.get_current_style();
+                let Some((stroke, gap, opacity, offset)) = way_appearance(style, map_data, way, WayPass::Road, self.viewport.scale)
+                    .map(|look| look.with_tagged_width(style, way, self.pixels_per_meter()))
+                    .map(|look| self.as_carriageway(look, style, &carriageways, way.id))
+                    .map(|look| match map_data.get_style_override(crate::core::ElementType::Way, way.id) {
+                        Some(style_override) => look.with_override(style_override),
+                        None => look,
+                    })
+                    .and_then(|look| Some((look.stroke?, look.gap, look.opacity, look.offset))) else {
+                    roads.filter();
+                    continue;
+                };
+                let color = Self::style_color(&stroke.color, opacity);
                 
-                let points: Vec<Pos2> = way.nodes
-                    .iter()
-                    .filter_map(|&node_id| map_data.nodes.get(&node_id))
-                    .map(|node| self.map_to_screen(node.lon, node.lat, rect))
-                    .collect();
+                let points = Self::offset_points(self.way_screen_points(way, map_data, rect), offset);
                 
                 if points.len() >= 2 {
-                    rendered_roads += 1;
+                    roads.render();
                     
-                    // Debug: Check if this is a specific road
-                    if let Some(name) = way.tags.get("name") {
-                        if name.to_lowercase().contains("bezons") || name.to_lowercase().contains("bernanos") {
-                            debug!("Rendering fill for '{}' - highway: {}, width: {:.1}, color: {:?}", 
-                                     name, highway, width, color);
-                        }
+                    // Bridge decks are set off from their casing
+                    if let Some(gap) = &gap {
+                        Self::draw_way_stroke(&painter, &points, gap, Self::style_color(&gap.color, opacity));
                     }
-                    
-                    painter.add(egui::Shape::line(
-                        points,
-                        egui::Stroke::new(width, color),
-                    ));
+                    // Lifecycle roads (under construction, proposed, disused) are dashed
+                    Self::draw_way_stroke(&painter, &points, &stroke, color);
+                    if way.is_roundabout() {
+                        roundabouts.push(points);
+                    }
+                }
+            }
+        }
+        
+        // Direction arrows on top of all road fills
+        let arrows = &style_manager.get_current_style().roundabouts;
+        if arrows.arrows_at(self.viewport.scale) {
+            let stroke = egui::Stroke::new(arrows.arrow_width, Self::style_color(&arrows.arrow_color, 1.0));
+            for points in roundabouts {
+                let points: Vec<(f64, f64)> = points.iter().map(|p| (p.x as f64, p.y as f64)).collect();
+                for chevron in direction_chevrons(&points, arrows.arrow_spacing, arrows.arrow_size as f64) {
+                    let chevron = chevron.iter().map(|&(x, y)| Pos2::new(x as f32, y as f32)).collect();
+                    painter.add(egui::Shape::line(chevron, stroke));
                 }
             }
         }
         
-        debug!("Road fills - Total: {}, Filtered: {}, Rendered: {}", 
-                 total_roads, filtered_roads, rendered_roads);
+        debug!("Road fills - {}", roads);
     }
     
-    fn draw_railways(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, visible_bounds: &VisibleBounds, style_manager: &StyleManager) {
+    /// Mini roundabouts, turning circles and the like, over the road fills
+    fn draw_road_nodes(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, visible_bounds: &VisibleBounds, style: &MapStyle) {
         let painter = ui.painter_at(rect);
-        
-        for way in map_data.ways.values() {
-            if !self.way_intersects_bounds(way, map_data, visible_bounds) {
+        for mark in road_node_marks(style, map_data, self.viewport.scale, self.pixels_per_meter()) {
+            let Some(node) = map_data.nodes.get(&mark.node_id).filter(|node| self.node_intersects_bounds(node, visible_bounds)) else {
                 continue;
-            }
-            
-            if way.tags.get("railway").is_some() {
-                let points: Vec<Pos2> = way.nodes
-                    .iter()
-                    .filter_map(|&node_id| map_data.nodes.get(&node_id))
-                    .map(|node| self.map_to_screen(node.lon, node.lat, rect))
-                    .collect();
-                
-                if points.len() >= 2 {
-                    // Use StyleManager for railway styling
-                    let style = style_manager.get_current_style();
-                    let rail_color = Self::hex_to_rgb(&style.railway.rail_color);
-                    
-                    // Draw railway as dashed line
-                    painter.add(egui::Shape::dashed_line(
-                        &points,
-                        egui::Stroke::new(style.railway.rail_width, Color32::from_rgb(rail_color.0, rail_color.1, rail_color.2)),
-                        10.0,
-                        5.0,
-                    ));
+            };
+            let center = self.map_to_screen(node.lon, node.lat, rect);
+            let fill = mark.fill.as_deref().map_or(Color32::TRANSPARENT, |color| Self::style_color(color, 1.0));
+            let stroke = mark.outline.as_ref()
+                .map_or(egui::Stroke::NONE, |(color, width)| egui::Stroke::new(*width, Self::style_color(color, 1.0)));
+            match mark.shape {
+                RoadNodeShape::Disc | RoadNodeShape::Ring => {
+                    painter.circle(center, mark.size / 2.0, fill, stroke);
+                }
+                RoadNodeShape::Square => {
+                    painter.rect(Rect::from_center_size(center, egui::vec2(mark.size, mark.size)), 0.0, fill, stroke);
                 }
             }
         }
     }
     
+    fn draw_north_arrow(&self, ui: &mut Ui, rect: Rect, style_manager: &StyleManager) {
+        let style = style_manager.get_current_style();
+        let arrow_style = &style.north_arrow;
+        if !arrow_style.enabled {
+            return;
+        }
+        
+        let painter = ui.painter_at(rect);
+        let shape = NorthArrowShape::new(arrow_style, rect.width() as f64, rect.height() as f64, self.viewport.rotation);
+        let to_screen = |(x, y): (f64, f64)| Pos2::new(rect.min.x + x as f32, rect.min.y + y as f32);
+        
+        let (r, g, b) = Self::hex_to_rgb(&arrow_style.fill);
+        let fill = Color32::from_rgb(r, g, b);
+        let (r, g, b) = Self::hex_to_rgb(&arrow_style.stroke);
+        let light = Color32::from_rgb(r, g, b);
+        let outline = egui::Stroke::new(arrow_style.stroke_width, fill);
+        
+        painter.add(egui::Shape::convex_polygon(shape.light_half.iter().copied().map(to_screen).collect(), light, outline));
+        painter.add(egui::Shape::convex_polygon(shape.dark_half.iter().copied().map(to_screen).collect(), fill, outline));
+        
+        if arrow_style.show_label {
+            painter.text(
+                to_screen(shape.label_pos),
+                egui::Align2::CENTER_CENTER,
+                "N",
+                egui::FontId::proportional(shape.label_size as f32),
+                fill,
+            );
+        }
+    }
+    
     fn draw_pois(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, visible_bounds: &VisibleBounds, style_manager: &StyleManager) {
         let painter = ui.painter_at(rect);
+        let icon_size = style_manager.get_current_style().icons.size;
         
-        // Draw POIs from nodes with amenity, shop, or other POI tags
-        for node in map_data.nodes.values() {
-            // Check if node is within visible bounds
-            if !self.node_intersects_bounds(node, visible_bounds) {
+        // Collect visible POIs from nodes with amenity, shop, or other POI tags
+        let style = style_manager.get_current_style();
+        let pois: Vec<(&crate::core::Node, String, Pos2)> = map_data.nodes.values()
+            .filter(|node| self.node_intersects_bounds(node, visible_bounds))
+            .filter(|node| style.poi_visible_at(&node.tags, self.viewport.scale))
+            .filter_map(|node| {
+                let poi_type = MapStyle::poi_key(&node.tags)?;
+                Some((node, poi_type, self.map_to_screen(node.lon, node.lat, rect)))
+            })
+            .collect();
+        
+        let clustered = self.draw_poi_clusters(&painter, map_data, &pois, style_manager);
+        
+        for (node, poi_type, screen_pos) in pois {
+            if clustered.contains(&node.id) {
                 continue;
             }
             
-            // Check if this node is a POI
-            if let Some(poi_type) = self.get_poi_type(node) {
-                let screen_pos = self.map_to_screen(node.lon, node.lat, rect);
-                
-                // Get style for this POI type
-                let (color_str, radius) = style_manager.get_current_style().get_poi_style(&poi_type);
-                let color = Self::hex_to_rgb(color_str);
-                let poi_color = Color32::from_rgb(color.0, color.1, color.2);
-                
+            // Get style for this POI type
+            let (color_str, radius) = style_manager.get_current_style().get_poi_style(&poi_type);
+            let color = Self::hex_to_rgb(color_str);
+            let poi_override = self.override_color(map_data, crate::core::ElementType::Node, node.id);
+            let poi_color = poi_override
+                .unwrap_or(Color32::from_rgb(color.0, color.1, color.2));
+            
+            // Prefer the style's symbol unless the user overrode this POI's colour
+            let icon = if poi_override.is_none() {
+                style_manager.get_current_style().get_poi_icon(&poi_type)
+                    .and_then(|path| self.poi_icon_texture(ui.ctx(), path, icon_size))
+            } else {
+                None
+            };
+            
+            let radius = if let Some(texture) = icon {
+                let icon_rect = Rect::from_center_size(screen_pos, Vec2::splat(icon_size));
+                painter.image(
+                    texture.id(),
+                    icon_rect,
+                    Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+                    Color32::WHITE,
+                );
+                icon_size / 2.0
+            } else {
                 // Draw POI as a circle
                 painter.circle_filled(screen_pos, radius, poi_color);
                 
                 // Add a subtle border
                 painter.circle_stroke(screen_pos, radius, egui::Stroke::new(0.5, Color32::from_rgb(0, 0, 0)));
-                
-                // Optionally draw POI name if available and zoom level is high enough
-                if self.viewport.scale > 50.0 {
-                    if let Some(name) = node.tags.get("name") {
-                        let label_pos = Pos2::new(screen_pos.x, screen_pos.y - radius - 2.0);
-                        painter.text(
-                            label_pos,
-                            egui::Align2::CENTER_BOTTOM,
-                            name,
-                            egui::FontId::proportional(9.0),
-                            Color32::BLACK,
-                        );
-                    }
+                radius
+            };
+            
+            // Optionally draw POI name if available and zoom level is high enough; place
+            // names are drawn with the other labels
+            if self.viewport.scale > 50.0 && !node.tags.contains_key("place") {
+                if let Some(name) = display_name(&node.tags, &style.labels.languages) {
+                    let placement = style_manager.get_current_style().labels.poi;
+                    let (x, y) = label_position(placement.text_anchor, (screen_pos.x as f64, screen_pos.y as f64), radius as f64, placement.text_offset);
+                    Self::draw_wrapped_label(&painter, Pos2::new(x as f32, y as f32), placement.text_anchor, name, 9.0, &style.labels, Color32::BLACK);
                 }
             }
         }
     }
     
-    /// Determine if a node is a POI and return its type
-    fn get_poi_type(&self, node: &crate::core::Node) -> Option<String> {
-        // Check amenity tags first (restaurants, cafes, hospitals, etc.)
-        if let Some(amenity) = node.tags.get("amenity") {
-            return Some(amenity.clone());
-        }
-        
-        // Check shop tags
-        if let Some(shop) = node.tags.get("shop") {
-            return Some(format!("shop_{}", shop));
-        }
-        
-        // Check tourism tags
-        if let Some(tourism) = node.tags.get("tourism") {
-            return Some(format!("tourism_{}", tourism));
-        }
-        
-        // Check leisure tags
-        if let Some(leisure) = node.tags.get("leisure") {
-            return Some(format!("leisure_{}", leisure));
-        }
-        
-        // Check office tags
-        if let Some(office) = node.tags.get("office") {
-            return Some(format!("office_{}", office));
-        }
-        
-        // Check healthcare tags
-        if let Some(healthcare) = node.tags.get("healthcare") {
-            return Some(format!("healthcare_{}", healthcare));
+    /// Draw a counted marker for each dense group of same-type POIs and return the ids it covers.
+    /// POIs the user restyled are always drawn on their own.
+    fn draw_poi_clusters(&self, painter: &egui::Painter, map_data: &MapData, pois: &[(&crate::core::Node, String, Pos2)], style_manager: &StyleManager) -> HashSet<i64> {
+        let style = style_manager.get_current_style();
+        let clusters_style = &style.poi_clusters;
+        let mut clustered = HashSet::new();
+        if !clusters_style.active_at(self.viewport.scale) {
+            return clustered;
         }
         
-        // Check public transport
-        if node.tags.contains_key("public_transport") {
-            return Some("public_transport".to_string());
-        }
+        // Crossings, barriers and the like belong to their way and are never merged
+        let points = pois.iter()
+            .filter(|(node, _, _)| MapStyle::way_node_key(&node.tags).is_none())
+            .filter(|(node, _, _)| self.override_color(map_data, crate::core::ElementType::Node, node.id).is_none())
+            .map(|(node, poi_type, pos)| (node.id, poi_type.clone(), pos.x as f64, pos.y as f64));
         
-        // Check if it's a place (city, town, village, etc.)
-        if let Some(place) = node.tags.get("place") {
-            return Some(format!("place_{}", place));
+        let text_color = Self::hex_to_rgb(&clusters_style.text_color);
+        for cluster in cluster_points(points, clusters_style.radius) {
+            if cluster.len() < clusters_style.min_count.max(2) {
+                continue;
+            }
+            
+            let center = Pos2::new(cluster.x as f32, cluster.y as f32);
+            let radius = clusters_style.marker_radius(cluster.len());
+            let (color_str, _) = style.get_poi_style(&cluster.key);
+            let color = Self::hex_to_rgb(color_str);
+            
+            painter.circle_filled(center, radius, Color32::from_rgb(color.0, color.1, color.2));
+            painter.circle_stroke(center, radius, egui::Stroke::new(1.5, Color32::WHITE));
+            painter.text(
+                center,
+                egui::Align2::CENTER_CENTER,
+                cluster.len().to_string(),
+                egui::FontId::proportional(clusters_style.font_size),
+                Color32::from_rgb(text_color.0, text_color.1, text_color.2),
+            );
+            
+            clustered.extend(cluster.members);
         }
-        
-        None
+        clustered
+    }
+    
+    /// Get (or rasterize and cache) the texture for a POI icon
+    fn poi_icon_texture(&self, ctx: &egui::Context, path: &Path, size: f32) -> Option<egui::TextureHandle> {
+        self.poi_icons
+            .borrow_mut()
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                // Rasterize at twice the display size so icons stay crisp on HiDPI screens
+                let pixels = (size * 2.0).ceil().max(1.0) as u32;
+                match Self::rasterize_icon(path, pixels) {
+                    Ok(image) => Some(ctx.load_texture(path.display().to_string(), image, egui::TextureOptions::LINEAR)),
+                    Err(e) => {
+                        warn!("Failed to load POI icon {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            })
+            .clone()
+    }
+    
+    fn rasterize_icon(path: &Path, pixels: u32) -> anyhow::Result<egui::ColorImage> {
+        let data = std::fs::read(path)?;
+        let tree = resvg::usvg::Tree::from_data(&data, &resvg::usvg::Options::default())?;
+        let mut pixmap = resvg::tiny_skia::Pixmap::new(pixels, pixels)
+            .ok_or_else(|| anyhow::anyhow!("Invalid icon size {}", pixels))?;
+        
+        let tree_size = tree.size();
+        let transform = resvg::tiny_skia::Transform::from_scale(
+            pixels as f32 / tree_size.width(),
+            pixels as f32 / tree_size.height(),
+        );
+        resvg::render(&tree, transform, &mut pixmap.as_mut());
+        
+        Ok(egui::ColorImage::from_rgba_premultiplied(
+            [pixels as usize, pixels as usize],
+            pixmap.data(),
+        ))
     }
     
     /// Check if a node intersects with visible bounds
@@ -1407,147 +1829,164 @@ impl MapView {
     
     // Points of interest drawing has been disabled - no individual nodes will be drawn
     
-    fn draw_text_labels(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, visible_bounds: &VisibleBounds, style_manager: &StyleManager) {
+    fn has_shield(&self, way: &crate::core::Way, style_manager: &StyleManager) -> bool {
+        let style = style_manager.get_current_style();
+        self.viewport.scale >= style.shields.min_scale
+            && !style.restricted_access.hides(&way.tags)
+            && way.tags.contains_key("ref")
+            && way.tags.get("highway").is_some_and(|highway| style.get_shield_style(highway).is_some())
+    }
+    
+    fn draw_road_shields(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, visible_bounds: &VisibleBounds, style_manager: &StyleManager, grid: &mut LabelDensityGrid) {
         let painter = ui.painter_at(rect);
+        let style = style_manager.get_current_style();
+        let shields = &style.shields;
+        
+        let mut candidates: Vec<(u32, &crate::core::Way, String, &str)> = Vec::new();
+        for way in map_data.ways.values() {
+            if !self.has_shield(way, style_manager) || !self.way_intersects_bounds(way, map_data, visible_bounds) {
+                continue;
+            }
+            if let (Some(text), Some(highway)) = (way.tags.get("ref").and_then(|r| shield_text(r)), way.tags.get("highway")) {
+                candidates.push((label_priority(&way.tags), way, text, highway.as_str()));
+            }
+        }
+        candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.id.cmp(&b.1.id)));
+        
+        let (r, g, b) = Self::hex_to_rgb(&shields.border_color);
+        let border = egui::Stroke::new(1.0, Color32::from_rgb(r, g, b));
+        
+        for (_, way, text, highway) in candidates {
+            let Some((fill, text_color)) = style.get_shield_style(highway) else {
+                continue;
+            };
+            let (r, g, b) = Self::hex_to_rgb(fill);
+            let fill = Color32::from_rgb(r, g, b);
+            let (r, g, b) = Self::hex_to_rgb(text_color);
+            let galley = painter.layout_no_wrap(text, egui::FontId::proportional(shields.font_size), Color32::from_rgb(r, g, b));
+            
+            let points: Vec<(f64, f64)> = way.node_refs()
+                .filter_map(|node_id| map_data.nodes.get(&node_id))
+                .map(|node| {
+                    let pos = self.map_to_screen(node.lon, node.lat, rect);
+                    (pos.x as f64, pos.y as f64)
+                })
+                .collect();
+            
+            for (x, y) in points_along(&points, shields.spacing) {
+                let center = Pos2::new(x as f32, y as f32);
+                if !rect.contains(center) || !grid.try_place(x, y) {
+                    continue;
+                }
+                
+                let badge = Rect::from_center_size(center, galley.size() + Vec2::splat(shields.padding * 2.0));
+                painter.rect(badge, shields.corner_radius, fill, border);
+                painter.galley(center - galley.size() / 2.0, galley.clone(), Color32::PLACEHOLDER);
+            }
+        }
+    }
+    
+    /// Place and road names, then house numbers; names take their cells in `grid`
+    fn draw_text_labels(&self, ui: &mut Ui, rect: Rect, map_data: &MapData, visible_bounds: &VisibleBounds, style_manager: &StyleManager, grid: &mut LabelDensityGrid) {
+        let painter = ui.painter_at(rect);
+        
+        let style = style_manager.get_current_style();
+        let label_style = &style.labels;
+        
+        // Place names come first and at any zoom, they are what a zoomed out map is read by
+        for place in place_labels(map_data, &label_style.languages) {
+            let screen_pos = self.map_to_screen(place.lon, place.lat, rect);
+            if !rect.contains(screen_pos) || !grid.try_place(screen_pos.x as f64, screen_pos.y as f64) {
+                continue;
+            }
+            let placement = label_style.place;
+            let (x, y) = label_position(placement.text_anchor, (screen_pos.x as f64, screen_pos.y as f64), 0.0, placement.text_offset);
+            let font_size = style.get_place_label_font_size(place.place) as f32;
+            Self::draw_wrapped_label(&painter, Pos2::new(x as f32, y as f32), placement.text_anchor, place.name, font_size, label_style, Color32::BLACK);
+        }
         
-        // Only show labels at higher zoom levels
-        if self.viewport.scale < 2000.0 {
+        // Only show other labels at higher zoom levels
+        if self.viewport.scale < label_style.label_min_scale {
             return;
         }
         
+        // Collect candidates at each way's middle node, most important first
+        let mut candidates: Vec<(u32, i64, &String, Pos2)> = Vec::new();
         for way in map_data.ways.values() {
             if !self.way_intersects_bounds(way, map_data, visible_bounds) {
                 continue;
             }
             
-            if let Some(name) = way.tags.get("name") {
-                // Calculate center point for label
-                let center_node_id = way.nodes.get(way.nodes.len() / 2).copied();
-                if let Some(node_id) = center_node_id {
-                    if let Some(node) = map_data.nodes.get(&node_id) {
-                        let screen_pos = self.map_to_screen(node.lon, node.lat, rect);
-                        
-                        let (font_size, font_color) = self.get_label_style(way);
-                        
-                        // Draw text with subtle background for readability
-                        painter.text(
-                            screen_pos + Vec2::new(0.0, -2.0),
-                            egui::Align2::CENTER_CENTER,
-                            name,
-                            egui::FontId::proportional(font_size),
-                            font_color,
-                        );
-                    }
+            // Places mapped as areas are named at their centroid above; hidden private roads go unnamed
+            if way.tags.contains_key("place") || style.restricted_access.hides(&way.tags) {
+                continue;
+            }
+            
+            if let Some(name) = display_name(&way.tags, &label_style.languages) {
+                // A name that only repeats the ref is already shown on the shield
+                if self.has_shield(way, style_manager) && way.tags.get("ref").and_then(|r| shield_text(r)).as_ref() == Some(name) {
+                    continue;
+                }
+                
+                let center_node = way.nodes.get(way.nodes.len() / 2)
+                    .and_then(|node_id| map_data.nodes.get(node_id));
+                if let Some(node) = center_node {
+                    let screen_pos = self.map_to_screen(node.lon, node.lat, rect);
+                    candidates.push((label_priority(&way.tags), way.id, name, screen_pos));
                 }
             }
         }
+        candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        
+        for (_, way_id, name, screen_pos) in candidates {
+            if !grid.try_place(screen_pos.x as f64, screen_pos.y as f64) {
+                continue;
+            }
+            
+            // Same size and colour as the export's road labels
+            let font_size = map_data.ways[&way_id].tags.get("highway")
+                .map_or(8, |highway| style.get_road_label_font_size(highway)) as f32;
+            let font_color = Color32::BLACK;
+            
+            let placement = label_style.road;
+            let (x, y) = label_position(placement.text_anchor, (screen_pos.x as f64, screen_pos.y as f64), 0.0, placement.text_offset);
+            painter.text(
+                Pos2::new(x as f32, y as f32),
+                Self::text_align(placement.text_anchor),
+                name,
+                egui::FontId::proportional(font_size),
+                font_color,
+            );
+        }
+        
+        // House numbers last, one per small cell, and only when zoomed far in
+        let housenumbers = &style.housenumbers;
+        if housenumbers.visible_at(self.viewport.scale) {
+            let mut grid = LabelDensityGrid::new(housenumbers.cell_size, 1);
+            let color = Self::style_color(&housenumbers.color, 1.0);
+            for housenumber in housenumber_labels(map_data) {
+                let screen_pos = self.map_to_screen(housenumber.lon, housenumber.lat, rect);
+                if !rect.contains(screen_pos) || !grid.try_place(screen_pos.x as f64, screen_pos.y as f64) {
+                    continue;
+                }
+                painter.text(screen_pos, egui::Align2::CENTER_CENTER, housenumber.text, egui::FontId::proportional(housenumbers.font_size), color);
+            }
+        }
         
         // Draw node labels (POI names) - DISABLED
         // No longer drawing individual OSM nodes or their labels
     }
     
-    // Legacy hardcoded style functions - replaced with StyleManager
-    // These remain for backward compatibility but should not be used
-    #[allow(dead_code)]
-    
-    fn get_road_casing_style(&self, highway: &str) -> (f32, Color32) {
-        match highway {
-            "motorway" => (8.0, Color32::from_rgb(140, 100, 0)),
-            "trunk" => (7.0, Color32::from_rgb(160, 120, 0)),
-            "primary" => (6.0, Color32::from_rgb(180, 140, 0)),
-            "secondary" => (5.0, Color32::from_rgb(180, 160, 80)),
-            "tertiary" => (4.0, Color32::from_rgb(180, 180, 120)),
-            "residential" => (3.5, Color32::from_rgb(200, 200, 200)),
-            "service" => (2.5, Color32::from_rgb(210, 210, 210)),
-            _ => (0.0, Color32::TRANSPARENT), // No casing for minor roads
-        }
-    }
-    
-    fn get_road_fill_style(&self, highway: &str) -> (f32, Color32) {
-        match highway {
-            "motorway" => (6.0, Color32::from_rgb(231, 170, 56)), // Orange
-            "trunk" => (5.5, Color32::from_rgb(255, 196, 56)), // Light orange
-            "primary" => (5.0, Color32::from_rgb(255, 220, 56)), // Yellow
-            "secondary" => (4.0, Color32::from_rgb(255, 240, 120)), // Light yellow
-            "tertiary" => (3.5, Color32::WHITE), // White
-            "residential" => (3.0, Color32::WHITE), // White
-            "service" => (2.0, Color32::from_rgb(245, 245, 245)), // Very light gray
-            "footway" | "path" => (1.5, Color32::from_rgb(220, 180, 140)), // Brown
-            "cycleway" => (1.5, Color32::from_rgb(100, 150, 255)), // Blue
-            _ => (2.0, Color32::from_rgb(230, 230, 230)), // Light gray default
-        }
-    }
-    
-    fn get_label_style(&self, way: &crate::core::Way) -> (f32, Color32) {
-        if let Some(highway) = way.tags.get("highway") {
-            match highway.as_str() {
-                "motorway" | "trunk" => (11.0, Color32::WHITE),
-                "primary" => (10.0, Color32::BLACK),
-                "secondary" | "tertiary" => (9.0, Color32::from_rgb(60, 60, 60)),
-                _ => (8.0, Color32::from_rgb(80, 80, 80)),
-            }
-        } else if way.tags.contains_key("building") {
-            (8.0, Color32::from_rgb(100, 100, 100))
-        } else {
-            (8.0, Color32::from_rgb(80, 80, 80))
-        }
-    }
-    
     /// Handle element selection when clicking on the map in Select mode
-    fn handle_element_selection(&mut self, click_pos: Pos2, rect: Rect, map_data: &Option<MapData>) {
+    /// Select the element under a click. With `add` the element is added to the selection, or
+    /// taken out of it if it was already in; otherwise it replaces the selection.
+    fn handle_element_selection(&mut self, click_pos: Pos2, rect: Rect, map_data: &Option<MapData>, add: bool) {
         if let Some(data) = map_data {
             let (click_lon, click_lat) = self.screen_to_map(click_pos, rect);
-            let tolerance = 10.0 / self.viewport.scale; // Click tolerance in map units
-            
-            debug!("Element selection at: {:.6}, {:.6} (tolerance: {:.6})", click_lon, click_lat, tolerance);
-            
-            // Find the closest element to the click position
-            let mut closest_element: Option<SelectedElement> = None;
-            let mut closest_distance = f64::INFINITY;
-            
-            // Check ways (roads, buildings, areas)
-            for way in data.ways.values() {
-                if let Some(distance) = self.calculate_way_distance(way, data, click_lon, click_lat) {
-                    if distance < tolerance && distance < closest_distance {
-                        closest_distance = distance;
-                        
-                        // Determine the style information
-                        let style_info = self.determine_style_info(&way.tags);
-                        
-                        closest_element = Some(SelectedElement {
-                            element_type: ElementType::Way,
-                            element_id: way.id,
-                            tags: way.tags.clone(),
-                            style_info,
-                        });
-                    }
-                }
-            }
-            
-            // Check nodes (POIs, etc.) if no way was found nearby
-            if closest_element.is_none() {
-                for node in data.nodes.values() {
-                    let node_distance = ((node.lon - click_lon).powi(2) + (node.lat - click_lat).powi(2)).sqrt();
-                    if node_distance < tolerance && node_distance < closest_distance {
-                        closest_distance = node_distance;
-                        
-                        // Only select nodes that have interesting tags (POIs, etc.)
-                        if self.is_selectable_node(node) {
-                            let style_info = self.determine_style_info(&node.tags);
-                            
-                            closest_element = Some(SelectedElement {
-                                element_type: ElementType::Node,
-                                element_id: node.id,
-                                tags: node.tags.clone(),
-                                style_info,
-                            });
-                        }
-                    }
-                }
-            }
+            debug!("Element selection at: {:.6}, {:.6} (tolerance: {:.1}px)", click_lon, click_lat, self.selection_tolerance);
             
             // Update selection
-            if let Some(element) = closest_element {
+            if let Some(element) = self.element_at(click_pos, rect, data) {
                 info!("Selected {} {} with tags: {:?}", 
                       match element.element_type {
                           ElementType::Way => "way",
@@ -1562,68 +2001,209 @@ impl MapView {
                        element.style_info.subcategory,
                        element.style_info.toml_section);
                        
-                self.selected_element = Some(element);
-            } else {
+                if !add {
+                    self.selected_elements.clear();
+                }
+                self.toggle_selected(element);
+            } else if !add {
                 debug!("No selectable element found near click position");
-                self.selected_element = None;
+                self.selected_elements.clear();
             }
         }
     }
     
-    /// Calculate the distance from a point to a way (line or polygon)
-    fn calculate_way_distance(&self, way: &crate::core::Way, map_data: &MapData, lon: f64, lat: f64) -> Option<f64> {
-        if way.nodes.len() < 2 {
-            return None;
+    /// Add an element to the selection, or remove it if it is already selected
+    fn toggle_selected(&mut self, element: SelectedElement) {
+        let position = self.selected_elements.iter()
+            .position(|selected| selected.element_type == element.element_type && selected.element_id == element.element_id);
+        match position {
+            Some(index) => {
+                self.selected_elements.remove(index);
+            }
+            None => self.selected_elements.push(element),
         }
-        
-        let mut min_distance = f64::INFINITY;
-        
-        // Convert way nodes to coordinates
-        let coords: Vec<(f64, f64)> = way.nodes.iter()
-            .filter_map(|&node_id| {
-                map_data.nodes.get(&node_id).map(|node| (node.lon, node.lat))
-            })
-            .collect();
-            
-        if coords.len() < 2 {
-            return None;
+    }
+    
+    /// Drag out a box in Select mode; on release everything it touches is selected, added to
+    /// the selection with `add`
+    fn handle_box_selection(&mut self, response: &Response, rect: Rect, map_data: &Option<MapData>, add: bool) {
+        if response.drag_started() {
+            if let Some(pos) = response.interact_pointer_pos().filter(|pos| rect.contains(*pos)) {
+                self.selection_rect = Some(SelectionRect {
+                    start_pos: pos,
+                    current_pos: pos,
+                    has_been_dragged: true,
+                });
+            }
+        } else if response.dragged() {
+            if let (Some(selection), Some(pos)) = (&mut self.selection_rect, response.interact_pointer_pos()) {
+                selection.current_pos = pos.clamp(rect.min, rect.max);
+            }
+        } else if let Some(selection) = self.selection_rect.take() {
+            let Some(data) = map_data else {
+                return;
+            };
+            let elements = self.elements_in_box(Rect::from_two_pos(selection.start_pos, selection.current_pos), rect, data);
+            info!("Box selection picked {} elements", elements.len());
+            if !add {
+                self.selected_elements.clear();
+            }
+            for element in elements {
+                let selected = self.selected_elements.iter()
+                    .any(|selected| selected.element_type == element.element_type && selected.element_id == element.element_id);
+                if !selected {
+                    self.selected_elements.push(element);
+                }
+            }
+        }
+    }
+    
+    /// Ways crossing or inside a screen box, areas covering it, and selectable nodes within it
+    fn elements_in_box(&self, selection: Rect, rect: Rect, data: &MapData) -> Vec<SelectedElement> {
+        let to_coord = |p: Pos2| geo_types::Coord { x: p.x as f64, y: p.y as f64 };
+        let clip = ClipRect::new(selection.min.x as f64, selection.min.y as f64, selection.max.x as f64, selection.max.y as f64);
+        let center = to_coord(selection.center());
+        
+        let mut elements = Vec::new();
+        for way in data.ways.values() {
+            let points: Vec<geo_types::Coord<f64>> = self.way_screen_points(way, data, rect).into_iter().map(to_coord).collect();
+            let touches = points.windows(2).any(|segment| clip.clip_segment(segment[0], segment[1]).is_some())
+                || (way.is_area() && GeometryUtils::point_in_polygon(&center, &points));
+            if touches {
+                elements.push(SelectedElement {
+                    element_type: ElementType::Way,
+                    element_id: way.id,
+                    tags: way.tags.clone(),
+                    style_info: self.determine_style_info(&way.tags),
+                });
+            }
+        }
+        for node in data.nodes.values().filter(|node| self.is_selectable_node(node)) {
+            if selection.contains(self.map_to_screen(node.lon, node.lat, rect)) {
+                elements.push(SelectedElement {
+                    element_type: ElementType::Node,
+                    element_id: node.id,
+                    tags: node.tags.clone(),
+                    style_info: self.determine_style_info(&node.tags),
+                });
+            }
+        }
+        elements
+    }
+    
+    /// Short description of the feature under the cursor. The lookup walks every feature, so
+    /// it is only repeated once the cursor has moved a few pixels or the view has changed.
+    fn hover_description(&mut self, pos: Pos2, rect: Rect, data: &MapData) -> Option<String> {
+        let view = (self.viewport.center_x, self.viewport.center_y, self.viewport.scale, self.viewport.rotation);
+        let stale = self.hover.as_ref().is_none_or(|hover| {
+            hover.view != view || hover.pos.distance(pos) > HOVER_MOVE_THRESHOLD
+        });
+        if stale {
+            let description = self.element_at(pos, rect, data).and_then(|element| Self::describe_tags(&element.tags));
+            self.hover = Some(HoverLookup { pos, view, description });
+        }
+        self.hover.as_ref().and_then(|hover| hover.description.clone())
+    }
+    
+    /// A feature's name with its kind, e.g. "Rue de Rivoli (primary)", or just the kind
+    /// ("highway: residential") when it has no name
+    fn describe_tags(tags: &HashMap<String, String>) -> Option<String> {
+        let kind = HOVER_KEYS.iter().find_map(|&key| tags.get(key).map(|value| (key, value)));
+        match (tags.get("name"), kind) {
+            (Some(name), Some((key, value))) if value == "yes" => Some(format!("{} ({})", name, key)),
+            (Some(name), Some((_, value))) => Some(format!("{} ({})", name, value.replace('_', " "))),
+            (Some(name), None) => Some(name.clone()),
+            (None, Some((key, value))) => Some(format!("{}: {}", key, value.replace('_', " "))),
+            (None, None) => None,
+        }
+    }
+    
+    /// The feature nearest to a screen position, within the selection tolerance
+    fn element_at(&self, pos: Pos2, rect: Rect, data: &MapData) -> Option<SelectedElement> {
+        // Distances are measured on screen, so the tolerance is the same in every
+        // direction at any latitude, zoom or rotation
+        let tolerance = self.selection_tolerance;
+        
+        let mut closest_element: Option<SelectedElement> = None;
+        let mut closest_distance = f32::INFINITY;
+        
+        // Check ways (roads, buildings, areas)
+        for way in data.ways.values() {
+            if let Some(distance) = self.calculate_way_distance(way, data, pos, rect) {
+                if distance < tolerance && distance < closest_distance {
+                    closest_distance = distance;
+                    
+                    // Determine the style information
+                    let style_info = self.determine_style_info(&way.tags);
+                    
+                    closest_element = Some(SelectedElement {
+                        element_type: ElementType::Way,
+                        element_id: way.id,
+                        tags: way.tags.clone(),
+                        style_info,
+                    });
+                }
+            }
         }
         
-        // Calculate distance to each line segment
-        for i in 0..coords.len() - 1 {
-            let (x1, y1) = coords[i];
-            let (x2, y2) = coords[i + 1];
-            let distance = self.point_to_line_distance(lon, lat, x1, y1, x2, y2);
-            min_distance = min_distance.min(distance);
+        // Nodes drawn as points (POIs, crossings on a road, etc.) sit on top of the ways, so
+        // one within reach wins over any way, including the way it is part of. Other
+        // interesting nodes are only picked if no way was found nearby.
+        let mut closest_node: Option<(bool, f32, &crate::core::Node)> = None;
+        for node in data.nodes.values() {
+            // Only select nodes that have interesting tags (POIs, etc.)
+            if !self.is_selectable_node(node) {
+                continue;
+            }
+            let node_distance = self.map_to_screen(node.lon, node.lat, rect).distance(pos);
+            if node_distance >= tolerance {
+                continue;
+            }
+            let drawn = MapStyle::poi_key(&node.tags).is_some();
+            if closest_node.is_none_or(|(best_drawn, best_distance, _)| (drawn, -node_distance) > (best_drawn, -best_distance)) {
+                closest_node = Some((drawn, node_distance, node));
+            }
+        }
+        if let Some((drawn, _, node)) = closest_node {
+            if drawn || closest_element.is_none() {
+                closest_element = Some(SelectedElement {
+                    element_type: ElementType::Node,
+                    element_id: node.id,
+                    tags: node.tags.clone(),
+                    style_info: self.determine_style_info(&node.tags),
+                });
+            }
         }
         
-        // If it's a closed way (polygon), also check the closing segment
-        if coords.len() > 2 && coords[0] == *coords.last().unwrap() {
-            // Already handled by the loop above since it's a closed way
+        closest_element
+    }
+    
+    /// Screen distance in pixels from a point to a way (line or polygon outline)
+    fn calculate_way_distance(&self, way: &crate::core::Way, map_data: &MapData, pos: Pos2, rect: Rect) -> Option<f32> {
+        let points = self.way_screen_points(way, map_data, rect);
+        if points.len() < 2 {
+            return None;
         }
         
-        Some(min_distance)
+        // Closed ways repeat their first node, so the outline's closing segment is included
+        points.windows(2)
+            .map(|segment| Self::point_to_line_distance(pos, segment[0], segment[1]))
+            .min_by(|a, b| a.total_cmp(b))
     }
     
-    /// Calculate distance from a point to a line segment
-    fn point_to_line_distance(&self, px: f64, py: f64, x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
-        let line_length_sq = (x2 - x1).powi(2) + (y2 - y1).powi(2);
+    /// Distance from a point to a line segment, in screen pixels
+    fn point_to_line_distance(point: Pos2, start: Pos2, end: Pos2) -> f32 {
+        let line = end - start;
+        let line_length_sq = line.length_sq();
         
         if line_length_sq == 0.0 {
             // Line segment is actually a point
-            return ((px - x1).powi(2) + (py - y1).powi(2)).sqrt();
+            return point.distance(start);
         }
         
-        // Calculate the parameter t that represents the closest point on the line segment
-        let t = ((px - x1) * (x2 - x1) + (py - y1) * (y2 - y1)) / line_length_sq;
-        let t = t.clamp(0.0, 1.0); // Clamp to line segment
-        
-        // Find the closest point on the line segment
-        let closest_x = x1 + t * (x2 - x1);
-        let closest_y = y1 + t * (y2 - y1);
-        
-        // Return distance to closest point
-        ((px - closest_x).powi(2) + (py - closest_y).powi(2)).sqrt()
+        // Closest point on the segment, clamped to its ends
+        let t = ((point - start).dot(line) / line_length_sq).clamp(0.0, 1.0);
+        point.distance(start + line * t)
     }
     
     /// Determine if a node is selectable (has interesting tags)
@@ -1639,6 +2219,7 @@ impl MapView {
         node.tags.contains_key("historic") ||
         node.tags.contains_key("natural") ||
         node.tags.contains_key("barrier") ||
+        MapStyle::way_node_key(&node.tags).is_some() ||
         (node.tags.contains_key("name") && node.tags.len() > 1)
     }
     
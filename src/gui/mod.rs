@@ -1,11 +1,17 @@
+pub mod export_dialog;
 pub mod geocoding_panel;
+pub mod gpx_panel;
 pub mod layers_panel;
 pub mod map_view;
 pub mod style_editor;
+pub mod tag_panel;
 pub mod tool_panel;
 pub mod toolbar;
 pub mod widgets;
 
+use crate::core::geometry::FitPadding;
+use crate::export::Attribution;
+use crate::parsers::stylesheet::ColorTransform;
 use serde::{Deserialize, Serialize};
 
 /// Geocoding result from a search query
@@ -18,27 +24,78 @@ pub struct GeocodeResult {
     pub importance: f32,
 }
 
-/// GUI state management
+/// GUI state management, saved with the session so panels and layer toggles survive restarts.
+/// Dialogs and in-flight searches are not persisted.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GuiState {
+    #[serde(skip)]
     pub show_style_editor_modal: bool,
     pub show_tool_panel: bool,
+    #[serde(skip)]
     pub show_about: bool,
     pub show_layers_panel: bool,
+    pub show_gpx_stats: bool,
+    /// Floating panel counting the tags of the loaded data
+    pub show_tag_stats: bool,
     pub show_geocoding_panel: bool,
     pub current_tool: Tool,
+    /// Current web map zoom level, mirrored from the map view
     pub zoom_level: f32,
+    /// Zooming in stops at this web map zoom level
+    pub max_zoom: f32,
+    /// Clicks select features within this many screen pixels
+    pub selection_tolerance: f32,
+    /// Margin kept around the data by Zoom to Fit
+    pub fit_padding: FitPadding,
+    /// Distances, elevations and speeds are shown in these units
+    pub units: crate::utils::units::UnitSystem,
+    /// Positions are shown and copied in this format
+    pub coordinate_format: crate::utils::units::CoordinateFormat,
     pub pan_offset: (f32, f32),
     pub selected_style: String,
+    /// Style to go back to when the color-blind safe palette is switched off
+    pub style_before_colorblind: Option<String>,
+    /// Brightness, saturation and hue adjustment applied to every color of the style
+    pub color_transform: ColorTransform,
     pub show_pois: bool,
     pub show_buildings: bool,
     pub show_roads: bool,
     pub show_water: bool,
     pub show_landuse: bool,
+    pub show_boundaries: bool,
     pub show_gpx: bool,
     pub show_all_road_names: bool,
+    /// Label every drawn way and node with its OSM id, for debugging styles
+    pub show_debug_ids: bool,
+    pub transparent_background: bool,
+    pub background_image: Option<std::path::PathBuf>,
+    /// Exports render the current view at this resolution; 96 matches the screen
+    pub export_dpi: f32,
+    /// How SVG exports write labels
+    pub svg_text: crate::export::SvgTextMode,
+    /// Exports keep ways whole instead of cutting them at the canvas edge
+    pub full_geometry: bool,
+    /// Exports split way segments longer than this many meters along the great circle
+    pub densify_segments: Option<f64>,
+    /// SVG exports snap coordinates to multiples of this many pixels
+    pub coordinate_grid: Option<f64>,
+    /// JPEG export quality, from 1 to 100
+    pub jpeg_quality: u8,
+    /// Exports drop way vertices within this many pixels of the line through their neighbours
+    pub simplify_tolerance: Option<f64>,
+    /// Exports place road labels along centerlines simplified with this tolerance, in pixels
+    pub label_simplify_tolerance: Option<f64>,
+    /// Layers left out of exports, by id
+    pub export_hidden_layers: Vec<String>,
+    /// Credit, title and date stamped on exports; `None` leaves them off
+    pub attribution: Option<Attribution>,
     pub search_query: String,
+    /// Search the names in the loaded map instead of the online geocoder
+    pub search_loaded_data: bool,
+    #[serde(skip)]
     pub geocoding_results: Vec<GeocodeResult>,
+    #[serde(skip)]
     pub is_geocoding: bool,
 }
 
@@ -56,19 +113,43 @@ impl GuiState {
             show_tool_panel: false,
             show_about: false,
             show_layers_panel: false,
+            show_gpx_stats: false,
+            show_tag_stats: false,
             show_geocoding_panel: false,
             current_tool: Tool::Pan,
             zoom_level: 1.0,
+            max_zoom: map_view::DEFAULT_MAX_ZOOM,
+            selection_tolerance: map_view::DEFAULT_SELECTION_TOLERANCE,
+            fit_padding: FitPadding::default(),
+            units: crate::utils::units::UnitSystem::default(),
+            coordinate_format: crate::utils::units::CoordinateFormat::default(),
             pan_offset: (0.0, 0.0),
-            selected_style: "google-maps".to_string(),
+            selected_style: crate::styles::loader::DEFAULT_STYLE.to_string(),
+            style_before_colorblind: None,
+            color_transform: ColorTransform::IDENTITY,
             show_pois: true,
             show_buildings: true,
             show_roads: true,
             show_water: true,
             show_landuse: true,
+            show_boundaries: true,
             show_gpx: false,
             show_all_road_names: false,
+            show_debug_ids: false,
+            transparent_background: false,
+            background_image: None,
+            export_dpi: crate::export::BASELINE_DPI,
+            svg_text: crate::export::SvgTextMode::default(),
+            full_geometry: false,
+            densify_segments: None,
+            coordinate_grid: None,
+            jpeg_quality: crate::export::DEFAULT_JPEG_QUALITY,
+            simplify_tolerance: None,
+            label_simplify_tolerance: None,
+            export_hidden_layers: Vec::new(),
+            attribution: Some(Attribution::default()),
             search_query: String::new(),
+            search_loaded_data: false,
             geocoding_results: Vec::new(),
             is_geocoding: false,
         }
@@ -82,9 +163,12 @@ impl Default for GuiState {
 }
 
 // Re-export GUI components
+pub use export_dialog::ExportDialog;
 pub use geocoding_panel::{GeocodingPanel, GeocodingAction};
+pub use gpx_panel::GpxPanel;
 pub use layers_panel::LayersPanel;
 pub use map_view::MapView;
 pub use style_editor::StyleEditor;
+pub use tag_panel::TagPanel;
 pub use tool_panel::{ToolPanel, ToolPanelAction};
 pub use toolbar::{Toolbar, ToolbarAction};
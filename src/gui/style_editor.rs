@@ -1,4 +1,4 @@
-use crate::styles::loader::{StyleManager, MapStyle};
+use crate::styles::loader::StyleManager;
 use crate::parsers::stylesheet::{StyleSheet, StyleRule, Color};
 use crate::gui::map_view::SelectedElement;
 use egui::{Ui, Color32, ScrollArea, Context, TextEdit};
@@ -10,9 +10,12 @@ pub struct StyleEditor {
     selected_rule: Option<usize>,
     color_picker_open: bool,
     current_color: Color32,
+    /// Zoom level the next stop added in the rule editor goes at
+    new_stop_zoom: f32,
     // TOML editor state
     toml_content: String,
     toml_error: Option<String>,
+    toml_warnings: Vec<String>, // Unknown keys/sections that parsing silently ignores
     selected_tab: StyleEditorTab,
     // For live preview
     has_unsaved_changes: bool,
@@ -59,8 +62,10 @@ impl StyleEditor {
             selected_rule: None,
             color_picker_open: false,
             current_color: Color32::BLACK,
+            new_stop_zoom: 14.0,
             toml_content: String::new(),
             toml_error: None,
+            toml_warnings: Vec::new(),
             selected_tab: StyleEditorTab::TomlEditor,
             has_unsaved_changes: false,
             needs_validation: false,
@@ -343,6 +348,16 @@ impl StyleEditor {
                             ui.text_edit_singleline(&mut value_str);
                             *value = if value_str.is_empty() { None } else { Some(value_str) };
                         }
+                        crate::parsers::stylesheet::FeatureSelector::TagListContains { key, value } => {
+                            ui.label("Tag:");
+                            ui.text_edit_singleline(key);
+                            ui.label("~=").on_hover_text("Matches one of several ;-separated values");
+                            ui.text_edit_singleline(value);
+                        }
+                        crate::parsers::stylesheet::FeatureSelector::Conditions(conditions) => {
+                            ui.label("Conditions:");
+                            ui.monospace(conditions.iter().map(|condition| condition.to_string()).collect::<String>());
+                        }
                         _ => {
                             ui.label("Other selector type");
                         }
@@ -514,6 +529,66 @@ impl StyleEditor {
                     }
                 }
             });
+            
+            // Zoom stops: width, line colour and opacity by zoom level
+            ui.label("Zoom stops:");
+            let mut removed = None;
+            for (index, stop) in rule.style.zoom_stops.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("z{}", stop.zoom));
+                    if let Some(ref mut width) = stop.width {
+                        ui.add(egui::DragValue::new(width).range(0.0..=50.0).speed(0.1).prefix("width "));
+                    } else if ui.small_button("+ width").clicked() {
+                        stop.width = Some(1.0);
+                    }
+                    if let Some(ref mut color) = stop.color {
+                        let mut rgb = [
+                            color.r as f32 / 255.0,
+                            color.g as f32 / 255.0,
+                            color.b as f32 / 255.0
+                        ];
+                        if ui.color_edit_button_rgb(&mut rgb).changed() {
+                            color.r = (rgb[0] * 255.0) as u8;
+                            color.g = (rgb[1] * 255.0) as u8;
+                            color.b = (rgb[2] * 255.0) as u8;
+                        }
+                    } else if ui.small_button("+ color").clicked() {
+                        stop.color = Some(Color::new(0, 0, 0, 255));
+                    }
+                    if let Some(ref mut color) = stop.fill_color {
+                        let mut rgb = [
+                            color.r as f32 / 255.0,
+                            color.g as f32 / 255.0,
+                            color.b as f32 / 255.0
+                        ];
+                        if ui.color_edit_button_rgb(&mut rgb).changed() {
+                            color.r = (rgb[0] * 255.0) as u8;
+                            color.g = (rgb[1] * 255.0) as u8;
+                            color.b = (rgb[2] * 255.0) as u8;
+                        }
+                    } else if ui.small_button("+ fill").clicked() {
+                        stop.fill_color = Some(Color::new(128, 128, 128, 255));
+                    }
+                    if let Some(ref mut opacity) = stop.opacity {
+                        ui.add(egui::Slider::new(opacity, 0.0..=1.0).text("opacity"));
+                    } else if ui.small_button("+ opacity").clicked() {
+                        stop.opacity = Some(1.0);
+                    }
+                    if ui.button("❌").clicked() {
+                        removed = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = removed {
+                rule.style.zoom_stops.remove(index);
+            }
+            ui.horizontal(|ui| {
+                ui.label("Add stop at zoom:");
+                ui.add(egui::DragValue::new(&mut self.new_stop_zoom).range(0.0..=20.0).speed(0.5));
+                if ui.button("Add").clicked() {
+                    rule.style.zoom_stop_mut(self.new_stop_zoom);
+                }
+            });
         });
     }
     
@@ -530,6 +605,9 @@ impl StyleEditor {
                         key.clone()
                     }
                 }
+                crate::parsers::stylesheet::FeatureSelector::TagListContains { key, value } => {
+                    format!("{}~={}", key, value)
+                }
                 crate::parsers::stylesheet::FeatureSelector::ElementType(element_type) => {
                     format!("{:?}", element_type)
                 }
@@ -539,6 +617,9 @@ impl StyleEditor {
                         max.map(|z| z.to_string()).unwrap_or("*".to_string())
                     )
                 }
+                crate::parsers::stylesheet::FeatureSelector::Conditions(conditions) => {
+                    conditions.iter().map(|condition| condition.to_string()).collect()
+                }
             }
         }
     }
@@ -547,12 +628,12 @@ impl StyleEditor {
     fn load_current_style(&mut self, style_manager: &StyleManager) {
         // Try to load the current style's TOML content
         if let Some(current_style_name) = style_manager.get_available_styles().first() {
-            let style_path = format!("assets/styles/{}.toml", current_style_name);
-            match std::fs::read_to_string(&style_path) {
+            // Embedded styles have no file but can still be edited and saved to one
+            match style_manager.style_text(current_style_name) {
                 Ok(content) => {
                     self.toml_content = content;
-                    self.toml_error = None;
                     self.has_unsaved_changes = false;
+                    self.validate_toml(style_manager);
                 }
                 Err(e) => {
                     self.toml_error = Some(format!("Failed to load style file: {}", e));
@@ -635,7 +716,7 @@ impl StyleEditor {
                 });
             
             if ui.button("🔄 Validate").clicked() {
-                self.validate_toml();
+                self.validate_toml(style_manager);
                 self.needs_validation = false;
                 // Set validation success based on whether there's a TOML error
                 self.last_validation_successful = self.toml_error.is_none();
@@ -675,6 +756,9 @@ impl StyleEditor {
         if let Some(ref error) = self.toml_error {
             ui.colored_label(egui::Color32::RED, format!("❌ Error: {}", error));
         }
+        for warning in &self.toml_warnings {
+            ui.colored_label(egui::Color32::from_rgb(230, 160, 0), format!("⚠ {}", warning));
+        }
         
         ui.separator();
         
@@ -822,6 +906,7 @@ impl StyleEditor {
             self.needs_validation = true;
             // Clear any previous validation state since content changed
             self.toml_error = None;
+            self.toml_warnings.clear();
         }
         
         // Handle keyboard shortcuts for search
@@ -1103,13 +1188,15 @@ impl StyleEditor {
     }
     
     /// Validate the current TOML content
-    fn validate_toml(&mut self) {
-        match toml::from_str::<MapStyle>(&self.toml_content) {
-            Ok(_) => {
+    fn validate_toml(&mut self, style_manager: &StyleManager) {
+        match style_manager.check_style_text(&self.toml_content) {
+            Ok(unknown) => {
                 self.toml_error = None;
+                self.toml_warnings = unknown.iter().map(|key| key.to_string()).collect();
             }
             Err(e) => {
                 self.toml_error = Some(format!("TOML syntax error: {}", e));
+                self.toml_warnings.clear();
             }
         }
     }
@@ -1117,7 +1204,7 @@ impl StyleEditor {
     /// Save the current style
     fn save_style(&mut self, style_manager: &mut StyleManager) {
         // First validate
-        self.validate_toml();
+        self.validate_toml(style_manager);
         
         if self.toml_error.is_some() {
             return;
@@ -0,0 +1,109 @@
+use crate::core::{counts_descending, MapData};
+use crate::gui::GuiState;
+use egui::{Context, Grid, Window};
+
+/// Tag key with how many elements carry it and its values, most frequent first
+struct KeyCount {
+    key: String,
+    total: usize,
+    values: Vec<(String, usize)>,
+}
+
+/// Floating panel counting the elements of the loaded map and the values of each tag key,
+/// to see what an extract holds before writing style rules for it
+pub struct TagPanel {
+    /// Only keys and values containing this are listed
+    filter: String,
+    /// Histogram of the loaded data, worked out when the panel is first shown
+    keys: Option<Vec<KeyCount>>,
+}
+
+impl TagPanel {
+    pub fn new() -> Self {
+        Self {
+            filter: String::new(),
+            keys: None,
+        }
+    }
+    
+    /// Forget the counts, for when other data has been loaded
+    pub fn invalidate(&mut self) {
+        self.keys = None;
+    }
+    
+    pub fn show(&mut self, ctx: &Context, gui_state: &mut GuiState, map_data: Option<&MapData>) {
+        if !gui_state.show_tag_stats {
+            return;
+        }
+        
+        let mut open = true;
+        
+        Window::new("🏷 Tag Statistics")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(300.0)
+            .default_height(400.0)
+            .show(ctx, |ui| {
+                let Some(map_data) = map_data else {
+                    ui.label("No map data loaded");
+                    return;
+                };
+                
+                ui.label(format!("{} nodes, {} ways, {} relations",
+                    map_data.nodes.len(), map_data.ways.len(), map_data.relations.len()));
+                ui.horizontal(|ui| {
+                    ui.label("Filter:");
+                    ui.text_edit_singleline(&mut self.filter);
+                });
+                ui.separator();
+                
+                let keys = self.keys.get_or_insert_with(|| Self::count(map_data));
+                let filter = self.filter.to_lowercase();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for key_count in keys.iter() {
+                        let key_matches = key_count.key.to_lowercase().contains(&filter);
+                        let values: Vec<&(String, usize)> = key_count.values.iter()
+                            .filter(|(value, _)| key_matches || value.to_lowercase().contains(&filter))
+                            .collect();
+                        if values.is_empty() {
+                            continue;
+                        }
+                        
+                        let header = format!("{} ({}, {} distinct)", key_count.key, key_count.total, key_count.values.len());
+                        ui.collapsing(header, |ui| {
+                            Grid::new(("tag_values", &key_count.key)).num_columns(2).striped(true).show(ui, |ui| {
+                                for (value, count) in values {
+                                    ui.label(value);
+                                    ui.label(count.to_string());
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    }
+                });
+            });
+        
+        if !open {
+            gui_state.show_tag_stats = false;
+        }
+    }
+    
+    /// Keys of the data by how many elements carry them
+    fn count(map_data: &MapData) -> Vec<KeyCount> {
+        let mut keys: Vec<KeyCount> = map_data.tag_statistics().into_iter()
+            .map(|(key, values)| KeyCount {
+                total: values.values().sum(),
+                values: counts_descending(&values).into_iter().map(|(value, count)| (value.to_string(), count)).collect(),
+                key,
+            })
+            .collect();
+        keys.sort_unstable_by(|a, b| b.total.cmp(&a.total).then(a.key.cmp(&b.key)));
+        keys
+    }
+}
+
+impl Default for TagPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
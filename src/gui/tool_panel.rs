@@ -73,7 +73,7 @@ impl ToolPanel {
                 }
             });
             
-            ui.label(format!("Level: {:.1}x", gui_state.zoom_level));
+            ui.label(format!("Level: z{:.1}", gui_state.zoom_level));
             
             if ui.button("🎯 Fit to Window").clicked() {
                 action = ToolPanelAction::FitToWindow;
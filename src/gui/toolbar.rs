@@ -6,6 +6,8 @@ pub enum ToolbarAction {
     ZoomIn,
     ZoomOut,
     FitToWindow,
+    /// Jump to a web map zoom level typed by the user
+    SetZoom(f64),
     ExportSvg,
     ExportPng,
     ExportPdf,
@@ -74,7 +76,17 @@ impl Toolbar {
                     if ui.button("🎯 Fit").clicked() {
                         action = ToolbarAction::FitToWindow;
                     }
-                    ui.label(format!("{:.1}x", gui_state.zoom_level / 10000.0));
+                    let mut zoom = gui_state.zoom_level;
+                    let zoom_field = ui.add(
+                        egui::DragValue::new(&mut zoom)
+                            .range(0.0..=gui_state.max_zoom)
+                            .speed(0.1)
+                            .fixed_decimals(1)
+                            .prefix("z"),
+                    ).on_hover_text("Web map zoom level; drag or type a level to jump to it");
+                    if zoom_field.changed() {
+                        action = ToolbarAction::SetZoom(zoom as f64);
+                    }
                 });
             });
             
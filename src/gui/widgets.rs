@@ -1,4 +1,5 @@
 /// Custom widgets for the map application
+use crate::utils::units::UnitSystem;
 use egui::{Ui, Response, Vec2, Pos2, Rect, Color32, Stroke};
 
 /// A custom color picker widget with better UX
@@ -116,40 +117,43 @@ impl CoordinateDisplay {
     }
 }
 
-/// A scale bar widget for the map
+/// A scale bar widget for the map. The bar is shortened to a round distance in the chosen
+/// units, so it never reads e.g. "873 m".
 pub struct ScaleBar {
     pub scale: f64, // pixels per meter
-    pub width: f32,
+    pub width: f32, // longest the bar may be
+    pub units: UnitSystem,
 }
 
 impl ScaleBar {
     pub fn new(scale: f64, width: f32) -> Self {
-        Self { scale, width }
+        Self { scale, width, units: UnitSystem::default() }
+    }
+    
+    pub fn with_units(mut self, units: UnitSystem) -> Self {
+        self.units = units;
+        self
     }
     
     pub fn show(&mut self, ui: &mut Ui) -> Response {
         let height = 20.0;
         let (rect, response) = ui.allocate_exact_size(Vec2::new(self.width, height), egui::Sense::hover());
         
-        // Calculate distance represented by the scale bar
-        let distance_meters = self.width as f64 / self.scale;
-        let (distance, unit) = if distance_meters < 1000.0 {
-            (distance_meters, "m")
-        } else {
-            (distance_meters / 1000.0, "km")
-        };
+        // Snap the distance represented by the scale bar to a round value
+        let (distance_meters, label) = self.units.round_scale_length(self.width as f64 / self.scale);
+        let bar_width = (distance_meters * self.scale) as f32;
         
         // Draw scale bar
         let bar_rect = Rect::from_min_size(
             rect.min + Vec2::new(0.0, height - 8.0),
-            Vec2::new(self.width, 4.0)
+            Vec2::new(bar_width, 4.0)
         );
         
         ui.painter().rect_filled(bar_rect, 0.0, Color32::BLACK);
         
         // Draw tick marks
         for i in 0..=4 {
-            let x = rect.min.x + (i as f32 * self.width / 4.0);
+            let x = rect.min.x + (i as f32 * bar_width / 4.0);
             let y1 = rect.min.y + height - 10.0;
             let y2 = rect.min.y + height - 2.0;
             
@@ -161,9 +165,9 @@ impl ScaleBar {
         
         // Draw distance text
         ui.painter().text(
-            rect.center() - Vec2::new(0.0, 8.0),
+            Pos2::new(rect.min.x + bar_width / 2.0, rect.center().y - 8.0),
             egui::Align2::CENTER_CENTER,
-            format!("{:.0} {}", distance, unit),
+            label,
             egui::FontId::proportional(10.0),
             Color32::BLACK,
         );
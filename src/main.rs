@@ -10,25 +10,21 @@ mod utils;
 use anyhow::Result;
 use clap::{Arg, Command};
 use env_logger;
-use log::{info, error, debug};
+use log::{info, error};
 
 use crate::app::MapscowMule;
+use crate::core::{counts_descending, MapBounds};
+use crate::core::geometry::FitPadding;
+use crate::export::{Attribution, ExportFormat, ExportOptions, ExportView, Exporter, SvgTextMode, MAX_EXPORT_SIZE};
 use crate::parsers::{osm::OsmParser, Parser};
-use std::path::Path;
+use crate::rendering::MapRenderer;
+use crate::utils::config::AppConfig;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 fn main() -> Result<()> {
     env_logger::init();
     
-    let args: Vec<String> = std::env::args().collect();
-    
-    if args.len() > 1 && args[1] == "debug-bezons" {
-        return debug_bezons_search();
-    }
-    
-    if args.len() > 1 && args[1] == "debug-bernanos" {
-        return debug_bernanos_rendering();
-    }
-    
     let matches = Command::new("mapscow-mule")
         .version("0.1.0")
         .author("Yann")
@@ -50,16 +46,176 @@ fn main() -> Result<()> {
             Arg::new("osm")
                 .long("osm")
                 .value_name("FILE")
-                .help("Load OSM file at startup")
+                .help("Load OSM file at startup (.osm, .osm.gz or .osm.bz2)")
+                .value_parser(clap::value_parser!(std::path::PathBuf)),
+        )
+        .arg(
+            Arg::new("bbox")
+                .long("bbox")
+                .value_name("MINLON,MINLAT,MAXLON,MAXLAT")
+                .help("Area to export in headless mode (defaults to the data bounds)")
+                .allow_hyphen_values(true)
+                .requires("headless")
+                .value_parser(|s: &str| s.parse::<MapBounds>().map_err(|e| e.to_string())),
+        )
+        .arg(
+            Arg::new("fit-padding")
+                .long("fit-padding")
+                .value_name("PX|PERCENT%")
+                .help("Margin around the data when no --bbox is given in headless mode, e.g. 24px or 5% (default 5%)")
+                .requires("headless")
+                .conflicts_with("bbox")
+                .value_parser(|s: &str| s.parse::<FitPadding>().map_err(|e| e.to_string())),
+        )
+        .arg(
+            Arg::new("style")
+                .long("style")
+                .value_name("NAME|FILE")
+                .help("Style to export with in headless mode, e.g. osm-default, or a TOML or Mapbox GL JSON style file; defaults to map.default_style in the config file")
+                .requires("headless"),
+        )
+        .arg(
+            Arg::new("styles")
+                .long("styles")
+                .value_name("NAME,NAME,...")
+                .help("Export the same area once per style in headless mode, into --output-dir")
+                .requires("headless")
+                .requires("output-dir")
+                .conflicts_with("style")
+                .value_delimiter(','),
+        )
+        .arg(
+            Arg::new("output-dir")
+                .long("output-dir")
+                .value_name("DIR")
+                .help("Directory for --styles exports, which are named after their style")
+                .requires("styles")
+                .conflicts_with("output")
+                .value_parser(clap::value_parser!(std::path::PathBuf)),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("FILE")
+                .help("Output file for headless export")
+                .requires("headless")
+                .value_parser(clap::value_parser!(std::path::PathBuf)),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Output format for headless export (defaults to the output file extension)")
+                .requires("headless")
+                .value_parser(["svg", "png", "jpeg", "jpg"]),
+        )
+        .arg(
+            Arg::new("quality")
+                .long("quality")
+                .value_name("1-100")
+                .help("JPEG quality for headless export")
+                .requires("headless")
+                .value_parser(clap::value_parser!(u8).range(1..=100)),
+        )
+        .arg(
+            Arg::new("svg-text")
+                .long("svg-text")
+                .value_name("MODE")
+                .help("How SVG exports write labels: outlined paths (default), text with the font embedded, or plain text")
+                .requires("headless")
+                .value_parser(["paths", "embedded", "font"]),
+        )
+        .arg(
+            Arg::new("densify")
+                .long("densify")
+                .value_name("METERS")
+                .help("Split way segments longer than this along the great circle in headless exports")
+                .requires("headless")
+                .value_parser(clap::value_parser!(f64)),
+        )
+        .arg(
+            Arg::new("grid")
+                .long("grid")
+                .value_name("PIXELS")
+                .help("Snap SVG coordinates to multiples of this many pixels in headless exports; smaller files, positions off by at most half a step")
+                .requires("headless")
+                .value_parser(clap::value_parser!(f64)),
+        )
+        .arg(
+            Arg::new("full-geometry")
+                .long("full-geometry")
+                .help("Write ways whole in headless exports instead of clipping them to the bbox")
+                .requires("headless")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("repair-geometry")
+                .long("repair-geometry")
+                .help("Close nearly closed area outlines and fix ring winding before headless exports")
+                .requires("headless")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("attribution")
+                .long("attribution")
+                .value_name("TEXT")
+                .help("Credit stamped in the bottom right corner of headless exports [default: © OpenStreetMap contributors]")
+                .requires("headless")
+                .conflicts_with("no-attribution"),
+        )
+        .arg(
+            Arg::new("no-attribution")
+                .long("no-attribution")
+                .help("Leave the credit off headless exports; OpenStreetMap data must then be credited some other way")
+                .requires("headless")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("title")
+                .long("title")
+                .value_name("TEXT")
+                .help("Title stamped above the credit in headless exports")
+                .requires("headless")
+                .conflicts_with("no-attribution"),
+        )
+        .arg(
+            Arg::new("date")
+                .long("date")
+                .value_name("TEXT")
+                .help("Date stamped above the credit in headless exports, written as given")
+                .requires("headless")
+                .conflicts_with("no-attribution"),
+        )
+        .arg(
+            Arg::new("tag-stats")
+                .long("tag-stats")
+                .help("Print how often each tag key and value occurs in the --osm file, then exit")
+                .action(clap::ArgAction::SetTrue)
+                .requires("osm"),
+        )
+        .arg(
+            Arg::new("check-style")
+                .long("check-style")
+                .value_name("FILE")
+                .help("Validate a TOML style file and report unknown keys, then exit")
                 .value_parser(clap::value_parser!(std::path::PathBuf)),
         )
         .get_matches();
 
+    if let Some(style_path) = matches.get_one::<std::path::PathBuf>("check-style") {
+        return check_style(style_path);
+    }
+    
+    if matches.get_flag("tag-stats") {
+        if let Some(osm_file) = matches.get_one::<PathBuf>("osm") {
+            return print_tag_statistics(osm_file);
+        }
+    }
+    
     if matches.get_flag("headless") {
         info!("Starting in headless mode");
-        // TODO: Implement headless mode for batch processing
-        debug!("Headless mode not implemented yet");
-        return Ok(());
+        return run_headless(&matches);
     }
 
     info!("Starting Mapscow Mule GUI");
@@ -90,242 +246,212 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-/// Load the application icon from embedded PNG data
-fn load_app_icon() -> Option<egui::IconData> {
-    // Check if the PNG file exists at compile time
-    if !std::path::Path::new("assets/icons/mapscow-mule.png").exists() {
-        error!("App icon not found. To add an icon:");
-        error!("1. Convert assets/icons/mapscow-mule.svg to PNG (512x512)");
-        error!("2. Save as assets/icons/mapscow-mule.png");
-        error!("3. Rebuild the application");
-        return None;
-    }
+/// Export the `--osm` file to `--output`, or once per `--styles` entry into `--output-dir`,
+/// without starting the GUI
+fn run_headless(matches: &clap::ArgMatches) -> Result<()> {
+    let osm_file = matches
+        .get_one::<PathBuf>("osm")
+        .ok_or_else(|| anyhow::anyhow!("Headless mode needs an input file: --osm FILE"))?;
+    let batch_styles: Option<Vec<&str>> = matches.get_many::<String>("styles").map(|styles| styles.map(|s| s.as_str()).collect());
+    let output_dir = matches.get_one::<PathBuf>("output-dir");
+    let output = match (matches.get_one::<PathBuf>("output"), output_dir) {
+        (Some(output), _) => output.clone(),
+        (None, Some(dir)) => dir.clone(),
+        (None, None) => return Err(anyhow::anyhow!("Headless mode needs an output file: --output FILE")),
+    };
     
-    // Include the PNG file at compile time
-    let icon_bytes = include_bytes!("../assets/icons/mapscow-mule.png");
+    let format = match matches.get_one::<String>("format").map(|s| s.as_str()) {
+        Some("png") => ExportFormat::Png,
+        Some("jpeg" | "jpg") => ExportFormat::Jpeg,
+        Some(_) => ExportFormat::Svg,
+        None => match output.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()) {
+            Some(ext) if ext == "png" && output_dir.is_none() => ExportFormat::Png,
+            Some(ext) if (ext == "jpg" || ext == "jpeg") && output_dir.is_none() => ExportFormat::Jpeg,
+            _ => ExportFormat::Svg,
+        },
+    };
     
-    // Try to load the icon
-    match eframe::icon_data::from_png_bytes(icon_bytes) {
-        Ok(icon_data) => {
-            info!("Successfully loaded application icon");
-            Some(icon_data)
-        }
-        Err(e) => {
-            error!("Failed to load application icon: {}", e);
-            None
+    // Check the styles before spending time on parsing
+    let mut style_manager = styles::loader::StyleManager::new()?;
+    let style = matches.get_one::<String>("style");
+    let mut available = style_manager.get_available_styles();
+    available.sort_unstable();
+    // --style may also name a style file
+    let style_name = style.map(|s| s.as_str()).filter(|s| !Path::new(s).is_file());
+    for name in style_name.into_iter().chain(batch_styles.iter().flatten().copied()) {
+        if !available.contains(&name) {
+            return Err(anyhow::anyhow!(
+                "Unknown style '{}' (available: {})",
+                name,
+                available.join(", ")
+            ));
         }
     }
-}
-
-fn debug_bezons_search() -> Result<()> {
-    debug!("Searching for roads near specific coordinates...");
-    
-    let osm_file = "C:\\Users\\yann\\Documents\\Maperitive\\sartrouville\\sartrouville.osm";
-    if !Path::new(osm_file).exists() {
-        error!("OSM file {} not found", osm_file);
-        return Ok(());
+    // Without --style, the preferred style from the config file
+    let style = match style {
+        Some(style) => {
+            style_manager.load_style(style)?;
+            style.clone()
+        }
+        None => style_manager.load_style_or_default(&AppConfig::load().map.default_style)?,
+    };
+    if let Some(dir) = output_dir {
+        if !dir.is_dir() {
+            return Err(anyhow::anyhow!("Output directory {} does not exist", dir.display()));
+        }
     }
     
-    let parser = OsmParser::new();
-    let map_data = parser.parse_file(osm_file)?;
+    let map_data = OsmParser::new()
+        .with_geometry_repair(matches.get_flag("repair-geometry"))
+        .parse_file(osm_file)
+        .map_err(|e| anyhow::anyhow!("{}: {}", osm_file.display(), e))?;
     
-    debug!("Loaded {} nodes, {} ways", map_data.nodes.len(), map_data.ways.len());
+    // An explicit bbox is the exact extent; data bounds get a margin so edge labels fit
+    let (bounds, padding) = match matches.get_one::<MapBounds>("bbox") {
+        Some(bbox) => (*bbox, FitPadding::Pixels(0.0)),
+        None if map_data.bounds.min_lat.is_finite() && map_data.bounds.width() > 0.0 && map_data.bounds.height() > 0.0 => {
+            (map_data.bounds, matches.get_one::<FitPadding>("fit-padding").copied().unwrap_or_default())
+        }
+        None => return Err(anyhow::anyhow!("{} has no data to export", osm_file.display())),
+    };
     
-    // Target coordinates
-    let targets = vec![
-        (48.9443224247288, 2.177457844649215, "Boulevard de Bezons target"),
-        (48.94396813214317, 2.1806281043179876, "Rue Georges Bernanos target"),
-    ];
+    // Keep the default width and size the height so the image covers the bounds plus padding.
+    // Areas too tall for that get the largest height instead and a narrower image.
+    let mut options = ExportOptions::new(format, output.to_string_lossy().to_string());
+    let (center_lat, center_lon) = bounds.center();
+    let mut scale = padding.inner(options.width as f64) / (bounds.width() * center_lat.to_radians().cos());
+    if padding.outer(bounds.height() * scale) > MAX_EXPORT_SIZE as f64 {
+        scale = padding.inner(MAX_EXPORT_SIZE as f64) / bounds.height();
+        options.width = padding.outer(bounds.width() * center_lat.to_radians().cos() * scale).round().max(1.0) as u32;
+    }
+    options.height = padding.outer(bounds.height() * scale).round().clamp(1.0, MAX_EXPORT_SIZE as f64) as u32;
     
-    for (target_lat, target_lon, desc) in &targets {
-        debug!("\n=== Roads near {} ({:.6}, {:.6}) ===", desc, target_lat, target_lon);
-        
-        let mut nearby_roads = Vec::new();
-        
-        for way in map_data.ways.values() {
-            if let Some(highway) = way.tags.get("highway") {
-                let mut min_distance = f64::INFINITY;
-                let mut closest_coord = None;
-                
-                // Check all nodes in this way
-                for &node_id in &way.nodes {
-                    if let Some(node) = map_data.nodes.get(&node_id) {
-                        let lat_diff = node.lat - target_lat;
-                        let lon_diff = node.lon - target_lon;
-                        let distance = (lat_diff * lat_diff + lon_diff * lon_diff).sqrt();
-                        
-                        if distance < min_distance {
-                            min_distance = distance;
-                            closest_coord = Some((node.lat, node.lon));
-                        }
-                    }
-                }
-                
-                // Include roads within 0.01 degrees (~1km)
-                if min_distance <= 0.01 {
-                    let name = way.tags.get("name").unwrap_or(&"<unnamed>".to_string()).clone();
-                    nearby_roads.push((way.id, name, highway.clone(), min_distance, closest_coord));
-                }
-            }
+    options = options.with_style(style);
+    if matches.get_flag("full-geometry") {
+        options = options.with_full_geometry(true);
+    }
+    if let Some(&max_segment_length) = matches.get_one::<f64>("densify") {
+        options = options.with_densify(Some(max_segment_length));
+    }
+    if let Some(&step) = matches.get_one::<f64>("grid") {
+        options = options.with_coordinate_grid(Some(step));
+    }
+    if let Some(&quality) = matches.get_one::<u8>("quality") {
+        options = options.with_quality(quality);
+    }
+    if matches.get_flag("no-attribution") {
+        options = options.with_attribution(None);
+    } else {
+        let mut attribution = matches.get_one::<String>("attribution")
+            .map_or_else(Attribution::default, |text| Attribution::new(text.clone()));
+        if let Some(title) = matches.get_one::<String>("title") {
+            attribution = attribution.with_title(title.clone());
         }
-        
-        // Sort by distance
-        nearby_roads.sort_by(|a, b| a.3.partial_cmp(&b.3).unwrap());
-        
-        debug!("Found {} roads within 1km:", nearby_roads.len());
-        for (i, (id, name, highway, distance, coord)) in nearby_roads.iter().take(15).enumerate() {
-            debug!("  {}: Way {} '{}' ({})", i+1, id, name, highway);
-            debug!("     Distance: {:.6} degrees (~{:.0}m)", distance, distance * 111000.0);
-            if let Some((lat, lon)) = coord {
-                debug!("     Closest point: {:.6}, {:.6}", lat, lon);
-            }
+        if let Some(date) = matches.get_one::<String>("date") {
+            attribution = attribution.with_date(date.clone());
         }
-        
-        // Specifically look for streets with the name we're looking for
-        let search_name = if desc.contains("Bezons") { "bezons" } else { "bernanos" };
-        debug!("\nSpecific search for '{}':", search_name);
-        
-        for (id, name, highway, distance, coord) in &nearby_roads {
-            if name.to_lowercase().contains(search_name) {
-                debug!("  ✓ FOUND: Way {} '{}' ({})", id, name, highway);
-                debug!("    Distance: {:.6} degrees (~{:.0}m)", distance, distance * 111000.0);
-                if let Some((lat, lon)) = coord {
-                    debug!("    Closest point: {:.6}, {:.6}", lat, lon);
-                }
-            }
+        options = options.with_attribution(Some(attribution));
+    }
+    match matches.get_one::<String>("svg-text").map(|s| s.as_str()) {
+        Some("embedded") => options = options.with_svg_text(SvgTextMode::EmbeddedFont),
+        Some("font") => options = options.with_svg_text(SvgTextMode::Font),
+        _ => {}
+    }
+    
+    if let (Some(styles), Some(dir)) = (&batch_styles, output_dir) {
+        let written = Exporter::new().export_styles(
+            &map_data,
+            &MapRenderer::new(),
+            &options,
+            styles,
+            dir,
+            ExportView::new(center_lat, center_lon, scale),
+        )?;
+        for path in written {
+            println!("Exported {} to {}", osm_file.display(), path.display());
         }
+        return Ok(());
     }
     
+    Exporter::new().export_map_with_viewport(
+        &map_data,
+        &MapRenderer::new(),
+        &options,
+        ExportView::new(center_lat, center_lon, scale),
+    )?;
+    
+    println!("Exported {} to {}", osm_file.display(), output.display());
     Ok(())
 }
 
-fn debug_bernanos_rendering() -> Result<()> {
-    debug!("=== Debug: Rue Georges Bernanos Rendering ===");
-    
-    let osm_file = "C:\\Users\\yann\\Documents\\Maperitive\\sartrouville\\sartrouville.osm";
-    if !Path::new(osm_file).exists() {
-        error!("OSM file {} not found", osm_file);
-        return Ok(());
-    }
+/// Print the element counts of an OSM file and its tag histogram: keys by how many elements
+/// carry them, each with its most common values
+fn print_tag_statistics(osm_file: &Path) -> Result<()> {
+    /// Values listed under each key; keys like `name` have one per element
+    const MAX_LISTED_VALUES: usize = 20;
     
-    let parser = OsmParser::new();
-    let map_data = parser.parse_file(osm_file)?;
+    let map_data = OsmParser::new()
+        .parse_file(osm_file)
+        .map_err(|e| anyhow::anyhow!("{}: {}", osm_file.display(), e))?;
+    println!("{}: {} nodes, {} ways, {} relations",
+        osm_file.display(), map_data.nodes.len(), map_data.ways.len(), map_data.relations.len());
     
-    debug!("Loaded {} nodes, {} ways", map_data.nodes.len(), map_data.ways.len());
-    
-    // Find Rue Georges Bernanos specifically
-    let mut bernanos_ways = Vec::new();
-    for way in map_data.ways.values() {
-        if let Some(name) = way.tags.get("name") {
-            if name.to_lowercase().contains("bernanos") {
-                bernanos_ways.push(way);
-            }
+    let statistics = map_data.tag_statistics();
+    let key_totals: HashMap<String, usize> = statistics.iter()
+        .map(|(key, values)| (key.clone(), values.values().sum()))
+        .collect();
+    for (key, total) in counts_descending(&key_totals) {
+        let values = counts_descending(&statistics[key]);
+        println!("{} ({}, {} distinct)", key, total, values.len());
+        for (value, count) in values.iter().take(MAX_LISTED_VALUES) {
+            println!("  {:>8}  {}", count, value);
+        }
+        if values.len() > MAX_LISTED_VALUES {
+            println!("  {:>8}  ... {} more values", "", values.len() - MAX_LISTED_VALUES);
         }
     }
+    Ok(())
+}
+
+/// Report problems in a style file; unknown keys are warnings, parse failures are errors
+fn check_style(path: &Path) -> Result<()> {
+    let (_, unknown) = styles::loader::MapStyle::load_from_file_checked(path)
+        .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
     
-    debug!("Found {} ways with 'bernanos' in name:", bernanos_ways.len());
-    
-    // Also search for the specific way ID we know exists
-    debug!("\n--- Searching for Way ID 188677600 (known Rue Georges Bernanos) ---");
-    if let Some(way) = map_data.ways.get(&188677600) {
-        debug!("✓ Found Way 188677600!");
-        debug!("  Name: {:?}", way.tags.get("name"));
-        println!("  Highway: {:?}", way.tags.get("highway"));
-        println!("  All tags: {:?}", way.tags);
+    if unknown.is_empty() {
+        println!("{}: OK", path.display());
     } else {
-        println!("✗ Way 188677600 not found in parsed data");
-    }
-    
-    // Check if any ways contain "Georges" or "Bernanos" separately
-    let mut georges_ways = 0;
-    let mut bernanos_ways_count = 0;
-    for way in map_data.ways.values() {
-        if let Some(name) = way.tags.get("name") {
-            if name.to_lowercase().contains("georges") {
-                georges_ways += 1;
-                debug!("Found way with 'georges': {}", name);
-            }
-            if name.to_lowercase().contains("bernanos") {
-                bernanos_ways_count += 1;
-                debug!("Found way with 'bernanos': {}", name);
-            }
+        for key in &unknown {
+            eprintln!("warning: {}: {}", path.display(), key);
         }
+        println!("{}: {} unknown key(s)", path.display(), unknown.len());
     }
-    debug!("Total ways with 'georges': {}", georges_ways);
-    println!("Total ways with 'bernanos': {}", bernanos_ways_count);
-    
-    for way in &bernanos_ways {
-        debug!("\n--- Way {} ---", way.id);
-        debug!("Name: {:?}", way.tags.get("name"));
-        println!("Highway: {:?}", way.tags.get("highway"));
-        println!("All tags: {:?}", way.tags);
-        println!("Node count: {}", way.nodes.len());
-        
-        // Get coordinates
-        let mut coordinates = Vec::new();
-        for &node_id in &way.nodes {
-            if let Some(node) = map_data.nodes.get(&node_id) {
-                coordinates.push((node.lat, node.lon));
-                println!("  Node {}: {:.6}, {:.6}", node_id, node.lat, node.lon);
-            }
-        }
-        
-        if !coordinates.is_empty() {
-            let first = coordinates[0];
-            let last = coordinates[coordinates.len() - 1];
-            
-            // Calculate bounds
-            let min_lat = coordinates.iter().map(|(lat, _)| lat).fold(f64::INFINITY, |a, &b| a.min(b));
-            let max_lat = coordinates.iter().map(|(lat, _)| lat).fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-            let min_lon = coordinates.iter().map(|(_, lon)| lon).fold(f64::INFINITY, |a, &b| a.min(b));
-            let max_lon = coordinates.iter().map(|(_, lon)| lon).fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-            
-            println!("  First point: {:.6}, {:.6}", first.0, first.1);
-            println!("  Last point: {:.6}, {:.6}", last.0, last.1);
-            println!("  Bounds: lat {:.6} to {:.6}, lon {:.6} to {:.6}", min_lat, max_lat, min_lon, max_lon);
-            
-            // Check distance to target coordinate
-            let target = (48.94396813214317, 2.1806281043179876);
-            let dist_to_first = ((first.0 - target.0).powi(2) + (first.1 - target.1).powi(2)).sqrt();
-            let dist_to_last = ((last.0 - target.0).powi(2) + (last.1 - target.1).powi(2)).sqrt();
-            
-            debug!("  Distance to target from first: {:.6} degrees (~{:.0}m)", 
-                     dist_to_first, dist_to_first * 111000.0);
-            println!("  Distance to target from last: {:.6} degrees (~{:.0}m)", 
-                     dist_to_last, dist_to_last * 111000.0);
-        }
+    Ok(())
+}
+
+/// Load the application icon from embedded PNG data
+fn load_app_icon() -> Option<egui::IconData> {
+    // Check if the PNG file exists at compile time
+    if !std::path::Path::new("assets/icons/mapscow-mule.png").exists() {
+        error!("App icon not found. To add an icon:");
+        error!("1. Convert assets/icons/mapscow-mule.svg to PNG (512x512)");
+        error!("2. Save as assets/icons/mapscow-mule.png");
+        error!("3. Rebuild the application");
+        return None;
     }
     
-    // Check what's near the target coordinates
-    let target = (48.94396813214317, 2.1806281043179876);
-    debug!("\n=== Roads within 100m of target ({:.6}, {:.6}) ===", target.0, target.1);
+    // Include the PNG file at compile time
+    let icon_bytes = include_bytes!("../assets/icons/mapscow-mule.png");
     
-    let mut nearby_roads = Vec::new();
-    for way in map_data.ways.values() {
-        if let Some(highway) = way.tags.get("highway") {
-            let mut min_distance = f64::INFINITY;
-            
-            for &node_id in &way.nodes {
-                if let Some(node) = map_data.nodes.get(&node_id) {
-                    let distance = ((node.lat - target.0).powi(2) + (node.lon - target.1).powi(2)).sqrt();
-                    min_distance = min_distance.min(distance);
-                }
-            }
-            
-            // Within 100m (roughly 0.001 degrees)
-            if min_distance <= 0.001 {
-                let name = way.tags.get("name").unwrap_or(&"<unnamed>".to_string()).clone();
-                nearby_roads.push((way.id, name, highway.clone(), min_distance));
-            }
+    // Try to load the icon
+    match eframe::icon_data::from_png_bytes(icon_bytes) {
+        Ok(icon_data) => {
+            info!("Successfully loaded application icon");
+            Some(icon_data)
+        }
+        Err(e) => {
+            error!("Failed to load application icon: {}", e);
+            None
         }
     }
-    
-    nearby_roads.sort_by(|a, b| a.3.partial_cmp(&b.3).unwrap());
-    
-    for (id, name, highway, distance) in nearby_roads.iter().take(10) {
-        println!("  Way {}: '{}' ({}) - {:.6} degrees (~{:.0}m)", 
-                 id, name, highway, distance, distance * 111000.0);
-    }
-    
-    Ok(())
 }
@@ -1,24 +1,58 @@
 use crate::core::{GpxTrack, GpxSegment, GpxPoint};
-use crate::parsers::{Parser, ParseError};
+use crate::parsers::{decode_xml, normalize_xml_text, Parser, ParseError};
 use anyhow::Result;
 use std::path::Path;
 
 /// GPX file parser
-pub struct GpxParser;
+pub struct GpxParser {
+    /// Moving average window in points; 0 or 1 leaves the positions as recorded
+    smoothing_window: usize,
+    /// Douglas-Peucker tolerance in meters; 0 keeps every point
+    simplify_tolerance: f64,
+}
 
 impl GpxParser {
     pub fn new() -> Self {
-        Self
+        Self {
+            smoothing_window: 0,
+            simplify_tolerance: 0.0,
+        }
+    }
+    
+    /// Smooth the parsed tracks with a moving average over `window` points
+    pub fn with_smoothing(mut self, window: usize) -> Self {
+        self.smoothing_window = window;
+        self
+    }
+    
+    /// Simplify the parsed tracks, dropping points closer than `tolerance` meters to the line
+    pub fn with_simplification(mut self, tolerance: f64) -> Self {
+        self.simplify_tolerance = tolerance;
+        self
+    }
+    
+    /// Smoothing runs first so the simplification works on the cleaned-up line
+    fn process(&self, tracks: Vec<GpxTrack>) -> Vec<GpxTrack> {
+        tracks
+            .into_iter()
+            .map(|track| track.smoothed(self.smoothing_window).simplified(self.simplify_tolerance))
+            .collect()
     }
 }
 
 impl Parser<Vec<GpxTrack>> for GpxParser {
     fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<GpxTrack>> {
-        let content = std::fs::read_to_string(path)?;
+        let content = decode_xml(&std::fs::read(path)?)?;
         self.parse_string(&content)
     }
     
     fn parse_string(&self, content: &str) -> Result<Vec<GpxTrack>> {
+        self.parse_tracks(&normalize_xml_text(content)).map(|tracks| self.process(tracks))
+    }
+}
+
+impl GpxParser {
+    fn parse_tracks(&self, content: &str) -> Result<Vec<GpxTrack>> {
         // Try to use the gpx crate first
         match gpx::read(content.as_bytes()) {
             Ok(gpx_data) => {
@@ -30,10 +64,14 @@ impl Parser<Vec<GpxTrack>> for GpxParser {
                     for segment in track.segments {
                         let points: Vec<GpxPoint> = segment.points
                             .into_iter()
-                            .map(|waypoint| GpxPoint {                    lat: waypoint.point().y(),
-                    lon: waypoint.point().x(),
-                    elevation: waypoint.elevation,
-                    time: waypoint.time.map(|_t| chrono::Utc::now()), // Placeholder - use current time
+                            .map(|waypoint| GpxPoint {
+                                lat: waypoint.point().y(),
+                                lon: waypoint.point().x(),
+                                elevation: waypoint.elevation,
+                                time: waypoint.time
+                                    .and_then(|time| time.format().ok())
+                                    .and_then(|time| chrono::DateTime::parse_from_rfc3339(&time).ok())
+                                    .map(|time| time.with_timezone(&chrono::Utc)),
                             })
                             .collect();
                         
@@ -55,9 +93,7 @@ impl Parser<Vec<GpxTrack>> for GpxParser {
             }
         }
     }
-}
-
-impl GpxParser {
+    
     /// Manual GPX parsing as fallback
     fn parse_gpx_manual(&self, content: &str) -> Result<Vec<GpxTrack>> {
         use quick_xml::events::Event;
@@ -0,0 +1,451 @@
+use crate::parsers::stylesheet::{Color, Comparison, DrawMode, FeatureSelector, LineCap, LineJoin, RenderStyle, StyleRule, StyleSheet, TagCondition};
+use crate::parsers::{ParseError, Parser};
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Filters expanding to more alternatives than this are not converted
+const MAX_FILTER_ALTERNATIVES: usize = 64;
+
+/// Zoom just below a `step` threshold, where the previous value still holds
+const STEP_EPSILON: f32 = 0.01;
+
+/// Reads the `line` and `fill` layers of a Mapbox GL style JSON into a [`StyleSheet`].
+///
+/// Filters become tag conditions, so they only match when the style's source uses OSM tag
+/// names. `paint` colours, widths and opacities carry over, as constants or as zoom stops
+/// (exponential curves are approximated as linear, steps as sharp ramps). Layers are read
+/// top to bottom, since a stylesheet draws each feature with the first rule matching it.
+pub struct MapboxGlParser;
+
+/// A converted style and what could not be carried over
+pub struct MapboxImport {
+    pub stylesheet: StyleSheet,
+    /// Colour of the topmost visible `background` layer
+    pub background: Option<Color>,
+    /// One line for each layer or property left out, e.g. data-driven paint values
+    pub warnings: Vec<String>,
+}
+
+impl MapboxGlParser {
+    pub fn new() -> Self {
+        Self
+    }
+    
+    pub fn convert(&self, json: &str) -> Result<MapboxImport> {
+        let style: Value = serde_json::from_str(json)
+            .map_err(|e| ParseError::InvalidFormat(format!("Not a JSON style: {}", e)))?;
+        let layers = style.get("layers").and_then(Value::as_array)
+            .ok_or_else(|| ParseError::MissingField("layers".to_string()))?;
+        
+        let mut stylesheet = StyleSheet::default();
+        let mut background = None;
+        let mut warnings = Vec::new();
+        // Layer ids by unsupported type, reported together
+        let mut skipped: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        
+        for layer in layers.iter().rev() {
+            let id = layer.get("id").and_then(Value::as_str).unwrap_or("(unnamed)");
+            let layer_type = layer.get("type").and_then(Value::as_str).unwrap_or("");
+            if layer.pointer("/layout/visibility").and_then(Value::as_str) == Some("none") {
+                continue;
+            }
+            match layer_type {
+                "line" | "fill" => match convert_layer(layer, layer_type, &mut warnings) {
+                    Ok(rule) => stylesheet.rules.push(rule),
+                    Err(reason) => warnings.push(format!("Layer '{}' skipped: {}", id, reason)),
+                },
+                "background" if background.is_none() => match layer.pointer("/paint/background-color").map(|value| zoom_value(value, parse_color)) {
+                    Some(Ok(ZoomValue::Constant(color))) => background = Some(color),
+                    Some(Ok(ZoomValue::Stops(stops))) => background = stops.first().map(|&(_, color)| color),
+                    Some(Err(reason)) => warnings.push(format!("Layer '{}' skipped: {}", id, reason)),
+                    None => {}
+                },
+                "background" => {}
+                _ => skipped.entry(layer_type.to_string()).or_default().push(id.to_string()),
+            }
+        }
+        
+        if !skipped.is_empty() {
+            let dropped: Vec<String> = skipped.iter()
+                // Listed in style order, they were collected top to bottom
+                .map(|(layer_type, ids)| {
+                    let ids: Vec<&str> = ids.iter().rev().map(String::as_str).collect();
+                    format!("{} ({}: {})", layer_type, ids.len(), ids.join(", "))
+                })
+                .collect();
+            warnings.insert(0, format!("Unsupported layer types skipped: {}", dropped.join("; ")));
+        }
+        Ok(MapboxImport { stylesheet, background, warnings })
+    }
+}
+
+impl Parser<StyleSheet> for MapboxGlParser {
+    fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<StyleSheet> {
+        let content = std::fs::read_to_string(path)?;
+        self.parse_string(&content)
+    }
+    
+    /// Warnings about what was left out are logged
+    fn parse_string(&self, content: &str) -> Result<StyleSheet> {
+        let import = self.convert(content)?;
+        for warning in &import.warnings {
+            log::warn!("Mapbox GL import: {}", warning);
+        }
+        Ok(import.stylesheet)
+    }
+}
+
+impl Default for MapboxGlParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A paint value, fixed or varying with zoom
+enum ZoomValue<T> {
+    Constant(T),
+    Stops(Vec<(f32, T)>),
+}
+
+fn convert_layer(layer: &Value, layer_type: &str, warnings: &mut Vec<String>) -> std::result::Result<StyleRule, String> {
+    let id = layer.get("id").and_then(Value::as_str).unwrap_or("(unnamed)");
+    let alternatives = match layer.get("filter") {
+        Some(filter) => convert_filter(filter)?,
+        None => vec![Vec::new()],
+    };
+    let selectors = alternatives.into_iter().map(FeatureSelector::Conditions).collect();
+    
+    let mut style = RenderStyle {
+        draw_mode: if layer_type == "fill" { DrawMode::Fill } else { DrawMode::Line },
+        line_color: None,
+        fill_color: None,
+        min_zoom: layer.get("minzoom").and_then(Value::as_f64).map(|zoom| zoom.floor() as u32),
+        max_zoom: layer.get("maxzoom").and_then(Value::as_f64).map(|zoom| zoom.ceil() as u32),
+        ..RenderStyle::default()
+    };
+    let paint = layer.get("paint");
+    let property = |name: &str| paint.and_then(|paint| paint.get(name));
+    let mut warn = |name: &str, reason: String| warnings.push(format!("Layer '{}': {} dropped, {}", id, name, reason));
+    
+    if layer_type == "line" {
+        match property("line-color").map(|value| zoom_value(value, parse_color)) {
+            Some(Ok(ZoomValue::Constant(color))) => style.line_color = Some(color),
+            Some(Ok(ZoomValue::Stops(stops))) => {
+                style.line_color = stops.first().map(|&(_, color)| color);
+                for (zoom, color) in stops {
+                    style.zoom_stop_mut(zoom).color = Some(color);
+                }
+            }
+            Some(Err(reason)) => warn("line-color", reason),
+            // The Mapbox default
+            None => style.line_color = Some(Color::new(0, 0, 0, 255)),
+        }
+        match property("line-width").map(|value| zoom_value(value, parse_number)) {
+            Some(Ok(ZoomValue::Constant(width))) => style.line_width = width,
+            Some(Ok(ZoomValue::Stops(stops))) => {
+                style.line_width = stops.first().map_or(1.0, |&(_, width)| width);
+                for (zoom, width) in stops {
+                    style.zoom_stop_mut(zoom).width = Some(width);
+                }
+            }
+            Some(Err(reason)) => warn("line-width", reason),
+            None => style.line_width = 1.0,
+        }
+        // Caps and joins are layout properties; without them the lines keep their round ends
+        if let Some(value) = layer.pointer("/layout/line-cap") {
+            match value.as_str().and_then(LineCap::from_name) {
+                Some(cap) => style.line_cap = cap,
+                None => warn("line-cap", format!("unsupported value {}", value)),
+            }
+        }
+        if let Some(value) = layer.pointer("/layout/line-join") {
+            match value.as_str().and_then(LineJoin::from_name) {
+                Some(join) => style.line_join = join,
+                None => warn("line-join", format!("unsupported value {}", value)),
+            }
+        }
+    } else {
+        match property("fill-color").map(|value| zoom_value(value, parse_color)) {
+            Some(Ok(ZoomValue::Constant(color))) => style.fill_color = Some(color),
+            Some(Ok(ZoomValue::Stops(stops))) => {
+                style.fill_color = stops.first().map(|&(_, color)| color);
+                for (zoom, color) in stops {
+                    style.zoom_stop_mut(zoom).fill_color = Some(color);
+                }
+            }
+            Some(Err(reason)) => warn("fill-color", reason),
+            None => style.fill_color = Some(Color::new(0, 0, 0, 255)),
+        }
+        match property("fill-outline-color").map(|value| zoom_value(value, parse_color)) {
+            Some(Ok(ZoomValue::Constant(color))) => {
+                style.draw_mode = DrawMode::Both;
+                style.line_color = Some(color);
+            }
+            Some(Ok(ZoomValue::Stops(_))) => warn("fill-outline-color", "zoom stops are only read for the fill".to_string()),
+            Some(Err(reason)) => warn("fill-outline-color", reason),
+            None => {}
+        }
+    }
+    
+    let opacity_name = if layer_type == "fill" { "fill-opacity" } else { "line-opacity" };
+    match property(opacity_name).map(|value| zoom_value(value, parse_number)) {
+        Some(Ok(ZoomValue::Constant(opacity))) => {
+            let fade = |color: Color| Color { a: (color.a as f32 * opacity.clamp(0.0, 1.0)).round() as u8, ..color };
+            style.line_color = style.line_color.map(fade);
+            style.fill_color = style.fill_color.map(fade);
+        }
+        Some(Ok(ZoomValue::Stops(stops))) => {
+            for (zoom, opacity) in stops {
+                style.zoom_stop_mut(zoom).opacity = Some(opacity);
+            }
+        }
+        Some(Err(reason)) => warn(opacity_name, reason),
+        None => {}
+    }
+    
+    Ok(StyleRule { selectors, style })
+}
+
+/// A filter as alternatives any of which may hold, each a list of conditions that must all
+/// hold: the shape of a stylesheet rule's selectors. Both the legacy filter syntax
+/// (`["==", "class", "motorway"]`) and expressions (`["==", ["get", "class"], "motorway"]`)
+/// are read; geometry type tests are dropped, the layer type already decides that.
+fn convert_filter(filter: &Value) -> std::result::Result<Vec<Vec<TagCondition>>, String> {
+    let unsupported = || format!("unsupported filter {}", filter);
+    let items = filter.as_array().ok_or_else(unsupported)?;
+    let operator = items.first().and_then(Value::as_str).ok_or_else(unsupported)?;
+    let args = &items[1..];
+    
+    // Conditions on the geometry type or id always hold here
+    let key = match args.first().map(filter_key) {
+        Some(Some(FilterKey::Meta)) => return Ok(vec![Vec::new()]),
+        Some(Some(FilterKey::Tag(key))) => Some(key),
+        _ => None,
+    };
+    let single = |condition: TagCondition| Ok(vec![vec![condition]]);
+    
+    match (operator, key) {
+        ("all", _) => {
+            let mut alternatives = vec![Vec::new()];
+            for part in args {
+                let part = convert_filter(part)?;
+                alternatives = alternatives.iter()
+                    .flat_map(|conditions| part.iter().map(move |more| [conditions.clone(), more.clone()].concat()))
+                    .collect();
+                if alternatives.len() > MAX_FILTER_ALTERNATIVES {
+                    return Err("filter has too many alternatives".to_string());
+                }
+            }
+            Ok(alternatives)
+        }
+        ("any", _) => {
+            let mut alternatives = Vec::new();
+            for part in args {
+                alternatives.extend(convert_filter(part)?);
+            }
+            Ok(alternatives)
+        }
+        ("has", Some(key)) => single(TagCondition::Present(key)),
+        ("!has", Some(key)) => single(TagCondition::Absent(key)),
+        ("!", None) => match args.first().and_then(Value::as_array).map(Vec::as_slice) {
+            Some([operator, key]) if operator == "has" => match filter_key(key) {
+                Some(FilterKey::Tag(key)) => single(TagCondition::Absent(key)),
+                _ => Err(unsupported()),
+            },
+            _ => Err(unsupported()),
+        },
+        ("==", Some(key)) => single(TagCondition::Equals { key, value: filter_value(args.get(1)).ok_or_else(unsupported)? }),
+        ("!=", Some(key)) => single(TagCondition::NotEquals { key, value: filter_value(args.get(1)).ok_or_else(unsupported)? }),
+        (">" | ">=" | "<" | "<=", Some(key)) => {
+            let comparison = match operator {
+                ">" => Comparison::Greater,
+                ">=" => Comparison::GreaterOrEqual,
+                "<" => Comparison::Less,
+                _ => Comparison::LessOrEqual,
+            };
+            let value = args.get(1).and_then(Value::as_f64).ok_or_else(unsupported)?;
+            single(TagCondition::Compare { key, comparison, value })
+        }
+        // Legacy syntax: ["in", key, value, ...]
+        ("in", Some(key)) if args.get(1).is_some_and(|value| !value.is_array()) => {
+            args[1..].iter()
+                .map(|value| Ok(vec![TagCondition::Equals { key: key.clone(), value: filter_value(Some(value)).ok_or_else(unsupported)? }]))
+                .collect()
+        }
+        ("!in", Some(key)) => {
+            let conditions = args[1..].iter()
+                .map(|value| Ok(TagCondition::NotEquals { key: key.clone(), value: filter_value(Some(value)).ok_or_else(unsupported)? }))
+                .collect::<std::result::Result<Vec<_>, String>>()?;
+            Ok(vec![conditions])
+        }
+        // ["match", ["get", key], labels, true|false, ..., fallback]
+        ("match", Some(key)) if args.len() >= 4 && args.len() % 2 == 0 => {
+            let fallback = args.last().and_then(Value::as_bool).ok_or_else(unsupported)?;
+            if fallback {
+                return Err(unsupported());
+            }
+            let mut alternatives = Vec::new();
+            for pair in args[1..args.len() - 1].chunks(2) {
+                if pair[1].as_bool().ok_or_else(unsupported)? {
+                    let labels = match &pair[0] {
+                        Value::Array(labels) => labels.iter().collect(),
+                        label => vec![label],
+                    };
+                    for label in labels {
+                        alternatives.push(vec![TagCondition::Equals { key: key.clone(), value: filter_value(Some(label)).ok_or_else(unsupported)? }]);
+                    }
+                }
+            }
+            Ok(alternatives)
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+enum FilterKey {
+    Tag(String),
+    /// `$type`, `$id`, `["geometry-type"]` or `["id"]`
+    Meta,
+}
+
+/// The tag a filter tests: a key string in the legacy syntax, `["get", key]` in expressions
+fn filter_key(value: &Value) -> Option<FilterKey> {
+    match value {
+        Value::String(key) if key.starts_with('$') => Some(FilterKey::Meta),
+        Value::String(key) => Some(FilterKey::Tag(key.clone())),
+        Value::Array(items) => match items.as_slice() {
+            [operator] if operator == "geometry-type" || operator == "id" => Some(FilterKey::Meta),
+            [operator, Value::String(key)] if operator == "get" => Some(FilterKey::Tag(key.clone())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A compared value as the tag text it would match
+fn filter_value(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(if *flag { "yes" } else { "no" }.to_string()),
+        _ => None,
+    }
+}
+
+/// Read a paint value: a constant, a legacy `{"stops": ...}` function or an `interpolate`
+/// or `step` expression over `["zoom"]`. Values depending on feature properties are refused.
+fn zoom_value<T: Copy>(value: &Value, parse: fn(&Value) -> Option<T>) -> std::result::Result<ZoomValue<T>, String> {
+    let invalid = || format!("unsupported value {}", value);
+    if let Some(constant) = parse(value) {
+        return Ok(ZoomValue::Constant(constant));
+    }
+    let stop_list = |pairs: &[Value]| -> std::result::Result<Vec<(f32, T)>, String> {
+        pairs.chunks(2).map(|pair| match pair {
+            [zoom, value] => Ok((zoom.as_f64().ok_or_else(invalid)? as f32, parse(value).ok_or_else(invalid)?)),
+            _ => Err(invalid()),
+        }).collect()
+    };
+    
+    match value {
+        Value::Object(function) => {
+            if function.contains_key("property") {
+                return Err("data-driven values are not supported".to_string());
+            }
+            let stops = function.get("stops").and_then(Value::as_array).ok_or_else(invalid)?;
+            let pairs: Vec<Value> = stops.iter()
+                .filter_map(Value::as_array)
+                .flat_map(|pair| pair.iter().cloned())
+                .collect();
+            Ok(ZoomValue::Stops(stop_list(&pairs)?))
+        }
+        Value::Array(items) => {
+            let operator = items.first().and_then(Value::as_str).unwrap_or("");
+            let on_zoom = |input: Option<&Value>| input.and_then(Value::as_array).is_some_and(|input| input.len() == 1 && input[0] == "zoom");
+            match operator {
+                "literal" => items.get(1).and_then(parse).map(ZoomValue::Constant).ok_or_else(invalid),
+                "interpolate" | "interpolate-hcl" | "interpolate-lab" if on_zoom(items.get(2)) => {
+                    Ok(ZoomValue::Stops(stop_list(&items[3..])?))
+                }
+                "step" if on_zoom(items.get(1)) => {
+                    let mut previous = items.get(2).and_then(parse).ok_or_else(invalid)?;
+                    let mut stops = vec![(0.0, previous)];
+                    for (zoom, value) in stop_list(&items[3..])? {
+                        stops.push(((zoom - STEP_EPSILON).max(0.0), previous));
+                        stops.push((zoom, value));
+                        previous = value;
+                    }
+                    Ok(ZoomValue::Stops(stops))
+                }
+                "interpolate" | "interpolate-hcl" | "interpolate-lab" | "step" | "get" | "match" | "case" | "coalesce" => {
+                    Err("data-driven values are not supported".to_string())
+                }
+                _ => Err(invalid()),
+            }
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_number(value: &Value) -> Option<f32> {
+    value.as_f64().map(|number| number as f32)
+}
+
+/// CSS colours as Mapbox GL accepts them: `#rgb`, `#rrggbb`, `rgb()`, `rgba()`, `hsl()`,
+/// `hsla()` and a few names
+fn parse_color(value: &Value) -> Option<Color> {
+    let text = value.as_str()?.trim().to_ascii_lowercase();
+    if let Some(hex) = text.strip_prefix('#') {
+        if hex.len() == 3 {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|digit| digit * 17);
+            return Some(Color::new(channel(0)?, channel(1)?, channel(2)?, 255));
+        }
+        return Color::from_hex(&text);
+    }
+    
+    if let Some((function, arguments)) = text.strip_suffix(')').and_then(|text| text.split_once('(')) {
+        let numbers: Vec<f32> = arguments.split(',')
+            .map(|part| part.trim().trim_end_matches('%').parse::<f32>())
+            .collect::<std::result::Result<_, _>>()
+            .ok()?;
+        let alpha = (numbers.get(3).copied().unwrap_or(1.0).clamp(0.0, 1.0) * 255.0).round() as u8;
+        let byte = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+        return match (function.trim(), numbers.len()) {
+            ("rgb" | "rgba", 3 | 4) => Some(Color::new(byte(numbers[0]), byte(numbers[1]), byte(numbers[2]), alpha)),
+            ("hsl" | "hsla", 3 | 4) => {
+                let (r, g, b) = hsl_to_rgb(numbers[0], numbers[1] / 100.0, numbers[2] / 100.0);
+                Some(Color::new(byte(r * 255.0), byte(g * 255.0), byte(b * 255.0), alpha))
+            }
+            _ => None,
+        };
+    }
+    
+    match text.as_str() {
+        "black" => Some(Color::new(0, 0, 0, 255)),
+        "white" => Some(Color::new(255, 255, 255, 255)),
+        "red" => Some(Color::new(255, 0, 0, 255)),
+        "green" => Some(Color::new(0, 128, 0, 255)),
+        "blue" => Some(Color::new(0, 0, 255, 255)),
+        "gray" | "grey" => Some(Color::new(128, 128, 128, 255)),
+        "transparent" => Some(Color::new(0, 0, 0, 0)),
+        _ => None,
+    }
+}
+
+/// Hue in degrees, saturation and lightness from 0.0 to 1.0, to red, green and blue from 0.0 to 1.0
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (f32, f32, f32) {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    (r + m, g + m, b + m)
+}
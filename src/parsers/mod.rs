@@ -1,9 +1,16 @@
 pub mod osm;
 pub mod gpx;
+pub mod mapbox_gl;
 pub mod stylesheet;
 
 use anyhow::Result;
+use encoding_rs::{Encoding, UTF_8};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Read};
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 /// Common trait for all parsers
 pub trait Parser<T> {
@@ -11,6 +18,128 @@ pub trait Parser<T> {
     fn parse_string(&self, content: &str) -> Result<T>;
 }
 
+/// Compression of an input file, as `.osm.gz` and `.osm.bz2` extracts are distributed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+}
+
+impl Compression {
+    /// Detect from the first bytes of the file, falling back to its extension
+    pub fn detect(header: &[u8], path: &Path) -> Self {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            return Compression::Gzip;
+        }
+        if header.starts_with(b"BZh") {
+            return Compression::Bzip2;
+        }
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("gz") => Compression::Gzip,
+            Some(extension) if extension.eq_ignore_ascii_case("bz2") => Compression::Bzip2,
+            _ => Compression::None,
+        }
+    }
+}
+
+/// Open `path` for reading, decompressing gzip and bzip2 transparently. `bytes_read` counts
+/// the bytes taken from the file itself, so progress can be measured against its size even
+/// when the parser sees the larger decompressed stream.
+pub fn open_input(path: &Path, bytes_read: Arc<AtomicU64>) -> Result<(Box<dyn BufRead + Send>, Compression)> {
+    let mut file = BufReader::new(CountingReader { inner: File::open(path)?, bytes_read });
+    let compression = Compression::detect(file.fill_buf()?, path);
+
+    let input: Box<dyn BufRead + Send> = match compression {
+        Compression::None => Box::new(file),
+        // Multi-member decoders, as parallel compressors write several streams back to back
+        Compression::Gzip => Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(file))),
+        Compression::Bzip2 => Box::new(BufReader::new(bzip2::bufread::MultiBzDecoder::new(file))),
+    };
+    Ok((input, compression))
+}
+
+/// Strip the byte order mark from `input` and, if the document is in another encoding than
+/// UTF-8, decode it so the XML readers, which only read UTF-8, see the right characters.
+/// UTF-8 input is still streamed; other encodings are decoded whole, in memory.
+pub fn decode_xml_input(mut input: Box<dyn BufRead + Send>) -> Result<Box<dyn BufRead + Send>> {
+    let (encoding, bom_length) = detect_xml_encoding(input.fill_buf()?);
+    if encoding == UTF_8 {
+        input.consume(bom_length);
+        return Ok(input);
+    }
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes)?;
+    Ok(Box::new(Cursor::new(decode_xml(&bytes)?.into_bytes())))
+}
+
+/// Decode an XML document to UTF-8 following its byte order mark or `encoding` declaration,
+/// which is rewritten to match. Malformed input for the encoding is an error, as invalid
+/// UTF-8 always was.
+pub fn decode_xml(bytes: &[u8]) -> Result<String> {
+    let (encoding, bom_length) = detect_xml_encoding(bytes);
+    let (text, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+    if had_errors {
+        return Err(ParseError::InvalidFormat(format!("Input is not valid {}", encoding.name())).into());
+    }
+    Ok(normalize_xml_text(&text).into_owned())
+}
+
+/// An already decoded document as the XML readers expect it: without a byte order mark and
+/// with any `encoding` declaration saying UTF-8
+pub fn normalize_xml_text(text: &str) -> Cow<'_, str> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    match declared_encoding(text.as_bytes()) {
+        Some(label) if !text[label.clone()].eq_ignore_ascii_case("utf-8") => {
+            Cow::Owned(format!("{}UTF-8{}", &text[..label.start], &text[label.end..]))
+        }
+        _ => Cow::Borrowed(text),
+    }
+}
+
+/// Encoding of an XML document from the start of it, and the length of its byte order mark.
+/// A byte order mark wins over the declaration; without either the document is UTF-8.
+pub fn detect_xml_encoding(header: &[u8]) -> (&'static Encoding, usize) {
+    if let Some(found) = Encoding::for_bom(header) {
+        return found;
+    }
+    let declared = declared_encoding(header).and_then(|label| Encoding::for_label(&header[label]));
+    (declared.unwrap_or(UTF_8), 0)
+}
+
+/// Where the encoding name sits in the `<?xml ... encoding="..."?>` declaration, if there is one.
+/// The declaration is ASCII in every encoding this is used with, so bytes or text both work.
+fn declared_encoding(header: &[u8]) -> Option<std::ops::Range<usize>> {
+    if !header.starts_with(b"<?xml") {
+        return None;
+    }
+    let end = header.windows(2).position(|pair| pair == b"?>")?;
+    let declaration = &header[..end];
+    let attribute = declaration.windows(8).position(|window| window == b"encoding")?;
+    let mut position = attribute + 8;
+    while declaration.get(position).is_some_and(|byte| byte.is_ascii_whitespace() || *byte == b'=') {
+        position += 1;
+    }
+    let quote = *declaration.get(position).filter(|byte| **byte == b'"' || **byte == b'\'')?;
+    let start = position + 1;
+    let length = declaration[start..].iter().position(|byte| *byte == quote)?;
+    Some(start..start + length)
+}
+
+/// Counts the bytes read through it
+struct CountingReader<R> {
+    inner: R,
+    bytes_read: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.bytes_read.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
 /// Error types for parsing operations
 #[derive(thiserror::Error, Debug)]
 pub enum ParseError {
@@ -29,3 +158,64 @@ pub enum ParseError {
     #[error("Invalid coordinate: lat={lat}, lon={lon}")]
     InvalidCoordinate { lat: f64, lon: f64 },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::MapData;
+
+    /// A one-node OSM document around `name`, declared in `encoding`
+    fn osm_document(encoding: &str, name: &[u8]) -> Vec<u8> {
+        let mut document = format!(
+            "<?xml version=\"1.0\" encoding=\"{}\"?>\n<osm version=\"0.6\">\n<node id=\"1\" lat=\"47.3769\" lon=\"8.5417\">\n<tag k=\"name\" v=\"",
+            encoding
+        ).into_bytes();
+        document.extend_from_slice(name);
+        document.extend_from_slice(b"\"/>\n</node>\n</osm>\n");
+        document
+    }
+
+    fn decode_input(bytes: Vec<u8>) -> String {
+        let mut text = String::new();
+        decode_xml_input(Box::new(Cursor::new(bytes))).unwrap().read_to_string(&mut text).unwrap();
+        text
+    }
+
+    fn node_name(text: &str) -> String {
+        let map_data: MapData = osm::OsmParser::new().parse_string(text).unwrap();
+        map_data.nodes[&1].tags["name"].clone()
+    }
+
+    #[test]
+    fn latin1_input_is_decoded() {
+        // "Zürich" with ü as the single ISO-8859-1 byte 0xFC
+        let bytes = osm_document("ISO-8859-1", b"Z\xfcrich");
+        assert_eq!(detect_xml_encoding(&bytes).0.name(), "windows-1252");
+
+        let text = decode_xml(&bytes).unwrap();
+        assert!(text.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(text.contains("v=\"Zürich\""));
+
+        let text = decode_input(bytes);
+        assert_eq!(node_name(&text), "Zürich");
+    }
+
+    #[test]
+    fn utf8_byte_order_mark_is_stripped() {
+        let mut bytes = b"\xef\xbb\xbf".to_vec();
+        bytes.extend(osm_document("UTF-8", "Zürich".as_bytes()));
+
+        let text = decode_input(bytes.clone());
+        assert!(text.starts_with("<?xml"));
+        assert_eq!(node_name(&text), "Zürich");
+        assert_eq!(decode_xml(&bytes).unwrap(), text);
+    }
+
+    #[test]
+    fn decoded_text_declares_utf8() {
+        let text = format!("\u{feff}{}", String::from_utf8(osm_document("ISO-8859-1", "Zürich".as_bytes())).unwrap());
+        let normalized = normalize_xml_text(&text);
+        assert!(normalized.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert_eq!(node_name(&normalized), "Zürich");
+    }
+}
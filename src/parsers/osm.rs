@@ -1,24 +1,51 @@
 use crate::core::{MapData, Node, Way, Relation, RelationMember, ElementType};
-use crate::parsers::{Parser, ParseError};
+use crate::parsers::{decode_xml_input, normalize_xml_text, open_input, Compression, Parser, ParseError};
 use anyhow::Result;
-use log::{warn, debug};
+use log::{warn, debug, info};
 use quick_xml::events::Event;
 use quick_xml::Reader;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufReader;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Elements parsed between two progress reports
+const PROGRESS_INTERVAL: usize = 10_000;
+
+/// How far a parse has got, reported to the callback set with [`OsmParser::with_progress`]
+#[derive(Debug, Clone, Copy)]
+pub struct ParseProgress {
+    pub bytes_read: u64,
+    /// Size of the input, when known
+    pub total_bytes: Option<u64>,
+    /// Nodes, ways and relations parsed so far
+    pub elements: usize,
+}
+
+impl ParseProgress {
+    /// Fraction of the input read, from 0.0 to 1.0
+    pub fn fraction(&self) -> Option<f32> {
+        self.total_bytes
+            .filter(|&total| total > 0)
+            .map(|total| (self.bytes_read as f64 / total as f64).min(1.0) as f32)
+    }
+}
 
 /// OSM XML parser
 pub struct OsmParser {
     /// Whether to include metadata (user, timestamp, etc.)
     include_metadata: bool,
+    /// Whether to run `MapData::validate_and_repair` once the data is read
+    repair_geometry: bool,
+    progress: Option<Arc<dyn Fn(ParseProgress) + Send + Sync>>,
 }
 
 impl OsmParser {
     pub fn new() -> Self {
         Self {
             include_metadata: false,
+            repair_geometry: false,
+            progress: None,
         }
     }
     
@@ -26,30 +53,57 @@ impl OsmParser {
         self.include_metadata = include;
         self
     }
+    
+    /// Close nearly closed area outlines and fix ring winding after parsing
+    pub fn with_geometry_repair(mut self, repair: bool) -> Self {
+        self.repair_geometry = repair;
+        self
+    }
+    
+    /// Call `callback` every few thousand elements and once at the end, e.g. to drive a progress bar
+    pub fn with_progress(mut self, callback: impl Fn(ParseProgress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
 }
 
 impl Parser<MapData> for OsmParser {
     fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<MapData> {
-        let file = File::open(path)?;
-        let buf_reader = BufReader::new(file);
-        let mut reader = Reader::from_reader(buf_reader);
+        let path = path.as_ref();
+        let total_bytes = std::fs::metadata(path).ok().map(|metadata| metadata.len());
+        let bytes_read = Arc::new(AtomicU64::new(0));
+        let (input, compression) = open_input(path, bytes_read.clone())?;
+        if compression != Compression::None {
+            debug!("Decompressing {:?} input {}", compression, path.display());
+        }
+        let mut reader = Reader::from_reader(decode_xml_input(input)?);
         reader.config_mut().trim_text(true);
         
-        self.parse_osm_xml(&mut reader)
+        self.parse_osm_xml(&mut reader, total_bytes, Some(&bytes_read))
     }
     
     fn parse_string(&self, content: &str) -> Result<MapData> {
-        let mut reader = Reader::from_str(content);
+        let content = normalize_xml_text(content);
+        let mut reader = Reader::from_str(&content);
         reader.config_mut().trim_text(true);
         
-        self.parse_osm_xml(&mut reader)
+        self.parse_osm_xml(&mut reader, Some(content.len() as u64), None)
     }
 }
 
 impl OsmParser {
-    fn parse_osm_xml<R: std::io::BufRead>(&self, reader: &mut Reader<R>) -> Result<MapData> {
+    /// `input_bytes` counts what has been read from a file when it differs from the reader's
+    /// position, as for compressed input
+    fn parse_osm_xml<R: std::io::BufRead>(&self, reader: &mut Reader<R>, total_bytes: Option<u64>, input_bytes: Option<&AtomicU64>) -> Result<MapData> {
         let mut map_data = MapData::new();
         let mut buf = Vec::new();
+        let mut elements = 0;
+        let report_progress = |reader: &Reader<R>, elements: usize| {
+            if let Some(callback) = &self.progress {
+                let bytes_read = input_bytes.map_or(reader.buffer_position(), |bytes| bytes.load(Ordering::Relaxed));
+                callback(ParseProgress { bytes_read, total_bytes, elements });
+            }
+        };
         
         // Current element being parsed
         let mut current_element: Option<OsmElement> = None;
@@ -58,7 +112,19 @@ impl OsmParser {
         let mut current_relation_members = Vec::new();
         
         loop {
-            match reader.read_event_into(&mut buf) {
+            let event = reader.read_event_into(&mut buf);
+            let element_done = match &event {
+                Ok(Event::End(e)) => matches!(e.name().as_ref(), b"node" | b"way" | b"relation"),
+                Ok(Event::Empty(e)) => e.name().as_ref() == b"node",
+                _ => false,
+            };
+            if element_done {
+                elements += 1;
+                if elements % PROGRESS_INTERVAL == 0 {
+                    report_progress(reader, elements);
+                }
+            }
+            match event {
                 Ok(Event::Start(ref e)) => {
                     match e.name().as_ref() {
                         b"osm" => {
@@ -184,6 +250,26 @@ impl OsmParser {
             }
             buf.clear();
         }
+        report_progress(reader, elements);
+        
+        let report = map_data.resolve_missing_nodes();
+        if report.missing_references > 0 {
+            info!(
+                "{} of {} way node references are missing ({} ways affected, {} incomplete)",
+                report.missing_references, report.total_references, report.affected_ways, report.incomplete_ways
+            );
+        }
+        
+        if self.repair_geometry {
+            let report = map_data.validate_and_repair();
+            for issue in &report.unrepairable {
+                debug!("{:?} {} not repaired: {}", issue.element_type, issue.id, issue.reason);
+            }
+            info!(
+                "Geometry repair: {} rings closed, {} rings reversed, {} areas left unrepaired",
+                report.rings_closed, report.rings_reversed, report.unrepairable.len()
+            );
+        }
         
         Ok(map_data)
     }
@@ -76,7 +76,16 @@ impl StylesheetParser {
         let mut selectors = Vec::new();
         
         for selector_str in &parts[1..] {
-            if selector_str.contains('=') {
+            if selector_str.starts_with('[') {
+                // "[highway][!tunnel][maxspeed>50]": all of the bracketed conditions must hold
+                selectors.push(FeatureSelector::Conditions(self.parse_conditions(selector_str)?));
+            } else if let Some((key, value)) = selector_str.split_once("~=") {
+                // "cuisine~=pizza" matches any element listing pizza, e.g. cuisine=pizza;italian
+                selectors.push(FeatureSelector::TagListContains {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                });
+            } else if selector_str.contains('=') {
                 let (key, value) = selector_str.split_once('=').unwrap();
                 selectors.push(FeatureSelector::Tag {
                     key: key.to_string(),
@@ -96,6 +105,48 @@ impl StylesheetParser {
         })
     }
     
+    /// Parse a run of MapCSS-like conditions such as `[highway][!tunnel][maxspeed>50]`
+    fn parse_conditions(&self, selector_str: &str) -> Result<Vec<TagCondition>> {
+        let invalid = |reason: &str| ParseError::InvalidFormat(format!("{} in selector {}", reason, selector_str));
+        let inner = selector_str
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+            .ok_or_else(|| invalid("Unbalanced brackets"))?;
+        
+        inner.split("][").map(|condition| {
+            // Two-character operators first so ">=" is not read as ">"
+            const OPERATORS: [(&str, Option<Comparison>); 6] = [
+                ("!=", None),
+                (">=", Some(Comparison::GreaterOrEqual)),
+                ("<=", Some(Comparison::LessOrEqual)),
+                (">", Some(Comparison::Greater)),
+                ("<", Some(Comparison::Less)),
+                ("=", None),
+            ];
+            let condition = condition.trim();
+            let operator = OPERATORS.iter().find_map(|&(symbol, comparison)| {
+                condition.split_once(symbol).map(|(key, value)| (symbol, comparison, key.trim(), value.trim()))
+            });
+            let parsed = match operator {
+                None => match condition.strip_prefix('!') {
+                    Some(key) => TagCondition::Absent(key.trim().to_string()),
+                    None => TagCondition::Present(condition.to_string()),
+                },
+                Some((_, Some(comparison), key, value)) => TagCondition::Compare {
+                    key: key.to_string(),
+                    comparison,
+                    value: value.parse().map_err(|_| invalid(&format!("Expected a number after {}", key)))?,
+                },
+                Some(("!=", None, key, value)) => TagCondition::NotEquals { key: key.to_string(), value: value.to_string() },
+                Some((_, None, key, value)) => TagCondition::Equals { key: key.to_string(), value: value.to_string() },
+            };
+            if parsed.key().is_empty() {
+                return Err(invalid("Missing tag key").into());
+            }
+            Ok(parsed)
+        }).collect()
+    }
+    
     fn parse_define_line(&self, line: &str, stylesheet: &mut StyleSheet) -> Result<()> {
         // Example: "define water-color #4A90E2"
         let parts: Vec<&str> = line.split_whitespace().collect();
@@ -137,6 +188,14 @@ impl StylesheetParser {
                 "line-width" => {
                     rule.style.line_width = value.parse::<f32>().unwrap_or(1.0);
                 }
+                "line-cap" => {
+                    rule.style.line_cap = LineCap::from_name(value)
+                        .ok_or_else(|| ParseError::InvalidFormat(format!("Unknown line cap: {}", value)))?;
+                }
+                "line-join" => {
+                    rule.style.line_join = LineJoin::from_name(value)
+                        .ok_or_else(|| ParseError::InvalidFormat(format!("Unknown line join: {}", value)))?;
+                }
                 "font-family" => {
                     rule.style.font_family = Some(value.to_string());
                 }
@@ -152,6 +211,53 @@ impl StylesheetParser {
                 "max-zoom" => {
                     rule.style.max_zoom = Some(value.parse::<u32>().unwrap_or(18));
                 }
+                "fill-pattern" => {
+                    rule.style.fill_pattern = match value {
+                        "none" => None,
+                        _ => {
+                            let kind = PatternKind::from_name(value)
+                                .ok_or_else(|| ParseError::InvalidFormat(format!("Unknown fill pattern: {}", value)))?;
+                            Some(FillPattern { kind, ..rule.style.fill_pattern.unwrap_or_default() })
+                        }
+                    };
+                }
+                "fill-pattern-color" => {
+                    rule.style.fill_pattern.get_or_insert_with(FillPattern::default).color = self.parse_color(value)?;
+                }
+                "fill-pattern-spacing" => {
+                    rule.style.fill_pattern.get_or_insert_with(FillPattern::default).spacing = value.parse::<f32>().unwrap_or(8.0);
+                }
+                "fill-pattern-width" => {
+                    rule.style.fill_pattern.get_or_insert_with(FillPattern::default).line_width = value.parse::<f32>().unwrap_or(1.0);
+                }
+                "text-anchor" => {
+                    rule.style.text_anchor = TextAnchor::from_name(value)
+                        .ok_or_else(|| ParseError::InvalidFormat(format!("Unknown text anchor: {}", value)))?;
+                }
+                "line-width-stops" | "line-color-stops" | "fill-color-stops" | "opacity-stops" => {
+                    // "zoom value, zoom value, ...", e.g. "line-width-stops: 10 1, 14 3, 18 12"
+                    for stop in value.split(',').map(str::trim).filter(|stop| !stop.is_empty()) {
+                        let (zoom, stop_value) = stop.split_once(char::is_whitespace)
+                            .ok_or_else(|| ParseError::InvalidFormat(format!("Expected a zoom and a value in stop: {}", stop)))?;
+                        let zoom = zoom.parse::<f32>()
+                            .map_err(|_| ParseError::InvalidFormat(format!("Invalid zoom in stop: {}", stop)))?;
+                        let stop_value = stop_value.trim();
+                        let number = || stop_value.parse::<f32>()
+                            .map_err(|_| ParseError::InvalidFormat(format!("Invalid number in stop: {}", stop)));
+                        match key {
+                            "line-width-stops" => rule.style.zoom_stop_mut(zoom).width = Some(number()?),
+                            "line-color-stops" => rule.style.zoom_stop_mut(zoom).color = Some(self.parse_color(stop_value)?),
+                            "fill-color-stops" => rule.style.zoom_stop_mut(zoom).fill_color = Some(self.parse_color(stop_value)?),
+                            _ => rule.style.zoom_stop_mut(zoom).opacity = Some(number()?),
+                        }
+                    }
+                }
+                "text-offset" => {
+                    // "dx dy" or "dx,dy" in pixels
+                    let mut parts = value.split(|c: char| c == ',' || c.is_whitespace()).filter(|part| !part.is_empty());
+                    let mut next = || parts.next().and_then(|part| part.parse::<f32>().ok()).unwrap_or(0.0);
+                    rule.style.text_offset = (next(), next());
+                }
                 _ => {
                     // Unknown property - could log a warning
                 }
@@ -233,8 +339,100 @@ pub struct StyleRule {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FeatureSelector {
     Tag { key: String, value: Option<String> },
+    /// Matches when `value` is one of the `;`-separated values of the tag (`key~=value`)
+    TagListContains { key: String, value: String },
     ElementType(ElementType),
     ZoomRange { min: Option<u32>, max: Option<u32> },
+    /// MapCSS-like conditions, e.g. `[highway][!tunnel][maxspeed>50]`, which must all hold
+    Conditions(Vec<TagCondition>),
+}
+
+/// One bracketed condition of a [`FeatureSelector::Conditions`] selector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TagCondition {
+    /// `[key]`
+    Present(String),
+    /// `[!key]`
+    Absent(String),
+    /// `[key=value]`
+    Equals { key: String, value: String },
+    /// `[key!=value]`, also true when the tag is missing
+    NotEquals { key: String, value: String },
+    /// `[key>50]` and the like. Tags whose value does not start with a number never match,
+    /// units after the number are ignored (`maxspeed=50 mph` compares as 50).
+    Compare { key: String, comparison: Comparison, value: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+    
+    fn holds(&self, left: f64, right: f64) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+impl TagCondition {
+    pub fn key(&self) -> &str {
+        match self {
+            TagCondition::Present(key) | TagCondition::Absent(key) => key,
+            TagCondition::Equals { key, .. } | TagCondition::NotEquals { key, .. } | TagCondition::Compare { key, .. } => key,
+        }
+    }
+    
+    pub fn matches(&self, tags: &HashMap<String, String>) -> bool {
+        let tag = tags.get(self.key());
+        match self {
+            TagCondition::Present(_) => tag.is_some(),
+            TagCondition::Absent(_) => tag.is_none(),
+            TagCondition::Equals { value, .. } => tag == Some(value),
+            TagCondition::NotEquals { value, .. } => tag != Some(value),
+            TagCondition::Compare { comparison, value, .. } => tag
+                .and_then(|tag| tag.split_whitespace().next()?.parse::<f64>().ok())
+                .is_some_and(|number| comparison.holds(number, *value)),
+        }
+    }
+}
+
+impl std::fmt::Display for TagCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TagCondition::Present(key) => write!(f, "[{}]", key),
+            TagCondition::Absent(key) => write!(f, "[!{}]", key),
+            TagCondition::Equals { key, value } => write!(f, "[{}={}]", key, value),
+            TagCondition::NotEquals { key, value } => write!(f, "[{}!={}]", key, value),
+            TagCondition::Compare { key, comparison, value } => write!(f, "[{}{}{}]", key, comparison.symbol(), value),
+        }
+    }
+}
+
+/// Whether `tags` satisfy every one of `conditions`
+pub fn conditions_match(conditions: &[TagCondition], tags: &HashMap<String, String>) -> bool {
+    conditions.iter().all(|condition| condition.matches(tags))
+}
+
+/// Check whether a `;`-separated OSM tag value (e.g. `pizza;italian`) lists `expected`
+pub fn tag_list_contains(tag_value: &str, expected: &str) -> bool {
+    tag_value.split(';').any(|item| item.trim() == expected)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -251,11 +449,274 @@ pub struct RenderStyle {
     pub line_color: Option<Color>,
     pub fill_color: Option<Color>,
     pub line_width: f32,
+    #[serde(default)]
+    pub line_cap: LineCap,
+    #[serde(default)]
+    pub line_join: LineJoin,
     pub font_family: Option<String>,
     pub font_size: f32,
     pub text_field: Option<String>,
     pub min_zoom: Option<u32>,
     pub max_zoom: Option<u32>,
+    /// Hatching or dots drawn over the fill colour
+    #[serde(default)]
+    pub fill_pattern: Option<FillPattern>,
+    /// Which end of the label sits on its anchor point
+    #[serde(default)]
+    pub text_anchor: TextAnchor,
+    /// Label shift from its anchor point in pixels, `(right, down)`
+    #[serde(default)]
+    pub text_offset: (f32, f32),
+    /// Width, colours and opacity at given zoom levels, interpolated in between by
+    /// [`RenderStyle::at_zoom`]; kept sorted by zoom
+    #[serde(default)]
+    pub zoom_stops: Vec<ZoomStop>,
+}
+
+/// Values a style takes at one zoom level. Each property is interpolated between the stops
+/// that set it and held at the first and last of them beyond their range.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ZoomStop {
+    pub zoom: f32,
+    #[serde(default)]
+    pub width: Option<f32>,
+    /// Line colour
+    #[serde(default)]
+    pub color: Option<Color>,
+    #[serde(default)]
+    pub fill_color: Option<Color>,
+    /// Multiplies the alpha of the line and fill colours, from 0.0 to 1.0
+    #[serde(default)]
+    pub opacity: Option<f32>,
+}
+
+impl ZoomStop {
+    pub fn new(zoom: f32) -> Self {
+        Self { zoom, width: None, color: None, fill_color: None, opacity: None }
+    }
+}
+
+impl RenderStyle {
+    /// This style as it applies at `zoom`: the line width, colours and opacity follow its
+    /// zoom stops. Styles without stops come back unchanged.
+    pub fn at_zoom(&self, zoom: f64) -> RenderStyle {
+        let mut style = self.clone();
+        let zoom = zoom as f32;
+        if let Some(width) = interpolate_stops(&self.zoom_stops, zoom, |stop| stop.width, |a, b, t| a + (b - a) * t) {
+            style.line_width = width;
+        }
+        if let Some(color) = interpolate_stops(&self.zoom_stops, zoom, |stop| stop.color, Color::lerp) {
+            style.line_color = Some(color);
+        }
+        if let Some(color) = interpolate_stops(&self.zoom_stops, zoom, |stop| stop.fill_color, Color::lerp) {
+            style.fill_color = Some(color);
+        }
+        if let Some(opacity) = interpolate_stops(&self.zoom_stops, zoom, |stop| stop.opacity, |a, b, t| a + (b - a) * t) {
+            let fade = |color: Color| Color { a: (color.a as f32 * opacity.clamp(0.0, 1.0)).round() as u8, ..color };
+            style.line_color = style.line_color.map(fade);
+            style.fill_color = style.fill_color.map(fade);
+        }
+        style
+    }
+
+    /// The style with `transform` applied to its line, fill and pattern colours, zoom stops
+    /// included
+    pub fn with_color_transform(mut self, transform: &ColorTransform) -> RenderStyle {
+        if transform.is_identity() {
+            return self;
+        }
+        self.line_color = self.line_color.map(|color| transform.apply(color));
+        self.fill_color = self.fill_color.map(|color| transform.apply(color));
+        if let Some(pattern) = &mut self.fill_pattern {
+            pattern.color = transform.apply(pattern.color);
+        }
+        for stop in &mut self.zoom_stops {
+            stop.color = stop.color.map(|color| transform.apply(color));
+            stop.fill_color = stop.fill_color.map(|color| transform.apply(color));
+        }
+        self
+    }
+
+    /// The stop at `zoom`, added in zoom order if there is none yet
+    pub fn zoom_stop_mut(&mut self, zoom: f32) -> &mut ZoomStop {
+        let index = match self.zoom_stops.iter().position(|stop| stop.zoom >= zoom) {
+            Some(index) if self.zoom_stops[index].zoom == zoom => index,
+            Some(index) => {
+                self.zoom_stops.insert(index, ZoomStop::new(zoom));
+                index
+            }
+            None => {
+                self.zoom_stops.push(ZoomStop::new(zoom));
+                self.zoom_stops.len() - 1
+            }
+        };
+        &mut self.zoom_stops[index]
+    }
+}
+
+/// Value of one property at `zoom`, linear between the two stops around it that set the
+/// property; `None` when no stop sets it
+fn interpolate_stops<T: Copy>(stops: &[ZoomStop], zoom: f32, value: impl Fn(&ZoomStop) -> Option<T>, lerp: impl Fn(T, T, f32) -> T) -> Option<T> {
+    let mut below: Option<(f32, T)> = None;
+    for stop in stops {
+        let Some(current) = value(stop) else {
+            continue;
+        };
+        if stop.zoom >= zoom {
+            return Some(match below {
+                Some((below_zoom, below_value)) if stop.zoom > below_zoom => {
+                    lerp(below_value, current, (zoom - below_zoom) / (stop.zoom - below_zoom))
+                }
+                _ => current,
+            });
+        }
+        below = Some((stop.zoom, current));
+    }
+    below.map(|(_, value)| value)
+}
+
+/// Horizontal alignment of a label on its anchor point, as SVG `text-anchor`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TextAnchor {
+    /// The label starts at the anchor and runs to the right of it
+    Start,
+    #[default]
+    Middle,
+    /// The label ends at the anchor
+    End,
+}
+
+impl TextAnchor {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "start" | "left" => Some(TextAnchor::Start),
+            "middle" | "center" => Some(TextAnchor::Middle),
+            "end" | "right" => Some(TextAnchor::End),
+            _ => None,
+        }
+    }
+    
+    pub fn as_svg_value(&self) -> &'static str {
+        match self {
+            TextAnchor::Start => "start",
+            TextAnchor::Middle => "middle",
+            TextAnchor::End => "end",
+        }
+    }
+}
+
+/// Shape of the open ends of a line, as SVG `stroke-linecap`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineCap {
+    /// Cut square at the end point
+    Butt,
+    #[default]
+    Round,
+    /// Cut square half the line width past the end point
+    Square,
+}
+
+impl LineCap {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "butt" => Some(LineCap::Butt),
+            "round" => Some(LineCap::Round),
+            "square" => Some(LineCap::Square),
+            _ => None,
+        }
+    }
+
+    pub fn as_svg_value(&self) -> &'static str {
+        match self {
+            LineCap::Butt => "butt",
+            LineCap::Round => "round",
+            LineCap::Square => "square",
+        }
+    }
+}
+
+/// Shape of the corners of a line, as SVG `stroke-linejoin`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineJoin {
+    /// Sharp corners
+    Miter,
+    #[default]
+    Round,
+    /// Corners cut off flat
+    Bevel,
+}
+
+impl LineJoin {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "miter" => Some(LineJoin::Miter),
+            "round" => Some(LineJoin::Round),
+            "bevel" => Some(LineJoin::Bevel),
+            _ => None,
+        }
+    }
+
+    pub fn as_svg_value(&self) -> &'static str {
+        match self {
+            LineJoin::Miter => "miter",
+            LineJoin::Round => "round",
+            LineJoin::Bevel => "bevel",
+        }
+    }
+}
+
+/// Repeating pattern layered over an area's flat fill
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FillPattern {
+    pub kind: PatternKind,
+    /// Distance between hatch lines or dots, in pixels
+    pub spacing: f32,
+    /// Hatch line width, or dot radius
+    pub line_width: f32,
+    pub color: Color,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    /// Diagonal lines running down to the left
+    Hatch,
+    /// Diagonal lines in both directions
+    CrossHatch,
+    Dots,
+}
+
+impl PatternKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hatch" => Some(PatternKind::Hatch),
+            "cross-hatch" | "cross_hatch" | "crosshatch" => Some(PatternKind::CrossHatch),
+            "dots" => Some(PatternKind::Dots),
+            _ => None,
+        }
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatternKind::Hatch => "hatch",
+            PatternKind::CrossHatch => "cross-hatch",
+            PatternKind::Dots => "dots",
+        }
+    }
+}
+
+impl Default for FillPattern {
+    fn default() -> Self {
+        Self {
+            kind: PatternKind::Hatch,
+            spacing: 8.0,
+            line_width: 1.0,
+            color: Color::new(0, 0, 0, 96),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -293,6 +754,114 @@ impl Color {
     pub fn to_hex(&self) -> String {
         format!("#{:02X}{:02X}{:02X}{:02X}", self.r, self.g, self.b, self.a)
     }
+    
+    /// The colour a fraction `t` of the way from `self` to `other`, channel by channel
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t.clamp(0.0, 1.0)).round() as u8;
+        Color::new(channel(self.r, other.r), channel(self.g, other.g), channel(self.b, other.b), channel(self.a, other.a))
+    }
+
+    /// Parse `#RRGGBB` or `#RRGGBBAA`
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim().strip_prefix('#')?;
+        let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+        match hex.len() {
+            6 => Some(Self::new(channel(0)?, channel(2)?, channel(4)?, 255)),
+            8 => Some(Self::new(channel(0)?, channel(2)?, channel(4)?, channel(6)?)),
+            _ => None,
+        }
+    }
+
+    /// Hue in degrees from 0 to 360, saturation and lightness from 0.0 to 1.0
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let (r, g, b, _) = self.to_rgba_f32();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let lightness = (max + min) / 2.0;
+        let chroma = max - min;
+        if chroma == 0.0 {
+            return (0.0, 0.0, lightness);
+        }
+
+        let saturation = chroma / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == r {
+            ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / chroma + 2.0
+        } else {
+            (r - g) / chroma + 4.0
+        };
+        (hue * 60.0, saturation.min(1.0), lightness)
+    }
+
+    /// The colour with hue `hue` in degrees, and `saturation` and `lightness` from 0.0 to 1.0
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32, a: u8) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let offset = lightness - chroma / 2.0;
+        let channel = |c: f32| ((c + offset) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Self::new(channel(r), channel(g), channel(b), a)
+    }
+}
+
+/// Adjustment applied to every colour a style resolves to, in HSL space, to derive muted or
+/// dark variants of a style without rewriting it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorTransform {
+    /// Multiplies lightness; below 1.0 darkens
+    pub brightness: f32,
+    /// Multiplies saturation; 0.0 gives greys
+    pub saturation: f32,
+    /// Degrees added to the hue
+    pub hue_shift: f32,
+}
+
+impl ColorTransform {
+    /// Leaves colours unchanged
+    pub const IDENTITY: ColorTransform = ColorTransform { brightness: 1.0, saturation: 1.0, hue_shift: 0.0 };
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        if self.is_identity() {
+            return color;
+        }
+        let (hue, saturation, lightness) = color.to_hsl();
+        Color::from_hsl(
+            hue + self.hue_shift,
+            (saturation * self.saturation).clamp(0.0, 1.0),
+            (lightness * self.brightness).clamp(0.0, 1.0),
+            color.a,
+        )
+    }
+
+    /// Transform a `#RRGGBB` or `#RRGGBBAA` colour, keeping its form; `None` for other text
+    pub fn apply_hex(&self, hex: &str) -> Option<String> {
+        let color = self.apply(Color::from_hex(hex)?);
+        if hex.trim().len() == 7 {
+            Some(format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b))
+        } else {
+            Some(format!("#{:02x}{:02x}{:02x}{:02x}", color.r, color.g, color.b, color.a))
+        }
+    }
+}
+
+impl Default for ColorTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
 }
 
 /// Variable types that can be defined in stylesheets
@@ -319,11 +888,17 @@ impl Default for RenderStyle {
             line_color: Some(Color::new(0, 0, 0, 255)),
             fill_color: None,
             line_width: 1.0,
+            line_cap: LineCap::default(),
+            line_join: LineJoin::default(),
             font_family: Some("Arial".to_string()),
             font_size: 12.0,
             text_field: None,
             min_zoom: None,
             max_zoom: None,
+            fill_pattern: None,
+            text_anchor: TextAnchor::default(),
+            text_offset: (0.0, 0.0),
+            zoom_stops: Vec::new(),
         }
     }
 }
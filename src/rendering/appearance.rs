@@ -0,0 +1,609 @@
+use crate::core::geometry::GeometryUtils;
+use crate::core::{MapData, StyleOverride, Way};
+use crate::parsers::stylesheet::{FillPattern, LineCap, LineJoin};
+use crate::styles::loader::{BuildingStyle, DashPattern, LineSide, LineSymbol, MapStyle, RestrictedAccessMode, StrokeStyle};
+use std::collections::HashMap;
+
+/// Railway values drawn as track; disused and abandoned lines are left out
+const DRAWN_RAILWAYS: &[&str] = &["rail", "light_rail", "subway", "tram", "narrow_gauge", "funicular", "monorail"];
+
+/// Tags that give an area its fill in the landuse pass, most important first
+const AREA_FILL_KEYS: &[&str] = &["landuse", "leisure", "natural", "amenity"];
+
+/// Colour names used in `colour=*` tags, besides hex codes
+const NAMED_COLOURS: &[(&str, &str)] = &[
+    ("black", "#000000"), ("white", "#ffffff"), ("grey", "#808080"), ("gray", "#808080"),
+    ("silver", "#c0c0c0"), ("red", "#ff0000"), ("maroon", "#800000"), ("orange", "#ffa500"),
+    ("yellow", "#ffff00"), ("gold", "#ffd700"), ("olive", "#808000"), ("lime", "#00ff00"),
+    ("green", "#008000"), ("teal", "#008080"), ("aqua", "#00ffff"), ("cyan", "#00ffff"),
+    ("blue", "#0000ff"), ("navy", "#000080"), ("purple", "#800080"), ("violet", "#ee82ee"),
+    ("magenta", "#ff00ff"), ("fuchsia", "#ff00ff"), ("pink", "#ffc0cb"), ("brown", "#a52a2a"),
+    ("beige", "#f5f5dc"), ("tan", "#d2b48c"),
+];
+
+/// Lifecycle stages a road can be tagged with before or after it is in regular use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoadLifecycle {
+    Construction,
+    Proposed,
+    Disused,
+}
+
+const ROAD_LIFECYCLES: [(&str, RoadLifecycle); 3] = [
+    ("construction", RoadLifecycle::Construction),
+    ("proposed", RoadLifecycle::Proposed),
+    ("disused", RoadLifecycle::Disused),
+];
+
+/// The drawing passes ways are sorted into, back to front. The interactive view and the SVG
+/// export both walk them in this order and ask [`way_appearance`] how each way looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WayPass {
+    Water,
+    Landuse,
+    Aeroway,
+    Building,
+    /// `building:part` ways, drawn over the building outlines they detail
+    BuildingPart,
+    /// Cliffs, walls, hedges and other lines with marks along them
+    Decorated,
+    Railway,
+    Road,
+    Boundary,
+}
+
+/// One stroke of a way, in style units (pixels at the baseline DPI)
+#[derive(Debug, Clone, PartialEq)]
+pub struct WayStroke {
+    pub color: String,
+    pub width: f32,
+    pub dash: Option<DashPattern>,
+    /// Line ends; the map view draws round and square caps itself
+    pub cap: LineCap,
+    /// Corners; egui lines always join round
+    pub join: LineJoin,
+    /// Shift sideways from the rest of the way, to the right of its direction
+    pub offset: f32,
+}
+
+impl WayStroke {
+    /// A stroke with round or square-cut ends and corners. Round ones take the style's
+    /// `[strokes]` caps and joins in [`way_appearance`].
+    fn solid(color: &str, width: f32, round: bool) -> Self {
+        let (cap, join) = if round { (LineCap::Round, LineJoin::Round) } else { (LineCap::Butt, LineJoin::Miter) };
+        Self {
+            color: color.to_string(),
+            width,
+            dash: None,
+            cap,
+            join,
+            offset: 0.0,
+        }
+    }
+
+    fn is_round(&self) -> bool {
+        self.cap == LineCap::Round && self.join == LineJoin::Round
+    }
+
+    fn cut_square(&mut self) {
+        self.cap = LineCap::Butt;
+        self.join = LineJoin::Miter;
+    }
+
+    /// Dash and gap lengths in pixels for the stroke drawn `width` pixels wide, which may
+    /// differ from `self.width` after DPI scaling or a per-element override
+    pub fn dash_array(&self, width: f32) -> Option<Vec<f32>> {
+        let lengths = self.dash.as_ref()?.lengths(width);
+        (!lengths.is_empty()).then_some(lengths)
+    }
+
+    /// First dash and gap length, for renderers that only draw simple dashes
+    pub fn dash_lengths(&self) -> Option<(f32, f32)> {
+        let lengths = self.dash_array(self.width)?;
+        let mut parts = lengths.into_iter();
+        match (parts.next(), parts.next()) {
+            (Some(dash), Some(gap)) if dash > 0.0 && gap > 0.0 => Some((dash, gap)),
+            _ => None,
+        }
+    }
+}
+
+/// Marks repeated along a line, in style units like [`WayStroke`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineDecoration {
+    pub symbol: LineSymbol,
+    pub side: LineSide,
+    pub spacing: f64,
+    /// Tick length or dot diameter
+    pub size: f32,
+    /// Stroke width of ticks
+    pub width: f32,
+    pub color: String,
+}
+
+/// Cosmetic reshaping of building outlines, applied in output pixels after projection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FootprintShaping {
+    /// Drop corners within this many pixels of the line between their neighbours
+    pub simplify_tolerance: Option<f64>,
+    /// Square off outlines whose walls are all within this many degrees of a right angle
+    pub rectangularize_threshold: Option<f64>,
+}
+
+impl FootprintShaping {
+    fn from_style(buildings: &BuildingStyle) -> Option<Self> {
+        let shaping = Self {
+            simplify_tolerance: Some(buildings.simplify_tolerance as f64).filter(|&tolerance| buildings.simplify && tolerance > 0.0),
+            rectangularize_threshold: Some(buildings.rectangularize_threshold as f64).filter(|_| buildings.rectangularize),
+        };
+        (shaping.simplify_tolerance.is_some() || shaping.rectangularize_threshold.is_some()).then_some(shaping)
+    }
+
+    /// The same shaping with pixel distances multiplied by `factor`, for output at another DPI
+    pub fn scaled(self, factor: f64) -> Self {
+        Self {
+            simplify_tolerance: self.simplify_tolerance.map(|tolerance| tolerance * factor),
+            ..self
+        }
+    }
+
+    /// Reshape a ring of projected points; a closing point equal to the first is kept
+    pub fn apply(&self, ring: &[geo_types::Coord<f64>]) -> Vec<geo_types::Coord<f64>> {
+        let mut ring = ring.to_vec();
+        if let Some(tolerance) = self.simplify_tolerance {
+            ring = GeometryUtils::simplify_ring(&ring, tolerance);
+        }
+        if let Some(threshold) = self.rectangularize_threshold {
+            ring = GeometryUtils::rectangularize_ring(&ring, threshold);
+        }
+        ring
+    }
+}
+
+/// How a way is drawn in one pass, resolved from the TOML style
+#[derive(Debug, Clone)]
+pub struct WayAppearance {
+    pub fill: Option<String>,
+    pub pattern: Option<FillPattern>,
+    /// Reshaping of the outline before it is drawn, for buildings
+    pub footprint: Option<FootprintShaping>,
+    /// Drawn under the stroke, full width (the stroke width plus the border on both sides)
+    pub casing: Option<WayStroke>,
+    /// Drawn between the casing and the stroke, e.g. the background around a bridge deck
+    pub gap: Option<WayStroke>,
+    pub stroke: Option<WayStroke>,
+    /// Drawn over the stroke, e.g. the ties of a railway
+    pub overlay: Option<WayStroke>,
+    /// Ticks or dots along the line, e.g. the downslope side of a cliff
+    pub decoration: Option<LineDecoration>,
+    pub opacity: f32,
+    /// Shift of the whole line sideways, to the right of the way's direction
+    pub offset: f32,
+}
+
+impl WayAppearance {
+    fn area(fill: &str) -> Self {
+        Self {
+            fill: Some(fill.to_string()),
+            pattern: None,
+            footprint: None,
+            casing: None,
+            gap: None,
+            stroke: None,
+            overlay: None,
+            decoration: None,
+            opacity: 1.0,
+            offset: 0.0,
+        }
+    }
+
+    fn line(stroke: WayStroke) -> Self {
+        Self {
+            fill: None,
+            pattern: None,
+            footprint: None,
+            casing: None,
+            gap: None,
+            stroke: Some(stroke),
+            overlay: None,
+            decoration: None,
+            opacity: 1.0,
+            offset: 0.0,
+        }
+    }
+
+    pub fn is_area(&self) -> bool {
+        self.fill.is_some()
+    }
+
+    /// Give the round strokes the style's caps and joins
+    fn with_line_ends(mut self, ends: &StrokeStyle) -> Self {
+        for stroke in [&mut self.casing, &mut self.gap, &mut self.stroke, &mut self.overlay].into_iter().flatten() {
+            if stroke.is_round() {
+                stroke.cap = ends.line_cap;
+                stroke.join = ends.line_join;
+            }
+        }
+        self
+    }
+
+    /// Replace the main colour with a per-element override: the fill of an area, else the stroke
+    /// and any marks along it
+    pub fn with_color(mut self, color: String) -> Self {
+        if let Some(decoration) = &mut self.decoration {
+            decoration.color = color.clone();
+        }
+        match (&mut self.fill, &mut self.stroke) {
+            (Some(fill), _) => *fill = color,
+            (None, Some(stroke)) => stroke.color = color,
+            (None, None) => {}
+        }
+        self
+    }
+
+    /// Apply a per-element override: its colour as in [`Self::with_color`] and, if it sets one,
+    /// its line width, keeping the casing as wide around the line as it was
+    pub fn with_override(self, style_override: &StyleOverride) -> Self {
+        let mut look = match style_override.color(self.is_area()) {
+            Some(color) => self.with_color(format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)),
+            None => self,
+        };
+        if let Some(width) = style_override.line_width {
+            look.set_stroke_width(width);
+        }
+        look
+    }
+
+    /// Draw a road as wide as its `width` tag says when the style draws tagged widths to
+    /// scale, at `pixels_per_meter` in style units. The casing keeps its border around it.
+    pub fn with_tagged_width(mut self, style: &MapStyle, way: &Way, pixels_per_meter: f64) -> Self {
+        let widths = &style.road_widths;
+        let Some(meters) = widths.to_scale.then(|| tagged_width(way)).flatten() else {
+            return self;
+        };
+        let width = (meters * pixels_per_meter).max(widths.min_width as f64) as f32;
+        self.set_stroke_width(width);
+        self
+    }
+
+    /// Change the stroke width, keeping the casing and gap as wide around it as they were
+    fn set_stroke_width(&mut self, width: f32) {
+        let Some(stroke) = &mut self.stroke else {
+            return;
+        };
+        for outer in self.casing.iter_mut().chain(self.gap.iter_mut()) {
+            outer.width += width - stroke.width;
+        }
+        stroke.width = width;
+    }
+
+    /// Draw one carriageway of a divided road `offset_meters` sideways, on the middle of the
+    /// road, widened to stand for both, as long as the two carriageways would overlap where
+    /// they are. Once zoomed in far enough to tell them apart they are left in place.
+    pub fn with_carriageway_offset(mut self, style: &MapStyle, offset_meters: f64, pixels_per_meter: f64) -> Self {
+        let Some(stroke) = &self.stroke else {
+            return self;
+        };
+        let width = stroke.width * style.dual_carriageways.width_factor;
+        let full_width = self.casing.as_ref().map_or(width, |casing| casing.width + width - stroke.width);
+        let offset = offset_meters * pixels_per_meter;
+        if offset.abs() * 2.0 > full_width as f64 {
+            return self;
+        }
+        self.set_stroke_width(width);
+        self.offset = offset as f32;
+        self
+    }
+}
+
+/// A way's `width` tag in meters: a plain number or one ending in ` m`
+pub fn tagged_width(way: &Way) -> Option<f64> {
+    let value = way.tags.get("width")?.trim();
+    let number = value.strip_suffix('m').map_or(value, str::trim_end);
+    number.parse::<f64>().ok().filter(|meters| meters.is_finite() && *meters > 0.0)
+}
+
+/// Meters per storey, for parts tagged with `building:levels` but no `height`
+const LEVEL_HEIGHT: f64 = 3.0;
+
+/// Height of a building or building part in meters, from its `height` tag (a plain number or
+/// one ending in ` m`) or its `building:levels`
+pub fn building_height(way: &Way) -> Option<f64> {
+    let meters = |key: &str| {
+        let value = way.tags.get(key)?.trim();
+        let number = value.strip_suffix('m').map_or(value, str::trim_end);
+        number.parse::<f64>().ok().filter(|meters| meters.is_finite() && *meters >= 0.0)
+    };
+    meters("height").or_else(|| meters("building:levels").map(|levels| levels * LEVEL_HEIGHT))
+}
+
+/// Whether `way` is a `building:part`. Ways also tagged `building` are drawn as buildings.
+pub fn is_building_part(way: &Way) -> bool {
+    way.tags.get("building:part").is_some_and(|part| part != "no") && !way.tags.contains_key("building")
+}
+
+/// Building parts in the order they are drawn: by `layer` tag, then lowest first so taller
+/// parts cover the ones at their feet, with the id as a tie-break so the order is stable.
+/// Parts with no height count as ground level.
+pub fn building_parts_in_draw_order<'a>(ways: impl IntoIterator<Item = &'a Way>) -> Vec<&'a Way> {
+    let mut parts: Vec<(&'a Way, f64)> = ways.into_iter()
+        .filter(|way| is_building_part(way))
+        .map(|way| (way, building_height(way).unwrap_or(0.0)))
+        .collect();
+    parts.sort_by(|(a, a_height), (b, b_height)| {
+        a.layer().cmp(&b.layer()).then(a_height.total_cmp(b_height)).then(a.id.cmp(&b.id))
+    });
+    parts.into_iter().map(|(way, _)| way).collect()
+}
+
+/// `waterway` values mapped as the outline of an area of water rather than along its course
+const WATER_AREA_WATERWAYS: &[&str] = &["riverbank", "dock", "boatyard"];
+
+/// Water bodies, coastlines and waterways
+pub fn is_water_feature(way: &Way) -> bool {
+    matches!(way.tags.get("natural").map(|s| s.as_str()), Some("water" | "coastline"))
+        || way.tags.contains_key("waterway")
+        || way.tags.contains_key("water")
+}
+
+/// The `colour` (or `color`) tag of a feature as `#rrggbb`, if it is a hex code or a common
+/// colour name. Of several `;`-separated values the first is used.
+pub fn osm_colour(tags: &HashMap<String, String>) -> Option<String> {
+    let value = tags.get("colour").or_else(|| tags.get("color"))?;
+    let value = value.split(';').next()?.trim().to_ascii_lowercase();
+    match value.strip_prefix('#') {
+        Some(hex) if !hex.chars().all(|c| c.is_ascii_hexdigit()) => None,
+        Some(hex) if hex.len() == 6 => Some(value),
+        Some(hex) if hex.len() == 3 => Some(hex.chars().fold("#".to_string(), |mut rgb, c| {
+            rgb.push(c);
+            rgb.push(c);
+            rgb
+        })),
+        Some(_) => None,
+        None => NAMED_COLOURS.iter().find(|(name, _)| *name == value).map(|(_, hex)| hex.to_string()),
+    }
+}
+
+/// Roads in the order they are drawn: by `layer` tag, then, when casings are merged at
+/// junctions, widest first, with the id as a tie-break so the order is stable. Widths are
+/// those of the class, or of the `width` tag at `pixels_per_meter` for roads drawn to scale.
+pub fn roads_in_draw_order<'a>(style: &MapStyle, ways: impl IntoIterator<Item = &'a Way>, pixels_per_meter: f64) -> Vec<&'a Way> {
+    let widths = &style.road_widths;
+    let mut roads: Vec<(&'a Way, f32)> = ways.into_iter()
+        .filter_map(|way| {
+            let (highway, _) = road_class(way)?;
+            let (_, class_width, _, border_width) = style.get_road_style(highway);
+            let width = match widths.to_scale.then(|| tagged_width(way)).flatten() {
+                Some(meters) => (meters * pixels_per_meter).max(widths.min_width as f64) as f32,
+                None => class_width,
+            };
+            Some((way, width + border_width.max(0.0) * 2.0))
+        })
+        .collect();
+    if style.road_casings.merge_junctions {
+        roads.sort_by(|(a, a_width), (b, b_width)| {
+            a.layer().cmp(&b.layer()).then(b_width.total_cmp(a_width)).then(a.id.cmp(&b.id))
+        });
+    } else {
+        roads.sort_by_key(|(way, _)| (way.layer(), way.id));
+    }
+    roads.into_iter().map(|(way, _)| way).collect()
+}
+
+/// The road class of `way` and its lifecycle stage, if it is a road at all.
+/// `highway=construction` + `construction=primary` and `construction:highway=primary` both give
+/// `("primary", Some(Construction))`; a stage without a known class falls back to `"road"`.
+pub fn road_class(way: &Way) -> Option<(&str, Option<RoadLifecycle>)> {
+    fn class(value: Option<&String>) -> &str {
+        match value.map(|s| s.as_str()) {
+            Some("yes") | None => "road",
+            Some(value) => value,
+        }
+    }
+
+    if let Some(highway) = way.tags.get("highway") {
+        return Some(match ROAD_LIFECYCLES.iter().find(|(prefix, _)| highway.as_str() == *prefix) {
+            Some(&(prefix, stage)) => {
+                let base = way.tags.get(prefix).or_else(|| way.tags.get(&format!("{}:highway", prefix)));
+                (class(base), Some(stage))
+            }
+            None => (highway.as_str(), None),
+        });
+    }
+    ROAD_LIFECYCLES.iter().find_map(|&(prefix, stage)| {
+        way.tags.get(&format!("{}:highway", prefix)).map(|base| (class(Some(base)), Some(stage)))
+    })
+}
+
+/// How `way` is drawn in `pass` at `scale` (pixels per degree), or `None` if it is not part
+/// of that pass or is hidden at this level of detail
+pub fn way_appearance(style: &MapStyle, map_data: &MapData, way: &Way, pass: WayPass, scale: f64) -> Option<WayAppearance> {
+    let look = style_appearance(style, map_data, way, pass, scale)?.with_line_ends(&style.strokes);
+    if !style.osm_colour.respect_osm_colour {
+        return Some(look);
+    }
+    Some(match osm_colour(&way.tags) {
+        Some(colour) => look.with_color(colour),
+        None => look,
+    })
+}
+
+/// How the style alone draws `way`, before its own colour tag is taken into account
+fn style_appearance(style: &MapStyle, map_data: &MapData, way: &Way, pass: WayPass, scale: f64) -> Option<WayAppearance> {
+    let area_visible = || map_data.way_bounds(way).is_some_and(|bounds| style.area_visible_at(&bounds, scale));
+
+    match pass {
+        WayPass::Water => {
+            if !is_water_feature(way) {
+                return None;
+            }
+            let waterway = way.tags.get("waterway").map(|s| s.as_str());
+            let mut look = if way.is_area() {
+                if !area_visible() {
+                    return None;
+                }
+                let mut look = WayAppearance::area(&style.water.color);
+                look.stroke = style.water.outline_width_at(scale)
+                    .map(|width| WayStroke::solid(&style.water.outline_color(), width, true));
+                look
+            } else if let Some(waterway) = waterway.filter(|waterway| !WATER_AREA_WATERWAYS.contains(waterway)) {
+                // Centerlines, as wide as the kind of waterway
+                WayAppearance::line(WayStroke::solid(&style.water.color, style.water.waterway_width(waterway), true))
+            } else if way.tags.get("natural").map(|s| s.as_str()) == Some("coastline") {
+                WayAppearance::line(WayStroke::solid(&style.water.color, style.water.line_width, true))
+            } else {
+                // An unclosed piece of a lake or riverbank outline, part of a multipolygon;
+                // stroking it would draw the shore as a river
+                return None;
+            };
+            look.opacity = style.water.opacity;
+            Some(look)
+        }
+        WayPass::Landuse => {
+            if !way.is_area() || is_water_feature(way) || !area_visible() {
+                return None;
+            }
+            // One fill per area: landuse wins over leisure, which wins over natural, then amenity
+            let (key, value, color) = AREA_FILL_KEYS.iter().find_map(|&key| {
+                let value = way.tags.get(key)?;
+                let color = match key {
+                    "landuse" => style.get_landuse_color(value),
+                    "leisure" => style.get_leisure_color(value),
+                    "natural" => style.get_natural_color(value),
+                    _ => style.get_amenity_color(value),
+                }?;
+                Some((key, value, color))
+            })?;
+            let mut look = WayAppearance::area(color);
+            if key == "landuse" {
+                look.pattern = style.get_landuse_pattern(value);
+            }
+            look.stroke = style.area_outline(key, value)
+                .map(|outline| WayStroke::solid(&outline.color, outline.width, false));
+            Some(look)
+        }
+        WayPass::Aeroway => {
+            way.tags.get("aeroway")?;
+            if way.is_area() {
+                area_visible().then(|| WayAppearance::area(&style.aeroway.default))
+            } else {
+                // Runways and taxiways
+                Some(WayAppearance::line(WayStroke::solid(&style.aeroway.default, 4.0, false)))
+            }
+        }
+        WayPass::Building => {
+            if !way.tags.contains_key("building") || !way.is_area() || !style.buildings_visible_at(scale) {
+                return None;
+            }
+            let mut look = WayAppearance::area(&style.buildings.fill);
+            look.stroke = Some(WayStroke::solid(&style.buildings.stroke, style.buildings.stroke_width, false));
+            look.footprint = FootprintShaping::from_style(&style.buildings);
+            Some(look)
+        }
+        WayPass::BuildingPart => {
+            let buildings = &style.buildings;
+            if !buildings.parts || !is_building_part(way) || !way.is_area() || !style.buildings_visible_at(scale) {
+                return None;
+            }
+            let mut look = WayAppearance::area(buildings.part_fill.as_deref().unwrap_or(&buildings.fill));
+            look.stroke = Some(WayStroke::solid(buildings.part_stroke.as_deref().unwrap_or(&buildings.stroke), buildings.stroke_width, false));
+            look.footprint = FootprintShaping::from_style(buildings);
+            Some(look)
+        }
+        WayPass::Decorated => {
+            let decorated = style.decorated_line(&way.tags).filter(|decorated| scale >= decorated.min_scale)?;
+            let mut look = WayAppearance::line(WayStroke::solid(&decorated.color, decorated.line_width, true));
+            look.stroke = look.stroke.filter(|stroke| stroke.width > 0.0);
+            look.decoration = Some(LineDecoration {
+                symbol: decorated.symbol,
+                side: decorated.side,
+                spacing: decorated.spacing,
+                size: decorated.size,
+                width: decorated.symbol_width,
+                color: decorated.color.clone(),
+            });
+            Some(look)
+        }
+        WayPass::Railway => {
+            let railway = way.tags.get("railway")?;
+            if !DRAWN_RAILWAYS.contains(&railway.as_str()) {
+                return None;
+            }
+            let rail = &style.railway;
+            let mut look = WayAppearance::line(WayStroke::solid(&rail.rail_color, rail.rail_width, true));
+            look.overlay = Some(WayStroke {
+                dash: Some(rail.rail_dash_pattern.clone()),
+                ..WayStroke::solid(&rail.rail_dash_color, rail.rail_dash_width, true)
+            });
+            Some(look)
+        }
+        WayPass::Road => {
+            let (highway, lifecycle) = road_class(way)?;
+            if !style.highway_visible_at(highway, scale) || style.restricted_access.hides(&way.tags) {
+                return None;
+            }
+            let (color, width, border_color, border_width) = style.get_road_style(highway);
+            let mut look = WayAppearance::line(WayStroke::solid(color, width, true));
+            if !border_color.is_empty() && border_width > 0.0 {
+                look.casing = Some(WayStroke {
+                    offset: style.roads.get(highway).map_or(0.0, |road| road.casing_offset),
+                    ..WayStroke::solid(border_color, width + border_width * 2.0, true)
+                });
+            }
+            let bridges = &style.bridges;
+            if bridges.applies_to(&way.tags) {
+                // Butt caps end the casing square at the abutments
+                let gap_color = if bridges.gap_color.is_empty() { &style.background.color } else { &bridges.gap_color };
+                let gap_width = width + bridges.gap_width * 2.0;
+                look.gap = Some(WayStroke::solid(gap_color, gap_width, false));
+                look.casing = Some(WayStroke::solid(&bridges.casing_color, gap_width + bridges.casing_width * 2.0, false));
+            }
+            if let Some(lifecycle) = lifecycle {
+                // Roads not in use keep their class colour but are dashed; only those under
+                // construction keep the casing, the others are faded out
+                if let Some(stroke) = &mut look.stroke {
+                    stroke.dash = Some(DashPattern::Pixels(format!("{},{}", (width * 2.0).max(4.0), (width * 1.5).max(3.0))));
+                    stroke.cut_square();
+                }
+                if lifecycle != RoadLifecycle::Construction {
+                    look.casing = None;
+                    look.gap = None;
+                    look.opacity = 0.5;
+                }
+            }
+            let access = &style.restricted_access;
+            if access.is_restricted(&way.tags) {
+                match access.mode {
+                    RestrictedAccessMode::Fade => look.opacity = look.opacity.min(access.opacity),
+                    RestrictedAccessMode::Dash => {
+                        if let Some(stroke) = &mut look.stroke {
+                            stroke.dash = Some(DashPattern::Pixels(format!("{},{}", (width * 1.5).max(3.0), (width * 1.5).max(3.0))));
+                            stroke.cut_square();
+                        }
+                        look.casing = None;
+                        look.gap = None;
+                    }
+                    RestrictedAccessMode::Show | RestrictedAccessMode::Hide => {}
+                }
+            }
+            Some(look)
+        }
+        WayPass::Boundary => {
+            // Ways of a boundary relation take its level; a way can also carry the tags itself
+            let level = match map_data.boundary_levels.get(&way.id) {
+                Some(level) => Some(level.as_str()),
+                None if way.tags.get("boundary").map(|s| s.as_str()) == Some("administrative") => {
+                    way.tags.get("admin_level").map(|s| s.as_str())
+                }
+                None => return None,
+            };
+            let (color, width, dash, opacity) = style.get_boundary_style(level);
+            let mut look = WayAppearance::line(WayStroke {
+                dash: Some(dash.clone()).filter(|dash| !dash.is_solid()),
+                ..WayStroke::solid(color, width, false)
+            });
+            look.opacity = opacity;
+            Some(look)
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use crate::core::projection::METERS_PER_DEGREE;
+use crate::core::{MapData, Way};
+use crate::rendering::appearance::road_class;
+use crate::styles::loader::{DrivingSide, DualCarriagewayStyle};
+use std::collections::HashMap;
+
+/// Oncoming carriageways run within this angle of opposite directions (cos 30°)
+const MIN_OPPOSITE_COSINE: f64 = 0.866;
+
+/// Share of a way's points that must have its partner alongside for the two to pair up
+const MIN_PAIRED_SHARE: f64 = 0.5;
+
+/// A one-way road as its points in meters, in the direction traffic goes
+struct Carriageway {
+    id: i64,
+    /// +1.0 when traffic follows the way's node order, -1.0 for `oneway=-1`
+    direction: f64,
+    points: Vec<(f64, f64)>,
+}
+
+/// Sideways shift in meters bringing each paired carriageway onto the middle of its road,
+/// positive to the right of the way's node order, keyed by way id. Ways left out have no
+/// partner: they are two-way, unnamed, too far from any oncoming carriageway, or have it on
+/// the wrong side for `style.driving_side`.
+pub fn carriageway_offsets(style: &DualCarriagewayStyle, map_data: &MapData) -> HashMap<i64, f64> {
+    let mut offsets = HashMap::new();
+    if !style.merge {
+        return offsets;
+    }
+
+    // Local plane in meters around the middle of the data, y pointing north
+    let (center_lat, _) = map_data.bounds.center();
+    let x_scale = METERS_PER_DEGREE * center_lat.to_radians().cos();
+    let mut groups: HashMap<(&str, &str), Vec<Carriageway>> = HashMap::new();
+    for way in map_data.ways.values() {
+        let Some((highway, None)) = road_class(way) else {
+            continue;
+        };
+        let Some(direction) = oneway_direction(way) else {
+            continue;
+        };
+        let Some(key) = way.tags.get("ref").or_else(|| way.tags.get("name")) else {
+            continue;
+        };
+        let mut points: Vec<(f64, f64)> = way.node_refs()
+            .filter_map(|node_id| map_data.nodes.get(&node_id))
+            .map(|node| (node.lon * x_scale, node.lat * METERS_PER_DEGREE))
+            .collect();
+        if direction < 0.0 {
+            points.reverse();
+        }
+        if points.len() >= 2 {
+            groups.entry((highway, key.as_str())).or_default().push(Carriageway { id: way.id, direction, points });
+        }
+    }
+
+    for group in groups.values().filter(|group| group.len() >= 2) {
+        for carriageway in group {
+            let others = group.iter().filter(|other| other.id != carriageway.id);
+            if let Some(separation) = separation(carriageway, others, style) {
+                // Oncoming traffic passes on the left where traffic keeps right, so the middle
+                // of the road is to the left of travel, and the other way round
+                let towards_middle = match style.driving_side {
+                    DrivingSide::Right => -1.0,
+                    DrivingSide::Left => 1.0,
+                };
+                offsets.insert(carriageway.id, towards_middle * carriageway.direction * separation / 2.0);
+            }
+        }
+    }
+    offsets
+}
+
+/// +1.0 for ways one-way in their node order, -1.0 for those one-way against it
+fn oneway_direction(way: &Way) -> Option<f64> {
+    if way.is_roundabout() {
+        return None;
+    }
+    match way.tags.get("oneway").map(|s| s.as_str()) {
+        Some("yes" | "true" | "1") => Some(1.0),
+        Some("-1" | "reverse") => Some(-1.0),
+        _ => None,
+    }
+}
+
+/// Mean distance in meters from `carriageway` to the oncoming carriageways beside it, if they
+/// run alongside most of it on the side oncoming traffic belongs
+fn separation<'a>(carriageway: &Carriageway, others: impl Iterator<Item = &'a Carriageway>, style: &DualCarriagewayStyle) -> Option<f64> {
+    let others: Vec<&Carriageway> = others.collect();
+    let points = &carriageway.points;
+    let mut distances = Vec::new();
+    for (i, &point) in points.iter().enumerate() {
+        let (before, after) = (points[i.saturating_sub(1)], points[(i + 1).min(points.len() - 1)]);
+        let Some(heading) = unit(after.0 - before.0, after.1 - before.1) else {
+            continue;
+        };
+        let nearest = others.iter()
+            .flat_map(|other| other.points.windows(2))
+            .filter_map(|segment| {
+                let direction = unit(segment[1].0 - segment[0].0, segment[1].1 - segment[0].1)?;
+                if direction.0 * heading.0 + direction.1 * heading.1 > -MIN_OPPOSITE_COSINE {
+                    return None;
+                }
+                let closest = closest_point(point, segment[0], segment[1]);
+                let offset = (closest.0 - point.0, closest.1 - point.1);
+                // Positive when the other carriageway is on the left of travel (y is north)
+                let side = heading.0 * offset.1 - heading.1 * offset.0;
+                Some((offset.0.hypot(offset.1), side))
+            })
+            .filter(|&(distance, _)| distance <= style.max_distance)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((distance, side)) = nearest {
+            let on_oncoming_side = match style.driving_side {
+                DrivingSide::Right => side > 0.0,
+                DrivingSide::Left => side < 0.0,
+            };
+            if on_oncoming_side {
+                distances.push(distance);
+            }
+        }
+    }
+
+    (!distances.is_empty() && distances.len() as f64 >= points.len() as f64 * MIN_PAIRED_SHARE)
+        .then(|| distances.iter().sum::<f64>() / distances.len() as f64)
+}
+
+fn unit(dx: f64, dy: f64) -> Option<(f64, f64)> {
+    let length = dx.hypot(dy);
+    (length > 0.0).then(|| (dx / length, dy / length))
+}
+
+fn closest_point(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> (f64, f64) {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length_squared = dx * dx + dy * dy;
+    if length_squared == 0.0 {
+        return start;
+    }
+    let t = (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length_squared).clamp(0.0, 1.0);
+    (start.0 + t * dx, start.1 + t * dy)
+}
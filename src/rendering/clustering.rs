@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+/// POIs of one type that fall into the same grid cell on screen
+#[derive(Debug, Clone)]
+pub struct PoiCluster {
+    pub key: String,
+    /// Centroid of the members in screen coordinates
+    pub x: f64,
+    pub y: f64,
+    /// Node ids, sorted
+    pub members: Vec<i64>,
+}
+
+impl PoiCluster {
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+/// Group screen-space POIs `(node id, POI key, x, y)` of the same key into cells of `radius`
+/// pixels. Because the grid lives in screen space, clusters split up by themselves as the
+/// map is zoomed in. Every point ends up in exactly one cluster, singletons included.
+pub fn cluster_points<I>(points: I, radius: f64) -> Vec<PoiCluster>
+where
+    I: IntoIterator<Item = (i64, String, f64, f64)>,
+{
+    let radius = radius.max(1.0);
+    let mut cells: HashMap<_, Vec<(i64, f64, f64)>> = HashMap::new();
+    for (id, key, x, y) in points {
+        let cell = ((x / radius).floor() as i64, (y / radius).floor() as i64);
+        cells.entry((key, cell.0, cell.1)).or_default().push((id, x, y));
+    }
+
+    let mut clusters: Vec<PoiCluster> = cells
+        .into_iter()
+        .map(|((key, _, _), mut members)| {
+            members.sort_by_key(|(id, _, _)| *id);
+            let count = members.len() as f64;
+            PoiCluster {
+                key,
+                x: members.iter().map(|(_, x, _)| x).sum::<f64>() / count,
+                y: members.iter().map(|(_, _, y)| y).sum::<f64>() / count,
+                members: members.into_iter().map(|(id, _, _)| id).collect(),
+            }
+        })
+        .collect();
+
+    // HashMap order is random; keep the drawing order stable between frames
+    clusters.sort_by(|a, b| a.key.cmp(&b.key).then(a.members[0].cmp(&b.members[0])));
+    clusters
+}
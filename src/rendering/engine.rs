@@ -1,5 +1,5 @@
 use crate::rendering::{StyledMap, RenderedMap, RenderElement, ElementStyle, FeatureGeometry};
-use crate::core::geometry::Transform2D;
+use crate::core::geometry::{GeometryUtils, Transform2D, MIN_MAP_EXTENT};
 use crate::export::ExportOptions;
 use anyhow::Result;
 
@@ -44,7 +44,7 @@ impl RenderingEngine {
         };
         
         // Calculate the transformation from geographic coordinates to screen coordinates
-        let transform = self.calculate_transform(&styled_map.bounds, options.width, options.height);
+        let transform = self.calculate_transform(&styled_map.bounds, options.width, options.height, options.rotation);
         
         // Sort features by z-index for proper rendering order
         let mut sorted_features = styled_map.features.clone();
@@ -149,14 +149,19 @@ impl RenderingEngine {
         Ok(RenderedMap { elements })
     }
 
-    fn calculate_transform(&self, bounds: &crate::rendering::MapBounds, width: u32, height: u32) -> Transform2D {
-        let map_width = bounds.max_lon - bounds.min_lon;
-        let map_height = bounds.max_lat - bounds.min_lat;
+    fn calculate_transform(&self, bounds: &crate::rendering::MapBounds, width: u32, height: u32, rotation: f64) -> Transform2D {
+        // A single point or a straight vertical or horizontal way has no extent to fit
+        let (min_lon, max_lon) = GeometryUtils::with_min_extent(bounds.min_lon, bounds.max_lon, MIN_MAP_EXTENT);
+        let (min_lat, max_lat) = GeometryUtils::with_min_extent(bounds.min_lat, bounds.max_lat, MIN_MAP_EXTENT);
+        let map_width = max_lon - min_lon;
+        let map_height = max_lat - min_lat;
         
-        // Calculate scale to fit the map in the specified dimensions with padding
+        // Calculate scale to fit the rotated map in the specified dimensions with padding
         let padding = 0.1; // 10% padding
-        let scale_x = (width as f64 * (1.0 - padding)) / map_width;
-        let scale_y = (height as f64 * (1.0 - padding)) / map_height;
+        let angle = rotation.to_radians();
+        let (sin_a, cos_a) = (angle.sin().abs(), angle.cos().abs());
+        let scale_x = (width as f64 * (1.0 - padding)) / (map_width * cos_a + map_height * sin_a);
+        let scale_y = (height as f64 * (1.0 - padding)) / (map_width * sin_a + map_height * cos_a);
         let scale = scale_x.min(scale_y);
         
         // Calculate translation to center the map
@@ -166,7 +171,8 @@ impl RenderingEngine {
         let translate_x = width as f64 / 2.0 - center_x * scale;
         let translate_y = height as f64 / 2.0 - center_y * scale;
         
-        Transform2D::translation(translate_x, translate_y)
+        Transform2D::rotation_about(angle, width as f64 / 2.0, height as f64 / 2.0)
+            .compose(&Transform2D::translation(translate_x, translate_y))
             .compose(&Transform2D::scale(scale, -scale)) // Flip Y axis for screen coordinates
     }
 
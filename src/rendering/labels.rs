@@ -0,0 +1,381 @@
+use crate::core::{ElementType, MapData};
+use crate::parsers::stylesheet::TextAnchor;
+use crate::styles::loader::LineSide;
+use std::collections::{HashMap, HashSet};
+
+/// Caps how many labels land in each square cell of the output so dense areas stay readable.
+/// Candidates should be offered in priority order; later ones are refused once a cell is full.
+pub struct LabelDensityGrid {
+    cell_size: f64,
+    max_per_cell: usize,
+    counts: HashMap<(i64, i64), usize>,
+}
+
+impl LabelDensityGrid {
+    /// `max_per_cell == 0` disables the cap
+    pub fn new(cell_size: f64, max_per_cell: usize) -> Self {
+        Self {
+            cell_size: cell_size.max(1.0),
+            max_per_cell,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Reserve a slot for a label anchored at the given screen position
+    pub fn try_place(&mut self, x: f64, y: f64) -> bool {
+        if self.max_per_cell == 0 {
+            return true;
+        }
+
+        let cell = ((x / self.cell_size).floor() as i64, (y / self.cell_size).floor() as i64);
+        let count = self.counts.entry(cell).or_insert(0);
+        if *count >= self.max_per_cell {
+            return false;
+        }
+
+        *count += 1;
+        true
+    }
+}
+
+/// Label importance from a feature's tags; higher values are placed first
+pub fn label_priority(tags: &HashMap<String, String>) -> u32 {
+    if let Some(highway) = tags.get("highway") {
+        match highway.as_str() {
+            "motorway" => 100,
+            "trunk" => 90,
+            "primary" => 80,
+            "secondary" => 70,
+            "tertiary" => 60,
+            "motorway_link" | "trunk_link" | "primary_link" | "secondary_link" | "tertiary_link" => 50,
+            "residential" | "unclassified" | "living_street" => 40,
+            "pedestrian" => 30,
+            "service" => 20,
+            _ => 10,
+        }
+    } else if tags.contains_key("waterway") {
+        45
+    } else {
+        5
+    }
+}
+
+/// Label importance of a `place` value: cities are placed before towns, towns before villages
+pub fn place_rank(place: &str) -> u32 {
+    match place {
+        "country" => 100,
+        "state" | "region" | "province" => 90,
+        "city" => 80,
+        "town" => 70,
+        "suburb" | "borough" => 60,
+        "village" => 50,
+        "quarter" => 45,
+        "hamlet" => 40,
+        "neighbourhood" => 35,
+        "isolated_dwelling" | "locality" => 20,
+        _ => 10,
+    }
+}
+
+/// The name a label shows: the first `name:<lang>` tag present for `languages`, in order,
+/// otherwise the plain `name`
+pub fn display_name<'a>(tags: &'a HashMap<String, String>, languages: &[String]) -> Option<&'a String> {
+    languages.iter()
+        .find_map(|lang| tags.get(&format!("name:{}", lang)))
+        .or_else(|| tags.get("name"))
+}
+
+/// A named `place=*` feature and where its label goes
+pub struct PlaceLabel<'a> {
+    pub element_type: ElementType,
+    pub id: i64,
+    pub name: &'a str,
+    /// The `place` value, e.g. `city`, which sets the label's font size
+    pub place: &'a str,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Every named place, most important first. Nodes are labelled where they are. Ways and
+/// relations mapped as areas are labelled on their `label` member node when they have one,
+/// otherwise at the centroid of their outline, and are skipped when a place node already
+/// carries the same name. Names are picked by [`display_name`] from `languages`.
+pub fn place_labels<'a>(map_data: &'a MapData, languages: &[String]) -> Vec<PlaceLabel<'a>> {
+    let mut labels: Vec<PlaceLabel> = map_data.nodes.values()
+        .filter_map(|node| Some(PlaceLabel {
+            element_type: ElementType::Node,
+            id: node.id,
+            name: display_name(&node.tags, languages)?,
+            place: node.tags.get("place")?,
+            lat: node.lat,
+            lon: node.lon,
+        }))
+        .collect();
+    let node_names: HashSet<&str> = labels.iter().map(|label| label.name).collect();
+    let outline = |way_id: i64| -> Vec<(f64, f64)> {
+        map_data.ways.get(&way_id)
+            .filter(|_| !map_data.is_way_incomplete(way_id))
+            .map(|way| way.node_refs().filter_map(|id| map_data.nodes.get(&id)).map(|node| (node.lon, node.lat)).collect())
+            .unwrap_or_default()
+    };
+
+    for way in map_data.ways.values().filter(|way| way.is_closed) {
+        let (Some(name), Some(place)) = (display_name(&way.tags, languages), way.tags.get("place")) else {
+            continue;
+        };
+        if node_names.contains(name.as_str()) {
+            continue;
+        }
+        if let Some(((lon, lat), _)) = ring_centroid(&outline(way.id)) {
+            labels.push(PlaceLabel { element_type: ElementType::Way, id: way.id, name, place, lat, lon });
+        }
+    }
+
+    for relation in map_data.relations.values() {
+        let (Some(name), Some(place)) = (display_name(&relation.tags, languages), relation.tags.get("place")) else {
+            continue;
+        };
+        if node_names.contains(name.as_str()) {
+            continue;
+        }
+        let label_node = relation.members.iter()
+            .filter(|member| member.element_type == ElementType::Node && member.role == "label")
+            .find_map(|member| map_data.nodes.get(&member.id));
+        let position = label_node.map(|node| (node.lon, node.lat)).or_else(|| {
+            let outlines: Vec<Vec<(f64, f64)>> = relation.members.iter()
+                .filter(|member| member.element_type == ElementType::Way && (member.role == "outer" || member.role.is_empty()))
+                .map(|member| outline(member.id))
+                .collect();
+            outlines_centroid(&outlines)
+        });
+        if let Some((lon, lat)) = position {
+            labels.push(PlaceLabel { element_type: ElementType::Relation, id: relation.id, name, place, lat, lon });
+        }
+    }
+
+    labels.sort_by(|a, b| place_rank(b.place).cmp(&place_rank(a.place)).then(a.id.cmp(&b.id)));
+    labels
+}
+
+/// An `addr:housenumber` and where it is drawn
+pub struct HousenumberLabel<'a> {
+    pub element_type: ElementType,
+    pub id: i64,
+    pub text: &'a str,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Every house number: on address nodes where they are, and on buildings mapped as closed
+/// ways or multipolygons at the centroid of their outline. Nodes come first, then ways and
+/// relations, each by id, so dense blocks keep the same numbers from one render to the next.
+pub fn housenumber_labels<'a>(map_data: &'a MapData) -> Vec<HousenumberLabel<'a>> {
+    let mut labels: Vec<HousenumberLabel> = map_data.nodes.values()
+        .filter_map(|node| Some(HousenumberLabel {
+            element_type: ElementType::Node,
+            id: node.id,
+            text: node.tags.get("addr:housenumber")?,
+            lat: node.lat,
+            lon: node.lon,
+        }))
+        .collect();
+    labels.sort_by_key(|label| label.id);
+
+    let building = |element_type: ElementType, id: i64, tags: &'a HashMap<String, String>| -> Option<HousenumberLabel<'a>> {
+        let text = tags.get("addr:housenumber").filter(|_| tags.contains_key("building"))?;
+        let ring: Vec<(f64, f64)> = map_data.outline(element_type, id)?.iter().map(|coord| (coord.x, coord.y)).collect();
+        let ((lon, lat), _) = ring_centroid(&ring)?;
+        Some(HousenumberLabel { element_type, id, text, lat, lon })
+    };
+    let mut ways: Vec<HousenumberLabel> = map_data.ways.values()
+        .filter(|way| !map_data.is_way_incomplete(way.id))
+        .filter_map(|way| building(ElementType::Way, way.id, &way.tags))
+        .collect();
+    ways.sort_by_key(|label| label.id);
+    let mut relations: Vec<HousenumberLabel> = map_data.relations.values()
+        .filter_map(|relation| building(ElementType::Relation, relation.id, &relation.tags))
+        .collect();
+    relations.sort_by_key(|label| label.id);
+
+    labels.extend(ways);
+    labels.extend(relations);
+    labels
+}
+
+/// Centroid of a closed ring of `(lon, lat)` points, with the area it encloses in square
+/// degrees; `None` for rings that enclose nothing
+fn ring_centroid(ring: &[(f64, f64)]) -> Option<((f64, f64), f64)> {
+    if ring.len() < 3 {
+        return None;
+    }
+    // Relative to the first point, so large coordinates don't swamp the sums
+    let (x0, y0) = ring[0];
+    let (mut twice_area, mut cx, mut cy) = (0.0, 0.0, 0.0);
+    for (&(ax, ay), &(bx, by)) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+        let (ax, ay, bx, by) = (ax - x0, ay - y0, bx - x0, by - y0);
+        let cross = ax * by - bx * ay;
+        twice_area += cross;
+        cx += (ax + bx) * cross;
+        cy += (ay + by) * cross;
+    }
+    if twice_area.abs() < 1e-18 {
+        return None;
+    }
+    Some(((x0 + cx / (3.0 * twice_area), y0 + cy / (3.0 * twice_area)), twice_area.abs() / 2.0))
+}
+
+/// Centroid of an area made of several outer ways, weighted by the area each closed one
+/// encloses. Outlines split over several open ways fall back to the mean of their points.
+fn outlines_centroid(outlines: &[Vec<(f64, f64)>]) -> Option<(f64, f64)> {
+    let closed: Vec<((f64, f64), f64)> = outlines.iter()
+        .filter(|outline| outline.len() > 3 && outline.first() == outline.last())
+        .filter_map(|outline| ring_centroid(outline))
+        .collect();
+    let total_area: f64 = closed.iter().map(|(_, area)| area).sum();
+    if total_area > 0.0 {
+        let lon = closed.iter().map(|((lon, _), area)| lon * area).sum::<f64>() / total_area;
+        let lat = closed.iter().map(|((_, lat), area)| lat * area).sum::<f64>() / total_area;
+        return Some((lon, lat));
+    }
+
+    let points: Vec<&(f64, f64)> = outlines.iter().flatten().collect();
+    if points.is_empty() {
+        return None;
+    }
+    let count = points.len() as f64;
+    Some((points.iter().map(|(lon, _)| lon).sum::<f64>() / count, points.iter().map(|(_, lat)| lat).sum::<f64>() / count))
+}
+
+/// Anchor point of a label for a point `clearance` pixels in radius (e.g. a POI marker) at
+/// `(x, y)`: beside it on the side the text runs to, or on it when centered, then shifted by `offset`
+pub fn label_position(anchor: TextAnchor, (x, y): (f64, f64), clearance: f64, offset: (f32, f32)) -> (f64, f64) {
+    let side = match anchor {
+        TextAnchor::Start => clearance,
+        TextAnchor::Middle => 0.0,
+        TextAnchor::End => -clearance,
+    };
+    (x + side + offset.0 as f64, y + offset.1 as f64)
+}
+
+/// Average glyph width as a share of the font size, for measuring labels without a font
+const AVERAGE_CHAR_WIDTH: f64 = 0.6;
+
+/// Split a label at spaces into lines no wider than `max_width` pixels at `font_size`, going
+/// by an average glyph width. Words longer than a line stay whole; a `max_width` of 0 keeps
+/// the label on one line.
+pub fn wrap_label(text: &str, font_size: f64, max_width: f64) -> Vec<String> {
+    let width = |line: &str| line.chars().count() as f64 * font_size * AVERAGE_CHAR_WIDTH;
+    if max_width <= 0.0 || width(text) <= max_width {
+        return vec![text.to_string()];
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && width(&line) + width(" ") + width(word) > max_width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Vertical shift of each of `count` stacked lines `line_height` pixels apart, so the block is
+/// centered on the label position
+pub fn line_offsets(count: usize, line_height: f64) -> impl Iterator<Item = f64> {
+    let first = -(count.saturating_sub(1) as f64) * line_height / 2.0;
+    (0..count).map(move |i| first + i as f64 * line_height)
+}
+
+/// Text for a road shield from a `ref` tag; multiple refs (`A 4;E 50`) share one badge
+pub fn shield_text(ref_tag: &str) -> Option<String> {
+    let refs: Vec<&str> = ref_tag.split(';').map(str::trim).filter(|r| !r.is_empty()).collect();
+    if refs.is_empty() {
+        None
+    } else {
+        Some(refs.join(" / "))
+    }
+}
+
+/// Positions every `spacing` pixels along a screen-space polyline, starting half a spacing in
+/// so shields stay clear of junctions. Lines shorter than `spacing` get one position at the middle.
+pub fn points_along(points: &[(f64, f64)], spacing: f64) -> Vec<(f64, f64)> {
+    placements_along(points, spacing).into_iter().map(|(position, _)| position).collect()
+}
+
+/// Like [`points_along`], with the direction of travel at each position as a unit vector
+pub fn placements_along(points: &[(f64, f64)], spacing: f64) -> Vec<((f64, f64), (f64, f64))> {
+    let segment_lengths: Vec<f64> = points
+        .windows(2)
+        .map(|pair| ((pair[1].0 - pair[0].0).powi(2) + (pair[1].1 - pair[0].1).powi(2)).sqrt())
+        .collect();
+    let total: f64 = segment_lengths.iter().sum();
+    if total <= 0.0 || spacing <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut offsets = Vec::new();
+    if total < spacing {
+        offsets.push(total / 2.0);
+    } else {
+        let mut offset = spacing / 2.0;
+        while offset < total {
+            offsets.push(offset);
+            offset += spacing;
+        }
+    }
+
+    let mut placements = Vec::with_capacity(offsets.len());
+    let mut walked = 0.0;
+    let mut segment = 0;
+    for offset in offsets {
+        // Zero-length segments have no direction, so they are never picked
+        while segment < segment_lengths.len() && (walked + segment_lengths[segment] < offset || segment_lengths[segment] <= 0.0) {
+            walked += segment_lengths[segment];
+            segment += 1;
+        }
+        let Some(&length) = segment_lengths.get(segment) else {
+            break;
+        };
+        let t = (offset - walked) / length;
+        let (start, end) = (points[segment], points[segment + 1]);
+        let direction = ((end.0 - start.0) / length, (end.1 - start.1) / length);
+        placements.push(((start.0 + (end.0 - start.0) * t, start.1 + (end.1 - start.1) * t), direction));
+    }
+    placements
+}
+
+/// Open arrow heads (`>`) every `spacing` pixels along a polyline, pointing the way its nodes
+/// run; each is `size` pixels long and wide. Used to show the direction of roundabouts.
+pub fn direction_chevrons(points: &[(f64, f64)], spacing: f64, size: f64) -> Vec<[(f64, f64); 3]> {
+    let half = size / 2.0;
+    placements_along(points, spacing)
+        .into_iter()
+        .map(|((x, y), (dx, dy))| {
+            let tip = (x + dx * half, y + dy * half);
+            let back = (x - dx * half, y - dy * half);
+            [(back.0 - dy * half, back.1 + dx * half), tip, (back.0 + dy * half, back.1 - dx * half)]
+        })
+        .collect()
+}
+
+/// Strokes `length` pixels long square to a polyline every `spacing` pixels, starting on the
+/// line and pointing to `side` of it (or across it for both sides). Used for cliffs and walls.
+pub fn perpendicular_ticks(points: &[(f64, f64)], spacing: f64, length: f64, side: LineSide) -> Vec<[(f64, f64); 2]> {
+    // In screen coordinates (y down) the right of a direction (dx, dy) is (-dy, dx)
+    let (from, to) = match side {
+        LineSide::Left => (0.0, -length),
+        LineSide::Right => (0.0, length),
+        LineSide::Both => (-length / 2.0, length / 2.0),
+    };
+    placements_along(points, spacing)
+        .into_iter()
+        .map(|((x, y), (dx, dy))| [(x - dy * from, y + dx * from), (x - dy * to, y + dx * to)])
+        .collect()
+}
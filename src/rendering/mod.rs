@@ -1,127 +1,177 @@
+pub mod appearance;
+pub mod carriageways;
+pub mod clustering;
 pub mod engine;
+pub mod labels;
+pub mod north_arrow;
+pub mod patterns;
+pub mod road_nodes;
+pub mod stats;
+pub mod tessellation;
 
-use crate::core::geometry::Transform2D;
+use crate::core::geometry::{GeometryUtils, Transform2D, MIN_MAP_EXTENT};
+use crate::core::projection::project;
+use crate::core::ProjectionSystem;
 use crate::export::ExportOptions;
-use crate::parsers::stylesheet::{Color, RenderStyle};
+use crate::parsers::stylesheet::{Color, FillPattern, LineCap, LineJoin, RenderStyle, TextAnchor};
 use anyhow::Result;
 use geo_types::Coord;
+use rayon::prelude::*;
 
-/// Main map renderer
+/// Main map renderer, the last step of the stylesheet pipeline:
+///
+/// 1. [`crate::styles::StyleManager::apply_styles`] matches the active stylesheet against the
+///    map data and gives a [`StyledMap`] in geographic coordinates, sorted by z-index.
+/// 2. The styled map can be edited freely: drop features from `features`, or add your own
+///    with [`StyledMap::push_feature`].
+/// 3. [`MapRenderer::render`] projects it with the renderer's [`ProjectionSystem`] and fits it
+///    to the output size, or [`MapRenderer::render_with_transform`] places it where you choose.
 pub struct MapRenderer {
     transform: Transform2D,
+    projection: ProjectionSystem,
 }
 
 impl MapRenderer {
     pub fn new() -> Self {
         Self {
             transform: Transform2D::identity(),
+            projection: ProjectionSystem::default(),
         }
     }
     
+    /// Project with `projection` instead of plain longitude and latitude
+    pub fn with_projection(mut self, projection: ProjectionSystem) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Render with the map's bounds fitted to the output size, rotated by `options.rotation`
     pub fn render(&self, styled_map: &StyledMap, options: &ExportOptions) -> Result<RenderedMap> {
-        let mut elements = Vec::new();
-        
         // Calculate the transformation from geographic coordinates to screen coordinates
-        let transform = self.calculate_transform(&styled_map.bounds, options.width, options.height);
-        
-        // Render features in proper order (fills first, then lines, then points, then text)
-        for feature in &styled_map.features {
-            match &feature.geometry {
-                FeatureGeometry::Point(coord) => {
-                    let screen_pos = transform.transform_point(coord);
-                    elements.push(RenderElement::Circle {
-                        center: (screen_pos.x, screen_pos.y),
-                        radius: feature.style.point_radius().unwrap_or(3.0) as f64,
-                        style: ElementStyle::from_render_style(&feature.style),
-                    });
-                }
-                FeatureGeometry::LineString(coords) => {
-                    let screen_coords: Vec<(f64, f64)> = coords
-                        .iter()
-                        .map(|coord| {
-                            let screen_pos = transform.transform_point(coord);
-                            (screen_pos.x, screen_pos.y)
-                        })
-                        .collect();
-                    
-                    elements.push(RenderElement::Line {
-                        points: screen_coords,
-                        style: ElementStyle::from_render_style(&feature.style),
-                    });
-                }
-                FeatureGeometry::Polygon { exterior, holes } => {
-                    let screen_exterior: Vec<(f64, f64)> = exterior
-                        .iter()
-                        .map(|coord| {
-                            let screen_pos = transform.transform_point(coord);
-                            (screen_pos.x, screen_pos.y)
-                        })
-                        .collect();
-                    
-                    let screen_holes: Vec<Vec<(f64, f64)>> = holes
+        let transform = self.calculate_transform(&styled_map.bounds, options.width, options.height, options.rotation);
+        Ok(self.render_with_transform(styled_map, &transform))
+    }
+
+    /// Render with an explicit transform from projected coordinates (with the default LatLon
+    /// projection, x = longitude and y = latitude) to output pixels, e.g. to place the map in
+    /// a larger drawing
+    pub fn render_with_transform(&self, styled_map: &StyledMap, transform: &Transform2D) -> RenderedMap {
+        // Features arrive sorted by z-index; transform them in parallel and collect in the same order
+        let elements = styled_map.features
+            .par_iter()
+            .flat_map_iter(|feature| Self::render_feature(feature, self.projection, transform))
+            .collect();
+
+        RenderedMap { elements }
+    }
+
+    /// Project a single styled feature to screen space, plus its text label if it has one
+    fn render_feature(feature: &StyledFeature, projection: ProjectionSystem, transform: &Transform2D) -> Vec<RenderElement> {
+        let mut elements = Vec::with_capacity(2);
+        let to_screen = |coord: &Coord<f64>| {
+            let (x, y) = project(projection, coord.y, coord.x);
+            let screen_pos = transform.transform_point(&Coord { x, y });
+            (screen_pos.x, screen_pos.y)
+        };
+
+        match &feature.geometry {
+            FeatureGeometry::Point(coord) => {
+                elements.push(RenderElement::Circle {
+                    center: to_screen(coord),
+                    radius: feature.style.point_radius().unwrap_or(3.0) as f64,
+                    style: ElementStyle::from_render_style(&feature.style),
+                });
+            }
+            FeatureGeometry::LineString(coords) => {
+                elements.push(RenderElement::Line {
+                    points: coords.iter().map(to_screen).collect(),
+                    style: ElementStyle::from_render_style(&feature.style),
+                });
+            }
+            FeatureGeometry::Polygon { exterior, holes } => {
+                elements.push(RenderElement::Polygon {
+                    exterior: exterior.iter().map(to_screen).collect(),
+                    holes: holes
                         .iter()
-                        .map(|hole| {
-                            hole.iter()
-                                .map(|coord| {
-                                    let screen_pos = transform.transform_point(coord);
-                                    (screen_pos.x, screen_pos.y)
-                                })
-                                .collect()
-                        })
-                        .collect();
-                    
-                    elements.push(RenderElement::Polygon {
-                        exterior: screen_exterior,
-                        holes: screen_holes,
-                        style: ElementStyle::from_render_style(&feature.style),
-                    });
-                }
+                        .map(|hole| hole.iter().map(to_screen).collect())
+                        .collect(),
+                    style: ElementStyle::from_render_style(&feature.style),
+                });
             }
-            
-            // Add text label if specified
-            if let Some(ref text) = feature.text {
-                if let Some(center) = feature.geometry.center() {
-                    let screen_pos = transform.transform_point(&center);
-                    elements.push(RenderElement::Text {
-                        position: (screen_pos.x, screen_pos.y),
-                        text: text.clone(),
-                        style: ElementStyle::from_render_style(&feature.style),
-                    });
-                }
+        }
+
+        // Add text label if specified
+        if let Some(ref text) = feature.text {
+            if let Some(center) = feature.geometry.center() {
+                let style = ElementStyle::from_render_style(&feature.style);
+                elements.push(RenderElement::Text {
+                    position: labels::label_position(style.text_anchor, to_screen(&center), 0.0, style.text_offset),
+                    text: text.clone(),
+                    style,
+                });
             }
         }
         
-        Ok(RenderedMap { elements })
+        elements
     }
     
-    fn calculate_transform(&self, bounds: &MapBounds, width: u32, height: u32) -> Transform2D {
-        let map_width = bounds.max_lon - bounds.min_lon;
-        let map_height = bounds.max_lat - bounds.min_lat;
+    fn calculate_transform(&self, bounds: &MapBounds, width: u32, height: u32, rotation: f64) -> Transform2D {
+        // A single point or a straight vertical or horizontal way has no extent to fit
+        let (min_lon, max_lon) = GeometryUtils::with_min_extent(bounds.min_lon, bounds.max_lon, MIN_MAP_EXTENT);
+        let (min_lat, max_lat) = GeometryUtils::with_min_extent(bounds.min_lat, bounds.max_lat, MIN_MAP_EXTENT);
+
+        // Fit the projected corners; UTM bends the edges of the box slightly
+        let corners = [(min_lat, min_lon), (min_lat, max_lon), (max_lat, min_lon), (max_lat, max_lon)]
+            .map(|(lat, lon)| project(self.projection, lat, lon));
+        let (min_x, max_x) = corners.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &(x, _)| (lo.min(x), hi.max(x)));
+        let (min_y, max_y) = corners.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &(_, y)| (lo.min(y), hi.max(y)));
+        let map_width = max_x - min_x;
+        let map_height = max_y - min_y;
         
-        // Calculate scale to fit the map in the specified dimensions
-        let scale_x = width as f64 / map_width;
-        let scale_y = height as f64 / map_height;
+        // Calculate scale to fit the map, rotated by the bearing, in the specified dimensions
+        let angle = rotation.to_radians();
+        let (sin_a, cos_a) = (angle.sin().abs(), angle.cos().abs());
+        let scale_x = width as f64 / (map_width * cos_a + map_height * sin_a);
+        let scale_y = height as f64 / (map_width * sin_a + map_height * cos_a);
         let scale = scale_x.min(scale_y);
         
         // Calculate translation to center the map
-        let center_x = (bounds.min_lon + bounds.max_lon) / 2.0;
-        let center_y = (bounds.min_lat + bounds.max_lat) / 2.0;
+        let center_x = (min_x + max_x) / 2.0;
+        let center_y = (min_y + max_y) / 2.0;
         
         let translate_x = width as f64 / 2.0 - center_x * scale;
         let translate_y = height as f64 / 2.0 - center_y * scale;
         
-        Transform2D::translation(translate_x, translate_y)
+        Transform2D::rotation_about(angle, width as f64 / 2.0, height as f64 / 2.0)
+            .compose(&Transform2D::translation(translate_x, translate_y))
             .compose(&Transform2D::scale(scale, -scale)) // Flip Y axis for screen coordinates
     }
 }
 
-/// Map with applied styling
+/// Map with applied styling. `features` are kept sorted by z-index, which is the order
+/// they are drawn in; [`StyledMap::push_feature`] keeps it that way.
 #[derive(Debug, Clone)]
 pub struct StyledMap {
     pub features: Vec<StyledFeature>,
     pub bounds: MapBounds,
 }
 
+impl StyledMap {
+    /// An empty map covering `bounds`
+    pub fn new(bounds: MapBounds) -> Self {
+        Self {
+            features: Vec::new(),
+            bounds,
+        }
+    }
+
+    /// Add a feature, drawn over the features with the same or a lower z-index
+    pub fn push_feature(&mut self, feature: StyledFeature) {
+        let index = self.features.partition_point(|existing| existing.z_index <= feature.z_index);
+        self.features.insert(index, feature);
+    }
+}
+
 /// Individual feature with applied style
 #[derive(Debug, Clone)]
 pub struct StyledFeature {
@@ -131,6 +181,28 @@ pub struct StyledFeature {
     pub z_index: i32,
 }
 
+impl StyledFeature {
+    /// A feature without a label, at z-index 0
+    pub fn new(geometry: FeatureGeometry, style: RenderStyle) -> Self {
+        Self {
+            geometry,
+            style,
+            text: None,
+            z_index: 0,
+        }
+    }
+
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+}
+
 /// Simplified geometry types for rendering
 #[derive(Debug, Clone)]
 pub enum FeatureGeometry {
@@ -222,10 +294,15 @@ pub struct ElementStyle {
     pub stroke_opacity: f32,
     pub fill_opacity: f32,
     pub stroke_dash: Vec<f32>,
+    pub line_cap: LineCap,
+    pub line_join: LineJoin,
     pub font_family: Option<String>,
     pub font_size: f32,
     pub font_weight: u32,
     pub point_radius: Option<f32>,
+    pub fill_pattern: Option<FillPattern>,
+    pub text_anchor: TextAnchor,
+    pub text_offset: (f32, f32),
 }
 
 impl ElementStyle {
@@ -237,10 +314,15 @@ impl ElementStyle {
             stroke_opacity: 1.0,
             fill_opacity: 1.0,
             stroke_dash: Vec::new(),
+            line_cap: style.line_cap,
+            line_join: style.line_join,
             font_family: style.font_family.clone(),
             font_size: style.font_size,
             font_weight: 400,
             point_radius: None,
+            fill_pattern: style.fill_pattern,
+            text_anchor: style.text_anchor,
+            text_offset: style.text_offset,
         }
     }
 }
@@ -0,0 +1,50 @@
+use crate::styles::loader::{NorthArrowStyle, OverlayPosition};
+
+/// Screen-space geometry of the north arrow, shared by the interactive view and the SVG export
+#[derive(Debug, Clone)]
+pub struct NorthArrowShape {
+    /// Filled half of the arrow head
+    pub dark_half: [(f64, f64); 3],
+    /// Outlined half of the arrow head
+    pub light_half: [(f64, f64); 3],
+    /// Center of the "N" label, just beyond the tip
+    pub label_pos: (f64, f64),
+    pub label_size: f64,
+}
+
+impl NorthArrowShape {
+    /// Lay out the arrow in its corner of a `width` x `height` canvas, pointing north for a
+    /// map turned clockwise by `rotation` degrees. The label stays upright.
+    pub fn new(style: &NorthArrowStyle, width: f64, height: f64, rotation: f64) -> Self {
+        let size = style.size as f64;
+        let label_size = size * 0.35;
+        // Reserve room for the label on every side so it never leaves the canvas while rotating
+        let radius = size / 2.0 + label_size;
+        let inset = style.margin as f64 + radius;
+
+        let center_x = match style.position {
+            OverlayPosition::TopLeft | OverlayPosition::BottomLeft => inset,
+            OverlayPosition::TopRight | OverlayPosition::BottomRight => width - inset,
+        };
+        let center_y = match style.position {
+            OverlayPosition::TopLeft | OverlayPosition::TopRight => inset,
+            OverlayPosition::BottomLeft | OverlayPosition::BottomRight => height - inset,
+        };
+
+        let (sin_a, cos_a) = rotation.to_radians().sin_cos();
+        let place = |x: f64, y: f64| {
+            (center_x + x * cos_a - y * sin_a, center_y + x * sin_a + y * cos_a)
+        };
+
+        let half = size / 2.0;
+        let tip = place(0.0, -half);
+        let notch = place(0.0, half * 0.5);
+
+        Self {
+            dark_half: [tip, place(-half * 0.6, half), notch],
+            light_half: [tip, notch, place(half * 0.6, half)],
+            label_pos: place(0.0, -half - label_size * 0.7),
+            label_size,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::parsers::stylesheet::{FillPattern, PatternKind};
+
+/// Hatch directions in screen space (y down), matching the SVG pattern's `rotate(45)` of a
+/// vertical line (and, for cross-hatching, a horizontal one)
+const HATCH_ANGLE: f32 = 135.0;
+const CROSS_HATCH_ANGLE: f32 = 45.0;
+
+/// Beyond this many lines or dots the pattern would be invisible noise; skip it
+const MAX_PATTERN_MARKS: usize = 20_000;
+
+/// Stable identifier for an SVG `<pattern>` def, so identical patterns share one def
+pub fn pattern_id(pattern: &FillPattern) -> String {
+    let color = pattern.color;
+    format!(
+        "pattern-{}-{}-{}-{:02x}{:02x}{:02x}{:02x}",
+        pattern.kind.name(),
+        (pattern.spacing * 100.0).round() as i64,
+        (pattern.line_width * 100.0).round() as i64,
+        color.r,
+        color.g,
+        color.b,
+        color.a
+    )
+}
+
+/// Line segments to stroke for a hatch pattern clipped to `ring`, in the ring's coordinates
+pub fn hatch_segments(pattern: &FillPattern, ring: &[(f32, f32)]) -> Vec<[(f32, f32); 2]> {
+    match pattern.kind {
+        PatternKind::Hatch => clipped_lines(ring, HATCH_ANGLE, pattern.spacing),
+        PatternKind::CrossHatch => {
+            let mut segments = clipped_lines(ring, HATCH_ANGLE, pattern.spacing);
+            segments.extend(clipped_lines(ring, CROSS_HATCH_ANGLE, pattern.spacing));
+            segments
+        }
+        PatternKind::Dots => Vec::new(),
+    }
+}
+
+/// Centers of the dots of a dot pattern that fall inside `ring`, on the same grid as the SVG def
+pub fn dot_centers(pattern: &FillPattern, ring: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let spacing = pattern.spacing;
+    if pattern.kind != PatternKind::Dots || spacing <= 0.0 || ring.len() < 3 {
+        return Vec::new();
+    }
+
+    let (min_x, min_y, max_x, max_y) = ring.iter().fold(
+        (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        |(x0, y0, x1, y1), &(x, y)| (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+    );
+    let (first_col, last_col) = ((min_x / spacing - 0.5).floor() as i64, (max_x / spacing - 0.5).ceil() as i64);
+    let (first_row, last_row) = ((min_y / spacing - 0.5).floor() as i64, (max_y / spacing - 0.5).ceil() as i64);
+    if ((last_col - first_col + 1) * (last_row - first_row + 1)) as usize > MAX_PATTERN_MARKS {
+        return Vec::new();
+    }
+
+    let mut centers = Vec::new();
+    for row in first_row..=last_row {
+        for col in first_col..=last_col {
+            let center = ((col as f32 + 0.5) * spacing, (row as f32 + 0.5) * spacing);
+            if contains_point(ring, center) {
+                centers.push(center);
+            }
+        }
+    }
+    centers
+}
+
+/// Parallel lines at `angle_deg`, `spacing` apart and offset half a step from the origin like
+/// the SVG tile, cut to the inside of `ring` with the even-odd rule
+fn clipped_lines(ring: &[(f32, f32)], angle_deg: f32, spacing: f32) -> Vec<[(f32, f32); 2]> {
+    if spacing <= 0.0 || ring.len() < 3 {
+        return Vec::new();
+    }
+
+    let (sin_a, cos_a) = angle_deg.to_radians().sin_cos();
+    let along = |(x, y): (f32, f32)| x * cos_a + y * sin_a;
+    let across = |(x, y): (f32, f32)| -x * sin_a + y * cos_a;
+
+    let (min_offset, max_offset) = ring.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &p| {
+        (lo.min(across(p)), hi.max(across(p)))
+    });
+    let first = (min_offset / spacing - 0.5).ceil() as i64;
+    let last = (max_offset / spacing - 0.5).floor() as i64;
+    if last < first || (last - first) as usize > MAX_PATTERN_MARKS {
+        return Vec::new();
+    }
+
+    let mut segments = Vec::new();
+    let mut crossings: Vec<f32> = Vec::new();
+    for k in first..=last {
+        let offset = (k as f32 + 0.5) * spacing;
+        crossings.clear();
+
+        for i in 0..ring.len() {
+            let a = ring[i];
+            let b = ring[(i + 1) % ring.len()];
+            let (da, db) = (across(a) - offset, across(b) - offset);
+            // Half-open test so a vertex on the line is counted once
+            if (da <= 0.0) != (db <= 0.0) {
+                let t = da / (da - db);
+                crossings.push(along(a) + (along(b) - along(a)) * t);
+            }
+        }
+
+        crossings.sort_by(|a, b| a.total_cmp(b));
+        for pair in crossings.chunks_exact(2) {
+            let point = |u: f32| (u * cos_a - offset * sin_a, u * sin_a + offset * cos_a);
+            segments.push([point(pair[0]), point(pair[1])]);
+        }
+    }
+    segments
+}
+
+/// Even-odd point-in-polygon test
+fn contains_point(ring: &[(f32, f32)], (x, y): (f32, f32)) -> bool {
+    let mut inside = false;
+    for i in 0..ring.len() {
+        let (x1, y1) = ring[i];
+        let (x2, y2) = ring[(i + 1) % ring.len()];
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) / (y2 - y1) * (x2 - x1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
@@ -0,0 +1,94 @@
+use crate::core::MapData;
+use crate::rendering::appearance::{road_class, way_appearance, WayPass};
+use crate::styles::loader::{MapStyle, RoadNodeShape, RoadNodeSymbol};
+use std::collections::HashMap;
+
+/// A `[road_nodes]` symbol placed on its road, in style units (pixels at the baseline DPI)
+#[derive(Debug, Clone)]
+pub struct RoadNodeMark {
+    pub node_id: i64,
+    /// The widest road through the node, which the symbol is sized to
+    pub road_id: i64,
+    pub lat: f64,
+    pub lon: f64,
+    pub shape: RoadNodeShape,
+    /// Width across the symbol
+    pub size: f32,
+    /// `None` for rings
+    pub fill: Option<String>,
+    /// Colour and width of the outline, if there is one
+    pub outline: Option<(String, f32)>,
+}
+
+/// Widest road drawn through a node: its full width, fill colour and casing colour
+struct Road {
+    id: i64,
+    width: f32,
+    fill: String,
+    casing: Option<String>,
+}
+
+/// The road node symbols drawn at `scale`, in road drawing order so each lands on top of its
+/// road. Nodes on no drawn road have nothing to be sized to and are left out.
+pub fn road_node_marks(style: &MapStyle, map_data: &MapData, scale: f64, pixels_per_meter: f64) -> Vec<RoadNodeMark> {
+    let symbols: HashMap<i64, &RoadNodeSymbol> = map_data.nodes.values()
+        .filter_map(|node| Some((node.id, style.road_nodes.symbol_at(&node.tags, scale)?)))
+        .collect();
+    if symbols.is_empty() {
+        return Vec::new();
+    }
+
+    let mut roads: HashMap<i64, Road> = HashMap::new();
+    for way in map_data.ways.values() {
+        let Some((highway, _)) = road_class(way) else {
+            continue;
+        };
+        if !style.highway_visible_at(highway, scale) || !way.node_refs().any(|id| symbols.contains_key(&id)) {
+            continue;
+        }
+        let Some(look) = way_appearance(style, map_data, way, WayPass::Road, scale).map(|look| look.with_tagged_width(style, way, pixels_per_meter)) else {
+            continue;
+        };
+        let Some(stroke) = &look.stroke else {
+            continue;
+        };
+        let width = look.casing.as_ref().map_or(stroke.width, |casing| casing.width);
+        for id in way.node_refs().filter(|id| symbols.contains_key(id)) {
+            if roads.get(&id).is_none_or(|road| width > road.width || (width == road.width && way.id < road.id)) {
+                roads.insert(id, Road {
+                    id: way.id,
+                    width,
+                    fill: stroke.color.clone(),
+                    casing: look.casing.as_ref().map(|casing| casing.color.clone()),
+                });
+            }
+        }
+    }
+
+    let mut marks: Vec<RoadNodeMark> = roads.into_iter()
+        .filter_map(|(node_id, road)| {
+            let node = map_data.nodes.get(&node_id)?;
+            let symbol = symbols[&node_id];
+            let outline = symbol.outline_color.clone().or(road.casing)
+                .filter(|_| symbol.outline_width > 0.0)
+                .map(|color| (color, symbol.outline_width));
+            let fill = match symbol.shape {
+                RoadNodeShape::Ring if outline.is_none() => return None,
+                RoadNodeShape::Ring => None,
+                RoadNodeShape::Disc | RoadNodeShape::Square => Some(symbol.color.clone().unwrap_or(road.fill)),
+            };
+            Some(RoadNodeMark {
+                node_id,
+                road_id: road.id,
+                lat: node.lat,
+                lon: node.lon,
+                shape: symbol.shape,
+                size: road.width * symbol.size,
+                fill,
+                outline,
+            })
+        })
+        .collect();
+    marks.sort_by_key(|mark| (map_data.ways.get(&mark.road_id).map_or(0, |way| way.layer()), mark.node_id));
+    marks
+}
@@ -0,0 +1,35 @@
+use std::fmt;
+
+/// Running tally for one category of features while drawing: how many were
+/// considered, how many were culled before drawing and how many were drawn
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FeatureCounter {
+    pub total: usize,
+    pub filtered: usize,
+    pub rendered: usize,
+}
+
+impl FeatureCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn count(&mut self) {
+        self.total += 1;
+    }
+    
+    pub fn filter(&mut self) {
+        self.filtered += 1;
+    }
+    
+    pub fn render(&mut self) {
+        self.rendered += 1;
+    }
+
+}
+
+impl fmt::Display for FeatureCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Total: {}, Filtered: {}, Rendered: {}", self.total, self.filtered, self.rendered)
+    }
+}
@@ -0,0 +1,61 @@
+use lyon_tessellation::math::point;
+use lyon_tessellation::path::Path;
+use lyon_tessellation::{BuffersBuilder, FillOptions, FillRule, FillTessellator, FillVertex, VertexBuffers};
+
+/// Triangle mesh for a filled polygon, in the same coordinate space as the input ring
+#[derive(Debug, Clone, Default)]
+pub struct Triangles {
+    pub vertices: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+/// Triangulate an arbitrary (concave or self-intersecting) ring with the even-odd rule,
+/// matching `fill-rule="evenodd"` in the SVG export. The ring may be open or closed.
+/// Returns `None` for degenerate rings with fewer than 3 distinct points.
+pub fn tessellate_polygon(ring: &[(f32, f32)]) -> Option<Triangles> {
+    let mut points: Vec<(f32, f32)> = Vec::with_capacity(ring.len());
+    for &(x, y) in ring {
+        if !x.is_finite() || !y.is_finite() {
+            continue;
+        }
+        if points.last() != Some(&(x, y)) {
+            points.push((x, y));
+        }
+    }
+    // A closed ring repeats its first point at the end
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if points.len() < 3 {
+        return None;
+    }
+
+    let mut builder = Path::builder();
+    builder.begin(point(points[0].0, points[0].1));
+    for &(x, y) in &points[1..] {
+        builder.line_to(point(x, y));
+    }
+    builder.end(true);
+    let path = builder.build();
+
+    let mut buffers: VertexBuffers<[f32; 2], u32> = VertexBuffers::new();
+    let options = FillOptions::default().with_fill_rule(FillRule::EvenOdd);
+    let result = FillTessellator::new().tessellate_path(
+        &path,
+        &options,
+        &mut BuffersBuilder::new(&mut buffers, |vertex: FillVertex| vertex.position().to_array()),
+    );
+
+    if let Err(e) = result {
+        log::debug!("Skipping polygon that failed to tessellate: {:?}", e);
+        return None;
+    }
+    if buffers.indices.is_empty() {
+        return None;
+    }
+
+    Some(Triangles {
+        vertices: buffers.vertices,
+        indices: buffers.indices,
+    })
+}
@@ -1,15 +1,89 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use anyhow::Result;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use super::StyleError;
+use crate::parsers::mapbox_gl::MapboxGlParser;
+use crate::parsers::stylesheet::{Color, ColorTransform, DrawMode, FeatureSelector, FillPattern, LineCap, LineJoin, PatternKind, RenderStyle, TagCondition, TextAnchor};
+use notify::{RecursiveMode, Watcher};
 
+/// Quiet period after the last change event before a watched style is reloaded
+const STYLE_RELOAD_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Callback invoked from the watcher thread after a style was reloaded (e.g. to request a repaint)
+pub type ReloadNotifier = Box<dyn Fn() + Send>;
+
+/// Directory scanned for style files, relative to the working directory
+const STYLES_DIR: &str = "assets/styles";
+
+/// Bundled style used when no other is chosen, or when the chosen one can't be loaded
+pub const DEFAULT_STYLE: &str = "google-maps";
+
+/// The bundled styles, compiled into the binary so they work wherever it is run from.
+/// A file of the same name in `assets/styles` takes precedence.
+const EMBEDDED_STYLES: &[(&str, &str)] = &[
+    ("colorblind", include_str!("../../assets/styles/colorblind.toml")),
+    ("google-maps", include_str!("../../assets/styles/google-maps.toml")),
+    ("modern-clean", include_str!("../../assets/styles/modern-clean.toml")),
+    ("modern-light", include_str!("../../assets/styles/modern-light.toml")),
+    ("osm-default", include_str!("../../assets/styles/osm-default.toml")),
+];
+
+/// Where a named style is read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StyleSource {
+    Embedded(&'static str),
+    File(PathBuf),
+}
+
+impl StyleSource {
+    /// The style's TOML text
+    pub fn read(&self) -> Result<String, StyleError> {
+        match self {
+            Self::Embedded(content) => Ok(content.to_string()),
+            Self::File(path) => Ok(std::fs::read_to_string(path)?),
+        }
+    }
+    
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Embedded(_) => None,
+            Self::File(path) => Some(path),
+        }
+    }
+    
+    /// Modification time and size of a style file, which change whenever it is saved
+    fn file_signature(&self) -> Option<(SystemTime, u64)> {
+        let metadata = std::fs::metadata(self.path()?).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+}
+
+/// A map style read from TOML. Sections a file leaves out are taken from its `base` style,
+/// see [`MapStyle::parse_checked`], so a file only needs the sections it changes.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MapStyle {
+    /// Bundled style that sections missing from this file are taken from, e.g. `"osm-default"`;
+    /// the default style when not set
+    #[serde(default)]
+    pub base: Option<String>,
     pub background: BackgroundStyle,
     pub water: WaterStyle,
     pub landuse: HashMap<String, String>,
+    /// Hatching or dots over the flat colour of some landuse types, e.g. military zones
+    #[serde(default)]
+    pub landuse_patterns: HashMap<String, PatternStyle>,
     pub leisure: HashMap<String, String>,
     pub natural: HashMap<String, String>,
+    /// Fill of amenity areas such as parking lots; used when no landuse, leisure or natural colour applies
+    #[serde(default)]
+    pub amenity: HashMap<String, String>,
+    /// Outlines of landuse, leisure, natural and amenity areas, keyed like `leisure_park`;
+    /// a bare key like `landuse` applies to every value without its own entry
+    #[serde(default)]
+    pub area_outlines: HashMap<String, AreaOutlineStyle>,
     pub aeroway: AerowayStyle,
     pub buildings: BuildingStyle,
     pub roads: HashMap<String, RoadStyle>,
@@ -19,6 +93,43 @@ pub struct MapStyle {
     pub labels: LabelStyle,
     pub road_label_fonts: HashMap<String, u32>,
     pub place_label_fonts: HashMap<String, u32>,
+    #[serde(default)]
+    pub icons: IconStyle,
+    #[serde(default)]
+    pub north_arrow: NorthArrowStyle,
+    #[serde(default)]
+    pub shields: ShieldStyle,
+    #[serde(default)]
+    pub lod: LodStyle,
+    #[serde(default)]
+    pub poi_clusters: ClusterStyle,
+    #[serde(default)]
+    pub layers: LayerStyle,
+    #[serde(default)]
+    pub roundabouts: RoundaboutStyle,
+    #[serde(default)]
+    pub osm_colour: OsmColourStyle,
+    #[serde(default)]
+    pub road_casings: RoadCasingStyle,
+    #[serde(default)]
+    pub road_widths: RoadWidthStyle,
+    #[serde(default)]
+    pub restricted_access: AccessStyle,
+    #[serde(default)]
+    pub dual_carriageways: DualCarriagewayStyle,
+    #[serde(default)]
+    pub bridges: BridgeStyle,
+    #[serde(default)]
+    pub strokes: StrokeStyle,
+    /// Symbols for mini roundabouts, turning circles and other road features mapped as nodes
+    #[serde(default)]
+    pub road_nodes: RoadNodeStyle,
+    /// `addr:housenumber` labels on buildings and address nodes, shown only when zoomed far in
+    #[serde(default)]
+    pub housenumbers: HousenumberStyle,
+    /// Lines with repeated marks along them, keyed like `natural_cliff` or `barrier_hedge`
+    #[serde(default)]
+    pub decorated_lines: HashMap<String, DecoratedLineStyle>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -29,7 +140,90 @@ pub struct BackgroundStyle {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WaterStyle {
     pub color: String,
+    /// Applies to the outline of water areas as well as their fill
     pub opacity: f32,
+    /// Outline water areas when zoomed in; below `outline_min_scale` they are fill only
+    #[serde(default = "WaterStyle::default_outline")]
+    pub outline: bool,
+    /// Outline colour, by default the water colour darkened
+    #[serde(default)]
+    pub outline_color: Option<String>,
+    /// Outline width in pixels once zoomed in to `outline_full_scale`
+    #[serde(default = "WaterStyle::default_outline_width")]
+    pub outline_width: f32,
+    /// The outline appears from this scale (pixels per degree) up, thin at first
+    #[serde(default = "WaterStyle::default_outline_min_scale")]
+    pub outline_min_scale: f64,
+    /// The outline has its full width from this scale up
+    #[serde(default = "WaterStyle::default_outline_full_scale")]
+    pub outline_full_scale: f64,
+    /// Width in pixels of coastlines and of linear waterways missing from `waterway_widths`
+    #[serde(default = "WaterStyle::default_line_width")]
+    pub line_width: f32,
+    /// Width in pixels of waterway centerlines by `waterway` value, e.g. `river = 4.0`
+    #[serde(default = "WaterStyle::default_waterway_widths")]
+    pub waterway_widths: HashMap<String, f32>,
+}
+
+impl WaterStyle {
+    /// How much darker than the fill the default outline is, per RGB channel
+    const OUTLINE_DARKENING: u8 = 30;
+    
+    fn default_outline() -> bool {
+        true
+    }
+    
+    fn default_outline_width() -> f32 {
+        1.0
+    }
+    
+    fn default_outline_min_scale() -> f64 {
+        20000.0
+    }
+    
+    fn default_outline_full_scale() -> f64 {
+        80000.0
+    }
+    
+    fn default_line_width() -> f32 {
+        2.0
+    }
+    
+    fn default_waterway_widths() -> HashMap<String, f32> {
+        [("river", 4.0), ("canal", 3.0), ("stream", 1.5), ("brook", 1.5), ("drain", 1.0), ("ditch", 1.0)]
+            .into_iter()
+            .map(|(waterway, width)| (waterway.to_string(), width))
+            .collect()
+    }
+    
+    /// Stroke width of a linear waterway, by its `waterway` value
+    pub fn waterway_width(&self, waterway: &str) -> f32 {
+        self.waterway_widths.get(waterway).copied().unwrap_or(self.line_width)
+    }
+    
+    /// Width of the outline of water areas at the given scale, `None` where they are fill only.
+    /// The width grows from nothing at `outline_min_scale` so the edge does not pop in.
+    pub fn outline_width_at(&self, scale: f64) -> Option<f32> {
+        if !self.outline || self.outline_width <= 0.0 || scale < self.outline_min_scale {
+            return None;
+        }
+        let ramp = self.outline_full_scale - self.outline_min_scale;
+        let fraction = if ramp > 0.0 { ((scale - self.outline_min_scale) / ramp).min(1.0) } else { 1.0 };
+        Some(self.outline_width * fraction as f32).filter(|&width| width > 0.0)
+    }
+    
+    pub fn outline_color(&self) -> String {
+        if let Some(color) = &self.outline_color {
+            return color.clone();
+        }
+        match crate::parsers::stylesheet::Color::from_hex(&self.color) {
+            Some(fill) => {
+                let darken = |channel: u8| channel.saturating_sub(Self::OUTLINE_DARKENING);
+                format!("#{:02X}{:02X}{:02X}", darken(fill.r), darken(fill.g), darken(fill.b))
+            }
+            None => self.color.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -42,14 +236,85 @@ pub struct BuildingStyle {
     pub fill: String,
     pub stroke: String,
     pub stroke_width: f32,
+    /// Drop outline vertices within `simplify_tolerance` pixels of a straight wall
+    #[serde(default)]
+    pub simplify: bool,
+    #[serde(default = "BuildingStyle::default_simplify_tolerance")]
+    pub simplify_tolerance: f32,
+    /// Square off outlines whose corners are all within `rectangularize_threshold` degrees
+    /// of a right angle. This moves the walls, so it is off unless asked for.
+    #[serde(default)]
+    pub rectangularize: bool,
+    #[serde(default = "BuildingStyle::default_rectangularize_threshold")]
+    pub rectangularize_threshold: f32,
+    /// Draw `building:part` ways over the building outlines, lowest parts first
+    #[serde(default = "BuildingStyle::default_parts")]
+    pub parts: bool,
+    /// Fill of building parts; `fill` if left out
+    #[serde(default)]
+    pub part_fill: Option<String>,
+    /// Outline of building parts; `stroke` if left out
+    #[serde(default)]
+    pub part_stroke: Option<String>,
+}
+
+impl BuildingStyle {
+    fn default_parts() -> bool {
+        true
+    }
+    
+    fn default_simplify_tolerance() -> f32 {
+        0.5
+    }
+    
+    fn default_rectangularize_threshold() -> f32 {
+        10.0
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RoadStyle {
     pub color: String,
     pub width: f32,
+    /// Casing colour; roads without one, or with no `border_width`, are drawn uncased
+    #[serde(default)]
     pub border_color: String,
+    #[serde(default)]
     pub border_width: f32,
+    /// Shift of the casing sideways from the road in pixels, to the right of the way's
+    /// direction; e.g. a kerb drawn on one side only
+    #[serde(default)]
+    pub casing_offset: f32,
+}
+
+/// Dashes of a line: `"8,4"` gives lengths in pixels like SVG's `stroke-dasharray`, while
+/// `[2, 1]` gives them as multiples of the line width, so wide lines get longer dashes
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum DashPattern {
+    Pixels(String),
+    LineWidths(Vec<f32>),
+}
+
+impl DashPattern {
+    /// Dash and gap lengths in pixels for a line `line_width` pixels wide
+    pub fn lengths(&self, line_width: f32) -> Vec<f32> {
+        match self {
+            DashPattern::Pixels(pattern) => pattern
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter_map(|part| part.parse::<f32>().ok())
+                .collect(),
+            DashPattern::LineWidths(multiples) => multiples.iter().map(|multiple| multiple * line_width).collect(),
+        }
+    }
+    
+    /// A pattern with no lengths draws a solid line
+    pub fn is_solid(&self) -> bool {
+        match self {
+            DashPattern::Pixels(pattern) => pattern.trim().is_empty(),
+            DashPattern::LineWidths(multiples) => multiples.is_empty(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -58,15 +323,26 @@ pub struct RailwayStyle {
     pub rail_width: f32,
     pub rail_dash_color: String,
     pub rail_dash_width: f32,
-    pub rail_dash_pattern: String,
+    pub rail_dash_pattern: DashPattern,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BoundaryStyle {
     pub administrative_color: String,
     pub administrative_width: f32,
-    pub administrative_dash: String,
+    pub administrative_dash: DashPattern,
     pub administrative_opacity: f32,
+    /// Overrides keyed by `admin_level`; unset fields fall back to the `administrative_*` values
+    #[serde(default)]
+    pub levels: HashMap<String, BoundaryLevelStyle>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct BoundaryLevelStyle {
+    pub color: Option<String>,
+    pub width: Option<f32>,
+    pub dash: Option<DashPattern>,
+    pub opacity: Option<f32>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -75,143 +351,1983 @@ pub struct PoiStyle {
     pub radius: f32,
 }
 
+/// POI symbol configuration: maps POI keys (e.g. `restaurant`, `shop_bakery`,
+/// `tourism_hotel`) to icon names resolved as `<directory>/<name>.svg`
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct LabelStyle {
-    pub font_family: String,
-    pub road_label_stroke: String,
-    pub road_label_stroke_width: f32,
-    pub poi_label_stroke: String,
-    pub poi_label_stroke_width: f32,
-    pub place_label_stroke: String,
-    pub place_label_stroke_width: f32,
+pub struct IconStyle {
+    #[serde(default = "IconStyle::default_directory")]
+    pub directory: String,
+    #[serde(default = "IconStyle::default_size")]
+    pub size: f32,
+    #[serde(default)]
+    pub symbols: HashMap<String, String>,
+    /// Icon file of each POI key whose symbol exists on disk, looked up when the style is loaded
+    #[serde(skip)]
+    resolved: HashMap<String, PathBuf>,
 }
 
-impl MapStyle {
-    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let content = std::fs::read_to_string(path)?;
-        let style: MapStyle = toml::from_str(&content)?;
-        Ok(style)
+impl IconStyle {
+    /// Find the icon files of `symbols` in `directory`
+    fn resolve(&mut self) {
+        self.resolved = self.symbols.iter()
+            .filter_map(|(poi_key, name)| {
+                let path = Path::new(&self.directory).join(format!("{}.svg", name));
+                path.exists().then(|| (poi_key.clone(), path))
+            })
+            .collect();
     }
     
-    pub fn load_google_maps() -> Result<Self> {
-        let style_path = Path::new("assets/styles/google-maps.toml");
-        Self::load_from_file(style_path)
+    fn default_directory() -> String {
+        "assets/icons/poi".to_string()
     }
     
-    pub fn load_osm_default() -> Result<Self> {
-        let style_path = Path::new("assets/styles/osm-default.toml");
-        Self::load_from_file(style_path)
+    fn default_size() -> f32 {
+        14.0
+    }
+}
+
+impl Default for IconStyle {
+    fn default() -> Self {
+        Self {
+            directory: Self::default_directory(),
+            size: Self::default_size(),
+            symbols: HashMap::new(),
+            resolved: HashMap::new(),
+        }
+    }
+}
+
+/// Road reference shields: badges with the `ref` tag (e.g. "A4") repeated along the road
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ShieldStyle {
+    #[serde(default = "ShieldStyle::default_enabled")]
+    pub enabled: bool,
+    /// Shields are hidden below this scale (pixels per degree)
+    #[serde(default = "ShieldStyle::default_min_scale")]
+    pub min_scale: f64,
+    /// Distance between shields along the same road, in pixels
+    #[serde(default = "ShieldStyle::default_spacing")]
+    pub spacing: f64,
+    #[serde(default = "ShieldStyle::default_font_size")]
+    pub font_size: f32,
+    #[serde(default = "ShieldStyle::default_padding")]
+    pub padding: f32,
+    #[serde(default = "ShieldStyle::default_corner_radius")]
+    pub corner_radius: f32,
+    #[serde(default = "ShieldStyle::default_text_color")]
+    pub text_color: String,
+    #[serde(default = "ShieldStyle::default_border_color")]
+    pub border_color: String,
+    /// Badge fill per highway type; road classes not listed get no shield
+    #[serde(default = "ShieldStyle::default_colors")]
+    pub colors: HashMap<String, String>,
+}
+
+impl ShieldStyle {
+    fn default_enabled() -> bool {
+        true
     }
     
-    pub fn load_modern_clean() -> Result<Self> {
-        let style_path = Path::new("assets/styles/modern-clean.toml");
-        Self::load_from_file(style_path)
+    fn default_min_scale() -> f64 {
+        1000.0
     }
     
-    pub fn get_road_style(&self, highway: &str) -> (&str, f32, &str, f32) {
-        if let Some(style) = self.roads.get(highway) {
-            (&style.color, style.width, &style.border_color, style.border_width)
-        } else {
-            // Default road style
-            ("#e0e0e0", 1.0, "", 0.0)
+    fn default_spacing() -> f64 {
+        400.0
+    }
+    
+    fn default_font_size() -> f32 {
+        10.0
+    }
+    
+    fn default_padding() -> f32 {
+        3.0
+    }
+    
+    fn default_corner_radius() -> f32 {
+        3.0
+    }
+    
+    fn default_text_color() -> String {
+        "#ffffff".to_string()
+    }
+    
+    fn default_border_color() -> String {
+        "#ffffff".to_string()
+    }
+    
+    fn default_colors() -> HashMap<String, String> {
+        [
+            ("motorway", "#3465a4"),
+            ("trunk", "#2e7d32"),
+            ("primary", "#c62828"),
+            ("secondary", "#757575"),
+        ]
+        .into_iter()
+        .map(|(highway, color)| (highway.to_string(), color.to_string()))
+        .collect()
+    }
+}
+
+impl Default for ShieldStyle {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            min_scale: Self::default_min_scale(),
+            spacing: Self::default_spacing(),
+            font_size: Self::default_font_size(),
+            padding: Self::default_padding(),
+            corner_radius: Self::default_corner_radius(),
+            text_color: Self::default_text_color(),
+            border_color: Self::default_border_color(),
+            colors: Self::default_colors(),
         }
     }
+}
+
+/// Coarse level of detail applied automatically when zoomed out: minor road classes and
+/// small areas are dropped below the given scales (pixels per degree, like `label_min_scale`)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LodStyle {
+    #[serde(default = "LodStyle::default_enabled")]
+    pub enabled: bool,
+    /// Minimum scale per highway type; types not listed are always drawn
+    #[serde(default = "LodStyle::default_highway_min_scale")]
+    pub highway_min_scale: HashMap<String, f64>,
+    /// Buildings are hidden below this scale
+    #[serde(default = "LodStyle::default_building_min_scale")]
+    pub building_min_scale: f64,
+    /// Tagged nodes along ways (crossings, barriers, entrances) are hidden below this scale
+    #[serde(default = "LodStyle::default_way_node_min_scale")]
+    pub way_node_min_scale: f64,
+    /// Water and land use areas whose bounding box covers fewer screen pixels than this are skipped
+    #[serde(default = "LodStyle::default_min_area_pixels")]
+    pub min_area_pixels: f64,
+}
+
+impl LodStyle {
+    fn default_enabled() -> bool {
+        true
+    }
     
-    pub fn get_poi_style(&self, amenity: &str) -> (&str, f32) {
-        if let Some(style) = self.pois.get(amenity) {
-            (&style.color, style.radius)
-        } else if let Some(default_style) = self.pois.get("default") {
-            (&default_style.color, default_style.radius)
-        } else {
-            ("#95a5a6", 2.5)
+    fn default_highway_min_scale() -> HashMap<String, f64> {
+        [
+            ("secondary", 500.0),
+            ("secondary_link", 1000.0),
+            ("tertiary", 1000.0),
+            ("tertiary_link", 1500.0),
+            ("unclassified", 2000.0),
+            ("residential", 2000.0),
+            ("living_street", 2000.0),
+            ("service", 4000.0),
+            ("track", 4000.0),
+            ("pedestrian", 4000.0),
+            ("footway", 5000.0),
+            ("path", 5000.0),
+            ("cycleway", 5000.0),
+            ("bridleway", 5000.0),
+            ("steps", 5000.0),
+        ]
+        .into_iter()
+        .map(|(highway, scale)| (highway.to_string(), scale))
+        .collect()
+    }
+    
+    fn default_building_min_scale() -> f64 {
+        3000.0
+    }
+    
+    fn default_way_node_min_scale() -> f64 {
+        8000.0
+    }
+    
+    fn default_min_area_pixels() -> f64 {
+        16.0
+    }
+}
+
+impl Default for LodStyle {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            highway_min_scale: Self::default_highway_min_scale(),
+            building_min_scale: Self::default_building_min_scale(),
+            way_node_min_scale: Self::default_way_node_min_scale(),
+            min_area_pixels: Self::default_min_area_pixels(),
         }
     }
+}
+
+/// Pattern drawn over an area fill; `kind` is "hatch", "cross_hatch" or "dots"
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PatternStyle {
+    pub kind: PatternKind,
+    #[serde(default = "PatternStyle::default_spacing")]
+    pub spacing: f32,
+    /// Hatch line width, or dot radius
+    #[serde(default = "PatternStyle::default_line_width")]
+    pub line_width: f32,
+    #[serde(default = "PatternStyle::default_color")]
+    pub color: String,
+}
+
+impl PatternStyle {
+    fn default_spacing() -> f32 {
+        8.0
+    }
     
-    pub fn get_landuse_color(&self, landuse: &str) -> Option<&str> {
-        self.landuse.get(landuse).map(|s| s.as_str())
+    fn default_line_width() -> f32 {
+        1.0
     }
     
-    pub fn get_leisure_color(&self, leisure: &str) -> Option<&str> {
-        self.leisure.get(leisure).map(|s| s.as_str())
+    fn default_color() -> String {
+        "#00000060".to_string()
+    }
+}
+
+/// Mark repeated along a decorated line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineSymbol {
+    /// Short strokes square to the line, e.g. the downslope side of a cliff
+    Ticks,
+    Dots,
+}
+
+/// Side of a line, looking the way its nodes run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineSide {
+    Left,
+    /// The low side of cliffs and embankments as OSM draws them
+    #[default]
+    Right,
+    Both,
+}
+
+/// Caps and joins of the lines normally drawn with round ends: roads, waterways, railways
+/// and the like. Bridge ends and dashed lines are always cut square.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+pub struct StrokeStyle {
+    #[serde(default)]
+    pub line_cap: LineCap,
+    #[serde(default)]
+    pub line_join: LineJoin,
+}
+
+/// Line around a filled area, in pixels
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AreaOutlineStyle {
+    pub color: String,
+    #[serde(default = "AreaOutlineStyle::default_width")]
+    pub width: f32,
+}
+
+impl AreaOutlineStyle {
+    fn default_width() -> f32 {
+        1.0
+    }
+}
+
+/// A line with marks every `spacing` pixels along it, for cliffs, walls, hedges and the like
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DecoratedLineStyle {
+    pub color: String,
+    /// Width of the line itself; 0 draws the marks alone
+    #[serde(default = "DecoratedLineStyle::default_line_width")]
+    pub line_width: f32,
+    pub symbol: LineSymbol,
+    /// Which side ticks point to; dots sit on the line
+    #[serde(default)]
+    pub side: LineSide,
+    #[serde(default = "DecoratedLineStyle::default_spacing")]
+    pub spacing: f64,
+    /// Tick length or dot diameter in pixels
+    #[serde(default = "DecoratedLineStyle::default_size")]
+    pub size: f32,
+    /// Stroke width of ticks
+    #[serde(default = "DecoratedLineStyle::default_symbol_width")]
+    pub symbol_width: f32,
+    /// Drawn only from this scale (pixels per degree) up
+    #[serde(default = "DecoratedLineStyle::default_min_scale")]
+    pub min_scale: f64,
+}
+
+impl DecoratedLineStyle {
+    fn default_line_width() -> f32 {
+        1.0
     }
     
-    pub fn get_natural_color(&self, natural: &str) -> Option<&str> {
-        self.natural.get(natural).map(|s| s.as_str())
+    fn default_spacing() -> f64 {
+        6.0
     }
     
-    pub fn get_road_label_font_size(&self, highway: &str) -> u32 {
-        self.road_label_fonts.get(highway)
-            .copied()
-            .unwrap_or(8)
+    fn default_size() -> f32 {
+        4.0
     }
     
-    pub fn get_place_label_font_size(&self, place: &str) -> u32 {
-        self.place_label_fonts.get(place)
-            .or_else(|| self.place_label_fonts.get("default"))
-            .copied()
-            .unwrap_or(9)
+    fn default_symbol_width() -> f32 {
+        1.0
+    }
+    
+    fn default_min_scale() -> f64 {
+        20000.0
     }
 }
 
-pub struct StyleManager {
-    current_style: MapStyle,
-    available_styles: HashMap<String, PathBuf>,
+/// Clustering of dense POIs into a single marker with a count
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClusterStyle {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Size of the screen-space grid cell POIs are grouped by, in pixels
+    #[serde(default = "ClusterStyle::default_radius")]
+    pub radius: f64,
+    /// Fewer POIs than this in a cell are drawn individually
+    #[serde(default = "ClusterStyle::default_min_count")]
+    pub min_count: usize,
+    /// Clustering stops above this scale (pixels per degree) so every POI shows when zoomed in
+    #[serde(default = "ClusterStyle::default_max_scale")]
+    pub max_scale: f64,
+    #[serde(default = "ClusterStyle::default_text_color")]
+    pub text_color: String,
+    #[serde(default = "ClusterStyle::default_font_size")]
+    pub font_size: f32,
 }
 
-impl StyleManager {
-    pub fn new() -> Result<Self> {
-        let mut manager = Self {
-            current_style: MapStyle::load_google_maps()?,
-            available_styles: HashMap::new(),
-        };
-        
-        manager.scan_available_styles()?;
-        Ok(manager)
+impl ClusterStyle {
+    fn default_radius() -> f64 {
+        48.0
     }
     
-    pub fn new_with_default() -> Result<Self> {
-        // Fallback to google-maps style without scanning directory
-        Ok(Self {
-            current_style: MapStyle::load_google_maps()?,
-            available_styles: {
-                let mut styles = HashMap::new();
-                styles.insert("google-maps".to_string(), PathBuf::from("assets/styles/google-maps.toml"));
-                styles.insert("osm-default".to_string(), PathBuf::from("assets/styles/osm-default.toml"));
-                styles
-            },
-        })
+    fn default_min_count() -> usize {
+        3
     }
     
-    pub fn scan_available_styles(&mut self) -> Result<()> {
-        let styles_dir = Path::new("assets/styles");
-        if styles_dir.exists() {
-            for entry in std::fs::read_dir(styles_dir)? {
-                let entry = entry?;
-                let path = entry.path();
-                if path.extension().and_then(|s| s.to_str()) == Some("toml") {
-                    if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                        self.available_styles.insert(stem.to_string(), path);
-                    }
-                }
-            }
+    fn default_max_scale() -> f64 {
+        20000.0
+    }
+    
+    fn default_text_color() -> String {
+        "#ffffff".to_string()
+    }
+    
+    fn default_font_size() -> f32 {
+        10.0
+    }
+    
+    /// Whether POIs should be clustered at the given scale
+    pub fn active_at(&self, scale: f64) -> bool {
+        self.enabled && scale < self.max_scale
+    }
+    
+    /// Marker radius for a cluster, growing slowly with its size
+    pub fn marker_radius(&self, count: usize) -> f32 {
+        8.0 + 2.0 * (count as f32).log2()
+    }
+}
+
+impl Default for ClusterStyle {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            radius: Self::default_radius(),
+            min_count: Self::default_min_count(),
+            max_scale: Self::default_max_scale(),
+            text_color: Self::default_text_color(),
+            font_size: Self::default_font_size(),
         }
-        Ok(())
+    }
+}
+
+/// Inkscape layers of the SVG export, bottom to top, and rules moving ways out of the layer
+/// their drawing pass puts them in (`water`, `landuse`, `aeroway`, `buildings`, `barriers`,
+/// `railway`, `roads` or `boundaries`). POIs, shields and labels go to `pois`, `shields` and `labels`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LayerStyle {
+    /// Layers missing from the list are stacked right above the layer their ways came from
+    #[serde(default = "LayerStyle::default_order")]
+    pub order: Vec<String>,
+    /// Checked in order; the first rule matching a way decides its layer
+    #[serde(default)]
+    pub rules: Vec<LayerRule>,
+}
+
+/// Ways with `tag` set to one of `values` (or to anything, when `values` is empty) go to `layer`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LayerRule {
+    pub layer: String,
+    pub tag: String,
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+impl LayerStyle {
+    fn default_order() -> Vec<String> {
+        ["water", "landuse", "aeroway", "buildings", "barriers", "railway", "roads", "boundaries", "pois", "shields", "labels"]
+            .into_iter()
+            .map(String::from)
+            .collect()
     }
     
-    pub fn load_style(&mut self, style_name: &str) -> Result<()> {
-        if let Some(path) = self.available_styles.get(style_name) {
-            self.current_style = MapStyle::load_from_file(path)?;
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Style '{}' not found", style_name))
+    /// Layer for a way with these tags that its drawing pass puts in `default_layer`
+    pub fn layer_for<'a>(&'a self, tags: &HashMap<String, String>, default_layer: &'a str) -> &'a str {
+        self.rules.iter()
+            .find(|rule| tags.get(&rule.tag).is_some_and(|value| rule.values.is_empty() || rule.values.contains(value)))
+            .map_or(default_layer, |rule| rule.layer.as_str())
+    }
+    
+    /// Every layer an export can contain: the stacking order, then layers only rules name
+    pub fn layer_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for name in self.order.iter().chain(self.rules.iter().map(|rule| &rule.layer)) {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+        names
+    }
+}
+
+impl Default for LayerStyle {
+    fn default() -> Self {
+        Self {
+            order: Self::default_order(),
+            rules: Vec::new(),
         }
     }
+}
+
+/// Arrows along roundabouts showing their direction of travel
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RoundaboutStyle {
+    #[serde(default)]
+    pub arrows: bool,
+    #[serde(default = "RoundaboutStyle::default_arrow_color")]
+    pub arrow_color: String,
+    /// Length and width of each arrow head in pixels
+    #[serde(default = "RoundaboutStyle::default_arrow_size")]
+    pub arrow_size: f32,
+    #[serde(default = "RoundaboutStyle::default_arrow_width")]
+    pub arrow_width: f32,
+    /// Distance between arrows along the roundabout in pixels
+    #[serde(default = "RoundaboutStyle::default_arrow_spacing")]
+    pub arrow_spacing: f64,
+    /// Arrows are only drawn from this scale (pixels per degree) up
+    #[serde(default = "RoundaboutStyle::default_min_scale")]
+    pub min_scale: f64,
+}
+
+impl RoundaboutStyle {
+    fn default_arrow_color() -> String {
+        "#666666".to_string()
+    }
     
-    pub fn get_current_style(&self) -> &MapStyle {
-        &self.current_style
+    fn default_arrow_size() -> f32 {
+        5.0
     }
     
-    pub fn get_available_styles(&self) -> Vec<&str> {
-        self.available_styles.keys().map(|s| s.as_str()).collect()
+    fn default_arrow_width() -> f32 {
+        1.2
+    }
+    
+    fn default_arrow_spacing() -> f64 {
+        80.0
+    }
+    
+    fn default_min_scale() -> f64 {
+        40000.0
+    }
+    
+    pub fn arrows_at(&self, scale: f64) -> bool {
+        self.arrows && scale >= self.min_scale
+    }
+}
+
+/// Symbols drawn over road fills at nodes tagged e.g. `highway=turning_circle`, sized to the
+/// widest road through the node
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RoadNodeStyle {
+    /// Symbols are only drawn from this scale (pixels per degree) up
+    #[serde(default = "RoadNodeStyle::default_min_scale")]
+    pub min_scale: f64,
+    /// Keyed by the node's `highway` value, e.g. `mini_roundabout`
+    #[serde(default)]
+    pub symbols: HashMap<String, RoadNodeSymbol>,
+}
+
+impl RoadNodeStyle {
+    fn default_min_scale() -> f64 {
+        40000.0
+    }
+    
+    /// The symbol drawn for a node with these tags at the given scale, if any
+    pub fn symbol_at(&self, tags: &HashMap<String, String>, scale: f64) -> Option<&RoadNodeSymbol> {
+        if scale < self.min_scale {
+            return None;
+        }
+        tags.get("highway").and_then(|highway| self.symbols.get(highway))
+    }
+}
+
+impl Default for RoadNodeStyle {
+    fn default() -> Self {
+        Self {
+            min_scale: Self::default_min_scale(),
+            symbols: HashMap::new(),
+        }
+    }
+}
+
+/// One road node symbol. Colours left out are taken from the road the node is on.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RoadNodeSymbol {
+    pub shape: RoadNodeShape,
+    /// Width of the symbol as a multiple of the road's full width, casing included
+    #[serde(default = "RoadNodeSymbol::default_size")]
+    pub size: f32,
+    /// Fill of discs and squares; the road's fill colour if left out
+    #[serde(default)]
+    pub color: Option<String>,
+    /// The road's casing colour if left out
+    #[serde(default)]
+    pub outline_color: Option<String>,
+    /// In pixels; rings are drawn with this width alone
+    #[serde(default = "RoadNodeSymbol::default_outline_width")]
+    pub outline_width: f32,
+}
+
+impl RoadNodeSymbol {
+    fn default_size() -> f32 {
+        1.0
+    }
+    
+    fn default_outline_width() -> f32 {
+        1.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoadNodeShape {
+    /// Filled circle, e.g. the wider end of a road at a turning circle
+    Disc,
+    /// Circle outline, e.g. the island of a mini roundabout
+    Ring,
+    Square,
+}
+
+/// Small labels showing `addr:housenumber` at building centroids and on address nodes
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HousenumberStyle {
+    /// Numbers are only drawn from this scale (pixels per degree) up
+    #[serde(default = "HousenumberStyle::default_min_scale")]
+    pub min_scale: f64,
+    #[serde(default = "HousenumberStyle::default_font_size")]
+    pub font_size: f32,
+    #[serde(default = "HousenumberStyle::default_color")]
+    pub color: String,
+    #[serde(default = "HousenumberStyle::default_halo_color")]
+    pub halo_color: String,
+    #[serde(default = "HousenumberStyle::default_halo_width")]
+    pub halo_width: f32,
+    /// At most one number is placed in each square of this many pixels, so they don't overlap
+    #[serde(default = "HousenumberStyle::default_cell_size")]
+    pub cell_size: f64,
+}
+
+impl HousenumberStyle {
+    fn default_min_scale() -> f64 {
+        150000.0
+    }
+    
+    fn default_font_size() -> f32 {
+        8.0
+    }
+    
+    fn default_color() -> String {
+        "#666666".to_string()
+    }
+    
+    fn default_halo_color() -> String {
+        "#ffffff".to_string()
+    }
+    
+    fn default_halo_width() -> f32 {
+        1.5
+    }
+    
+    fn default_cell_size() -> f64 {
+        24.0
+    }
+    
+    pub fn visible_at(&self, scale: f64) -> bool {
+        scale >= self.min_scale
+    }
+}
+
+impl Default for HousenumberStyle {
+    fn default() -> Self {
+        Self {
+            min_scale: Self::default_min_scale(),
+            font_size: Self::default_font_size(),
+            color: Self::default_color(),
+            halo_color: Self::default_halo_color(),
+            halo_width: Self::default_halo_width(),
+            cell_size: Self::default_cell_size(),
+        }
+    }
+}
+
+impl Default for RoundaboutStyle {
+    fn default() -> Self {
+        Self {
+            arrows: false,
+            arrow_color: Self::default_arrow_color(),
+            arrow_size: Self::default_arrow_size(),
+            arrow_width: Self::default_arrow_width(),
+            arrow_spacing: Self::default_arrow_spacing(),
+            min_scale: Self::default_min_scale(),
+        }
+    }
+}
+
+/// Whether a `colour` (or `color`) tag on a way overrides the colour the style gives it,
+/// e.g. for coloured route lines or roofs
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct OsmColourStyle {
+    #[serde(default)]
+    pub respect_osm_colour: bool,
+}
+
+/// How road casings meet at junctions. Merged, every casing in a `layer` is drawn before any
+/// fill, widest class first, so a side street's casing can't cut across a main road's fill.
+/// Otherwise each road is drawn casing then fill before the next one.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RoadCasingStyle {
+    #[serde(default = "RoadCasingStyle::default_merge_junctions")]
+    pub merge_junctions: bool,
+}
+
+impl RoadCasingStyle {
+    fn default_merge_junctions() -> bool {
+        true
+    }
+}
+
+impl Default for RoadCasingStyle {
+    fn default() -> Self {
+        Self {
+            merge_junctions: Self::default_merge_junctions(),
+        }
+    }
+}
+
+/// Roads with a `width` tag drawn at their true width in meters instead of their class
+/// width, for technical maps. Untagged roads keep the class width.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RoadWidthStyle {
+    #[serde(default)]
+    pub to_scale: bool,
+    /// Narrowest a road is drawn to scale, in pixels, so it stays visible when zoomed out
+    #[serde(default = "RoadWidthStyle::default_min_width")]
+    pub min_width: f32,
+}
+
+impl RoadWidthStyle {
+    fn default_min_width() -> f32 {
+        1.0
+    }
+}
+
+impl Default for RoadWidthStyle {
+    fn default() -> Self {
+        Self {
+            to_scale: false,
+            min_width: Self::default_min_width(),
+        }
+    }
+}
+
+/// How roads and paths closed to the public are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestrictedAccessMode {
+    /// Like any other road of their class
+    #[default]
+    Show,
+    /// At the style's `opacity`
+    Fade,
+    /// Dashed in their class colour, without casing
+    Dash,
+    Hide,
+}
+
+/// Roads and paths whose `access` tag keeps the public out, e.g. `access=private`, set apart
+/// from public ones. Everything is shown alike unless a style picks another mode.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AccessStyle {
+    #[serde(default)]
+    pub mode: RestrictedAccessMode,
+    /// `access` values that count as closed to the public
+    #[serde(default = "AccessStyle::default_values")]
+    pub values: Vec<String>,
+    /// Opacity of faded roads, from 0.0 to 1.0
+    #[serde(default = "AccessStyle::default_opacity")]
+    pub opacity: f32,
+}
+
+impl AccessStyle {
+    fn default_values() -> Vec<String> {
+        vec!["private".to_string(), "no".to_string(), "customers".to_string()]
+    }
+    
+    fn default_opacity() -> f32 {
+        0.4
+    }
+    
+    /// Whether a feature with these tags is closed to the public
+    pub fn is_restricted(&self, tags: &HashMap<String, String>) -> bool {
+        tags.get("access").is_some_and(|access| self.values.iter().any(|value| value == access))
+    }
+    
+    /// Whether a feature with these tags is left off the map, labels included
+    pub fn hides(&self, tags: &HashMap<String, String>) -> bool {
+        self.mode == RestrictedAccessMode::Hide && self.is_restricted(tags)
+    }
+}
+
+impl Default for AccessStyle {
+    fn default() -> Self {
+        Self {
+            mode: RestrictedAccessMode::default(),
+            values: Self::default_values(),
+            opacity: Self::default_opacity(),
+        }
+    }
+}
+
+/// Side of the road traffic keeps to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrivingSide {
+    Left,
+    #[default]
+    Right,
+}
+
+/// Divided roads mapped as two one-way ways, one per direction, drawn as a single wider road
+/// between them while they are too close to tell apart. Carriageways pair up when they have
+/// the same class and `ref` (or `name`), run in opposite directions and each has the other on
+/// the side oncoming traffic passes on.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DualCarriagewayStyle {
+    #[serde(default)]
+    pub merge: bool,
+    /// Carriageways further apart than this many meters are never paired
+    #[serde(default = "DualCarriagewayStyle::default_max_distance")]
+    pub max_distance: f64,
+    #[serde(default)]
+    pub driving_side: DrivingSide,
+    /// Width of the merged road as a multiple of its class width
+    #[serde(default = "DualCarriagewayStyle::default_width_factor")]
+    pub width_factor: f32,
+}
+
+impl DualCarriagewayStyle {
+    fn default_max_distance() -> f64 {
+        40.0
+    }
+    
+    fn default_width_factor() -> f32 {
+        1.5
+    }
+}
+
+impl Default for DualCarriagewayStyle {
+    fn default() -> Self {
+        Self {
+            merge: false,
+            max_distance: Self::default_max_distance(),
+            driving_side: DrivingSide::default(),
+            width_factor: Self::default_width_factor(),
+        }
+    }
+}
+
+/// Roads on bridges (any `bridge` tag but `no`) drawn with a wider casing of their own, cut
+/// square at the abutments, and a band of background between it and the deck so the deck
+/// seems to float over what it crosses. Bridges look like other roads unless this is enabled.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BridgeStyle {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Replaces the road's own casing color
+    #[serde(default = "BridgeStyle::default_casing_color")]
+    pub casing_color: String,
+    /// Casing width on each side of the gap, in pixels
+    #[serde(default = "BridgeStyle::default_casing_width")]
+    pub casing_width: f32,
+    /// Width of the band between the deck and the casing on each side, in pixels
+    #[serde(default = "BridgeStyle::default_gap_width")]
+    pub gap_width: f32,
+    /// Color of that band; empty for the map background
+    #[serde(default)]
+    pub gap_color: String,
+}
+
+impl BridgeStyle {
+    fn default_casing_color() -> String {
+        "#505050".to_string()
+    }
+    
+    fn default_casing_width() -> f32 {
+        1.0
+    }
+    
+    fn default_gap_width() -> f32 {
+        1.0
+    }
+    
+    /// Whether a road with these tags gets the bridge casing
+    pub fn applies_to(&self, tags: &HashMap<String, String>) -> bool {
+        self.enabled && tags.get("bridge").is_some_and(|bridge| bridge != "no")
+    }
+}
+
+impl Default for BridgeStyle {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            casing_color: Self::default_casing_color(),
+            casing_width: Self::default_casing_width(),
+            gap_width: Self::default_gap_width(),
+            gap_color: String::new(),
+        }
+    }
+}
+
+/// Corner of the map an overlay is pinned to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayPosition {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl OverlayPosition {
+    pub fn label(&self) -> &'static str {
+        match self {
+            OverlayPosition::TopLeft => "Top left",
+            OverlayPosition::TopRight => "Top right",
+            OverlayPosition::BottomLeft => "Bottom left",
+            OverlayPosition::BottomRight => "Bottom right",
+        }
+    }
+}
+
+/// North arrow drawn over the map; it turns with the map bearing
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NorthArrowStyle {
+    #[serde(default = "NorthArrowStyle::default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub position: OverlayPosition,
+    /// Arrow height in pixels, not counting the "N" label
+    #[serde(default = "NorthArrowStyle::default_size")]
+    pub size: f32,
+    /// Distance from the map edges in pixels
+    #[serde(default = "NorthArrowStyle::default_margin")]
+    pub margin: f32,
+    #[serde(default = "NorthArrowStyle::default_fill")]
+    pub fill: String,
+    #[serde(default = "NorthArrowStyle::default_stroke")]
+    pub stroke: String,
+    #[serde(default = "NorthArrowStyle::default_stroke_width")]
+    pub stroke_width: f32,
+    #[serde(default = "NorthArrowStyle::default_enabled")]
+    pub show_label: bool,
+}
+
+impl NorthArrowStyle {
+    fn default_enabled() -> bool {
+        true
+    }
+    
+    fn default_size() -> f32 {
+        40.0
+    }
+    
+    fn default_margin() -> f32 {
+        16.0
+    }
+    
+    fn default_fill() -> String {
+        "#333333".to_string()
+    }
+    
+    fn default_stroke() -> String {
+        "#ffffff".to_string()
+    }
+    
+    fn default_stroke_width() -> f32 {
+        1.5
+    }
+}
+
+impl Default for NorthArrowStyle {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            position: OverlayPosition::default(),
+            size: Self::default_size(),
+            margin: Self::default_margin(),
+            fill: Self::default_fill(),
+            stroke: Self::default_stroke(),
+            stroke_width: Self::default_stroke_width(),
+            show_label: Self::default_enabled(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LabelStyle {
+    pub font_family: String,
+    pub road_label_stroke: String,
+    pub road_label_stroke_width: f32,
+    pub poi_label_stroke: String,
+    pub poi_label_stroke_width: f32,
+    pub place_label_stroke: String,
+    pub place_label_stroke_width: f32,
+    /// Way labels are hidden below this scale (pixels per degree)
+    #[serde(default = "LabelStyle::default_min_scale")]
+    pub label_min_scale: f64,
+    /// Maximum labels per `label_cell_size` square; 0 disables the cap
+    #[serde(default = "LabelStyle::default_max_labels_per_cell")]
+    pub max_labels_per_cell: usize,
+    #[serde(default = "LabelStyle::default_cell_size")]
+    pub label_cell_size: f64,
+    /// Place and POI names wider than this many pixels are wrapped at spaces; 0 keeps them on one line
+    #[serde(default = "LabelStyle::default_wrap_width")]
+    pub wrap_width: f32,
+    /// Distance between wrapped lines, as a multiple of the font size
+    #[serde(default = "LabelStyle::default_line_spacing")]
+    pub line_spacing: f32,
+    /// Preferred languages for names, e.g. `["fr", "en"]` shows `name:fr`, then `name:en`,
+    /// then `name`; empty uses `name` only
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default = "LabelStyle::default_poi_placement")]
+    pub poi: LabelPlacement,
+    #[serde(default)]
+    pub place: LabelPlacement,
+    #[serde(default)]
+    pub road: LabelPlacement,
+}
+
+/// Where one kind of label sits relative to the feature it names
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+pub struct LabelPlacement {
+    #[serde(default)]
+    pub text_anchor: TextAnchor,
+    /// Shift in pixels, `[right, down]`. POI labels are measured from the edge of the marker;
+    /// road labels along and across the road.
+    #[serde(default)]
+    pub text_offset: (f32, f32),
+}
+
+impl LabelStyle {
+    fn default_poi_placement() -> LabelPlacement {
+        LabelPlacement {
+            text_anchor: TextAnchor::Start,
+            text_offset: (8.0, 0.0),
+        }
+    }
+    
+    fn default_min_scale() -> f64 {
+        2000.0
+    }
+    
+    fn default_max_labels_per_cell() -> usize {
+        4
+    }
+    
+    fn default_cell_size() -> f64 {
+        256.0
+    }
+    
+    fn default_wrap_width() -> f32 {
+        120.0
+    }
+    
+    fn default_line_spacing() -> f32 {
+        1.2
+    }
+}
+
+/// Suffix listing the hints of a [`StyleError::Parse`], empty when there are none
+pub(crate) fn format_hints(hints: &[String]) -> String {
+    if hints.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", hints.join("; "))
+    }
+}
+
+/// Top-level sections of a style file, used to suggest fixes when a file fails to parse
+const STYLE_SECTIONS: &[&str] = &[
+    "base", "background", "water", "landuse", "landuse_patterns", "leisure", "natural", "amenity", "area_outlines", "aeroway", "buildings", "roads",
+    "railway", "boundaries", "pois", "labels", "road_label_fonts", "place_label_fonts",
+    "icons", "north_arrow", "shields", "lod", "poi_clusters", "layers", "roundabouts", "osm_colour",
+    "decorated_lines", "road_casings", "road_widths", "restricted_access",
+    "dual_carriageways", "bridges", "strokes", "road_nodes", "housenumbers",
+];
+
+/// Tags whose values select a `[decorated_lines]` entry, checked in this order
+const DECORATED_LINE_KEYS: &[&str] = &["barrier", "natural", "man_made"];
+
+/// A key or section in a style file that doesn't correspond to any style setting.
+/// Serde skips these silently, so a typo like `[buldings]` would otherwise go unnoticed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownStyleKey {
+    /// Dotted path of the key, e.g. `roads.primary.colour`
+    pub path: String,
+    pub is_section: bool,
+    /// Closest known key at the same level, if one is similar enough
+    pub suggestion: Option<String>,
+}
+
+impl std::fmt::Display for UnknownStyleKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_section {
+            write!(f, "unknown section [{}]", self.path)?;
+        } else {
+            write!(f, "unknown key `{}`", self.path)?;
+        }
+        match &self.suggestion {
+            Some(suggestion) if self.is_section => {
+                let parent = self.path.rsplit_once('.').map(|(parent, _)| format!("{}.", parent)).unwrap_or_default();
+                write!(f, ", did you mean [{}{}]?", parent, suggestion)
+            }
+            Some(suggestion) => write!(f, ", did you mean `{}`?", suggestion),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Walk the raw file alongside the re-serialized style: anything present in the file but
+/// missing from the parsed style was ignored by serde
+fn collect_unknown_keys(raw: &toml::Table, known: &toml::Table, prefix: &str, unknown: &mut Vec<UnknownStyleKey>) {
+    for (key, value) in raw {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match known.get(key) {
+            Some(toml::Value::Table(known_table)) => {
+                if let toml::Value::Table(raw_table) = value {
+                    collect_unknown_keys(raw_table, known_table, &path, unknown);
+                }
+            }
+            Some(_) => {}
+            None => unknown.push(UnknownStyleKey {
+                path,
+                is_section: value.is_table(),
+                suggestion: closest_key(key, known.keys().map(|k| k.as_str())),
+            }),
+        }
+    }
+}
+
+/// Whether style text is Mapbox GL JSON rather than TOML, by the file's extension or, without
+/// one, the style spec `version` only Mapbox GL styles have
+fn is_mapbox_gl(content: &str, path: Option<&Path>) -> bool {
+    if let Some(extension) = path.and_then(Path::extension).and_then(|ext| ext.to_str()) {
+        return extension.eq_ignore_ascii_case("json");
+    }
+    content.trim_start().starts_with('{')
+        && serde_json::from_str::<serde_json::Value>(content).is_ok_and(|style| style.get("version").and_then(|v| v.as_u64()) == Some(8))
+}
+
+/// `#RRGGBB`, or `#RRGGBBAA` for a colour that isn't opaque
+fn style_hex(color: Color) -> String {
+    if color.a == 255 {
+        format!("#{:02X}{:02X}{:02X}", color.r, color.g, color.b)
+    } else {
+        color.to_hex()
+    }
+}
+
+/// `content` with the sections it leaves out appended from its base style. The base is read
+/// from the copy compiled into the binary, so its own gaps are left to the serde defaults.
+/// Appending rather than rebuilding the TOML keeps parse errors pointing at the right line.
+fn with_base_sections<'a>(content: &'a str, raw: &toml::Table) -> Result<Cow<'a, str>, StyleError> {
+    let base_name = raw.get("base").and_then(|base| base.as_str()).unwrap_or(DEFAULT_STYLE);
+    let (_, base) = EMBEDDED_STYLES.iter()
+        .find(|(name, _)| *name == base_name)
+        .ok_or_else(|| StyleError::StyleNotFound(base_name.to_string()))?;
+    let base: toml::Table = toml::from_str(base)?;
+    let missing: toml::Table = base.into_iter()
+        .filter(|(key, value)| value.is_table() && !raw.contains_key(key))
+        .collect();
+    if missing.is_empty() {
+        return Ok(Cow::Borrowed(content));
+    }
+    
+    log::debug!("Taking [{}] from the '{}' style", missing.keys().cloned().collect::<Vec<_>>().join("], ["), base_name);
+    Ok(Cow::Owned(format!("{}\n{}", content, toml::to_string(&missing)?)))
+}
+
+/// Closest candidate by edit distance, if it is plausibly a typo of `key`
+fn closest_key<'a>(key: &str, candidates: impl Iterator<Item = &'a str>) -> Option<String> {
+    let max_distance = (key.chars().count() / 3).max(2);
+    candidates
+        .map(|candidate| (edit_distance(key, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// Apply `transform` to the hex colours anywhere in `value`
+fn transform_colors(value: &mut toml::Value, transform: &ColorTransform) {
+    match value {
+        toml::Value::String(text) => {
+            if let Some(color) = transform.apply_hex(text) {
+                *text = color;
+            }
+        }
+        toml::Value::Array(items) => items.iter_mut().for_each(|item| transform_colors(item, transform)),
+        toml::Value::Table(table) => table.iter_mut().for_each(|(_, item)| transform_colors(item, transform)),
+        _ => {}
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl MapStyle {
+    /// Load a style, logging any keys in the file that were ignored
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, StyleError> {
+        let path = path.as_ref();
+        let (style, unknown) = Self::load_from_file_checked(path)?;
+        for key in &unknown {
+            log::warn!("{}: {}", path.display(), key);
+        }
+        Ok(style)
+    }
+    
+    /// Load a style, TOML or Mapbox GL JSON, and report keys and sections that don't match
+    /// any setting
+    pub fn load_from_file_checked<P: AsRef<Path>>(path: P) -> Result<(Self, Vec<UnknownStyleKey>), StyleError> {
+        let content = std::fs::read_to_string(path.as_ref())?;
+        Self::parse_source(&content, Some(path.as_ref()))
+    }
+    
+    /// Parse style TOML, reporting unrecognized keys. Sections left out are filled in from
+    /// the bundled style named by `base`, or the default style, so a partial file that only
+    /// overrides `[roads]` still loads. When parsing fails, misspelled top-level sections are
+    /// added to the error since they are the usual cause.
+    pub fn parse_checked(content: &str) -> Result<(Self, Vec<UnknownStyleKey>), StyleError> {
+        let raw: toml::Table = toml::from_str(content)?;
+        let content = with_base_sections(content, &raw)?;
+        
+        let mut style: MapStyle = match toml::from_str(&content) {
+            Ok(style) => style,
+            Err(e) => {
+                let hints: Vec<String> = raw.iter()
+                    .filter(|(key, _)| !STYLE_SECTIONS.contains(&key.as_str()))
+                    .map(|(key, value)| UnknownStyleKey {
+                        path: key.clone(),
+                        is_section: value.is_table(),
+                        suggestion: closest_key(key, STYLE_SECTIONS.iter().copied()),
+                    }.to_string())
+                    .collect();
+                return Err(StyleError::Parse { source: e, hints });
+            }
+        };
+        style.icons.resolve();
+        
+        let mut unknown = Vec::new();
+        if let toml::Value::Table(known) = toml::Value::try_from(&style)? {
+            collect_unknown_keys(&raw, &known, "", &mut unknown);
+        }
+        Ok((style, unknown))
+    }
+    
+    /// One of the styles shipped with the application, preferring its file in `assets/styles`
+    pub fn load_bundled(name: &str) -> Result<Self, StyleError> {
+        let path = Path::new(STYLES_DIR).join(format!("{}.toml", name));
+        let source = match EMBEDDED_STYLES.iter().find(|(embedded, _)| *embedded == name) {
+            _ if path.is_file() => StyleSource::File(path),
+            Some((_, content)) => StyleSource::Embedded(content),
+            None => return Err(StyleError::StyleNotFound(name.to_string())),
+        };
+        let (style, unknown) = Self::parse_checked(&source.read()?)?;
+        for key in &unknown {
+            log::warn!("{}: {}", name, key);
+        }
+        Ok(style)
+    }
+    
+    /// Parse a style file's text: Mapbox GL style JSON when `path` ends in `.json` or the text
+    /// has `"version": 8`, TOML otherwise. A Mapbox style has no unknown keys to report; what
+    /// its conversion leaves out is logged instead.
+    pub fn parse_source(content: &str, path: Option<&Path>) -> Result<(Self, Vec<UnknownStyleKey>), StyleError> {
+        if !is_mapbox_gl(content, path) {
+            return Self::parse_checked(content);
+        }
+        let (style, warnings) = Self::from_mapbox_gl(content)?;
+        for warning in &warnings {
+            log::warn!("Mapbox GL import: {}", warning);
+        }
+        Ok((style, Vec::new()))
+    }
+    
+    /// The default style with the colours of a Mapbox GL style's line and fill layers, as read
+    /// by [`MapboxGlParser`]. A layer sets the colour of the first OSM tag its filter tests
+    /// that the style has one for, e.g. `landuse=forest` or `highway=primary`, and the topmost
+    /// layer for a tag wins. A wider line layer under a road's becomes its casing. Returns the
+    /// style with what could not be carried over.
+    pub fn from_mapbox_gl(json: &str) -> Result<(Self, Vec<String>), StyleError> {
+        let import = MapboxGlParser::new().convert(json).map_err(|e| StyleError::MapboxGl(e.to_string()))?;
+        let mut style = Self::load_bundled(DEFAULT_STYLE)?;
+        let mut warnings = import.warnings;
+        if let Some(color) = import.background {
+            style.background.color = style_hex(color);
+        }
+        
+        // Settings already taken by a layer higher up
+        let mut taken = HashSet::new();
+        let mut unused = 0;
+        for rule in &import.stylesheet.rules {
+            let conditions = rule.selectors.iter()
+                .filter_map(|selector| match selector {
+                    FeatureSelector::Conditions(conditions) => Some(conditions),
+                    _ => None,
+                })
+                .flatten();
+            let mut applied = false;
+            for condition in conditions {
+                let (key, value) = match condition {
+                    TagCondition::Equals { key, value } => (key.as_str(), Some(value.as_str())),
+                    TagCondition::Present(key) => (key.as_str(), None),
+                    _ => continue,
+                };
+                applied |= style.apply_mapbox_layer(key, value, &rule.style, &mut taken);
+            }
+            unused += !applied as usize;
+        }
+        if unused > 0 {
+            warnings.push(format!("{} layer(s) test no tag the style has a colour for", unused));
+        }
+        Ok((style, warnings))
+    }
+    
+    /// Take the colours of a converted layer whose filter tests `key=value` (or just `key`).
+    /// `false` if the style has no setting for that tag.
+    fn apply_mapbox_layer(&mut self, key: &str, value: Option<&str>, layer: &RenderStyle, taken: &mut HashSet<String>) -> bool {
+        let setting = match value {
+            Some(value) => format!("{}={}", key, value),
+            None => key.to_string(),
+        };
+        let fill = layer.fill_color.filter(|_| !matches!(layer.draw_mode, DrawMode::Line)).map(style_hex);
+        let line = layer.line_color.map(style_hex);
+        
+        match (key, value, fill) {
+            ("natural", Some("water"), Some(color)) | ("water", _, Some(color)) => {
+                if taken.insert("water".to_string()) {
+                    self.water.color = color;
+                }
+            }
+            ("landuse" | "leisure" | "natural" | "amenity", Some(value), Some(color)) => {
+                if taken.insert(setting) {
+                    let colors = match key {
+                        "landuse" => &mut self.landuse,
+                        "leisure" => &mut self.leisure,
+                        "natural" => &mut self.natural,
+                        _ => &mut self.amenity,
+                    };
+                    colors.insert(value.to_string(), color);
+                }
+            }
+            ("building", _, Some(color)) => {
+                if taken.insert("building".to_string()) {
+                    self.buildings.fill = color;
+                    if let Some(line) = line {
+                        self.buildings.stroke = line;
+                    }
+                }
+            }
+            ("aeroway", _, Some(color)) => {
+                if taken.insert("aeroway".to_string()) {
+                    self.aeroway.default = color;
+                }
+            }
+            ("highway", Some(value), None) => {
+                let Some(color) = line else {
+                    return false;
+                };
+                if taken.insert(setting.clone()) {
+                    let road = self.roads.entry(value.to_string()).or_insert_with(|| RoadStyle {
+                        color: String::new(),
+                        width: 0.0,
+                        border_color: String::new(),
+                        border_width: 0.0,
+                        casing_offset: 0.0,
+                    });
+                    road.color = color;
+                    road.width = layer.line_width;
+                    road.border_color.clear();
+                    road.border_width = 0.0;
+                } else if taken.insert(format!("{} casing", setting)) {
+                    let road = self.roads.get_mut(value).expect("road was styled by the layer above");
+                    if layer.line_width > road.width {
+                        road.border_color = color;
+                        road.border_width = (layer.line_width - road.width) / 2.0;
+                    }
+                }
+            }
+            ("railway", _, None) => {
+                let Some(color) = line else {
+                    return false;
+                };
+                if taken.insert("railway".to_string()) {
+                    self.railway.rail_color = color;
+                    self.railway.rail_width = layer.line_width;
+                }
+            }
+            ("boundary" | "admin_level", _, None) => {
+                let Some(color) = line else {
+                    return false;
+                };
+                if taken.insert("boundary".to_string()) {
+                    self.boundaries.administrative_color = color;
+                    self.boundaries.administrative_width = layer.line_width;
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+    
+    /// The style with every `#RRGGBB` and `#RRGGBBAA` colour in it passed through `transform`.
+    /// Named colours such as `white` are kept as they are.
+    pub fn with_color_transform(&self, transform: &ColorTransform) -> Result<Self, StyleError> {
+        if transform.is_identity() {
+            return Ok(self.clone());
+        }
+        let mut value = toml::Value::try_from(self)?;
+        transform_colors(&mut value, transform);
+        let mut style: Self = value.try_into()?;
+        style.icons.resolved = self.icons.resolved.clone();
+        Ok(style)
+    }
+    
+    pub fn load_google_maps() -> Result<Self, StyleError> {
+        Self::load_bundled("google-maps")
+    }
+    
+    pub fn load_osm_default() -> Result<Self, StyleError> {
+        Self::load_bundled("osm-default")
+    }
+    
+    pub fn load_modern_clean() -> Result<Self, StyleError> {
+        Self::load_bundled("modern-clean")
+    }
+    
+    /// Road (color, width, casing colour, casing width) for a highway type; types the style
+    /// doesn't list get a thin light grey line without casing
+    pub fn get_road_style(&self, highway: &str) -> (&str, f32, &str, f32) {
+        if let Some(style) = self.roads.get(highway) {
+            (&style.color, style.width, &style.border_color, style.border_width)
+        } else {
+            // Default road style
+            ("#e0e0e0", 1.0, "", 0.0)
+        }
+    }
+    
+    /// POI (color, radius) for a POI key, falling back to the `default` entry of `[pois]`,
+    /// then to a grey dot 2.5 pixels in radius
+    pub fn get_poi_style(&self, amenity: &str) -> (&str, f32) {
+        if let Some(style) = self.pois.get(amenity) {
+            (&style.color, style.radius)
+        } else if let Some(default_style) = self.pois.get("default") {
+            (&default_style.color, default_style.radius)
+        } else {
+            ("#95a5a6", 2.5)
+        }
+    }
+    
+    /// Build the POI key used by `[pois]` and `[icons.symbols]` from a node's tags
+    pub fn poi_key(tags: &HashMap<String, String>) -> Option<String> {
+        if let Some(amenity) = tags.get("amenity") {
+            Some(amenity.clone())
+        } else if let Some(shop) = tags.get("shop") {
+            Some(format!("shop_{}", shop))
+        } else if let Some(tourism) = tags.get("tourism") {
+            Some(format!("tourism_{}", tourism))
+        } else if let Some(leisure) = tags.get("leisure") {
+            Some(format!("leisure_{}", leisure))
+        } else if let Some(office) = tags.get("office") {
+            Some(format!("office_{}", office))
+        } else if let Some(healthcare) = tags.get("healthcare") {
+            Some(format!("healthcare_{}", healthcare))
+        } else {
+            Self::way_node_key(tags)
+        }
+    }
+    
+    /// POI key of the point features usually found as nodes of a way: crossings, signals and
+    /// other road furniture (`highway_crossing`), barriers (`barrier_gate`), `traffic_calming`
+    /// and `entrance`. They are drawn on top of their way, from `lod.way_node_min_scale` up.
+    pub fn way_node_key(tags: &HashMap<String, String>) -> Option<String> {
+        const HIGHWAY_POINTS: &[&str] = &["crossing", "traffic_signals", "stop", "give_way", "mini_roundabout", "turning_circle", "speed_camera"];
+        
+        if let Some(highway) = tags.get("highway").filter(|highway| HIGHWAY_POINTS.contains(&highway.as_str())) {
+            Some(format!("highway_{}", highway))
+        } else if let Some(barrier) = tags.get("barrier") {
+            Some(format!("barrier_{}", barrier))
+        } else if tags.contains_key("traffic_calming") {
+            Some("traffic_calming".to_string())
+        } else if tags.contains_key("entrance") {
+            Some("entrance".to_string())
+        } else {
+            tags.get("railway").filter(|railway| railway.as_str() == "level_crossing").map(|_| "railway_level_crossing".to_string())
+        }
+    }
+    
+    /// Whether the node with these tags is a POI drawn at the given scale. Nodes drawn as a
+    /// `[road_nodes]` symbol instead are not.
+    pub fn poi_visible_at(&self, tags: &HashMap<String, String>, scale: f64) -> bool {
+        if self.road_nodes.symbol_at(tags, scale).is_some() {
+            return false;
+        }
+        !self.lod.enabled || Self::way_node_key(tags).is_none() || scale >= self.lod.way_node_min_scale
+    }
+    
+    /// Path to the icon file for a POI key, if the style maps one and it existed on disk
+    /// when the style was loaded
+    pub fn get_poi_icon(&self, poi_key: &str) -> Option<&Path> {
+        self.icons.resolved.get(poi_key).map(PathBuf::as_path)
+    }
+    
+    /// Boundary (color, width, dash, opacity) for an `admin_level` tag value, using the
+    /// closest configured level at or below it (lower levels are the larger regions)
+    pub fn get_boundary_style(&self, admin_level: Option<&str>) -> (&str, f32, &DashPattern, f32) {
+        let base = &self.boundaries;
+        let level_style = admin_level
+            .and_then(|level| level.trim().parse::<u8>().ok())
+            .and_then(|level| {
+                base.levels
+                    .iter()
+                    .filter_map(|(key, style)| key.parse::<u8>().ok().map(|key| (key, style)))
+                    .filter(|(key, _)| *key <= level)
+                    .max_by_key(|(key, _)| *key)
+                    .map(|(_, style)| style)
+            });
+        
+        match level_style {
+            Some(style) => (
+                style.color.as_deref().unwrap_or(&base.administrative_color),
+                style.width.unwrap_or(base.administrative_width),
+                style.dash.as_ref().unwrap_or(&base.administrative_dash),
+                style.opacity.unwrap_or(base.administrative_opacity),
+            ),
+            None => (
+                &base.administrative_color,
+                base.administrative_width,
+                &base.administrative_dash,
+                base.administrative_opacity,
+            ),
+        }
+    }
+    
+    pub fn get_landuse_color(&self, landuse: &str) -> Option<&str> {
+        self.landuse.get(landuse).map(|s| s.as_str())
+    }
+    
+    pub fn get_landuse_pattern(&self, landuse: &str) -> Option<FillPattern> {
+        let pattern = self.landuse_patterns.get(landuse)?;
+        Some(FillPattern {
+            kind: pattern.kind,
+            spacing: pattern.spacing,
+            line_width: pattern.line_width,
+            color: Color::from_hex(&pattern.color)?,
+        })
+    }
+    
+    /// Decoration for a way tagged e.g. `natural=cliff` or `barrier=wall`, if the style has one
+    pub fn decorated_line(&self, tags: &HashMap<String, String>) -> Option<&DecoratedLineStyle> {
+        DECORATED_LINE_KEYS.iter()
+            .filter_map(|&key| tags.get(key).map(|value| format!("{}_{}", key, value)))
+            .find_map(|key| self.decorated_lines.get(&key))
+    }
+    
+    pub fn get_leisure_color(&self, leisure: &str) -> Option<&str> {
+        self.leisure.get(leisure).map(|s| s.as_str())
+    }
+    
+    pub fn get_natural_color(&self, natural: &str) -> Option<&str> {
+        self.natural.get(natural).map(|s| s.as_str())
+    }
+    
+    pub fn get_amenity_color(&self, amenity: &str) -> Option<&str> {
+        self.amenity.get(amenity).map(|s| s.as_str())
+    }
+    
+    /// Outline of an area filled for `key=value`, e.g. `leisure=park`, if the style draws one
+    pub fn area_outline(&self, key: &str, value: &str) -> Option<&AreaOutlineStyle> {
+        self.area_outlines.get(&format!("{}_{}", key, value))
+            .or_else(|| self.area_outlines.get(key))
+            .filter(|outline| outline.width > 0.0)
+    }
+    
+    /// Road name font size for a highway type, 8 for types the style doesn't list
+    pub fn get_road_label_font_size(&self, highway: &str) -> u32 {
+        self.road_label_fonts.get(highway)
+            .copied()
+            .unwrap_or(8)
+    }
+    
+    /// Place name font size for a `place` value, falling back to the `default` entry, then 9
+    pub fn get_place_label_font_size(&self, place: &str) -> u32 {
+        self.place_label_fonts.get(place)
+            .or_else(|| self.place_label_fonts.get("default"))
+            .copied()
+            .unwrap_or(9)
+    }
+    
+    /// Whether a road of this highway type is drawn at the given scale
+    pub fn highway_visible_at(&self, highway: &str, scale: f64) -> bool {
+        !self.lod.enabled || self.lod.highway_min_scale.get(highway).is_none_or(|&min| scale >= min)
+    }
+    
+    /// Whether buildings are drawn at the given scale
+    pub fn buildings_visible_at(&self, scale: f64) -> bool {
+        !self.lod.enabled || scale >= self.lod.building_min_scale
+    }
+    
+    /// Whether an area with these bounds is large enough on screen to be worth drawing
+    pub fn area_visible_at(&self, bounds: &crate::core::MapBounds, scale: f64) -> bool {
+        !self.lod.enabled || bounds.width() * scale * bounds.height() * scale >= self.lod.min_area_pixels
+    }
+    
+    /// Shield fill and text colour for a highway type; link roads share their class colour.
+    /// Returns `None` when the road class gets no shield.
+    pub fn get_shield_style(&self, highway: &str) -> Option<(&str, &str)> {
+        if !self.shields.enabled {
+            return None;
+        }
+        self.shields.colors.get(highway)
+            .or_else(|| self.shields.colors.get(highway.trim_end_matches("_link")))
+            .map(|fill| (fill.as_str(), self.shields.text_color.as_str()))
+    }
+}
+
+pub struct StyleManager {
+    /// The active style with `color_transform` applied
+    current_style: MapStyle,
+    /// The active style as read from its file
+    base_style: MapStyle,
+    /// Applied to every colour of the active style, e.g. to derive a dark variant
+    color_transform: ColorTransform,
+    current_path: Option<PathBuf>,
+    available_styles: HashMap<String, StyleSource>,
+    watcher: Option<StyleWatcher>,
+    reload_notifier: Arc<Mutex<Option<ReloadNotifier>>>,
+    /// Unrecognized keys found in the active style file
+    style_warnings: Vec<UnknownStyleKey>,
+    /// Styles read so far, by name
+    cache: Mutex<HashMap<String, CachedStyle>>,
+    /// Counts changes of the active style, see [`StyleManager::revision`]
+    revision: u64,
+}
+
+/// A style's text and parsed form, reused while its file is unchanged so switching styles or
+/// reopening the editor doesn't read and parse it again
+#[derive(Debug, Clone)]
+struct CachedStyle {
+    /// Signature of the file when it was read; embedded styles never change
+    signature: Option<(SystemTime, u64)>,
+    text: String,
+    style: MapStyle,
+    warnings: Vec<UnknownStyleKey>,
+}
+
+/// Style parsed by the watcher thread, waiting for the UI thread to pick it up
+type ReloadedStyle = Arc<Mutex<Option<(MapStyle, Vec<UnknownStyleKey>)>>>;
+
+/// Watches a style file and parses it on a background thread once saves settle down
+struct StyleWatcher {
+    path: PathBuf,
+    _watcher: notify::RecommendedWatcher,
+    reloaded: ReloadedStyle,
+}
+
+impl StyleManager {
+    pub fn new() -> Result<Self, StyleError> {
+        let mut manager = Self::new_with_default()?;
+        manager.scan_available_styles()?;
+        manager.load_style(DEFAULT_STYLE)?;
+        Ok(manager)
+    }
+    
+    pub fn new_with_default() -> Result<Self, StyleError> {
+        // Only the embedded styles, without scanning the styles directory
+        let available_styles: HashMap<String, StyleSource> = EMBEDDED_STYLES.iter()
+            .map(|(name, content)| (name.to_string(), StyleSource::Embedded(content)))
+            .collect();
+        let (current_style, style_warnings) = MapStyle::parse_checked(available_styles[DEFAULT_STYLE].read()?.as_str())?;
+        Ok(Self {
+            base_style: current_style.clone(),
+            current_style,
+            color_transform: ColorTransform::IDENTITY,
+            current_path: None,
+            available_styles,
+            watcher: None,
+            reload_notifier: Arc::new(Mutex::new(None)),
+            style_warnings,
+            cache: Mutex::new(HashMap::new()),
+            revision: 0,
+        })
+    }
+    
+    /// Add the style files in `assets/styles`, TOML or Mapbox GL JSON; a file replaces an
+    /// embedded style of the same name
+    pub fn scan_available_styles(&mut self) -> Result<(), StyleError> {
+        let styles_dir = Path::new(STYLES_DIR);
+        if styles_dir.exists() {
+            for entry in std::fs::read_dir(styles_dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                if matches!(path.extension().and_then(|s| s.to_str()), Some("toml" | "json")) {
+                    if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                        self.cache.get_mut().unwrap().remove(stem);
+                        self.available_styles.insert(stem.to_string(), StyleSource::File(path));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Load the style called `style_name`, or the TOML or Mapbox GL JSON style file at that
+    /// path when there is no style of that name
+    pub fn load_style(&mut self, style_name: &str) -> Result<(), StyleError> {
+        if !self.available_styles.contains_key(style_name) && Path::new(style_name).is_file() {
+            self.available_styles.insert(style_name.to_string(), StyleSource::File(PathBuf::from(style_name)));
+        }
+        let cached = self.cached_style(style_name)?;
+        self.set_base_style(cached.style);
+        self.style_warnings = cached.warnings;
+        self.current_path = self.available_styles.get(style_name).and_then(StyleSource::path).map(Path::to_path_buf);
+        
+        // Keep following the active style if hot-reload is on; embedded styles have no file to watch
+        match &self.current_path {
+            Some(path) if self.watcher.is_some() => self.watch_style(path.clone())?,
+            None => self.unwatch_style(),
+            _ => {}
+        }
+        Ok(())
+    }
+    
+    /// Load `style_name`, or the bundled default with a warning if that fails, e.g. for a
+    /// style named in the config that has since been removed. Returns the name now in use.
+    pub fn load_style_or_default(&mut self, style_name: &str) -> Result<String, StyleError> {
+        match self.load_style(style_name) {
+            Ok(()) => Ok(style_name.to_string()),
+            Err(e) => {
+                log::warn!("Could not load style '{}', using '{}' instead: {}", style_name, DEFAULT_STYLE, e);
+                self.load_style(DEFAULT_STYLE)?;
+                Ok(DEFAULT_STYLE.to_string())
+            }
+        }
+    }
+    
+    /// The style called `name`, parsed, from the cache unless its file changed since it was read
+    fn cached_style(&self, name: &str) -> Result<CachedStyle, StyleError> {
+        let Some(source) = self.available_styles.get(name) else {
+            return Err(StyleError::StyleNotFound(name.to_string()));
+        };
+        let signature = source.file_signature();
+        let unchanged = |cached: &&CachedStyle| source.path().is_none() || (signature.is_some() && cached.signature == signature);
+        if let Some(cached) = self.cache.lock().unwrap().get(name).filter(unchanged) {
+            return Ok(cached.clone());
+        }
+        
+        let label = source.path().map_or_else(|| name.to_string(), |path| path.display().to_string());
+        let text = source.read()?;
+        let (style, warnings) = MapStyle::parse_source(&text, source.path())
+            .map_err(|e| StyleError::InStyle { name: label.clone(), source: Box::new(e) })?;
+        for warning in &warnings {
+            log::warn!("{}: {}", label, warning);
+        }
+        let cached = CachedStyle { signature, text, style, warnings };
+        self.cache.lock().unwrap().insert(name.to_string(), cached.clone());
+        Ok(cached)
+    }
+    
+    /// TOML text of the style called `name`, for editing. A file that doesn't parse is still
+    /// returned so it can be fixed.
+    pub fn style_text(&self, name: &str) -> Result<String, StyleError> {
+        match self.cached_style(name) {
+            Ok(cached) => Ok(cached.text),
+            Err(StyleError::InStyle { .. }) => self.available_styles[name].read(),
+            Err(e) => Err(e),
+        }
+    }
+    
+    /// Parse style text, e.g. from the editor, and list its unknown keys. Text identical to a
+    /// style already read is not parsed again.
+    pub fn check_style_text(&self, text: &str) -> Result<Vec<UnknownStyleKey>, StyleError> {
+        if let Some(cached) = self.cache.lock().unwrap().values().find(|cached| cached.text == text) {
+            return Ok(cached.warnings.clone());
+        }
+        MapStyle::parse_checked(text).map(|(_, warnings)| warnings)
+    }
+    
+    /// Where the style called `name` is read from
+    pub fn style_source(&self, name: &str) -> Option<&StyleSource> {
+        self.available_styles.get(name)
+    }
+    
+    pub fn get_current_style(&self) -> &MapStyle {
+        &self.current_style
+    }
+    
+    /// Changes whenever another style becomes active, the active one is reloaded or its colours
+    /// are adjusted, so anything derived from the style can be cached until then
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+    
+    pub fn color_transform(&self) -> ColorTransform {
+        self.color_transform
+    }
+    
+    /// Adjust every colour of the active style, and of styles loaded after it, in HSL space
+    pub fn set_color_transform(&mut self, transform: ColorTransform) -> Result<(), StyleError> {
+        self.current_style = self.base_style.with_color_transform(&transform)?;
+        self.color_transform = transform;
+        self.revision += 1;
+        Ok(())
+    }
+    
+    /// Make `style` the active one, with the colour transform applied
+    fn set_base_style(&mut self, style: MapStyle) {
+        self.current_style = style.with_color_transform(&self.color_transform).unwrap_or_else(|e| {
+            log::warn!("Could not adjust the style's colors: {}", e);
+            style.clone()
+        });
+        self.base_style = style;
+        self.revision += 1;
+    }
+    
+    pub fn get_available_styles(&self) -> Vec<&str> {
+        self.available_styles.keys().map(|s| s.as_str()).collect()
+    }
+    
+    /// Keys in the active style file that were not recognized (typos, misplaced settings)
+    pub fn style_warnings(&self) -> &[UnknownStyleKey] {
+        &self.style_warnings
+    }
+    
+    /// File the active style was loaded from, if any
+    pub fn current_style_path(&self) -> Option<&Path> {
+        self.current_path.as_deref()
+    }
+    
+    /// Reload the style from `path` whenever it changes on disk. Bursts of writes are
+    /// debounced, and a file that fails to parse leaves the current style in place.
+    /// Call `apply_pending_reload` (e.g. once per frame) to pick up the new style.
+    pub fn watch_style<P: AsRef<Path>>(&mut self, path: P) -> Result<(), StyleError> {
+        let path = path.as_ref().to_path_buf();
+        let file_name = path.file_name()
+            .ok_or_else(|| StyleError::NotAStyleFile(path.clone()))?
+            .to_owned();
+        
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            match res {
+                Ok(event) => {
+                    let relevant = (event.kind.is_modify() || event.kind.is_create())
+                        && event.paths.iter().any(|p| p.file_name() == Some(file_name.as_os_str()));
+                    if relevant {
+                        let _ = tx.send(());
+                    }
+                }
+                Err(e) => log::warn!("Style watcher error: {}", e),
+            }
+        })?;
+        
+        // Watch the directory rather than the file: many editors save by replacing the file
+        let watch_dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        watcher.watch(&watch_dir, RecursiveMode::NonRecursive)?;
+        
+        let reloaded = Arc::new(Mutex::new(None));
+        let slot = Arc::clone(&reloaded);
+        let notifier = Arc::clone(&self.reload_notifier);
+        let style_path = path.clone();
+        
+        // The thread ends when the watcher (and with it the sender) is dropped
+        std::thread::spawn(move || {
+            while rx.recv().is_ok() {
+                while rx.recv_timeout(STYLE_RELOAD_DEBOUNCE).is_ok() {}
+                
+                match MapStyle::load_from_file_checked(&style_path) {
+                    Ok((style, warnings)) => {
+                        log::info!("Reloaded style from {}", style_path.display());
+                        for warning in &warnings {
+                            log::warn!("{}: {}", style_path.display(), warning);
+                        }
+                        *slot.lock().unwrap() = Some((style, warnings));
+                        if let Some(notify) = notifier.lock().unwrap().as_ref() {
+                            notify();
+                        }
+                    }
+                    Err(e) => {
+                        log::error!("Failed to reload style {}, keeping the previous one: {}", style_path.display(), e);
+                    }
+                }
+            }
+        });
+        
+        self.watcher = Some(StyleWatcher {
+            path,
+            _watcher: watcher,
+            reloaded,
+        });
+        Ok(())
+    }
+    
+    /// Stop watching the style file
+    pub fn unwatch_style(&mut self) {
+        self.watcher = None;
+    }
+    
+    /// Path of the watched style file, if hot-reload is enabled
+    pub fn watched_style_path(&self) -> Option<&Path> {
+        self.watcher.as_ref().map(|w| w.path.as_path())
+    }
+    
+    /// Register a callback run on the watcher thread after each successful reload
+    pub fn set_reload_notifier<F: Fn() + Send + 'static>(&mut self, notifier: F) {
+        *self.reload_notifier.lock().unwrap() = Some(Box::new(notifier));
+    }
+    
+    /// Swap in a style reloaded by the watcher. Returns true if the style changed.
+    pub fn apply_pending_reload(&mut self) -> bool {
+        let reloaded = self.watcher.as_ref().and_then(|w| w.reloaded.lock().unwrap().take());
+        match reloaded {
+            Some((style, warnings)) => {
+                self.set_base_style(style);
+                self.style_warnings = warnings;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn tags(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|&(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+    
+    #[test]
+    fn barrier_nodes_are_way_node_pois() {
+        let gate = tags(&[("barrier", "gate")]);
+        assert_eq!(MapStyle::way_node_key(&gate).as_deref(), Some("barrier_gate"));
+        assert_eq!(MapStyle::poi_key(&gate).as_deref(), Some("barrier_gate"));
+        
+        // A barrier that is also an amenity is styled as the amenity
+        let toilets = tags(&[("barrier", "gate"), ("amenity", "toilets")]);
+        assert_eq!(MapStyle::poi_key(&toilets).as_deref(), Some("toilets"));
+        
+        assert_eq!(MapStyle::poi_key(&tags(&[("barrier", "fence"), ("name", "Fence")])).as_deref(), Some("barrier_fence"));
+        assert_eq!(MapStyle::poi_key(&tags(&[("name", "Nothing")])), None);
+    }
+    
+    #[test]
+    fn barrier_nodes_take_their_style_from_scale_up() {
+        let style = MapStyle::load_bundled("google-maps").unwrap();
+        let bollard = tags(&[("barrier", "bollard")]);
+        let key = MapStyle::poi_key(&bollard).unwrap();
+        assert_eq!(style.get_poi_style(&key), ("#8c6d46", 1.5));
+        
+        assert!(!style.poi_visible_at(&bollard, style.lod.way_node_min_scale / 2.0));
+        assert!(style.poi_visible_at(&bollard, style.lod.way_node_min_scale));
+        // Ordinary POIs do not wait for the way node scale
+        assert!(style.poi_visible_at(&tags(&[("amenity", "cafe")]), style.lod.way_node_min_scale / 2.0));
+    }
+    
+    #[test]
+    fn poi_icons_are_resolved_at_load() {
+        let style = MapStyle::load_bundled("google-maps").unwrap();
+        assert_eq!(style.get_poi_icon("university"), Some(Path::new("assets/icons/poi/school.svg")));
+        assert_eq!(style.get_poi_icon("bench"), None);
+        
+        // A symbol without a file falls back to the circle
+        let mut icons = style.icons.clone();
+        icons.symbols.insert("bench".to_string(), "no-such-icon".to_string());
+        icons.resolve();
+        assert!(!icons.resolved.contains_key("bench"));
+    }
+    
+    #[test]
+    fn mapbox_gl_styles_convert_onto_the_default_style() {
+        let json = r##"{
+            "version": 8,
+            "layers": [
+                {"id": "background", "type": "background", "paint": {"background-color": "#f8f4f0"}},
+                {"id": "forest", "type": "fill", "filter": ["==", "landuse", "forest"], "paint": {"fill-color": "rgb(200, 220, 180)"}},
+                {"id": "primary-casing", "type": "line", "filter": ["==", ["get", "highway"], "primary"],
+                 "paint": {"line-color": "#a06b00", "line-width": 9}},
+                {"id": "primary", "type": "line", "filter": ["==", ["get", "highway"], "primary"],
+                 "paint": {"line-color": "#fcd6a4", "line-width": {"stops": [[10, 5], [16, 12]]}}},
+                {"id": "rail", "type": "line", "filter": ["==", "railway", "rail"], "paint": {"line-color": "#999", "line-width": 2}},
+                {"id": "mystery", "type": "line", "filter": ["==", "class", "motorway"], "paint": {"line-color": "#f00"}},
+                {"id": "road-names", "type": "symbol"}
+            ]
+        }"##;
+        let (style, warnings) = MapStyle::from_mapbox_gl(json).unwrap();
+        
+        assert_eq!(style.background.color, "#F8F4F0");
+        assert_eq!(style.landuse["forest"], "#C8DCB4");
+        let primary = &style.roads["primary"];
+        assert_eq!((primary.color.as_str(), primary.width), ("#FCD6A4", 5.0));
+        assert_eq!((primary.border_color.as_str(), primary.border_width), ("#A06B00", 2.0));
+        assert_eq!((style.railway.rail_color.as_str(), style.railway.rail_width), ("#999999", 2.0));
+        // What the layers don't set comes from the default style
+        let default = MapStyle::load_bundled(DEFAULT_STYLE).unwrap();
+        assert_eq!(style.water.color, default.water.color);
+        
+        assert!(warnings.iter().any(|warning| warning.contains("symbol (1: road-names)")), "{:?}", warnings);
+        assert!(warnings.iter().any(|warning| warning.contains("1 layer(s)")), "{:?}", warnings);
+        
+        // Style files are read as Mapbox GL by their extension or their `version`
+        let (detected, unknown) = MapStyle::parse_source(json, None).unwrap();
+        assert_eq!(detected.background.color, "#F8F4F0");
+        assert!(unknown.is_empty());
+        assert!(MapStyle::parse_source("{}", Some(Path::new("empty.json"))).is_err());
+        let toml = EMBEDDED_STYLES.iter().find(|(name, _)| *name == "osm-default").unwrap().1;
+        assert!(MapStyle::parse_source(toml, Some(Path::new("osm-default.toml"))).is_ok());
     }
 }
@@ -1,29 +1,155 @@
 pub mod manager;
 pub mod loader;
 
-use crate::core::MapData;
-use crate::parsers::stylesheet::{StyleRule, FeatureSelector, ElementType as StyleElementType, RenderStyle};
+use crate::core::{ElementType, MapData};
+use crate::parsers::stylesheet::{conditions_match, tag_list_contains, Color, ColorTransform, StyleRule, FeatureSelector, ElementType as StyleElementType, RenderStyle};
 use crate::rendering::{StyledMap, StyledFeature, FeatureGeometry, MapBounds};
+use crate::rendering::labels::display_name;
 use anyhow::Result;
+use rayon::prelude::*;
 use std::collections::HashMap;
 
 // Re-export for public API
 pub use crate::parsers::stylesheet::StyleSheet;
 
+/// Error types for loading and watching map styles
+#[derive(thiserror::Error, Debug)]
+pub enum StyleError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    
+    #[error("Style '{0}' not found")]
+    StyleNotFound(String),
+    
+    /// The TOML doesn't describe a style; `hints` name misspelled sections, the usual cause
+    #[error("{source}{}", loader::format_hints(.hints))]
+    Parse {
+        source: toml::de::Error,
+        hints: Vec<String>,
+    },
+    
+    #[error("Style serialization error: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    
+    #[error("Mapbox GL style: {0}")]
+    MapboxGl(String),
+    
+    #[error("Not a style file: {0}")]
+    NotAStyleFile(std::path::PathBuf),
+    
+    #[error("Style watcher error: {0}")]
+    Watch(#[from] notify::Error),
+    
+    /// Any of the above, for the style called `name` (or at that path)
+    #[error("{name}: {source}")]
+    InStyle {
+        name: String,
+        source: Box<StyleError>,
+    },
+}
+
+impl From<toml::de::Error> for StyleError {
+    fn from(source: toml::de::Error) -> Self {
+        StyleError::Parse { source, hints: Vec::new() }
+    }
+}
+
+/// Colour scheme of the built-in default stylesheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Palette {
+    #[default]
+    Standard,
+    /// Okabe-Ito colours, which stay distinguishable with the common forms of colour blindness
+    ColorBlindSafe,
+}
+
+/// The default stylesheet colours that carry meaning and so change with the palette
+struct PaletteColors {
+    motorway: Color,
+    trunk: Color,
+    primary: Color,
+    secondary: Color,
+    tertiary: Color,
+    amenity: Color,
+    shop: Color,
+    tourism: Color,
+}
+
+impl Palette {
+    fn colors(self) -> PaletteColors {
+        match self {
+            Palette::Standard => PaletteColors {
+                motorway: Color::new(231, 114, 0, 255),
+                trunk: Color::new(255, 156, 0, 255),
+                primary: Color::new(255, 205, 0, 255),
+                secondary: Color::new(255, 230, 100, 255),
+                tertiary: Color::new(255, 245, 150, 255),
+                amenity: Color::new(220, 20, 60, 255),
+                shop: Color::new(30, 144, 255, 255),
+                tourism: Color::new(50, 205, 50, 255),
+            },
+            // Road classes step from vermillion through orange to yellow in lightness as well
+            // as hue; POI groups use colours that never collapse into the same one
+            Palette::ColorBlindSafe => PaletteColors {
+                motorway: Color::new(213, 94, 0, 255),
+                trunk: Color::new(230, 159, 0, 255),
+                primary: Color::new(240, 228, 66, 255),
+                secondary: Color::new(247, 240, 150, 255),
+                tertiary: Color::new(252, 249, 210, 255),
+                amenity: Color::new(213, 94, 0, 255),
+                shop: Color::new(0, 114, 178, 255),
+                tourism: Color::new(0, 158, 115, 255),
+            },
+        }
+    }
+}
+
 /// Main style manager
 pub struct StyleManager {
     stylesheets: Vec<StyleSheet>,
     active_stylesheet: Option<usize>,
+    /// Languages tried before `name` when a style's text field is `name`
+    languages: Vec<String>,
+    /// Applied to every colour styles resolve to
+    color_transform: ColorTransform,
 }
 
 impl StyleManager {
     pub fn new() -> Self {
+        Self::with_palette(Palette::Standard)
+    }
+    
+    /// Start with the default stylesheet in the given colour scheme
+    pub fn with_palette(palette: Palette) -> Self {
         Self {
-            stylesheets: vec![Self::create_default_stylesheet()],
+            stylesheets: vec![Self::create_default_stylesheet(palette)],
             active_stylesheet: Some(0),
+            languages: Vec::new(),
+            color_transform: ColorTransform::IDENTITY,
         }
     }
     
+    /// Prefer `name:<lang>` for labels showing `name`, in the order given
+    pub fn with_languages(mut self, languages: Vec<String>) -> Self {
+        self.languages = languages;
+        self
+    }
+    
+    /// Adjust every resolved colour, e.g. to derive a dark variant of a light stylesheet
+    pub fn with_color_transform(mut self, transform: ColorTransform) -> Self {
+        self.color_transform = transform;
+        self
+    }
+    
+    pub fn set_color_transform(&mut self, transform: ColorTransform) {
+        self.color_transform = transform;
+    }
+    
+    /// Switch the built-in default stylesheet to another colour scheme
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.stylesheets[0] = Self::create_default_stylesheet(palette);
+    }
+    
     pub fn add_stylesheet(&mut self, stylesheet: StyleSheet) -> usize {
         self.stylesheets.push(stylesheet);
         self.stylesheets.len() - 1
@@ -45,78 +171,103 @@ impl StyleManager {
             .and_then(|idx| self.stylesheets.get_mut(idx))
     }
     
-    /// Apply styles to map data to create a styled map
+    /// Apply styles to map data to create a styled map, the first step of the pipeline
+    /// described on [`crate::rendering::MapRenderer`]. Zoom stops are ignored; see
+    /// [`StyleManager::apply_styles_at_zoom`].
     pub fn apply_styles(&self, map_data: &MapData) -> Result<StyledMap> {
+        self.styled_map(map_data, None)
+    }
+    
+    /// Same as `apply_styles`, with each style's width, colour and opacity resolved for the
+    /// web map zoom level `zoom`
+    pub fn apply_styles_at_zoom(&self, map_data: &MapData, zoom: f64) -> Result<StyledMap> {
+        self.styled_map(map_data, Some(zoom))
+    }
+    
+    fn styled_map(&self, map_data: &MapData, zoom: Option<f64>) -> Result<StyledMap> {
         let stylesheet = self.get_active_stylesheet()
             .ok_or_else(|| anyhow::anyhow!("No active stylesheet"))?;
         
-        let mut features = Vec::new();
-        
-        // Process ways (both lines and polygons)
-        for way in map_data.ways.values() {
-            if let Some(geometry) = map_data.get_way_geometry(way) {
-                let feature_geometry = match geometry {
-                    geo_types::Geometry::LineString(linestring) => {
-                        FeatureGeometry::LineString(linestring.coords().cloned().collect())
-                    }
-                    geo_types::Geometry::Polygon(polygon) => {
-                        let exterior: Vec<_> = polygon.exterior().coords().cloned().collect();
-                        let holes: Vec<Vec<_>> = polygon.interiors()
-                            .iter()
-                            .map(|hole| hole.coords().cloned().collect())
-                            .collect();
-                        
-                        FeatureGeometry::Polygon { exterior, holes }
-                    }
-                    _ => continue, // Skip other geometry types for now
-                };
-                
-                // Find matching style rules
-                for rule in &stylesheet.rules {
-                    if self.matches_rule(rule, &way.tags, &StyleElementType::Way) {
-                        let style = rule.style.clone();
-                        let text = self.extract_text(&way.tags, &style);
-                        
-                        features.push(StyledFeature {
-                            geometry: feature_geometry.clone(),
-                            style,
-                            text,
-                            z_index: self.calculate_z_index(&way.tags),
-                        });
-                        break; // Use first matching rule
-                    }
+        // Style ways (both lines and polygons) in parallel, keeping the element key for ordering
+        let way_features = map_data.ways.par_iter().filter_map(|(_, way)| {
+            let geometry = map_data.get_way_geometry(way)?;
+            let feature_geometry = match geometry {
+                geo_types::Geometry::LineString(linestring) => {
+                    FeatureGeometry::LineString(linestring.coords().cloned().collect())
                 }
-            }
-        }
-        
-        // Process nodes (points)
-        for node in map_data.nodes.values() {
-            if !node.tags.is_empty() { // Only process nodes with tags
-                let feature_geometry = FeatureGeometry::Point(geo_types::Coord {
-                    x: node.lon,
-                    y: node.lat,
-                });
-                
-                // Find matching style rules
-                for rule in &stylesheet.rules {
-                    if self.matches_rule(rule, &node.tags, &StyleElementType::Node) {
-                        let style = rule.style.clone();
-                        let text = self.extract_text(&node.tags, &style);
-                        
-                        features.push(StyledFeature {
-                            geometry: feature_geometry.clone(),
-                            style,
-                            text,
-                            z_index: self.calculate_z_index(&node.tags),
-                        });
-                        break; // Use first matching rule
-                    }
+                geo_types::Geometry::Polygon(polygon) => {
+                    let exterior: Vec<_> = polygon.exterior().coords().cloned().collect();
+                    let holes: Vec<Vec<_>> = polygon.interiors()
+                        .iter()
+                        .map(|hole| hole.coords().cloned().collect())
+                        .collect();
+                    
+                    FeatureGeometry::Polygon { exterior, holes }
                 }
+                _ => return None, // Skip other geometry types for now
+            };
+            
+            // Per-element overrides change only what they set on the first matching rule
+            let style_override = map_data.get_style_override(ElementType::Way, way.id);
+            let style = self.find_rule_style(stylesheet, &way.tags, &StyleElementType::Way)
+                .or_else(|| style_override.map(|_| RenderStyle::default()))?;
+            let style = match style_override {
+                Some(style_override) => style_override.apply_to(style),
+                None => style,
+            };
+            let style = match zoom {
+                Some(zoom) => style.at_zoom(zoom),
+                None => style,
+            }.with_color_transform(&self.color_transform);
+            let text = self.extract_text(&way.tags, &style);
+            
+            Some(((ElementType::Way as u8, way.id), StyledFeature {
+                geometry: feature_geometry,
+                style,
+                text,
+                z_index: self.calculate_z_index(&way.tags),
+            }))
+        });
+        
+        // Style nodes (points); only nodes with tags are rendered
+        let node_features = map_data.nodes.par_iter().filter_map(|(_, node)| {
+            if node.tags.is_empty() {
+                return None;
             }
-        }
+            
+            let feature_geometry = FeatureGeometry::Point(geo_types::Coord {
+                x: node.lon,
+                y: node.lat,
+            });
+            
+            // Per-element overrides change only what they set on the first matching rule
+            let style_override = map_data.get_style_override(ElementType::Node, node.id);
+            let style = self.find_rule_style(stylesheet, &node.tags, &StyleElementType::Node)
+                .or_else(|| style_override.map(|_| RenderStyle::default()))?;
+            let style = match style_override {
+                Some(style_override) => style_override.apply_to(style),
+                None => style,
+            };
+            let style = match zoom {
+                Some(zoom) => style.at_zoom(zoom),
+                None => style,
+            }.with_color_transform(&self.color_transform);
+            let text = self.extract_text(&node.tags, &style);
+            
+            Some(((ElementType::Node as u8, node.id), StyledFeature {
+                geometry: feature_geometry,
+                style,
+                text,
+                z_index: self.calculate_z_index(&node.tags),
+            }))
+        });
         
-        // Sort features by z-index for proper rendering order
-        features.sort_by_key(|f| f.z_index);
+        let mut keyed_features: Vec<_> = way_features.chain(node_features).collect();
+        
+        // Sort by z-index for proper rendering order. HashMap iteration order is arbitrary,
+        // so break ties on element type and id to keep the output deterministic.
+        keyed_features.sort_by_key(|(key, feature)| (feature.z_index, *key));
+        let features: Vec<StyledFeature> = keyed_features.into_iter().map(|(_, feature)| feature).collect();
         
         let bounds = MapBounds {
             min_lat: map_data.bounds.min_lat,
@@ -128,6 +279,14 @@ impl StyleManager {
         Ok(StyledMap { features, bounds })
     }
     
+    /// Style of the first rule matching the given tags
+    fn find_rule_style(&self, stylesheet: &StyleSheet, tags: &HashMap<String, String>, element_type: &StyleElementType) -> Option<RenderStyle> {
+        stylesheet.rules
+            .iter()
+            .find(|rule| self.matches_rule(rule, tags, element_type))
+            .map(|rule| rule.style.clone())
+    }
+    
     fn matches_rule(&self, rule: &StyleRule, tags: &HashMap<String, String>, element_type: &StyleElementType) -> bool {
         for selector in &rule.selectors {
             match selector {
@@ -143,6 +302,11 @@ impl StyleManager {
                         }
                     }
                 }
+                FeatureSelector::TagListContains { key, value } => {
+                    if tags.get(key).is_some_and(|tag_value| tag_list_contains(tag_value, value)) {
+                        return true;
+                    }
+                }
                 FeatureSelector::ElementType(selector_type) => {
                     if std::mem::discriminant(selector_type) == std::mem::discriminant(element_type) {
                         return true;
@@ -152,6 +316,11 @@ impl StyleManager {
                     // TODO: Implement zoom-based filtering
                     return true;
                 }
+                FeatureSelector::Conditions(conditions) => {
+                    if conditions_match(conditions, tags) {
+                        return true;
+                    }
+                }
             }
         }
         false
@@ -159,11 +328,11 @@ impl StyleManager {
     
     fn extract_text(&self, tags: &HashMap<String, String>, style: &RenderStyle) -> Option<String> {
         if let Some(ref text_field) = style.text_field {
-            // Support simple tag references like "name" or more complex expressions
-            if let Some(value) = tags.get(text_field) {
-                Some(value.clone())
+            // Names follow the preferred languages; other fields are plain tag references
+            if text_field == "name" {
+                display_name(tags, &self.languages).cloned()
             } else {
-                None
+                tags.get(text_field).cloned()
             }
         } else {
             None
@@ -195,7 +364,8 @@ impl StyleManager {
         }
     }
     
-    fn create_default_stylesheet() -> StyleSheet {
+    fn create_default_stylesheet(palette: Palette) -> StyleSheet {
+        let colors = palette.colors();
         let mut stylesheet = StyleSheet::default();
         
         // Google Maps-inspired default stylesheet
@@ -260,7 +430,7 @@ impl StyleManager {
                 ],
                 style: RenderStyle {
                     draw_mode: crate::parsers::stylesheet::DrawMode::Line,
-                    line_color: Some(crate::parsers::stylesheet::Color::new(231, 114, 0, 255)),
+                    line_color: Some(colors.motorway),
                     line_width: 6.0,
                     ..Default::default()
                 },
@@ -276,7 +446,7 @@ impl StyleManager {
                 ],
                 style: RenderStyle {
                     draw_mode: crate::parsers::stylesheet::DrawMode::Line,
-                    line_color: Some(crate::parsers::stylesheet::Color::new(255, 156, 0, 255)),
+                    line_color: Some(colors.trunk),
                     line_width: 5.0,
                     ..Default::default()
                 },
@@ -292,7 +462,7 @@ impl StyleManager {
                 ],
                 style: RenderStyle {
                     draw_mode: crate::parsers::stylesheet::DrawMode::Line,
-                    line_color: Some(crate::parsers::stylesheet::Color::new(255, 205, 0, 255)),
+                    line_color: Some(colors.primary),
                     line_width: 4.0,
                     ..Default::default()
                 },
@@ -308,7 +478,7 @@ impl StyleManager {
                 ],
                 style: RenderStyle {
                     draw_mode: crate::parsers::stylesheet::DrawMode::Line,
-                    line_color: Some(crate::parsers::stylesheet::Color::new(255, 230, 100, 255)),
+                    line_color: Some(colors.secondary),
                     line_width: 3.5,
                     ..Default::default()
                 },
@@ -324,7 +494,7 @@ impl StyleManager {
                 ],
                 style: RenderStyle {
                     draw_mode: crate::parsers::stylesheet::DrawMode::Line,
-                    line_color: Some(crate::parsers::stylesheet::Color::new(255, 245, 150, 255)),
+                    line_color: Some(colors.tertiary),
                     line_width: 3.0,
                     ..Default::default()
                 },
@@ -501,7 +671,7 @@ impl StyleManager {
                 },
             },
             
-            // POI - Amenities (restaurants, shops, etc.) - red circles
+            // POI - Amenities (restaurants, shops, etc.) - circles
             StyleRule {
                 selectors: vec![
                     FeatureSelector::Tag {
@@ -511,13 +681,13 @@ impl StyleManager {
                 ],
                 style: RenderStyle {
                     draw_mode: crate::parsers::stylesheet::DrawMode::Point,
-                    line_color: Some(crate::parsers::stylesheet::Color::new(220, 20, 60, 255)),
+                    line_color: Some(colors.amenity),
                     line_width: 4.0,
                     ..Default::default()
                 },
             },
             
-            // Shops - blue circles
+            // Shops - circles
             StyleRule {
                 selectors: vec![
                     FeatureSelector::Tag {
@@ -527,13 +697,13 @@ impl StyleManager {
                 ],
                 style: RenderStyle {
                     draw_mode: crate::parsers::stylesheet::DrawMode::Point,
-                    line_color: Some(crate::parsers::stylesheet::Color::new(30, 144, 255, 255)),
+                    line_color: Some(colors.shop),
                     line_width: 3.5,
                     ..Default::default()
                 },
             },
             
-            // Tourism - green circles
+            // Tourism - circles
             StyleRule {
                 selectors: vec![
                     FeatureSelector::Tag {
@@ -543,7 +713,7 @@ impl StyleManager {
                 ],
                 style: RenderStyle {
                     draw_mode: crate::parsers::stylesheet::DrawMode::Point,
-                    line_color: Some(crate::parsers::stylesheet::Color::new(50, 205, 50, 255)),
+                    line_color: Some(colors.tourism),
                     line_width: 3.5,
                     ..Default::default()
                 },
@@ -0,0 +1,53 @@
+use anyhow::Result;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Fraction of a background job that is done, shared between the worker and the UI
+#[derive(Debug, Clone, Default)]
+pub struct Progress(Arc<AtomicU32>);
+
+impl Progress {
+    /// Record how far the work has got, from 0.0 to 1.0
+    pub fn set(&self, fraction: f32) {
+        self.0.store(fraction.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+    
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// Slow work (parsing a large file, exporting a large map) running on its own thread so the
+/// UI stays responsive. The UI polls it every frame with [`BackgroundJob::try_finish`].
+pub struct BackgroundJob<T> {
+    handle: Option<JoinHandle<Result<T>>>,
+    progress: Progress,
+}
+
+impl<T: Send + 'static> BackgroundJob<T> {
+    pub fn spawn<F>(work: F) -> Self
+    where
+        F: FnOnce(Progress) -> Result<T> + Send + 'static,
+    {
+        let progress = Progress::default();
+        let worker_progress = progress.clone();
+        Self {
+            handle: Some(std::thread::spawn(move || work(worker_progress))),
+            progress,
+        }
+    }
+    
+    pub fn progress(&self) -> f32 {
+        self.progress.get()
+    }
+    
+    /// The result once the work is done, `None` while it is still running
+    pub fn try_finish(&mut self) -> Option<Result<T>> {
+        if !self.handle.as_ref()?.is_finished() {
+            return None;
+        }
+        let handle = self.handle.take()?;
+        Some(handle.join().unwrap_or_else(|_| Err(anyhow::anyhow!("Background task panicked"))))
+    }
+}
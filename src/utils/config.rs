@@ -1,3 +1,4 @@
+use crate::gui::GuiState;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -8,6 +9,12 @@ pub struct AppConfig {
     pub map: MapConfig,
     pub export: ExportConfig,
     pub recent_files: Vec<PathBuf>,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub gpx: GpxConfig,
+    #[serde(default)]
+    pub geocoding: GeocodingConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,7 +32,11 @@ pub struct MapConfig {
     pub background_color: (u8, u8, u8, u8),
     pub cache_enabled: bool,
     pub cache_size_mb: u32,
-    pub default_style: String,  // Added default style preference
+    /// Style the app starts with when there is no saved session to restore
+    pub default_style: String,
+    /// Close broken area outlines and fix ring winding when OSM files are loaded
+    #[serde(default)]
+    pub repair_geometry: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +48,68 @@ pub struct ExportConfig {
     pub last_export_directory: Option<PathBuf>,
 }
 
+/// Clean-up applied to GPX tracks when they are loaded
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GpxConfig {
+    /// Moving average window in points; 0 leaves the positions as recorded
+    pub smoothing_window: usize,
+    /// Douglas-Peucker tolerance in meters; 0 keeps every point
+    pub simplify_tolerance: f64,
+}
+
+/// Cache of online geocoding results, so repeated searches don't go back to Nominatim
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeocodingConfig {
+    /// Most queries kept; the least recently used are dropped first. 0 disables the cache
+    pub cache_size: usize,
+    /// Seconds a cached result is used before it is fetched again
+    pub cache_ttl_secs: u64,
+    /// Keep the cache in the config directory between sessions
+    pub persist_cache: bool,
+}
+
+impl Default for GeocodingConfig {
+    fn default() -> Self {
+        Self {
+            cache_size: 200,
+            cache_ttl_secs: 7 * 24 * 60 * 60,
+            persist_cache: true,
+        }
+    }
+}
+
+/// Where the last session left off, restored on the next start
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub last_file: Option<PathBuf>,
+    pub viewport: Option<ViewportConfig>,
+    pub gui: Option<GuiState>,
+}
+
+/// Map view position; scale is in pixels per degree like the map view itself
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ViewportConfig {
+    pub center_lat: f64,
+    pub center_lon: f64,
+    pub scale: f64,
+    #[serde(default)]
+    pub rotation: f64,
+}
+
+impl ViewportConfig {
+    /// Hand-edited or corrupt values should not send the view somewhere unusable
+    pub fn is_valid(&self) -> bool {
+        (-90.0..=90.0).contains(&self.center_lat)
+            && (-180.0..=180.0).contains(&self.center_lon)
+            && self.scale.is_finite()
+            && self.scale > 0.0
+            && self.rotation.is_finite()
+    }
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
@@ -52,7 +125,8 @@ impl Default for AppConfig {
                 background_color: (240, 248, 255, 255),
                 cache_enabled: true,
                 cache_size_mb: 256,
-                default_style: "google-maps".to_string(),  // Default to Google Maps style
+                default_style: crate::styles::loader::DEFAULT_STYLE.to_string(),
+                repair_geometry: false,
             },
             export: ExportConfig {
                 default_format: "svg".to_string(),
@@ -62,6 +136,9 @@ impl Default for AppConfig {
                 last_export_directory: None,
             },
             recent_files: Vec::new(),
+            session: SessionConfig::default(),
+            gpx: GpxConfig::default(),
+            geocoding: GeocodingConfig::default(),
         }
     }
 }
@@ -102,11 +179,12 @@ impl AppConfig {
     
     /// Get the path to the configuration file
     fn config_file_path() -> Option<PathBuf> {
-        dirs::config_dir().map(|mut path| {
-            path.push("mapscow-mule");
-            path.push("config.yaml");
-            path
-        })
+        Self::config_dir().map(|path| path.join("config.yaml"))
+    }
+
+    /// Directory the configuration and other saved state live in
+    pub fn config_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|path| path.join("mapscow-mule"))
     }
     
     /// Add a file to the recent files list
@@ -41,11 +41,12 @@ impl FileDialog {
 pub struct FileFilters;
 
 impl FileFilters {
-    pub const OSM: (&'static str, &'static [&'static str]) = ("OpenStreetMap files", &["osm", "osm.xml"]);
+    pub const OSM: (&'static str, &'static [&'static str]) = ("OpenStreetMap files", &["osm", "osm.xml", "gz", "bz2"]);
     pub const GPX: (&'static str, &'static [&'static str]) = ("GPX files", &["gpx"]);
     pub const SVG: (&'static str, &'static [&'static str]) = ("SVG files", &["svg"]);
     pub const PNG: (&'static str, &'static [&'static str]) = ("PNG images", &["png"]);
     pub const JPEG: (&'static str, &'static [&'static str]) = ("JPEG images", &["jpg", "jpeg"]);
     pub const PDF: (&'static str, &'static [&'static str]) = ("PDF documents", &["pdf"]);
+    pub const STYLE_OVERRIDES: (&'static str, &'static [&'static str]) = ("Style overrides", &["json"]);
     pub const STYLESHEET: (&'static str, &'static [&'static str]) = ("Style files", &["yaml", "yml", "mss"]);
 }
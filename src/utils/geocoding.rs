@@ -1,10 +1,159 @@
+use crate::core::MapData;
+use crate::gui::GeocodeResult;
+use crate::utils::config::{AppConfig, GeocodingConfig};
+use crate::utils::Utils;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Most matches a search of the loaded data returns
+const MAX_LOCAL_RESULTS: usize = 50;
+
+/// File the geocoding cache is saved to, in the config directory
+const CACHE_FILE: &str = "geocoding-cache.json";
 
 /// Service for geocoding addresses using Nominatim API
 pub struct GeocodingService {
     client: reqwest::Client,
     base_url: String,
+    cache: GeocodeCache,
+}
+
+/// Online search results by normalized query. Once full, the least recently used query is
+/// dropped; results older than the TTL are fetched again. A persistent cache is written to
+/// its file after every change and read back when it is created.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GeocodeCache {
+    entries: HashMap<String, CacheEntry>,
+    #[serde(skip)]
+    capacity: usize,
+    #[serde(skip)]
+    ttl_secs: u64,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    results: Vec<GeocodeResult>,
+    /// Unix milliseconds when the results were fetched
+    fetched_at: u64,
+    /// Unix milliseconds of the last search that used them
+    last_used: u64,
+}
+
+impl GeocodeCache {
+    /// An in-memory cache of up to `capacity` queries; 0 caches nothing
+    pub fn new(capacity: usize, ttl_secs: u64) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            ttl_secs,
+            path: None,
+        }
+    }
+
+    /// A cache kept in `path`, starting from what was saved there last. A missing or
+    /// unreadable file starts it empty.
+    pub fn persistent(capacity: usize, ttl_secs: u64, path: PathBuf) -> Self {
+        let saved = std::fs::read_to_string(&path).ok()
+            .and_then(|content| match serde_json::from_str::<GeocodeCache>(&content) {
+                Ok(saved) => Some(saved.entries),
+                Err(e) => {
+                    log::warn!("Ignoring geocoding cache {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        let mut cache = Self {
+            entries: saved,
+            path: Some(path),
+            ..Self::new(capacity, ttl_secs)
+        };
+        let now = Utils::current_timestamp_ms();
+        cache.entries.retain(|_, entry| !cache_expired(entry, ttl_secs, now));
+        cache.evict();
+        cache
+    }
+
+    /// The cache `config` asks for, saved in the config directory if it is to persist
+    pub fn from_config(config: &GeocodingConfig) -> Self {
+        match AppConfig::config_dir().filter(|_| config.persist_cache && config.cache_size > 0) {
+            Some(dir) => Self::persistent(config.cache_size, config.cache_ttl_secs, dir.join(CACHE_FILE)),
+            None => Self::new(config.cache_size, config.cache_ttl_secs),
+        }
+    }
+
+    /// Queries are matched ignoring case and spacing, so `Paris  France` finds `paris france`
+    pub fn normalize_query(query: &str) -> String {
+        query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Cached results for `query`, unless they are older than the TTL
+    pub fn get(&mut self, query: &str) -> Option<Vec<GeocodeResult>> {
+        let key = Self::normalize_query(query);
+        let now = Utils::current_timestamp_ms();
+        if cache_expired(self.entries.get(&key)?, self.ttl_secs, now) {
+            self.entries.remove(&key);
+            return None;
+        }
+        let entry = self.entries.get_mut(&key)?;
+        entry.last_used = now;
+        Some(entry.results.clone())
+    }
+
+    pub fn insert(&mut self, query: &str, results: Vec<GeocodeResult>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let now = Utils::current_timestamp_ms();
+        self.entries.insert(Self::normalize_query(query), CacheEntry { results, fetched_at: now, last_used: now });
+        self.evict();
+        if let Err(e) = self.save() {
+            log::warn!("Could not save the geocoding cache: {}", e);
+        }
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        self.entries.clear();
+        self.save()
+    }
+
+    /// Drop the least recently used queries until the cache fits its capacity
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            let Some(oldest) = self.entries.iter()
+                .min_by(|(a_key, a), (b_key, b)| a.last_used.cmp(&b.last_used).then_with(|| a_key.cmp(b_key)))
+                .map(|(key, _)| key.clone()) else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+fn cache_expired(entry: &CacheEntry, ttl_secs: u64, now: u64) -> bool {
+    now.saturating_sub(entry.fetched_at) > ttl_secs.saturating_mul(1000)
 }
 
 /// Response from Nominatim API
@@ -28,11 +177,31 @@ impl GeocodingService {
         Self {
             client: reqwest::Client::new(),
             base_url: "https://nominatim.openstreetmap.org".to_string(),
+            cache: GeocodeCache::default(),
+        }
+    }
+
+    /// Answer repeated searches from `cache` instead of the network
+    pub fn with_cache(mut self, cache: GeocodeCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn cache_mut(&mut self) -> &mut GeocodeCache {
+        &mut self.cache
+    }
+
+    /// Search for places matching the given query, from the cache when it was searched recently
+    pub async fn search(&mut self, query: &str) -> Result<Vec<GeocodeResult>> {
+        if let Some(results) = self.cache.get(query) {
+            return Ok(results);
         }
+        let results = self.fetch_search(query).await?;
+        self.cache.insert(query, results.clone());
+        Ok(results)
     }
 
-    /// Search for places matching the given query
-    pub async fn search(&self, query: &str) -> Result<Vec<crate::gui::GeocodeResult>> {
+    async fn fetch_search(&self, query: &str) -> Result<Vec<GeocodeResult>> {
         let url = format!("{}/search", self.base_url);
         
         let response = self.client
@@ -74,7 +243,7 @@ impl GeocodingService {
                     _ => "Other",
                 }.to_string();
                 
-                Some(crate::gui::GeocodeResult {
+                Some(GeocodeResult {
                     display_name: result.display_name,
                     lat,
                     lon,
@@ -116,3 +285,209 @@ impl Default for GeocodingService {
         Self::new()
     }
 }
+
+/// Named nodes and ways of the loaded map, lowercased once so searching them is a plain scan
+#[derive(Debug, Clone, Default)]
+pub struct NameIndex {
+    entries: Vec<NameEntry>,
+}
+
+#[derive(Debug, Clone)]
+struct NameEntry {
+    key: String,
+    name: String,
+    lat: f64,
+    lon: f64,
+    place_type: &'static str,
+}
+
+impl NameIndex {
+    /// Index every node and way with a `name` tag. Ways are found at the middle of their
+    /// bounds when they are areas, at their middle node otherwise so the point is on the line.
+    pub fn build(map_data: &MapData) -> Self {
+        let nodes = map_data.nodes.values().filter_map(|node| {
+            let name = node.tags.get("name")?;
+            Some(NameEntry::new(name, node.lat, node.lon, &node.tags))
+        });
+        let ways = map_data.ways.values().filter_map(|way| {
+            let name = way.tags.get("name")?;
+            let (lat, lon) = if way.is_area() {
+                map_data.way_bounds(way)?.center()
+            } else {
+                let resolved: Vec<_> = way.nodes.iter().filter_map(|id| map_data.nodes.get(id)).collect();
+                let middle = resolved.get(resolved.len() / 2)?;
+                (middle.lat, middle.lon)
+            };
+            Some(NameEntry::new(name, lat, lon, &way.tags))
+        });
+        Self {
+            entries: nodes.chain(ways).collect(),
+        }
+    }
+    
+    /// Names containing `query`, ignoring case. Exact matches come first, then names starting
+    /// with the query, then the rest, alphabetically within each group.
+    pub fn search(&self, query: &str) -> Vec<GeocodeResult> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        
+        let mut matches: Vec<(f32, &NameEntry)> = self.entries.iter()
+            .filter_map(|entry| {
+                let importance = if entry.key == query {
+                    1.0
+                } else if entry.key.starts_with(&query) {
+                    0.75
+                } else if entry.key.contains(&query) {
+                    0.5
+                } else {
+                    return None;
+                };
+                Some((importance, entry))
+            })
+            .collect();
+        matches.sort_by(|(a, a_entry), (b, b_entry)| b.total_cmp(a).then_with(|| a_entry.name.cmp(&b_entry.name)));
+        
+        matches.into_iter()
+            .take(MAX_LOCAL_RESULTS)
+            .map(|(importance, entry)| GeocodeResult {
+                display_name: entry.name.clone(),
+                lat: entry.lat,
+                lon: entry.lon,
+                place_type: entry.place_type.to_string(),
+                importance,
+            })
+            .collect()
+    }
+}
+
+impl NameEntry {
+    fn new(name: &str, lat: f64, lon: f64, tags: &HashMap<String, String>) -> Self {
+        Self {
+            key: name.to_lowercase(),
+            name: name.to_string(),
+            lat,
+            lon,
+            place_type: local_place_type(tags),
+        }
+    }
+}
+
+/// The same categories the online search reports, from the element's own tags
+fn local_place_type(tags: &HashMap<String, String>) -> &'static str {
+    if let Some(place) = tags.get("place") {
+        return match place.as_str() {
+            "city" | "town" | "village" | "hamlet" | "suburb" | "neighbourhood" => "Settlement",
+            "country" => "Country",
+            "state" => "State",
+            "county" => "County",
+            _ => "Other",
+        };
+    }
+    const KEYS: [(&str, &str); 6] = [
+        ("highway", "Street"),
+        ("amenity", "Amenity"),
+        ("shop", "Shop"),
+        ("tourism", "Tourism"),
+        ("leisure", "Leisure"),
+        ("building", "Building"),
+    ];
+    KEYS.iter()
+        .find(|(key, _)| tags.contains_key(*key))
+        .map_or("Other", |(_, place_type)| place_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn results(name: &str) -> Vec<GeocodeResult> {
+        vec![GeocodeResult {
+            display_name: name.to_string(),
+            lat: 48.8566,
+            lon: 2.3522,
+            place_type: "city".to_string(),
+            importance: 0.9,
+        }]
+    }
+
+    /// Let the millisecond clock move on so the next use gets a later timestamp
+    fn tick() {
+        std::thread::sleep(Duration::from_millis(5));
+    }
+
+    #[test]
+    fn least_recently_used_query_is_evicted() {
+        let mut cache = GeocodeCache::new(2, 3600);
+        cache.insert("paris", results("Paris"));
+        tick();
+        cache.insert("lyon", results("Lyon"));
+        tick();
+        assert!(cache.get("paris").is_some());
+        tick();
+        cache.insert("nice", results("Nice"));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("lyon").is_none());
+        assert!(cache.get("paris").is_some());
+        assert!(cache.get("nice").is_some());
+
+        let mut disabled = GeocodeCache::new(0, 3600);
+        disabled.insert("paris", results("Paris"));
+        assert!(disabled.is_empty());
+    }
+
+    #[test]
+    fn results_older_than_the_ttl_are_dropped() {
+        let mut cache = GeocodeCache::new(4, 3600);
+        cache.insert("paris", results("Paris"));
+        cache.insert("lyon", results("Lyon"));
+        cache.entries.get_mut("paris").unwrap().fetched_at -= 3_601_000;
+
+        assert!(cache.get("paris").is_none());
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get("lyon").unwrap()[0].display_name, "Lyon");
+    }
+
+    #[test]
+    fn queries_match_ignoring_case_and_spacing() {
+        assert_eq!(GeocodeCache::normalize_query("  Paris\t FRANCE "), "paris france");
+
+        let mut cache = GeocodeCache::new(4, 3600);
+        cache.insert("Paris  France", results("Paris"));
+        assert_eq!(cache.get("paris france").unwrap()[0].display_name, "Paris");
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn persistent_cache_is_read_back() {
+        let dir = std::env::temp_dir().join(format!("mapscow-geocoding-{}", std::process::id()));
+        let path = dir.join(CACHE_FILE);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut cache = GeocodeCache::persistent(4, 3600, path.clone());
+        cache.insert("paris", results("Paris"));
+        cache.insert("lyon", results("Lyon"));
+        drop(cache);
+
+        let mut reloaded = GeocodeCache::persistent(4, 3600, path.clone());
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded.get("Paris").unwrap()[0].display_name, "Paris");
+
+        // Expired entries and those beyond the capacity are dropped on load
+        reloaded.entries.get_mut("lyon").unwrap().fetched_at -= 3_601_000;
+        reloaded.save().unwrap();
+        assert_eq!(GeocodeCache::persistent(4, 3600, path.clone()).len(), 1);
+        reloaded.insert("nice", results("Nice"));
+        assert_eq!(GeocodeCache::persistent(1, 3600, path.clone()).len(), 1);
+
+        reloaded.clear().unwrap();
+        assert!(GeocodeCache::persistent(4, 3600, path.clone()).is_empty());
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(GeocodeCache::persistent(4, 3600, path).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,6 +1,8 @@
 pub mod file_dialog;
 pub mod config;
 pub mod geocoding;
+pub mod background;
+pub mod units;
 
 use std::time::{SystemTime, UNIX_EPOCH};
 
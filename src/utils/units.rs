@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+
+pub const METERS_PER_FOOT: f64 = 0.3048;
+pub const METERS_PER_MILE: f64 = 1609.344;
+
+/// Distances switch from feet to miles at a tenth of a mile, as road signs do
+const MIN_MILES: f64 = 0.1;
+
+/// Units distances, elevations and speeds are shown in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UnitSystem {
+    /// Meters and kilometers
+    #[default]
+    Metric,
+    /// Feet and miles
+    Imperial,
+}
+
+impl UnitSystem {
+    pub fn label(&self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "Metric (km, m)",
+            UnitSystem::Imperial => "Imperial (mi, ft)",
+        }
+    }
+    
+    /// A distance in the larger unit past its threshold, e.g. `"850 m"`, `"1.25 km"`, `"0.50 mi"`
+    pub fn format_distance(&self, meters: f64) -> String {
+        let ((long_length, long_symbol), (short_length, short_symbol)) = (self.long_unit(), self.short_unit());
+        if self.in_long_units(meters) {
+            format!("{:.2} {}", meters / long_length, long_symbol)
+        } else {
+            format!("{:.0} {}", meters / short_length, short_symbol)
+        }
+    }
+    
+    /// Whether a distance reads better in kilometers or miles than in meters or feet
+    fn in_long_units(&self, meters: f64) -> bool {
+        match self {
+            UnitSystem::Metric => meters.abs() >= 1000.0,
+            UnitSystem::Imperial => meters.abs() >= MIN_MILES * METERS_PER_MILE,
+        }
+    }
+    
+    /// A height or climb, always in the small unit
+    pub fn format_elevation(&self, meters: f64) -> String {
+        match self {
+            UnitSystem::Metric => format!("{:.0} m", meters),
+            UnitSystem::Imperial => format!("{:.0} ft", meters / METERS_PER_FOOT),
+        }
+    }
+    
+    /// Speed from meters per second, as km/h or mph
+    pub fn format_speed(&self, meters_per_second: f64) -> String {
+        match self {
+            UnitSystem::Metric => format!("{:.1} km/h", meters_per_second * 3.6),
+            UnitSystem::Imperial => format!("{:.1} mph", meters_per_second * 3600.0 / METERS_PER_MILE),
+        }
+    }
+    
+    /// Length of the large unit in meters and its symbol, e.g. for a pace "per km" or "per mi"
+    pub fn long_unit(&self) -> (f64, &'static str) {
+        match self {
+            UnitSystem::Metric => (1000.0, "km"),
+            UnitSystem::Imperial => (METERS_PER_MILE, "mi"),
+        }
+    }
+    
+    /// Length of the small unit in meters and its symbol
+    pub fn short_unit(&self) -> (f64, &'static str) {
+        match self {
+            UnitSystem::Metric => (1.0, "m"),
+            UnitSystem::Imperial => (METERS_PER_FOOT, "ft"),
+        }
+    }
+    
+    /// The longest round distance (1, 2 or 5 times a power of ten of meters or kilometers,
+    /// feet or miles) no longer than `max_meters`, for scale bars. Returns its length in meters
+    /// and its label.
+    pub fn round_scale_length(&self, max_meters: f64) -> (f64, String) {
+        let (unit_length, symbol) = if self.in_long_units(max_meters) {
+            self.long_unit()
+        } else {
+            self.short_unit()
+        };
+        let value = round_down_1_2_5(max_meters / unit_length);
+        (value * unit_length, format!("{} {}", value, symbol))
+    }
+}
+
+/// The largest 1, 2 or 5 times a power of ten not above `value`
+fn round_down_1_2_5(value: f64) -> f64 {
+    if value <= 0.0 || !value.is_finite() {
+        return 0.0;
+    }
+    let magnitude = 10f64.powf(value.log10().floor());
+    let step = [5.0, 2.0, 1.0].into_iter().find(|&step| step * magnitude <= value).unwrap_or(1.0);
+    step * magnitude
+}
+
+/// How positions are written, in the status bar and when copied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CoordinateFormat {
+    /// `48.858370, 2.294481`
+    #[default]
+    Decimal,
+    /// `48°51'30.1"N 2°17'40.1"E`
+    DegreesMinutesSeconds,
+}
+
+impl CoordinateFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CoordinateFormat::Decimal => "Decimal degrees",
+            CoordinateFormat::DegreesMinutesSeconds => "Degrees, minutes, seconds",
+        }
+    }
+    
+    /// A position, latitude first
+    pub fn format(&self, lat: f64, lon: f64) -> String {
+        match self {
+            CoordinateFormat::Decimal => format!("{:.6}, {:.6}", lat, lon),
+            CoordinateFormat::DegreesMinutesSeconds => format!("{} {}", dms(lat, 'N', 'S'), dms(lon, 'E', 'W')),
+        }
+    }
+}
+
+/// An angle as degrees, minutes and seconds to a tenth, with its hemisphere letter
+fn dms(degrees: f64, positive: char, negative: char) -> String {
+    let hemisphere = if degrees < 0.0 { negative } else { positive };
+    // Round once, in tenths of a second, so 59.96" carries into the minutes
+    let tenths = (degrees.abs() * 36_000.0).round() as u64;
+    format!("{}°{:02}'{:04.1}\"{}", tenths / 36_000, tenths / 600 % 60, (tenths % 600) as f64 / 10.0, hemisphere)
+}